@@ -0,0 +1,21 @@
+use std::path::PathBuf;
+
+fn main() {
+    println!("cargo:rerun-if-changed=src/lib.rs");
+    println!("cargo:rerun-if-env-changed=GHOST_REGENERATE_HEADER");
+    let header = "/* The C API of GhostMessaging; generated by cbindgen from ffi/src/lib.rs, don't edit. */";
+    let bindings = cbindgen::Builder::new()
+        .with_src("src/lib.rs")
+        .with_language(cbindgen::Language::C)
+        .with_header(header)
+        .with_include_guard("GHOST_H")
+        .with_documentation(true)
+        .generate()
+        .expect("could not generate ghost.h");
+    let out = PathBuf::from(std::env::var_os("OUT_DIR").expect("cargo sets OUT_DIR"));
+    bindings.write_to_file(out.join("ghost.h"));
+    // The committed header only changes when asked to, so a build never touches the tree.
+    if std::env::var_os("GHOST_REGENERATE_HEADER").is_some() {
+        bindings.write_to_file("include/ghost.h");
+    }
+}
@@ -0,0 +1,91 @@
+/* The C API of GhostMessaging; generated by cbindgen from ffi/src/lib.rs, don't edit. */
+
+#ifndef GHOST_H
+#define GHOST_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * One room and the node in it. Made by `ghost_client_new`, freed by `ghost_client_free`.
+ */
+typedef struct GhostClient GhostClient;
+
+/**
+ * Called for every event, with the `user_data` given to `ghost_client_new` and the event as
+ * one JSON object. It runs on one of the client's own threads, one event at a time; the
+ * string is only valid until it returns.
+ */
+typedef void (*GhostEventCallback)(void *user_data, const char *event);
+
+/**
+ * Why the last call that failed on this thread did. Owned by the library and valid until the
+ * next failure on the same thread; NULL when nothing has failed.
+ */
+const char *ghost_last_error(void);
+
+/**
+ * Hosts a new room as `name`, or with a `ticket` joins the room it invites to, waiting up to
+ * 30 seconds to get in. `secret_key` is the node's identity as 64 hex digits, so it stays the
+ * same between runs; NULL makes a new one. `callback` then gets every event until the client
+ * is freed.
+ *
+ * Returns NULL when the room can't be reached.
+ *
+ * # Safety
+ * `name` is a NUL-terminated string and `ticket` and `secret_key` are too or NULL. `callback`
+ * must be safe to call with `user_data` from another thread until `ghost_client_free` returns.
+ */
+struct GhostClient *ghost_client_new(const char *name,
+                                     const char *ticket,
+                                     const char *secret_key,
+                                     GhostEventCallback callback,
+                                     void *user_data);
+
+/**
+ * The room's ticket, `[Ghost:...]`, to let others in. Owned by the client.
+ *
+ * # Safety
+ * `client` came from `ghost_client_new` and hasn't been freed.
+ */
+const char *ghost_client_ticket(const struct GhostClient *client);
+
+/**
+ * This node's id, in hex. Owned by the client.
+ *
+ * # Safety
+ * `client` came from `ghost_client_new` and hasn't been freed.
+ */
+const char *ghost_client_node_id(const struct GhostClient *client);
+
+/**
+ * Posts `text`, formatted as Markdown with `@name` mentions, and returns the new message's
+ * id, or NULL when it couldn't be sent.
+ *
+ * # Safety
+ * `client` came from `ghost_client_new` and hasn't been freed; `text` is a NUL-terminated
+ * string. Not to be called from the event callback, which would wait on itself.
+ */
+char *ghost_send(const struct GhostClient *client, const char *text);
+
+/**
+ * Frees a string the library returned. NULL is fine.
+ *
+ * # Safety
+ * `s` came from this library, as a string the caller owns, and hasn't been freed.
+ */
+void ghost_string_free(char *s);
+
+/**
+ * Leaves the room, stops the node and frees the client; no more events come once it
+ * returns. NULL is fine. Returns -1 if it didn't shut down cleanly, 0 otherwise.
+ *
+ * # Safety
+ * `client` came from `ghost_client_new` and hasn't been freed. Not to be called from the
+ * event callback.
+ */
+int ghost_client_free(struct GhostClient *client);
+
+#endif  /* GHOST_H */
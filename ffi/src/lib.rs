@@ -0,0 +1,245 @@
+//! A C ABI over `ghost_messaging`, so apps in other languages can embed a node: host or
+//! join a room with `ghost_client_new`, post with `ghost_send`, and hear everything else
+//! through a callback, as the same JSON events `ghostterm pipe` prints.
+//!
+//! Strings go in as NUL-terminated UTF-8 and are only borrowed for the call. Strings that
+//! come back are freed with `ghost_string_free`, unless a function says the client owns them.
+//! A call that fails returns NULL or -1, and `ghost_last_error` says why.
+
+use std::{
+    cell::RefCell,
+    ffi::{c_char, c_int, c_void, CStr, CString},
+    ptr,
+};
+
+use anyhow::{anyhow, Context, Result};
+use futures_lite::StreamExt;
+use ghost_messaging::client::Options;
+use tokio::sync::{mpsc, oneshot};
+
+/// Called for every event, with the `user_data` given to `ghost_client_new` and the event as
+/// one JSON object. It runs on one of the client's own threads, one event at a time; the
+/// string is only valid until it returns.
+pub type GhostEventCallback = Option<unsafe extern "C" fn(user_data: *mut c_void, event: *const c_char)>;
+
+/// One room and the node in it. Made by `ghost_client_new`, freed by `ghost_client_free`.
+pub struct GhostClient {
+    runtime: tokio::runtime::Runtime,
+    commands: mpsc::UnboundedSender<Command>,
+    /// `run`, ending with how the node shut down.
+    task: tokio::task::JoinHandle<Result<()>>,
+    ticket: CString,
+    node_id: CString,
+}
+
+enum Command {
+    Send { text: String, done: oneshot::Sender<Result<String>> },
+    Quit,
+}
+
+/// The app's callback, moved onto the client's threads.
+struct Callback {
+    call: unsafe extern "C" fn(*mut c_void, *const c_char),
+    user_data: *mut c_void,
+}
+
+// SAFETY: the caller of `ghost_client_new` promises the callback may run on another thread
+// with its `user_data`.
+unsafe impl Send for Callback {}
+
+impl Callback {
+    fn event(&self, event: &serde_json::Value) {
+        let Ok(line) = CString::new(event.to_string()) else { return };
+        // SAFETY: as promised by the caller of `ghost_client_new`.
+        unsafe { (self.call)(self.user_data, line.as_ptr()) }
+    }
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn fail(err: anyhow::Error) {
+    let message = CString::new(format!("{:#}", err)).unwrap_or_default();
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+}
+
+/// `s` as a string, or `None` for NULL.
+///
+/// # Safety
+/// `s` is NULL or a NUL-terminated string.
+unsafe fn text(s: *const c_char, what: &str) -> Result<Option<String>> {
+    if s.is_null() {
+        return Ok(None);
+    }
+    Ok(Some(CStr::from_ptr(s).to_str().with_context(|| format!("{} is not UTF-8", what))?.to_string()))
+}
+
+/// Why the last call that failed on this thread did. Owned by the library and valid until the
+/// next failure on the same thread; NULL when nothing has failed.
+#[no_mangle]
+pub extern "C" fn ghost_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| last.borrow().as_ref().map_or(ptr::null(), |message| message.as_ptr()))
+}
+
+/// Hosts a new room as `name`, or with a `ticket` joins the room it invites to, waiting up to
+/// 30 seconds to get in. `secret_key` is the node's identity as 64 hex digits, so it stays the
+/// same between runs; NULL makes a new one. `callback` then gets every event until the client
+/// is freed.
+///
+/// Returns NULL when the room can't be reached.
+///
+/// # Safety
+/// `name` is a NUL-terminated string and `ticket` and `secret_key` are too or NULL. `callback`
+/// must be safe to call with `user_data` from another thread until `ghost_client_free` returns.
+#[no_mangle]
+pub unsafe extern "C" fn ghost_client_new(
+    name: *const c_char,
+    ticket: *const c_char,
+    secret_key: *const c_char,
+    callback: GhostEventCallback,
+    user_data: *mut c_void,
+) -> *mut GhostClient {
+    match new(name, ticket, secret_key, callback, user_data) {
+        Ok(client) => Box::into_raw(Box::new(client)),
+        Err(err) => {
+            fail(err);
+            ptr::null_mut()
+        }
+    }
+}
+
+unsafe fn new(
+    name: *const c_char,
+    ticket: *const c_char,
+    secret_key: *const c_char,
+    callback: GhostEventCallback,
+    user_data: *mut c_void,
+) -> Result<GhostClient> {
+    let name = text(name, "name")?.context("a name is needed")?;
+    let name = ghost_messaging::names::parse(&name).map_err(|err| anyhow!("name: {}", err))?;
+    let ticket = text(ticket, "ticket")?;
+    let secret_key = match text(secret_key, "secret_key")? {
+        Some(key) => Some(key.parse::<iroh::SecretKey>().context("secret_key is not 64 hex digits")?),
+        None => None,
+    };
+    let callback = callback.map(|call| Callback { call, user_data });
+    let runtime = tokio::runtime::Builder::new_multi_thread().enable_all().build()?;
+    let options = Options { secret_key, ..Options::default() };
+    let client = runtime.block_on(async {
+        match &ticket {
+            Some(ticket) => ghost_messaging::GhostClient::join(ticket, &name, options).await,
+            None => ghost_messaging::GhostClient::host(&name, options).await,
+        }
+    })?;
+    let ticket = CString::new(client.ticket())?;
+    let node_id = CString::new(client.node_id().to_string())?;
+    let (commands, receiver) = mpsc::unbounded_channel();
+    let task = runtime.spawn(run(client, receiver, callback));
+    Ok(GhostClient { runtime, commands, task, ticket, node_id })
+}
+
+/// Hands events to the app and posts for it until the client is freed or the room is gone.
+async fn run(mut client: ghost_messaging::GhostClient, mut commands: mpsc::UnboundedReceiver<Command>, callback: Option<Callback>) -> Result<()> {
+    let room = client.topic();
+    loop {
+        tokio::select! {
+            command = commands.recv() => match command {
+                Some(Command::Send { text, done }) => {
+                    let _ = done.send(client.send(&text).await);
+                }
+                Some(Command::Quit) | None => break,
+            },
+            event = client.next() => match event {
+                Some(event) => {
+                    if let Some(callback) = &callback {
+                        callback.event(&event.to_json(room));
+                    }
+                }
+                None => break,
+            },
+        }
+    }
+    client.shutdown().await
+}
+
+/// The room's ticket, `[Ghost:...]`, to let others in. Owned by the client.
+///
+/// # Safety
+/// `client` came from `ghost_client_new` and hasn't been freed.
+#[no_mangle]
+pub unsafe extern "C" fn ghost_client_ticket(client: *const GhostClient) -> *const c_char {
+    (*client).ticket.as_ptr()
+}
+
+/// This node's id, in hex. Owned by the client.
+///
+/// # Safety
+/// `client` came from `ghost_client_new` and hasn't been freed.
+#[no_mangle]
+pub unsafe extern "C" fn ghost_client_node_id(client: *const GhostClient) -> *const c_char {
+    (*client).node_id.as_ptr()
+}
+
+/// Posts `text`, formatted as Markdown with `@name` mentions, and returns the new message's
+/// id, or NULL when it couldn't be sent.
+///
+/// # Safety
+/// `client` came from `ghost_client_new` and hasn't been freed; `text` is a NUL-terminated
+/// string. Not to be called from the event callback, which would wait on itself.
+#[no_mangle]
+pub unsafe extern "C" fn ghost_send(client: *const GhostClient, text: *const c_char) -> *mut c_char {
+    let send = || -> Result<CString> {
+        let text = self::text(text, "text")?.context("no text")?;
+        let (done, sent) = oneshot::channel();
+        let client = &*client;
+        client.commands.send(Command::Send { text, done }).map_err(|_| anyhow!("the room is gone"))?;
+        let id = client.runtime.block_on(sent).map_err(|_| anyhow!("the room is gone"))??;
+        Ok(CString::new(id)?)
+    };
+    match send() {
+        Ok(id) => id.into_raw(),
+        Err(err) => {
+            fail(err);
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Frees a string the library returned. NULL is fine.
+///
+/// # Safety
+/// `s` came from this library, as a string the caller owns, and hasn't been freed.
+#[no_mangle]
+pub unsafe extern "C" fn ghost_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+/// Leaves the room, stops the node and frees the client; no more events come once it
+/// returns. NULL is fine. Returns -1 if it didn't shut down cleanly, 0 otherwise.
+///
+/// # Safety
+/// `client` came from `ghost_client_new` and hasn't been freed. Not to be called from the
+/// event callback.
+#[no_mangle]
+pub unsafe extern "C" fn ghost_client_free(client: *mut GhostClient) -> c_int {
+    if client.is_null() {
+        return 0;
+    }
+    let GhostClient { runtime, commands, task, .. } = *Box::from_raw(client);
+    let _ = commands.send(Command::Quit);
+    let done = runtime.block_on(async { tokio::time::timeout(std::time::Duration::from_secs(5), task).await });
+    match done {
+        Ok(Ok(Ok(()))) => 0,
+        Ok(Ok(Err(err))) => {
+            fail(err);
+            -1
+        }
+        _ => {
+            fail(anyhow!("the node did not shut down in time"));
+            -1
+        }
+    }
+}
@@ -0,0 +1,495 @@
+# GhostTerm-Oberfläche auf Deutsch. Fehlende Einträge fallen auf locales/en.ftl zurück.
+
+## Shared
+
+you-suffix = { $name } (du)
+unknown-peer = Unbekannt
+edited = (bearbeitet)
+relayed = (weitergegeben, ungeprüft)
+urgent = dringend
+ago-seconds = vor { $n } s
+ago-minutes = vor { $n } min
+ago-hours = vor { $n } h
+
+## Status bar and window title
+
+status-waiting = WARTE AUF PEERS
+status-connected = VERBUNDEN
+status-peers =
+    { $count ->
+        [one] 1 Peer
+       *[other] { $count } Peers
+    }
+status-broadcast = Sende-Raum
+status-recording = AUFNAHME { $time }
+window-title =
+    { $unread ->
+        [0] ghost — { $room }
+       *[other] ghost — { $room } ({ $unread } ungelesen)
+    }
+
+## Screen-reader layout
+
+linear-reply = Antwort an { $sender }: { $snippet }
+linear-image = hat ein Bild gesendet, { $name }
+linear-file = hat eine Datei angeboten, { $name } ({ $size })
+linear-voice = hat eine Sprachnachricht gesendet, { $length }
+linear-notice = Hinweis
+linear-reactions = Reaktionen: { $reactions }
+linear-location = Ort { $code } ({ $coordinates }): { $url }
+linear-announcement = Ankündigung: { $text }
+linear-preview = Link: { $title } ({ $site })
+linear-poll = Umfrage: { $question }
+linear-poll-option = { $number }. { $option }: { $votes ->
+    [one] 1 Stimme
+   *[other] { $votes } Stimmen
+    }
+thread-replies =
+    { $count ->
+        [one] 1 Antwort
+       *[other] { $count } Antworten
+    }
+thread-title = Thread · { $sender }: { $snippet } · Esc zurück
+thread-title-unknown = Thread · Esc zurück
+linear-status = ghost, Raum { $room }, { $peers } Peers, { $connected ->
+        [yes] verbunden
+       *[no] warte auf Peers
+    }. F1 für Hilfe.
+linear-search = Suche: { $query }
+linear-message = Nachricht: { $text }
+
+## Peer details (Ctrl+P, Enter)
+
+peer-direct = Direkt ({ $addr })
+peer-relayed = Über Relay ({ $url })
+peer-mixed = Direkt + Relay ({ $addr })
+peer-not-connected = Nicht verbunden
+peer-latency-unknown = unbekannt
+peer-never = nie
+peer-blocked = Blockiert
+peer-verified = Von dir verifiziert
+peer-unverified = Nicht verifiziert
+peer-fingerprint = Fingerabdruck
+peer-status = Status
+peer-connection = Verbindung
+peer-latency = Latenz
+peer-role = Rolle
+peer-presence = Anwesenheit
+peer-last-seen = Zuletzt
+peer-compare-hint = Vergleiche den Fingerabdruck über einen anderen Kanal, bevor du verifizierst.
+peer-keys = v verifizieren · b blockieren · Esc schließen
+
+## Chat pane
+
+density-compact = kompakt
+density-normal = normal
+density-cozy = luftig
+just-now = gerade eben
+new-below =
+    { $count ->
+        [one] 1 neue Nachricht — End drücken
+       *[other] { $count } neue Nachrichten — End drücken
+    }
+image-caption = Bild: { $name }
+image-caption-open = Bild: { $name }, auswählen und o drücken zum Öffnen
+you = du
+yes = ja
+no = nein
+esc-to-close = Esc schließt
+receipt-delivered = zugestellt
+receipt-read = gelesen
+file-shared = für den Raum freigegeben, solange du online bist
+file-offered = auswählen und a drücken zum Herunterladen
+file-fetching = von { $size }
+file-saved = gespeichert unter { $path }, o zum Öffnen
+file-failed = Download fehlgeschlagen: { $error }, a für neuen Versuch
+voice-clip = Sprachnachricht, { $length }
+voice-saved = heruntergeladen, o zum Abspielen (o noch einmal stoppt)
+
+## Message details (i in selection mode)
+
+info-title = Nachricht
+info-from = Von
+info-sent = Gesendet
+info-id = Id
+info-id-none = keine (älterer Client)
+info-edited = Bearbeitet
+info-reply-to = Antwort auf
+info-forwarded = Weitergeleitet von
+info-image = Bild
+info-file = Datei
+info-hash = Hash
+info-reaction = Reaktion
+info-delivered = Zugestellt
+info-read = Gelesen
+info-relayed = Weitergabe
+
+## Help overlay (F1)
+
+help-title = Hilfe
+help-keys = Tastenkürzel
+help-vim = Vim-Normalmodus
+help-commands = Befehle
+help-security = Sicherheit des Raums
+key-send = Nachricht senden / Befehl ausführen
+key-newline = Neue Zeile (für ```-Codeblöcke)
+key-cursor = Cursor in der Eingabe bewegen
+key-edit = Letzte eigene Nachricht bearbeiten
+key-emoji = Emoji-Auswahl (oder :shortcode: tippen)
+key-search = Verlauf durchsuchen (from:<name> filtert)
+key-scroll = Verlauf scrollen (End springt zur neuesten)
+key-select = Nachricht auswählen (y kopieren, r antworten, e reagieren, p anheften, d löschen, f weiterleiten, i Info, a Datei herunterladen, t Thread, 1-9 abstimmen)
+key-mouse = Rad scrollt, Klick wählt aus oder öffnet einen Link, Ziehen kopiert
+key-peers = Peers durchgehen (Enter Details, v verifizieren, b blockieren)
+key-tabs = Raum-Tab wechseln (auch Ctrl+Tab, Alt+←/→)
+key-sidebar = Peer-Seitenleiste ein- / ausblenden
+key-density = Dichte wechseln: kompakt / normal / luftig
+key-pins = Angeheftete Nachrichten auf- / zuklappen
+key-focus = Fokus in den anderen geteilten Bereich
+key-boss = Chef-Taste: hinter einer falschen Shell verstecken (--boss-key)
+key-help = Diese Übersicht ein- / ausblenden
+key-esc = Popup schließen / Antwort oder Bearbeitung abbrechen / Eingabe leeren
+key-quit = Beenden (mit Rückfrage)
+vim-insert = Einfügemodus (Esc zurück zum Normalmodus)
+vim-command = Einen /Befehl beginnen
+vim-scroll = Eine Nachricht scrollen (Ctrl+D / Ctrl+U halbe Seite)
+vim-ends = Älteste / neueste Nachricht
+vim-search = Verlauf durchsuchen
+vim-edit = In der Eingabe bewegen / löschen
+security-transport = Transport
+security-transport-value = QUIC + TLS 1.3 (iroh), pro Hop verschlüsselt
+security-room = Raum
+security-node-id = Deine NodeId
+security-peers = Peers
+security-history = Verlauf
+security-history-value = Nur im RAM, beim Beenden gelöscht
+security-history-kept = Auf der Platte { $days ->
+        [0] bis zum Löschen
+       *[other] für { $days } Tage
+    }, verschlüsselt: { $encrypted }
+security-names = Namen
+security-names-value = Selbst gewählt, nicht geprüft
+security-previews = Linkvorschau
+security-previews-on = Für deine gesendeten Links von dir abgerufen
+security-previews-off = Aus
+
+## Commands, pickers and links
+
+cmd-help = Diese Übersicht anzeigen
+cmd-ticket = Das Raum-Ticket anzeigen oder kopieren
+cmd-links = Alle Links dieser Sitzung auflisten
+cmd-img = Ein Bild senden
+cmd-sendfile = Eine Datei beliebiger Größe anbieten; andere laden sie direkt von dir
+cmd-voice = Sprachnachricht aufnehmen; /voice noch einmal sendet sie, /voice cancel verwirft sie
+cmd-join = Einem weiteren Raum in neuem Tab beitreten
+cmd-host = Einen neuen Raum in neuem Tab erstellen
+cmd-leave = Den aktuellen Tab schließen
+cmd-quit = GhostTerm verlassen (auch Ctrl+Q)
+cmd-split = Den nächsten Tab in einem zweiten Bereich verfolgen
+cmd-notify = Bei Nachrichten in Abwesenheit melden: off|bell|flash|both
+cmd-receipts = Absendern in diesem Raum zeigen, dass du ihre Nachrichten gesehen hast
+cmd-export = Den Chat dieses Raums als .md/.json/.txt speichern; --no-names, --no-times schwärzen
+cmd-nick = Deinen Namen überall ändern und fürs nächste Mal behalten
+cmd-away = In allen Räumen als abwesend zeigen, optional mit Grund
+cmd-busy = In allen Räumen als beschäftigt zeigen, optional mit Grund
+cmd-back = Wieder als online zeigen
+cmd-loc = Einen Punkt auf der Karte teilen, optional mit Beschriftung
+cmd-urgent = Eine Nachricht senden, die auch bei stummgeschaltetem Raum ankommt
+cmd-tmp = Eine Nachricht senden, die nach so vielen Sekunden (oder 10m, 2h, 1d) bei allen verschwindet
+cmd-poll = Den Raum fragen; Abstimmung endet nach der angegebenen Zeit (Standard 1h)
+cmd-topic = Das Thema des Raums für alle setzen (Host und Moderatoren)
+cmd-announce = Ein Banner für alle zeigen, mit --pin angeheftet (Host und Moderatoren)
+cmd-kick = Einen Peer für 10 Minuten aus dem Raum werfen (Host und Moderatoren)
+cmd-ban = Einen Peer dauerhaft aus dem Raum verbannen (Host und Moderatoren)
+cmd-role = Einen Peer hier zum Moderator, Mitglied oder nur lesend machen (nur Host)
+cmd-broadcast = Nur dich und Peers mit einer Rolle hier posten lassen (nur Host)
+cmd-destroy = Diesen Raum für alle löschen und schließen (nur Host)
+usage = Aufruf: { $usage }
+unknown-command = Unbekannter Befehl /{ $name } (F1 für Hilfe)
+link-confirm = Diesen Link im Browser öffnen?
+link-confirm-keys = y / Enter: öffnen    n / Esc: abbrechen
+link-open-title = Link öffnen
+links-title = Links · ↑/↓ auswählen · Enter öffnen · Esc schließen
+link-not-http = Nicht-HTTP-Link wird nicht geöffnet
+emoji-search = Suche:
+no-matches = Keine Treffer
+emoji-title = Emoji · ↑/↓ Enter einfügen · Esc
+react-title = Reagieren · ←/→ Enter · Esc
+
+## Clipboard and images
+
+clipboard-system = Zwischenablage
+clipboard-osc52 = Terminal-Zwischenablage (OSC 52)
+image-not-an-image = kein Bild
+image-unreadable = { $path } konnte nicht gelesen werden
+image-too-detailed = Bild ist zu detailreich, um es direkt zu senden
+
+## Main screen
+
+sidebar-title = Netzwerk
+sidebar-you = { $name } (Du)
+pinned = { $count } angeheftet
+pins-collapse = F4 zuklappen
+pins-expand = F4 aufklappen
+search-no-matches = keine Treffer
+search-title = Suche ({ $position }) · from:<name> filtert · Enter/↑ älter · ↓ neuer · Esc schließen
+input-selecting = Auswahl · ↑/↓ bewegen · y kopieren · r antworten · e reagieren · d löschen · f weiterleiten · i Info · o öffnen · a herunterladen · t Thread · 1-9 abstimmen · Esc fertig
+input-vim-normal = NORMAL · i einfügen · : Befehl · / suchen · j/k scrollen · gg/G Anfang/Ende
+input-editing = Letzte Nachricht bearbeiten · Enter speichern · Esc abbrechen
+input-replying = Antwort an { $sender }: { $snippet } · Esc abbrechen
+input-write = Nachricht schreiben
+input-thread = Im Thread antworten · Esc zurück zum Raum
+quit-title = Beenden
+quit-confirm = GhostTerm verlassen? Der gesamte Verlauf wird gelöscht.
+quit-draft = Du hast einen ungesendeten Entwurf in { $room }.
+quit-keys = y: beenden    n / Esc: bleiben
+destroy-title = Raum zerstören
+destroy-confirm = { $room } für alle zerstören?
+destroy-warning = Alle löschen den Verlauf und verlassen ihn.
+destroy-keys = y: zerstören    n / Esc: behalten
+destroyed-title = Raum zerstört
+destroyed-text = Der Host hat { $room } zerstört; der Verlauf ist weg.
+destroyed-yours = Du hast { $room } zerstört; der Verlauf ist weg.
+destroyed-keys = Esc: schließen
+destroyed-keys-last = Beliebige Taste zum Beenden
+
+## System notices
+
+copied = In { $target } kopiert
+copy-failed = Kopieren fehlgeschlagen: { $error }
+no-links = Keine Links gefunden
+already-in-room = Schon in diesem Raum
+history-loaded =
+    { $count ->
+        [one] ↑ 1 Nachricht aus früheren Sitzungen
+       *[other] ↑ { $count } Nachrichten aus früheren Sitzungen
+    }
+history-failed = Der gespeicherte Verlauf dieses Raums ließ sich nicht öffnen: { $error }
+joined-room = { $room } beigetreten · /ticket copy zum Teilen
+backfilled =
+    { $count ->
+        [one] ↑ 1 frühere Nachricht, weitergereicht von { $name }
+       *[other] ↑ { $count } frühere Nachrichten, weitergereicht von { $name }
+    }
+open-room-failed = Raum konnte nicht geöffnet werden: { $error }
+density-changed = Dichte: { $density }
+no-peers = Noch keine Peers
+how-to-quit = Ctrl+Q drücken oder /quit tippen zum Verlassen
+peer-renamed = { $old } heißt jetzt { $name }
+lagged = Einige Nachrichten wurden verpasst (Empfänger zu langsam)
+messages-missing = ⚠ Einige Nachrichten von { $name } fehlen vielleicht; sie wurden erneut angefordert
+flood-muted = Lauter Peer { $name } für { $seconds } Sekunden stummgeschaltet; er hat zu schnell gepostet
+message-expired = (Nachricht abgelaufen)
+redact-not-yours = Nur der Absender, der Host oder ein Moderator kann diese Nachricht löschen
+forwarded-from = weitergeleitet von { $sender } aus { $room }
+forward-title = Weiterleiten an
+forward-keys = ↑/↓ wählen · Enter weiterleiten · Esc abbrechen
+forward-done = Weitergeleitet an { $room }
+forward-no-rooms = Tritt einem weiteren Raum bei, um Nachrichten dorthin weiterzuleiten
+forward-read-only = Du kannst in { $room } nur lesen
+message-deleted = (Nachricht gelöscht)
+presence-online = online
+presence-away = abwesend
+presence-busy = beschäftigt
+status-set = Du wirst jetzt als { $status } angezeigt
+status-back = Du wirst wieder als online angezeigt
+lookalike-you = { $name } ({ $id }) nutzt einen Namen wie deinen
+lookalike-peer = { $name } ({ $id }) sieht aus wie { $other }; vergleiche die Fingerabdrücke (Ctrl+P), bevor du einem traust
+room-ticket = Raum-Ticket: { $ticket }
+send-image-failed = Bild konnte nicht gesendet werden: { $error }
+send-file-failed = Datei konnte nicht freigegeben werden: { $error }
+file-not-a-file = { $path } ist keine Datei
+voice-recording = Aufnahme läuft… /voice sendet sie (spätestens nach einer Minute), /voice cancel verwirft sie
+voice-cancelled = Sprachnachricht verworfen
+voice-failed = Sprachnachricht fehlgeschlagen: { $error }
+voice-unsupported = dieser Build kann keine Sprachnachrichten (mit --features voice bauen)
+wasm-unsupported = dieser Build kann keine WebAssembly-Plugins ausführen (mit --features wasm bauen)
+voice-no-microphone = kein Mikrofon gefunden
+voice-no-speaker = keine Audioausgabe gefunden
+voice-too-short = zu kurz zum Senden
+voice-not-opus = keine Ogg-Opus-Datei
+exported =
+    { $count ->
+        [one] 1 Nachricht nach { $path } exportiert
+       *[other] { $count } Nachrichten nach { $path } exportiert
+    }
+export-failed = Export fehlgeschlagen: { $error }
+nick-lookalike = „{ $name }“ ist zu nah an { $other }, der schon hier ist
+nick-changed = Du heißt jetzt { $name }
+nick-saved = Du heißt jetzt { $name }, gespeichert in { $path }
+nick-not-saved = Du heißt jetzt { $name }, aber das Speichern schlug fehl: { $error }
+joining-room = Trete Raum bei...
+alerts-set = Benachrichtigungen in { $room }: { $alert }
+receipts-set = Lesebestätigungen in { $room }: { $on }
+split-needs-room = Öffne zuerst einen weiteren Raum (/join oder /host)
+last-room = Das ist der letzte Raum; /quit zum Verlassen
+opened = { $target } geöffnet
+open-failed = { $target } konnte nicht geöffnet werden: { $error }
+open-image-failed = Bild konnte nicht geöffnet werden: { $error }
+pin-host-only = Nur Host und Moderatoren können in diesem Raum anheften
+topic-host-only = Nur Host und Moderatoren können das Thema dieses Raums setzen
+announcement-from = Ankündigung von { $sender }
+announce-host-only = Nur der Host und Moderatoren können Ankündigungen machen
+topic-set = Thema gesetzt: { $topic }
+topic-cleared = Thema entfernt
+poll-open = { $votes ->
+    [one] 1 Stimme
+   *[other] { $votes } Stimmen
+    } · endet in { $left } · auswählen und 1-9 drücken zum Abstimmen
+poll-closed = { $votes ->
+    [one] 1 Stimme
+   *[other] { $votes } Stimmen
+    } · beendet
+poll-no-option = Diese Umfrage hat keine Option { $option }
+poll-is-closed = Diese Umfrage ist beendet
+location-shared = Hat einen Ort geteilt
+topic-changed = Das Thema ist jetzt: { $topic }
+moderation-host-only = Nur Host und Moderatoren können in diesem Raum hinauswerfen oder verbannen
+moderation-outranked = { $name } ist selbst Host oder Moderator; nur der Host kann sie hinauswerfen
+moderation-no-peer = Hier heißt niemand { $name }
+moderation-ambiguous = Mehrere Peers passen zu { $name }; nimm den Anfang ihrer NodeId (Strg+P)
+peer-kicked = { $name } wurde für 10 Minuten hinausgeworfen
+peer-banned = { $name } wurde aus dem Raum verbannt
+you-kicked = Du wurdest für 10 Minuten hinausgeworfen; bis dahin sieht niemand deine Nachrichten
+you-banned = Du wurdest aus diesem Raum verbannt; niemand sieht deine Nachrichten mehr
+role-host = Host
+role-moderator = Moderator
+role-member = Mitglied
+role-read-only = nur lesend
+role-host-only = Nur der Host kann Rollen vergeben
+role-changed = { $name } ist jetzt { $role }
+role-yours = Der Host hat dich in diesem Raum zu { $role } gemacht
+mode-host-only = Nur der Host kann hieraus einen Sende-Raum machen
+destroy-host-only = Nur der Host kann den Raum zerstören
+ticket-redeemed = Ticket eingelöst von { $fingerprint } über { $place }
+redeemed-address-relay = { $address } (Relay { $relay })
+redeemed-relay = Relay { $relay }
+redeemed-nowhere = einen unbekannten Ort
+mode-broadcast = Das ist jetzt ein Sende-Raum: Nur der Host und Peers mit einer Rolle können posten
+mode-open = Das ist wieder ein normaler Raum: Alle können posten
+read-only-you = Du bist in diesem Raum nur lesend; was du sendest, würde niemand sehen
+urgent-too-soon = Eine dringende Nachricht alle { $minutes } Minuten; sende diese als normale Nachricht
+message-too-long = Zu lang zum Senden: Nachrichten dürfen bis zu { $max } KiB groß sein. Der Text steht noch im Eingabefeld; mit /sendfile geht er als Datei
+
+## Starting up
+
+name-saved = { $name } als Standardname in { $path } gespeichert (ändern mit /nick).
+name-not-saved = Dein Name konnte nicht gespeichert werden: { $error }
+ticket-banner = GHOST-TICKET
+ticket-copied = Ticket in { $target } kopiert.
+ticket-not-copied = Ticket konnte nicht kopiert werden: { $error }
+press-enter = ENTER drücken zum Starten...
+connecting = Verbinde...
+
+## Plain mode (no terminal)
+
+plain-help = Nachricht eingeben und Enter drücken zum Senden. Befehle: /peers, /ticket, /help, /quit
+plain-unknown = Unbekannter Befehl /{ $name } (siehe /help)
+plain-ready = Raum-Ticket: { $ticket }
+plain-joined = { $name } ist beigetreten
+plain-image = { $sender } hat ein Bild gesendet, { $name }
+plain-file = { $sender } hat eine Datei angeboten, { $name }
+plain-edit = { $sender } hat eine Nachricht bearbeitet: { $text }
+plain-peers = Peers: { $names }
+gui-name = Dein Name
+gui-ticket = Ticket
+gui-ticket-hint = Ein [Ghost:...]-Ticket einfügen, oder leer lassen, um einen neuen Raum zu eröffnen
+gui-host = Neuen Raum eröffnen
+gui-join = Beitreten
+gui-failed = Der Raum konnte nicht geöffnet werden: { $error }
+gui-joined = { $room } beigetreten; mit „Ticket kopieren“ kommen andere dazu
+gui-copy-ticket = Ticket kopieren
+gui-leave = Verlassen
+gui-send = Senden
+gui-input-hint = Nachricht eingeben und Enter drücken
+gui-destroyed = Der Host hat diesen Raum gelöscht; sein Verlauf ist weg.
+
+## Protocol compatibility
+
+protocol-older = { $name } nutzt ein älteres GhostTerm (Protokoll { $version }) und sieht deine { $types }-Nachrichten nicht
+plugins-loaded = Plugins: { $names }
+plugin-failed = Plugin { $path } nicht geladen: { $err }
+plugin-held = Ein Plugin hat diese Nachricht zurückgehalten
+script-failed = Skript { $path } nicht geladen: { $err }
+script-error = Skript { $name } fehlgeschlagen: { $err }
+protocol-newer = { $name } hat eine „{ $kind }“-Nachricht von einem neueren GhostTerm gesendet (Protokoll { $version }, du hast { $ours }); „ghostterm update“ ausführen, um solche Nachrichten zu sehen
+protocol-unknown-type = { $name } hat eine „{ $kind }“-Nachricht gesendet, die diese Version nicht kennt; „ghostterm update“ ausführen, um solche Nachrichten zu sehen
+newer-message = (eine Nachricht von einem neueren GhostTerm, die diese Version nicht anzeigen kann)
+extension-message = (eine „{ $kind }“-Nachricht von einer Erweiterung, die diese Version nicht anzeigen kann)
+peer-protocol = Protokoll
+peer-protocol-unknown = nicht angegeben (älterer Client)
+peer-protocol-same = { $version }, wie deines
+peer-protocol-newer = { $version }, neuer als deines ({ $ours }); aktualisieren, um alles zu sehen
+peer-protocol-behind = { $version }, älter als deines ({ $ours })
+peer-protocol-older = { $version }, älter; sieht deine { $types }-Nachrichten nicht
+peer-client = Client
+peer-client-version = GhostTerm { $version }
+peer-client-unknown = nicht angegeben (älterer Client)
+peer-features = Funktionen
+peer-features-all = alle deine
+peer-features-lacking = fehlt: { $features }
+peer-features-unknown = nicht angegeben (älterer Client)
+
+## First-run setup
+
+setup-welcome = Willkommen bei GhostTerm! Ein paar Fragen vor dem ersten Chat (alles später änderbar in der Konfigurationsdatei oder mit „ghostterm setup“).
+setup-name = Dein Name in Chats
+setup-theme = Farbschema ({ $themes })
+setup-theme-unknown = Wähle eines von: { $themes }
+setup-keep = Identität zwischen Starts behalten, damit Peers dich wiedererkennen und verifizieren können?
+setup-passphrase = Identität mit einer Passphrase schützen, die bei jedem Start abgefragt wird?
+setup-yes = j
+setup-no = n
+setup-yes-no = Bitte mit { $yes } oder { $no } antworten.
+setup-done = Einstellungen in { $path } gespeichert.
+setup-done-ephemeral = Einstellungen in { $path } gespeichert. Jeder Start nutzt eine neue Wegwerf-Identität; sonst wird nichts behalten.
+
+## Command line
+
+ticket-topic = Thema
+ticket-expires = Läuft ab
+ticket-expires-never = nie (Tickets haben kein Ablaufdatum; der Raum besteht, solange jemand darin ist)
+ticket-node-host = Knoten { $n } (Host)
+ticket-node-peer = Knoten { $n } (Peer)
+ticket-node-id = NodeId
+ticket-relay = Relay
+ticket-relay-none = keines
+ticket-direct = Direkt
+ticket-no-nodes = Keine Knoten: niemand zum Verbinden
+rooms-none = Keine gespeicherten Räume; einen hinzufügen mit `ghostterm rooms add <alias> <ticket>`
+rooms-invalid = ungültiges Ticket
+rooms-saved = { $alias } gespeichert
+rooms-updated = { $alias } aktualisiert
+rooms-removed = { $alias } entfernt
+rooms-unknown = kein gespeicherter Raum `{ $alias }` (siehe `ghostterm rooms list`)
+identity-created = Identität { $id } in { $path } erstellt
+identity-passphrase = Passphrase der Identität
+identity-new-passphrase = Neue Passphrase der Identität
+identity-repeat-passphrase = Passphrase wiederholen
+identity-empty-passphrase = leere Passphrase
+identity-mismatch = die Passphrasen stimmen nicht überein
+identity-wrong-passphrase = falsche Passphrase
+identity-exists = { $path } existiert bereits; mit --force ersetzen
+identity-missing = keine Identität in { $path }
+daemon-listening = Daemon lauscht auf { $addr }
+daemon-sent =
+    { $count ->
+        [one] { $id } über den Daemon an 1 Nachbarn gesendet
+       *[other] { $id } über den Daemon an { $count } Nachbarn gesendet
+    }
+daemon-no-peers = in diesem Raum ist kein Peer erreichbar
+daemon-error = Daemon: { $error }
+daemon-closed = der Daemon hat die Verbindung geschlossen
+service-wrote = { $path } geschrieben
+service-started = Gestartet; `systemctl --user status { $unit }` zeigt, wie es läuft.
+update-current = GhostTerm { $version } ist aktuell.
+update-available = GhostTerm { $version } ist verfügbar (dies ist { $current }); `ghostterm update` installiert es.
+update-downloading = Lade GhostTerm { $version } ({ $asset }) herunter...
+update-done = Aktualisiert: { $current } -> { $version }.
+name-empty = der Name ist leer
+name-too-long = Namen haben höchstens { $max } Zeichen
+name-invisible = Namen dürfen keine Steuer- oder unsichtbaren Zeichen enthalten
+alert-off = aus
+alert-bell = Glocke
+alert-flash = Blinken
+alert-both = Glocke und Blinken
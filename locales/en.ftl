@@ -0,0 +1,496 @@
+# GhostTerm interface text (English, also the fallback for other languages).
+# Message ids are shared by every locales/*.ftl file; see https://projectfluent.org for the syntax.
+
+## Shared
+
+you-suffix = { $name } (you)
+unknown-peer = Unknown
+edited = (edited)
+relayed = (relayed, unverified)
+urgent = urgent
+ago-seconds = { $n }s ago
+ago-minutes = { $n }m ago
+ago-hours = { $n }h ago
+
+## Status bar and window title
+
+status-waiting = WAITING FOR PEERS
+status-connected = CONNECTED
+status-peers =
+    { $count ->
+        [one] 1 peer
+       *[other] { $count } peers
+    }
+status-broadcast = broadcast
+status-recording = REC { $time }
+window-title =
+    { $unread ->
+        [0] ghost — { $room }
+       *[other] ghost — { $room } ({ $unread } unread)
+    }
+
+## Screen-reader layout
+
+linear-reply = in reply to { $sender }: { $snippet }
+linear-image = sent an image, { $name }
+linear-file = offered a file, { $name } ({ $size })
+linear-voice = sent a voice message, { $length }
+linear-notice = Notice
+linear-reactions = reactions: { $reactions }
+linear-location = location { $code } ({ $coordinates }): { $url }
+linear-announcement = announcement: { $text }
+linear-preview = link: { $title } ({ $site })
+linear-poll = poll: { $question }
+linear-poll-option = { $number }. { $option }: { $votes ->
+    [one] 1 vote
+   *[other] { $votes } votes
+    }
+thread-replies =
+    { $count ->
+        [one] 1 reply
+       *[other] { $count } replies
+    }
+thread-title = Thread · { $sender }: { $snippet } · Esc back
+thread-title-unknown = Thread · Esc back
+linear-status = ghost, room { $room }, { $peers } peers, { $connected ->
+        [yes] connected
+       *[no] waiting for peers
+    }. F1 for help.
+linear-search = search: { $query }
+linear-message = message: { $text }
+
+## Peer details (Ctrl+P, Enter)
+
+peer-direct = Direct ({ $addr })
+peer-relayed = Relayed ({ $url })
+peer-mixed = Direct + relay ({ $addr })
+peer-not-connected = Not connected
+peer-latency-unknown = unknown
+peer-never = never
+peer-blocked = Blocked
+peer-verified = Verified by you
+peer-unverified = Not verified
+peer-fingerprint = Fingerprint
+peer-status = Status
+peer-connection = Connection
+peer-latency = Latency
+peer-role = Role
+peer-presence = Presence
+peer-last-seen = Last seen
+peer-compare-hint = Compare the fingerprint with them over another channel before verifying.
+peer-keys = v verify · b block · Esc close
+
+## Chat pane
+
+density-compact = compact
+density-normal = normal
+density-cozy = cozy
+just-now = just now
+new-below =
+    { $count ->
+        [one] 1 new message — press End
+       *[other] { $count } new messages — press End
+    }
+image-caption = image: { $name }
+image-caption-open = image: { $name }, select and press o to open
+you = you
+yes = yes
+no = no
+esc-to-close = Esc to close
+receipt-delivered = delivered
+receipt-read = read
+file-shared = shared with the room while you're online
+file-offered = select and press a to download
+file-fetching = of { $size }
+file-saved = saved to { $path }, o to open
+file-failed = download failed: { $error }, a to retry
+voice-clip = voice message, { $length }
+voice-saved = downloaded, o to play (o again stops)
+
+## Message details (i in selection mode)
+
+info-title = Message
+info-from = From
+info-sent = Sent
+info-id = Id
+info-id-none = none (older client)
+info-edited = Edited
+info-reply-to = Reply to
+info-forwarded = Forwarded from
+info-image = Image
+info-file = File
+info-hash = Hash
+info-reaction = Reaction
+info-delivered = Delivered
+info-read = Read by
+info-relayed = Relayed by
+
+## Help overlay (F1)
+
+help-title = Help
+help-keys = Keybindings
+help-vim = Vim Normal Mode
+help-commands = Commands
+help-security = Room Security
+key-send = Send message / run command
+key-newline = New line (for ``` code blocks)
+key-cursor = Move the cursor in the input
+key-edit = Edit your last message
+key-emoji = Emoji picker (or type :shortcode:)
+key-search = Search history (from:<name> filters)
+key-scroll = Scroll history (End jumps to the newest)
+key-select = Select a message (y copy, r reply, e react, p pin, d delete, f forward, i info, a download a file, t thread, 1-9 vote)
+key-mouse = Wheel scrolls, click selects or opens a link, drag copies
+key-peers = Browse peers (Enter details, v verify, b block)
+key-tabs = Switch room tab (also Ctrl+Tab, Alt+←/→)
+key-sidebar = Show / hide the peer sidebar
+key-density = Cycle density: compact / normal / cozy
+key-pins = Expand / collapse pinned messages
+key-focus = Move focus to the other split pane
+key-boss = Boss key: hide behind a fake shell (--boss-key)
+key-help = Toggle this overlay
+key-esc = Close popup / cancel reply or edit / clear input
+key-quit = Quit (asks first)
+vim-insert = Insert mode (Esc back to normal)
+vim-command = Start a /command
+vim-scroll = Scroll one message (Ctrl+D / Ctrl+U half a page)
+vim-ends = Oldest / newest message
+vim-search = Search history
+vim-edit = Move / delete in the input
+security-transport = Transport
+security-transport-value = QUIC + TLS 1.3 (iroh), encrypted per hop
+security-room = Room
+security-node-id = Your NodeId
+security-peers = Peers
+security-history = History
+security-history-value = RAM only, erased on exit
+security-history-kept = On disk { $days ->
+        [0] until deleted
+       *[other] for { $days } days
+    }, encrypted: { $encrypted }
+security-names = Names
+security-names-value = Self-declared, not verified
+security-previews = Link previews
+security-previews-on = Fetched by you for links you send
+security-previews-off = Off
+
+## Commands, pickers and links
+
+cmd-help = Show this overlay
+cmd-ticket = Show the room ticket, or copy it
+cmd-links = List every link seen in this session
+cmd-img = Send an image
+cmd-sendfile = Offer a file of any size; peers fetch it straight from you
+cmd-voice = Record a voice message; /voice again sends it, /voice cancel drops it
+cmd-join = Join another room in a new tab
+cmd-host = Create a new room in a new tab
+cmd-leave = Close the current tab
+cmd-quit = Leave GhostTerm (also Ctrl+Q)
+cmd-split = Watch the next tab in a second pane
+cmd-notify = Alert on messages while away: off|bell|flash|both
+cmd-receipts = Tell senders when you've seen their messages in this room
+cmd-export = Save this room's chat as .md/.json/.txt; --no-names, --no-times redact
+cmd-nick = Change your name everywhere and keep it for next time
+cmd-away = Show as away in every room, with an optional word on why
+cmd-busy = Show as busy in every room, with an optional word on why
+cmd-back = Show as online again
+cmd-loc = Share a point on the map, with an optional label
+cmd-urgent = Send a message that rings through for peers who muted the room
+cmd-tmp = Send a message that disappears for everyone after that many seconds (or 10m, 2h, 1d)
+cmd-poll = Ask the room; votes close after the time given (default 1h)
+cmd-topic = Set the room's topic line for everyone (host and moderators)
+cmd-announce = Put a banner in front of everyone, pinned with --pin (host and moderators)
+cmd-kick = Put a peer out of the room for 10 minutes (host and moderators)
+cmd-ban = Put a peer out of the room for good (host and moderators)
+cmd-role = Make a peer a moderator, member or read-only here (host only)
+cmd-broadcast = Let only you and peers with a role post here (host only)
+cmd-destroy = Wipe this room for everyone in it, and close it (host only)
+usage = Usage: { $usage }
+unknown-command = Unknown command /{ $name } (press F1 for help)
+link-confirm = Open this link in your browser?
+link-confirm-keys = y / Enter: open    n / Esc: cancel
+link-open-title = Open Link
+links-title = Links · ↑/↓ select · Enter open · Esc close
+link-not-http = refusing to open non-http link
+emoji-search = Search:
+no-matches = No matches
+emoji-title = Emoji · ↑/↓ Enter insert · Esc
+react-title = React · ←/→ Enter · Esc
+
+## Clipboard and images
+
+clipboard-system = clipboard
+clipboard-osc52 = terminal clipboard (OSC 52)
+image-not-an-image = not an image
+image-unreadable = could not read { $path }
+image-too-detailed = image is too detailed to send inline
+
+## Main screen
+
+sidebar-title = Network
+sidebar-you = { $name } (You)
+pinned = { $count } pinned
+pins-collapse = F4 collapse
+pins-expand = F4 expand
+search-no-matches = no matches
+search-title = Search ({ $position }) · from:<name> filters · Enter/↑ older · ↓ newer · Esc close
+input-selecting = Selecting · ↑/↓ move · y copy · r reply · e react · d delete · f forward · i info · o open · a download · t thread · 1-9 vote · Esc done
+input-vim-normal = NORMAL · i insert · : command · / search · j/k scroll · gg/G top/bottom
+input-editing = Editing your last message · Enter save · Esc cancel
+input-replying = Replying to { $sender }: { $snippet } · Esc cancel
+input-write = Write a message
+input-thread = Reply in the thread · Esc back to the room
+quit-title = Quit
+quit-confirm = Leave GhostTerm? All history is erased.
+quit-draft = You have an unsent draft in { $room }.
+quit-keys = y: quit    n / Esc: stay
+destroy-title = Destroy room
+destroy-confirm = Destroy { $room } for everyone?
+destroy-warning = Every client wipes its history and leaves.
+destroy-keys = y: destroy    n / Esc: keep
+destroyed-title = Room destroyed
+destroyed-text = The host destroyed { $room }; its history is gone.
+destroyed-yours = You destroyed { $room }; its history is gone.
+destroyed-keys = Esc: close
+destroyed-keys-last = Press any key to quit
+
+## System notices
+
+copied = Copied to { $target }
+copy-failed = Copy failed: { $error }
+no-links = No links found
+already-in-room = Already in this room
+history-loaded =
+    { $count ->
+        [one] ↑ 1 message from earlier sessions
+       *[other] ↑ { $count } messages from earlier sessions
+    }
+history-failed = Couldn't open this room's saved history: { $error }
+joined-room = Joined { $room } · /ticket copy to share it
+backfilled =
+    { $count ->
+        [one] ↑ 1 earlier message, passed on by { $name }
+       *[other] ↑ { $count } earlier messages, passed on by { $name }
+    }
+open-room-failed = Could not open room: { $error }
+density-changed = Density: { $density }
+no-peers = No peers yet
+how-to-quit = Press Ctrl+Q or type /quit to leave
+peer-renamed = { $old } is now known as { $name }
+lagged = Some messages were missed (receiver lagged)
+messages-missing = ⚠ Some messages from { $name } may be missing; asked them to send them again
+flood-muted = Muted noisy peer { $name } for { $seconds } seconds; they were posting too fast
+message-expired = (message expired)
+redact-not-yours = Only its sender, the host or a moderator can delete that message
+forwarded-from = forwarded from { $sender } in { $room }
+forward-title = Forward to
+forward-keys = ↑/↓ choose · Enter forward · Esc cancel
+forward-done = Forwarded to { $room }
+forward-no-rooms = Join another room to forward a message there
+forward-read-only = You're read-only in { $room }
+message-deleted = (message deleted)
+presence-online = online
+presence-away = away
+presence-busy = busy
+status-set = You now show as { $status }
+status-back = You show as online again
+lookalike-you = { $name } ({ $id }) is using a name like yours
+lookalike-peer = { $name } ({ $id }) looks like { $other }; compare fingerprints (Ctrl+P) before trusting either
+room-ticket = Room ticket: { $ticket }
+send-image-failed = Could not send image: { $error }
+send-file-failed = Could not share file: { $error }
+file-not-a-file = { $path } is not a file
+voice-recording = Recording… /voice sends it (after a minute at most), /voice cancel drops it
+voice-cancelled = Voice message dropped
+voice-failed = Voice message failed: { $error }
+voice-unsupported = this build has no voice support (build it with --features voice)
+wasm-unsupported = this build can't run WebAssembly plugins (build it with --features wasm)
+voice-no-microphone = no microphone found
+voice-no-speaker = no audio output found
+voice-too-short = too short to send
+voice-not-opus = not an Ogg Opus clip
+exported =
+    { $count ->
+        [one] Exported 1 message to { $path }
+       *[other] Exported { $count } messages to { $path }
+    }
+export-failed = Could not export: { $error }
+nick-lookalike = "{ $name }" is too close to { $other }, who is already here
+nick-changed = You are now known as { $name }
+nick-saved = You are now known as { $name } and saved to { $path }
+nick-not-saved = You are now known as { $name }, but could not save it: { $error }
+joining-room = Joining room...
+alerts-set = New-message alerts in { $room }: { $alert }
+receipts-set = Read receipts in { $room }: { $on }
+split-needs-room = Open another room first (/join or /host)
+last-room = This is the last room; use /quit to leave
+opened = Opened { $target }
+open-failed = Could not open { $target }: { $error }
+open-image-failed = Could not open image: { $error }
+pin-host-only = Only the host and moderators can pin in this room
+topic-host-only = Only the host and moderators can set this room's topic
+announcement-from = Announcement from { $sender }
+announce-host-only = Only the host and moderators can make announcements
+topic-set = Topic set: { $topic }
+topic-cleared = Topic cleared
+poll-open = { $votes ->
+    [one] 1 vote
+   *[other] { $votes } votes
+    } · closes in { $left } · select it and press 1-9 to vote
+poll-closed = { $votes ->
+    [one] 1 vote
+   *[other] { $votes } votes
+    } · closed
+poll-no-option = This poll has no option { $option }
+poll-is-closed = This poll has closed
+location-shared = Shared a location
+topic-changed = The topic is now: { $topic }
+moderation-host-only = Only the host and moderators can kick or ban in this room
+moderation-outranked = { $name } is the host or a moderator too; only the host can put them out
+moderation-no-peer = Nobody here goes by { $name }
+moderation-ambiguous = More than one peer matches { $name }; use the start of their NodeId (Ctrl+P)
+peer-kicked = { $name } was kicked out for 10 minutes
+peer-banned = { $name } was banned from the room
+you-kicked = You were kicked out for 10 minutes; nobody sees your messages until then
+you-banned = You were banned from this room; nobody sees your messages any more
+role-host = host
+role-moderator = moderator
+role-member = member
+role-read-only = read-only
+role-host-only = Only the host can hand out roles
+role-changed = { $name } is now { $role }
+role-yours = The host made you { $role } in this room
+mode-host-only = Only the host can make this a broadcast room
+destroy-host-only = Only the host can destroy the room
+ticket-redeemed = Ticket redeemed by { $fingerprint } from { $place }
+redeemed-address-relay = { $address } (relay { $relay })
+redeemed-relay = relay { $relay }
+redeemed-nowhere = an unknown place
+mode-broadcast = This is now a broadcast room: only the host and peers it gave a role can post
+mode-open = This is an ordinary room again: everyone can post
+read-only-you = You're read-only in this room; nothing you send would be shown
+urgent-too-soon = One urgent message every { $minutes } minutes; send this one as an ordinary message
+message-too-long = Too long to send: messages can be up to { $max } KiB. It's still in the input box; /sendfile can share it as a file
+
+## Starting up
+
+name-saved = Saved { $name } as your default name in { $path } (change it with /nick).
+name-not-saved = Could not save your name: { $error }
+ticket-banner = GHOST TICKET
+ticket-copied = Ticket copied to { $target }.
+ticket-not-copied = Could not copy the ticket: { $error }
+press-enter = Press ENTER to Initialize...
+connecting = Connecting...
+
+## Plain mode (no terminal)
+
+plain-help = Type a message and press Enter to send it. Commands: /peers, /ticket, /help, /quit
+plain-unknown = Unknown command /{ $name } (try /help)
+plain-ready = Room ticket: { $ticket }
+plain-joined = { $name } joined
+plain-image = { $sender } sent an image, { $name }
+plain-file = { $sender } offered a file, { $name }
+plain-edit = { $sender } edited a message: { $text }
+plain-peers = Peers: { $names }
+gui-name = Your name
+gui-ticket = Ticket
+gui-ticket-hint = Paste a [Ghost:...] ticket, or leave empty to host a new room
+gui-host = Host a new room
+gui-join = Join
+gui-failed = Could not open the room: { $error }
+gui-joined = Joined { $room }; Copy ticket lets others in
+gui-copy-ticket = Copy ticket
+gui-leave = Leave
+gui-send = Send
+gui-input-hint = Type a message and press Enter
+gui-destroyed = The host destroyed this room; its history is gone.
+
+## Protocol compatibility
+
+protocol-older = { $name } runs an older GhostTerm (protocol { $version }) and won't see your { $types } messages
+plugins-loaded = Plugins: { $names }
+plugin-failed = Plugin { $path } not loaded: { $err }
+plugin-held = A plugin held that message back
+script-failed = Script { $path } not loaded: { $err }
+script-error = Script { $name } failed: { $err }
+protocol-newer = { $name } sent a “{ $kind }” message from a newer GhostTerm (protocol { $version }, you have { $ours }); run “ghostterm update” to see messages like it
+protocol-unknown-type = { $name } sent a “{ $kind }” message this version doesn't know; run “ghostterm update” to see messages like it
+newer-message = (a message from a newer GhostTerm that this version can't show)
+extension-message = (a “{ $kind }” message, from an add-on this version can't show)
+peer-protocol = Protocol
+peer-protocol-unknown = not announced (older client)
+peer-protocol-same = { $version }, same as yours
+peer-protocol-newer = { $version }, newer than yours ({ $ours }); update to see everything
+peer-protocol-behind = { $version }, older than yours ({ $ours })
+peer-protocol-older = { $version }, older; can't see your { $types } messages
+peer-client = Client
+peer-client-version = GhostTerm { $version }
+peer-client-unknown = not announced (older client)
+peer-features = Features
+peer-features-all = all of yours
+peer-features-lacking = missing: { $features }
+peer-features-unknown = not announced (older client)
+
+## First-run setup
+
+setup-welcome = Welcome to GhostTerm! A few questions before the first chat (change any of it later in the config file, or with “ghostterm setup”).
+setup-name = Your name in chats
+setup-theme = Colour scheme ({ $themes })
+setup-theme-unknown = Pick one of: { $themes }
+setup-keep = Keep your identity between runs, so peers can recognise and verify you?
+setup-passphrase = Protect the identity with a passphrase, asked for on every start?
+setup-yes = y
+setup-no = n
+setup-yes-no = Please answer { $yes } or { $no }.
+setup-done = Saved your settings to { $path }.
+setup-done-ephemeral = Saved your settings to { $path }. Each run will use a new throwaway identity; nothing else is kept.
+
+## Command line
+
+ticket-topic = Topic
+ticket-expires = Expires
+ticket-expires-never = never (tickets carry no expiry; the room lasts while someone is in it)
+ticket-node-host = Node { $n } (host)
+ticket-node-peer = Node { $n } (peer)
+ticket-node-id = NodeId
+ticket-relay = Relay
+ticket-relay-none = none
+ticket-direct = Direct
+ticket-no-nodes = No nodes: nobody to connect to
+rooms-none = No saved rooms; add one with `ghostterm rooms add <alias> <ticket>`
+rooms-invalid = invalid ticket
+rooms-saved = Saved { $alias }
+rooms-updated = Updated { $alias }
+rooms-removed = Removed { $alias }
+rooms-unknown = no saved room `{ $alias }` (see `ghostterm rooms list`)
+identity-created = Created identity { $id } in { $path }
+identity-passphrase = Identity passphrase
+identity-new-passphrase = New identity passphrase
+identity-repeat-passphrase = Repeat passphrase
+identity-empty-passphrase = empty passphrase
+identity-mismatch = passphrases don't match
+identity-wrong-passphrase = wrong passphrase
+identity-exists = { $path } already exists; pass --force to replace it
+identity-missing = no identity at { $path }
+daemon-listening = Daemon listening on { $addr }
+daemon-sent =
+    { $count ->
+        [one] Sent { $id } to 1 neighbor via the daemon
+       *[other] Sent { $id } to { $count } neighbors via the daemon
+    }
+daemon-no-peers = no peers reachable in this room
+daemon-error = daemon: { $error }
+daemon-closed = the daemon closed the connection
+service-wrote = Wrote { $path }
+service-started = Started; `systemctl --user status { $unit }` shows how it's doing.
+update-current = GhostTerm { $version } is up to date.
+update-available = GhostTerm { $version } is available (this is { $current }); `ghostterm update` installs it.
+update-downloading = Downloading GhostTerm { $version } ({ $asset })...
+update-done = Updated { $current } -> { $version }.
+name-empty = the name is empty
+name-too-long = names are at most { $max } characters
+name-invisible = names can't contain control or invisible characters
+alert-off = off
+alert-bell = bell
+alert-flash = flash
+alert-both = bell and flash
@@ -0,0 +1,495 @@
+# Interfaz de GhostTerm en español. Lo que falte aquí se toma de locales/en.ftl.
+
+## Shared
+
+you-suffix = { $name } (tú)
+unknown-peer = Desconocido
+edited = (editado)
+relayed = (reenviado, sin verificar)
+urgent = urgente
+ago-seconds = hace { $n } s
+ago-minutes = hace { $n } min
+ago-hours = hace { $n } h
+
+## Status bar and window title
+
+status-waiting = ESPERANDO PARES
+status-connected = CONECTADO
+status-peers =
+    { $count ->
+        [one] 1 par
+       *[other] { $count } pares
+    }
+status-broadcast = difusión
+status-recording = GRABANDO { $time }
+window-title =
+    { $unread ->
+        [0] ghost — { $room }
+       *[other] ghost — { $room } ({ $unread } sin leer)
+    }
+
+## Screen-reader layout
+
+linear-reply = en respuesta a { $sender }: { $snippet }
+linear-image = envió una imagen, { $name }
+linear-file = ofreció un archivo, { $name } ({ $size })
+linear-voice = envió un mensaje de voz, { $length }
+linear-notice = Aviso
+linear-reactions = reacciones: { $reactions }
+linear-location = ubicación { $code } ({ $coordinates }): { $url }
+linear-announcement = aviso: { $text }
+linear-preview = enlace: { $title } ({ $site })
+linear-poll = encuesta: { $question }
+linear-poll-option = { $number }. { $option }: { $votes ->
+    [one] 1 voto
+   *[other] { $votes } votos
+    }
+thread-replies =
+    { $count ->
+        [one] 1 respuesta
+       *[other] { $count } respuestas
+    }
+thread-title = Hilo · { $sender }: { $snippet } · Esc volver
+thread-title-unknown = Hilo · Esc volver
+linear-status = ghost, sala { $room }, { $peers } pares, { $connected ->
+        [yes] conectado
+       *[no] esperando pares
+    }. F1 para ayuda.
+linear-search = buscar: { $query }
+linear-message = mensaje: { $text }
+
+## Peer details (Ctrl+P, Enter)
+
+peer-direct = Directa ({ $addr })
+peer-relayed = Por relay ({ $url })
+peer-mixed = Directa + relay ({ $addr })
+peer-not-connected = Sin conexión
+peer-latency-unknown = desconocida
+peer-never = nunca
+peer-blocked = Bloqueado
+peer-verified = Verificado por ti
+peer-unverified = Sin verificar
+peer-fingerprint = Huella
+peer-status = Estado
+peer-connection = Conexión
+peer-latency = Latencia
+peer-role = Rol
+peer-presence = Presencia
+peer-last-seen = Visto
+peer-compare-hint = Compara la huella con esa persona por otro canal antes de verificar.
+peer-keys = v verificar · b bloquear · Esc cerrar
+
+## Chat pane
+
+density-compact = compacta
+density-normal = normal
+density-cozy = amplia
+just-now = ahora mismo
+new-below =
+    { $count ->
+        [one] 1 mensaje nuevo — pulsa End
+       *[other] { $count } mensajes nuevos — pulsa End
+    }
+image-caption = imagen: { $name }
+image-caption-open = imagen: { $name }, selecciónala y pulsa o para abrirla
+you = tú
+yes = sí
+no = no
+esc-to-close = Esc para cerrar
+receipt-delivered = entregado
+receipt-read = leído
+file-shared = compartido con la sala mientras estés conectado
+file-offered = selecciónalo y pulsa a para descargarlo
+file-fetching = de { $size }
+file-saved = guardado en { $path }, o para abrir
+file-failed = falló la descarga: { $error }, a para reintentar
+voice-clip = mensaje de voz, { $length }
+voice-saved = descargado, o para reproducir (o otra vez lo detiene)
+
+## Message details (i in selection mode)
+
+info-title = Mensaje
+info-from = De
+info-sent = Enviado
+info-id = Id
+info-id-none = ninguno (cliente antiguo)
+info-edited = Editado
+info-reply-to = Respuesta a
+info-forwarded = Reenviado de
+info-image = Imagen
+info-file = Archivo
+info-hash = Hash
+info-reaction = Reacción
+info-delivered = Entregado
+info-read = Leído por
+info-relayed = Reenviado
+
+## Help overlay (F1)
+
+help-title = Ayuda
+help-keys = Atajos de teclado
+help-vim = Modo normal de Vim
+help-commands = Comandos
+help-security = Seguridad de la sala
+key-send = Enviar mensaje / ejecutar comando
+key-newline = Nueva línea (para bloques de código ```)
+key-cursor = Mover el cursor en la entrada
+key-edit = Editar tu último mensaje
+key-emoji = Selector de emoji (o escribe :shortcode:)
+key-search = Buscar en el historial (from:<nombre> filtra)
+key-scroll = Desplazar el historial (End salta al más reciente)
+key-select = Seleccionar un mensaje (y copiar, r responder, e reaccionar, p fijar, d borrar, f reenviar, i info, a descargar un archivo, t hilo, 1-9 votar)
+key-mouse = La rueda desplaza, el clic selecciona o abre un enlace, arrastrar copia
+key-peers = Recorrer pares (Enter detalles, v verificar, b bloquear)
+key-tabs = Cambiar de pestaña (también Ctrl+Tab, Alt+←/→)
+key-sidebar = Mostrar / ocultar la barra de pares
+key-density = Cambiar densidad: compacta / normal / amplia
+key-pins = Expandir / contraer mensajes fijados
+key-focus = Pasar el foco al otro panel
+key-boss = Tecla del jefe: esconderse tras una shell falsa (--boss-key)
+key-help = Mostrar / ocultar esta ayuda
+key-esc = Cerrar ventana / cancelar respuesta o edición / vaciar la entrada
+key-quit = Salir (pregunta antes)
+vim-insert = Modo inserción (Esc vuelve al normal)
+vim-command = Empezar un /comando
+vim-scroll = Desplazar un mensaje (Ctrl+D / Ctrl+U media página)
+vim-ends = Mensaje más antiguo / más reciente
+vim-search = Buscar en el historial
+vim-edit = Moverse / borrar en la entrada
+security-transport = Transporte
+security-transport-value = QUIC + TLS 1.3 (iroh), cifrado por salto
+security-room = Sala
+security-node-id = Tu NodeId
+security-peers = Pares
+security-history = Historial
+security-history-value = Solo en RAM, se borra al salir
+security-history-kept = En disco { $days ->
+        [0] hasta que lo borres
+       *[other] durante { $days } días
+    }, cifrado: { $encrypted }
+security-names = Nombres
+security-names-value = Elegidos por cada cual, sin verificar
+security-previews = Vistas previas
+security-previews-on = Las obtienes tú para los enlaces que envías
+security-previews-off = Desactivadas
+
+## Commands, pickers and links
+
+cmd-help = Mostrar esta ayuda
+cmd-ticket = Mostrar el ticket de la sala, o copiarlo
+cmd-links = Listar todos los enlaces de esta sesión
+cmd-img = Enviar una imagen
+cmd-sendfile = Ofrecer un archivo de cualquier tamaño; los demás lo descargan directamente de ti
+cmd-voice = Grabar un mensaje de voz; /voice otra vez lo envía, /voice cancel lo descarta
+cmd-join = Unirse a otra sala en una pestaña nueva
+cmd-host = Crear una sala nueva en una pestaña nueva
+cmd-leave = Cerrar la pestaña actual
+cmd-quit = Salir de GhostTerm (también Ctrl+Q)
+cmd-split = Seguir la siguiente pestaña en un segundo panel
+cmd-notify = Avisar de mensajes en tu ausencia: off|bell|flash|both
+cmd-receipts = Avisar a quien escribe cuando has visto sus mensajes en esta sala
+cmd-export = Guardar el chat de esta sala como .md/.json/.txt; --no-names, --no-times ocultan
+cmd-nick = Cambiar tu nombre en todas partes y conservarlo
+cmd-away = Mostrarte ausente en todas las salas, con un motivo opcional
+cmd-busy = Mostrarte ocupado en todas las salas, con un motivo opcional
+cmd-back = Volver a mostrarte en línea
+cmd-loc = Compartir un punto del mapa, con una etiqueta opcional
+cmd-urgent = Enviar un mensaje que suena aunque la sala esté silenciada
+cmd-tmp = Enviar un mensaje que desaparece para todos tras esos segundos (o 10m, 2h, 1d)
+cmd-poll = Preguntar a la sala; la votación cierra tras el tiempo indicado (por defecto 1h)
+cmd-topic = Poner el tema de la sala para todos (anfitrión y moderadores)
+cmd-announce = Mostrar un aviso destacado a todos, fijado con --pin (anfitrión y moderadores)
+cmd-kick = Echar a un par de la sala durante 10 minutos (anfitrión y moderadores)
+cmd-ban = Echar a un par de la sala para siempre (anfitrión y moderadores)
+cmd-role = Hacer a un par moderador, miembro o solo lectura aquí (solo anfitrión)
+cmd-broadcast = Que solo tú y los pares con un rol publiquen aquí (solo anfitrión)
+cmd-destroy = Borrar esta sala para todos y cerrarla (solo anfitrión)
+usage = Uso: { $usage }
+unknown-command = Comando desconocido /{ $name } (pulsa F1 para ayuda)
+link-confirm = ¿Abrir este enlace en el navegador?
+link-confirm-keys = y / Enter: abrir    n / Esc: cancelar
+link-open-title = Abrir enlace
+links-title = Enlaces · ↑/↓ elegir · Enter abrir · Esc cerrar
+link-not-http = no se abren enlaces que no sean http
+emoji-search = Buscar:
+no-matches = Sin resultados
+emoji-title = Emoji · ↑/↓ Enter insertar · Esc
+react-title = Reaccionar · ←/→ Enter · Esc
+
+## Clipboard and images
+
+clipboard-system = portapapeles
+clipboard-osc52 = portapapeles del terminal (OSC 52)
+image-not-an-image = no es una imagen
+image-unreadable = no se pudo leer { $path }
+image-too-detailed = la imagen es demasiado detallada para enviarla en línea
+
+## Main screen
+
+sidebar-title = Red
+sidebar-you = { $name } (Tú)
+pinned = { $count } fijados
+pins-collapse = F4 contraer
+pins-expand = F4 expandir
+search-no-matches = sin resultados
+search-title = Buscar ({ $position }) · from:<nombre> filtra · Enter/↑ anteriores · ↓ posteriores · Esc cerrar
+input-selecting = Seleccionando · ↑/↓ mover · y copiar · r responder · e reaccionar · d borrar · f reenviar · i info · o abrir · a descargar · t hilo · 1-9 votar · Esc listo
+input-vim-normal = NORMAL · i insertar · : comando · / buscar · j/k desplazar · gg/G inicio/fin
+input-editing = Editando tu último mensaje · Enter guardar · Esc cancelar
+input-replying = Respondiendo a { $sender }: { $snippet } · Esc cancelar
+input-write = Escribe un mensaje
+input-thread = Responder en el hilo · Esc volver a la sala
+quit-title = Salir
+quit-confirm = ¿Salir de GhostTerm? Se borra todo el historial.
+quit-draft = Tienes un borrador sin enviar en { $room }.
+quit-keys = y: salir    n / Esc: quedarse
+destroy-title = Destruir sala
+destroy-confirm = ¿Destruir { $room } para todos?
+destroy-warning = Todos borran el historial y salen de ella.
+destroy-keys = y: destruir    n / Esc: conservar
+destroyed-title = Sala destruida
+destroyed-text = El anfitrión destruyó { $room }; el historial ya no está.
+destroyed-yours = Destruiste { $room }; el historial ya no está.
+destroyed-keys = Esc: cerrar
+destroyed-keys-last = Pulsa cualquier tecla para salir
+
+## System notices
+
+copied = Copiado al { $target }
+copy-failed = No se pudo copiar: { $error }
+no-links = No hay enlaces
+already-in-room = Ya estás en esta sala
+history-loaded =
+    { $count ->
+        [one] ↑ 1 mensaje de sesiones anteriores
+       *[other] ↑ { $count } mensajes de sesiones anteriores
+    }
+history-failed = No se pudo abrir el historial guardado de esta sala: { $error }
+joined-room = Te uniste a { $room } · /ticket copy para compartirla
+backfilled =
+    { $count ->
+        [one] ↑ 1 mensaje anterior, reenviado por { $name }
+       *[other] ↑ { $count } mensajes anteriores, reenviados por { $name }
+    }
+open-room-failed = No se pudo abrir la sala: { $error }
+density-changed = Densidad: { $density }
+no-peers = Aún no hay pares
+how-to-quit = Pulsa Ctrl+Q o escribe /quit para salir
+peer-renamed = { $old } ahora se llama { $name }
+lagged = Se perdieron algunos mensajes (el receptor se retrasó)
+messages-missing = ⚠ Puede que falten mensajes de { $name }; se le pidió que los reenvíe
+flood-muted = Par ruidoso { $name } silenciado durante { $seconds } segundos; publicaba demasiado rápido
+message-expired = (mensaje caducado)
+redact-not-yours = Solo su remitente, el anfitrión o un moderador pueden borrar ese mensaje
+forwarded-from = reenviado de { $sender } en { $room }
+forward-title = Reenviar a
+forward-keys = ↑/↓ elegir · Enter reenviar · Esc cancelar
+forward-done = Reenviado a { $room }
+forward-no-rooms = Únete a otra sala para reenviar un mensaje allí
+forward-read-only = Solo puedes leer en { $room }
+message-deleted = (mensaje eliminado)
+presence-online = en línea
+presence-away = ausente
+presence-busy = ocupado
+status-set = Ahora apareces como { $status }
+status-back = Vuelves a aparecer en línea
+lookalike-you = { $name } ({ $id }) usa un nombre parecido al tuyo
+lookalike-peer = { $name } ({ $id }) se parece a { $other }; compara las huellas (Ctrl+P) antes de confiar en ninguno
+room-ticket = Ticket de la sala: { $ticket }
+send-image-failed = No se pudo enviar la imagen: { $error }
+send-file-failed = No se pudo compartir el archivo: { $error }
+file-not-a-file = { $path } no es un archivo
+voice-recording = Grabando… /voice lo envía (como mucho tras un minuto), /voice cancel lo descarta
+voice-cancelled = Mensaje de voz descartado
+voice-failed = Falló el mensaje de voz: { $error }
+voice-unsupported = esta compilación no admite voz (compílala con --features voice)
+wasm-unsupported = esta compilación no ejecuta plugins WebAssembly (compílala con --features wasm)
+voice-no-microphone = no se encontró ningún micrófono
+voice-no-speaker = no se encontró ninguna salida de audio
+voice-too-short = demasiado corto para enviarlo
+voice-not-opus = no es un clip Ogg Opus
+exported =
+    { $count ->
+        [one] 1 mensaje exportado a { $path }
+       *[other] { $count } mensajes exportados a { $path }
+    }
+export-failed = No se pudo exportar: { $error }
+nick-lookalike = «{ $name }» se parece demasiado a { $other }, que ya está aquí
+nick-changed = Ahora te llamas { $name }
+nick-saved = Ahora te llamas { $name }, guardado en { $path }
+nick-not-saved = Ahora te llamas { $name }, pero no se pudo guardar: { $error }
+joining-room = Uniéndose a la sala...
+alerts-set = Avisos de mensajes en { $room }: { $alert }
+receipts-set = Confirmaciones de lectura en { $room }: { $on }
+split-needs-room = Abre antes otra sala (/join o /host)
+last-room = Esta es la última sala; usa /quit para salir
+opened = Abierto { $target }
+open-failed = No se pudo abrir { $target }: { $error }
+open-image-failed = No se pudo abrir la imagen: { $error }
+pin-host-only = Solo el anfitrión y los moderadores pueden fijar en esta sala
+topic-host-only = Solo el anfitrión y los moderadores pueden poner el tema de esta sala
+announcement-from = Aviso de { $sender }
+announce-host-only = Solo el anfitrión y los moderadores pueden publicar avisos
+topic-set = Tema puesto: { $topic }
+topic-cleared = Tema quitado
+poll-open = { $votes ->
+    [one] 1 voto
+   *[other] { $votes } votos
+    } · cierra en { $left } · selecciónala y pulsa 1-9 para votar
+poll-closed = { $votes ->
+    [one] 1 voto
+   *[other] { $votes } votos
+    } · cerrada
+poll-no-option = Esta encuesta no tiene la opción { $option }
+poll-is-closed = Esta encuesta ya cerró
+location-shared = Compartió una ubicación
+topic-changed = El tema ahora es: { $topic }
+moderation-host-only = Solo el anfitrión y los moderadores pueden echar o vetar en esta sala
+moderation-outranked = { $name } también es anfitrión o moderador; solo el anfitrión puede echarle
+moderation-no-peer = Aquí nadie se llama { $name }
+moderation-ambiguous = Más de un par coincide con { $name }; usa el inicio de su NodeId (Ctrl+P)
+peer-kicked = { $name } fue echado durante 10 minutos
+peer-banned = { $name } fue vetado de la sala
+you-kicked = Te echaron durante 10 minutos; hasta entonces nadie ve tus mensajes
+you-banned = Te vetaron de esta sala; nadie ve ya tus mensajes
+role-host = anfitrión
+role-moderator = moderador
+role-member = miembro
+role-read-only = solo lectura
+role-host-only = Solo el anfitrión puede repartir roles
+role-changed = { $name } ahora es { $role }
+role-yours = El anfitrión te hizo { $role } en esta sala
+mode-host-only = Solo el anfitrión puede convertir esta sala en un canal de difusión
+destroy-host-only = Solo el anfitrión puede destruir la sala
+ticket-redeemed = Invitación usada por { $fingerprint } desde { $place }
+redeemed-address-relay = { $address } (relay { $relay })
+redeemed-relay = relay { $relay }
+redeemed-nowhere = un lugar desconocido
+mode-broadcast = Esta sala es ahora un canal de difusión: solo el anfitrión y los pares con un rol pueden publicar
+mode-open = Esta sala vuelve a ser normal: todos pueden publicar
+read-only-you = En esta sala eres de solo lectura; nadie vería lo que envíes
+urgent-too-soon = Un mensaje urgente cada { $minutes } minutos; envía este como mensaje normal
+message-too-long = Demasiado largo para enviar: los mensajes pueden tener hasta { $max } KiB. Sigue en el cuadro de texto; /sendfile puede compartirlo como archivo
+
+## Starting up
+
+name-saved = { $name } guardado como tu nombre predeterminado en { $path } (cámbialo con /nick).
+name-not-saved = No se pudo guardar tu nombre: { $error }
+ticket-banner = TICKET GHOST
+ticket-copied = Ticket copiado al { $target }.
+ticket-not-copied = No se pudo copiar el ticket: { $error }
+press-enter = Pulsa ENTER para empezar...
+connecting = Conectando...
+
+## Plain mode (no terminal)
+
+plain-help = Escribe un mensaje y pulsa Enter para enviarlo. Comandos: /peers, /ticket, /help, /quit
+plain-unknown = Comando desconocido /{ $name } (prueba /help)
+plain-ready = Ticket de la sala: { $ticket }
+plain-joined = { $name } se unió
+plain-image = { $sender } envió una imagen, { $name }
+plain-file = { $sender } ofreció un archivo, { $name }
+plain-edit = { $sender } editó un mensaje: { $text }
+plain-peers = Pares: { $names }
+gui-name = Tu nombre
+gui-ticket = Ticket
+gui-ticket-hint = Pega un ticket [Ghost:...], o déjalo vacío para abrir una sala nueva
+gui-host = Abrir una sala nueva
+gui-join = Unirse
+gui-failed = No se pudo abrir la sala: { $error }
+gui-joined = Te uniste a { $room }; con Copiar ticket entran otros
+gui-copy-ticket = Copiar ticket
+gui-leave = Salir
+gui-send = Enviar
+gui-input-hint = Escribe un mensaje y pulsa Enter
+gui-destroyed = El anfitrión destruyó esta sala; su historial ya no existe.
+
+## Protocol compatibility
+
+protocol-older = { $name } usa un GhostTerm anterior (protocolo { $version }) y no verá tus mensajes { $types }
+plugins-loaded = Plugins: { $names }
+plugin-failed = Plugin { $path } no cargado: { $err }
+plugin-held = Un plugin retuvo ese mensaje
+script-failed = Script { $path } no cargado: { $err }
+script-error = El script { $name } falló: { $err }
+protocol-newer = { $name } envió un mensaje «{ $kind }» desde un GhostTerm más nuevo (protocolo { $version }, tú tienes { $ours }); ejecuta «ghostterm update» para ver mensajes así
+protocol-unknown-type = { $name } envió un mensaje «{ $kind }» que esta versión no conoce; ejecuta «ghostterm update» para ver mensajes así
+newer-message = (un mensaje de un GhostTerm más nuevo que esta versión no puede mostrar)
+extension-message = (un mensaje «{ $kind }» de una extensión que esta versión no puede mostrar)
+peer-protocol = Protocolo
+peer-protocol-unknown = sin anunciar (cliente antiguo)
+peer-protocol-same = { $version }, igual que el tuyo
+peer-protocol-newer = { $version }, más nuevo que el tuyo ({ $ours }); actualiza para verlo todo
+peer-protocol-behind = { $version }, más antiguo que el tuyo ({ $ours })
+peer-protocol-older = { $version }, más antiguo; no ve tus mensajes { $types }
+peer-client = Cliente
+peer-client-version = GhostTerm { $version }
+peer-client-unknown = no anunciado (cliente antiguo)
+peer-features = Funciones
+peer-features-all = todas las tuyas
+peer-features-lacking = le falta: { $features }
+peer-features-unknown = no anunciado (cliente antiguo)
+
+## First-run setup
+
+setup-welcome = ¡Bienvenido a GhostTerm! Unas preguntas antes del primer chat (puedes cambiarlo todo luego en el archivo de configuración o con «ghostterm setup»).
+setup-name = Tu nombre en los chats
+setup-theme = Esquema de colores ({ $themes })
+setup-theme-unknown = Elige uno de: { $themes }
+setup-keep = ¿Conservar tu identidad entre ejecuciones, para que los pares te reconozcan y verifiquen?
+setup-passphrase = ¿Proteger la identidad con una frase de paso que se pide en cada inicio?
+setup-yes = s
+setup-no = n
+setup-yes-no = Responde { $yes } o { $no }.
+setup-done = Ajustes guardados en { $path }.
+setup-done-ephemeral = Ajustes guardados en { $path }. Cada ejecución usará una identidad desechable nueva; no se guarda nada más.
+
+## Command line
+
+ticket-topic = Tema
+ticket-expires = Caduca
+ticket-expires-never = nunca (los tickets no caducan; la sala dura mientras haya alguien en ella)
+ticket-node-host = Nodo { $n } (anfitrión)
+ticket-node-peer = Nodo { $n } (par)
+ticket-node-id = NodeId
+ticket-relay = Relay
+ticket-relay-none = ninguno
+ticket-direct = Directo
+ticket-no-nodes = Sin nodos: no hay nadie a quien conectarse
+rooms-none = No hay salas guardadas; añade una con `ghostterm rooms add <alias> <ticket>`
+rooms-invalid = ticket no válido
+rooms-saved = { $alias } guardada
+rooms-updated = { $alias } actualizada
+rooms-removed = { $alias } eliminada
+rooms-unknown = no hay ninguna sala guardada `{ $alias }` (ver `ghostterm rooms list`)
+identity-created = Identidad { $id } creada en { $path }
+identity-passphrase = Frase de paso de la identidad
+identity-new-passphrase = Nueva frase de paso de la identidad
+identity-repeat-passphrase = Repite la frase de paso
+identity-empty-passphrase = frase de paso vacía
+identity-mismatch = las frases de paso no coinciden
+identity-wrong-passphrase = frase de paso incorrecta
+identity-exists = { $path } ya existe; usa --force para reemplazarlo
+identity-missing = no hay identidad en { $path }
+daemon-listening = Daemon escuchando en { $addr }
+daemon-sent =
+    { $count ->
+        [one] { $id } enviado a 1 vecino a través del daemon
+       *[other] { $id } enviado a { $count } vecinos a través del daemon
+    }
+daemon-no-peers = no hay pares alcanzables en esta sala
+daemon-error = daemon: { $error }
+daemon-closed = el daemon cerró la conexión
+service-wrote = { $path } escrito
+service-started = Iniciado; `systemctl --user status { $unit }` muestra cómo va.
+update-current = GhostTerm { $version } está al día.
+update-available = GhostTerm { $version } está disponible (esta es { $current }); `ghostterm update` lo instala.
+update-downloading = Descargando GhostTerm { $version } ({ $asset })...
+update-done = Actualizado de { $current } a { $version }.
+name-empty = el nombre está vacío
+name-too-long = los nombres tienen como máximo { $max } caracteres
+name-invisible = los nombres no pueden contener caracteres de control o invisibles
+alert-off = desactivadas
+alert-bell = campana
+alert-flash = destello
+alert-both = campana y destello
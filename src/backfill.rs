@@ -0,0 +1,104 @@
+use chrono::{DateTime, Utc};
+use iroh::NodeId;
+use serde::{Deserialize, Serialize};
+
+use crate::{location::Location, preview::{self, Preview}, rich::{self, Span}, ChatMessage, Forwarded, Message, Origin, Quote};
+
+/// How much of one gossip message a history may fill, leaving room for the envelope.
+const MAX_BYTES: usize = 60 * 1024;
+
+/// One message as passed on to someone who joined after it was sent.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Past {
+    pub id: String,
+    pub from: NodeId,
+    pub sender: String,
+    pub time: DateTime<Utc>,
+    pub text: String,
+    #[serde(default)]
+    pub reply: Option<Quote>,
+    #[serde(default)]
+    pub thread: Option<String>,
+    #[serde(default)]
+    pub preview: Option<Preview>,
+    #[serde(default)]
+    pub location: Option<Location>,
+    #[serde(default)]
+    pub rich: Vec<Span>,
+    #[serde(default)]
+    pub forwarded: Option<Forwarded>,
+}
+
+/// The last `count` text messages in `messages` as a `History`, oldest first, dropping the
+/// oldest until it fits in one gossip message. Images are left out for size, file offers
+/// because they are only good while the sender is online, polls because their votes
+/// don't travel with them, `/tmp` messages because they were never meant to last, and
+/// deleted ones because their senders took them back.
+pub fn history(messages: &[ChatMessage], me: NodeId, count: usize) -> Option<Message> {
+    let mut budget = MAX_BYTES;
+    let mut past: Vec<Past> = messages.iter().rev()
+        .filter(|m| m.origin != Origin::System && m.image.is_none() && m.file.is_none() && m.poll.is_none() && m.expires.is_none() && !m.redacted && !m.id.is_empty())
+        .filter_map(|m| Some(Past {
+            id: m.id.clone(),
+            from: if m.origin == Origin::Me { me } else { m.from? },
+            sender: m.sender.clone(),
+            time: m.time,
+            text: m.text.clone(),
+            reply: m.quote.clone(),
+            thread: m.thread.clone(),
+            preview: m.preview.clone(),
+            location: m.location,
+            rich: m.rich.clone(),
+            forwarded: m.forwarded.clone(),
+        }))
+        .take(count)
+        .take_while(|p| {
+            let size = serde_json::to_vec(p).map_or(usize::MAX, |b| b.len() + 1);
+            let fits = size <= budget;
+            budget = budget.saturating_sub(size);
+            fits
+        })
+        .collect();
+    past.reverse();
+    (!past.is_empty()).then_some(Message::History { messages: past })
+}
+
+/// Slots the messages `relayed_by` sent us into `messages` by time, skipping ids we already
+/// have and senders `keep` turns down. Nothing proves who wrote them, so any claiming to be
+/// ours are dropped and the rest are marked `relayed`. Indices in `anchors` are moved to
+/// follow the messages they point at. Returns the ids added.
+pub fn merge(
+    messages: &mut Vec<ChatMessage>,
+    past: Vec<Past>,
+    me: NodeId,
+    relayed_by: NodeId,
+    keep: impl Fn(&NodeId) -> bool,
+    anchors: &mut [&mut usize],
+) -> Vec<String> {
+    let mut added = Vec::new();
+    for past in past {
+        if past.id.is_empty() || past.from == me || !keep(&past.from) || messages.iter().any(|m| m.id == past.id) {
+            continue;
+        }
+        let at = messages.partition_point(|m| m.time <= past.time);
+        added.push(past.id.clone());
+        messages.insert(at, ChatMessage {
+            quote: past.reply,
+            thread: past.thread,
+            preview: past.preview.and_then(preview::received),
+            location: past.location.and_then(|l| Location::new(l.lat, l.lon)),
+            rich: rich::received(past.rich),
+            forwarded: past.forwarded,
+            // Old news: no read receipts for it.
+            seen: true,
+            relayed: Some(relayed_by),
+            ..ChatMessage::peer(past.id, past.from, past.sender, past.text, past.time)
+        });
+        for anchor in anchors.iter_mut() {
+            if **anchor >= at {
+                **anchor += 1;
+            }
+        }
+    }
+    added
+}
@@ -0,0 +1,107 @@
+use std::str::FromStr;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{prelude::*, widgets::Paragraph};
+
+/// A single key chord from the command line, e.g. `F12` or `Ctrl+B`.
+#[derive(Clone, Copy)]
+pub struct Hotkey {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl Hotkey {
+    pub fn matches(&self, key: &KeyEvent) -> bool {
+        key.code == self.code && key.modifiers.contains(self.modifiers)
+    }
+}
+
+impl FromStr for Hotkey {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = s.trim();
+        loop {
+            let lower = rest.to_ascii_lowercase();
+            if lower.starts_with("ctrl+") {
+                modifiers |= KeyModifiers::CONTROL;
+            } else if lower.starts_with("alt+") {
+                modifiers |= KeyModifiers::ALT;
+            } else {
+                break;
+            }
+            rest = &rest[rest.find('+').unwrap_or(0) + 1..];
+        }
+
+        let code = match rest.strip_prefix(['F', 'f']).and_then(|n| n.parse::<u8>().ok()) {
+            Some(n @ 1..=24) => KeyCode::F(n),
+            _ => {
+                let mut chars = rest.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) if modifiers != KeyModifiers::NONE => KeyCode::Char(c.to_ascii_lowercase()),
+                    _ => return Err(format!("expected F1..F24 or Ctrl/Alt+<key>, got '{}'", s)),
+                }
+            }
+        };
+        Ok(Hotkey { code, modifiers })
+    }
+}
+
+/// The fake shell shown while the chat is hidden.
+pub struct Disguise {
+    typed: String,
+    history: Vec<String>,
+    /// Off when a passphrase unlocks the chat, so it never shows on screen.
+    echo: bool,
+}
+
+impl Disguise {
+    pub fn new(echo: bool) -> Self {
+        Disguise { typed: String::new(), history: Vec::new(), echo }
+    }
+
+    /// Feeds a key to the fake prompt. Returns true when `passphrase` was entered.
+    pub fn key(&mut self, key: KeyEvent, passphrase: Option<&str>) -> bool {
+        match key.code {
+            KeyCode::Char(c) => self.typed.push(c),
+            KeyCode::Backspace => { self.typed.pop(); }
+            KeyCode::Enter => {
+                let line = std::mem::take(&mut self.typed);
+                if passphrase.is_some_and(|p| p == line) {
+                    return true;
+                }
+                self.history.push(if self.echo { line } else { String::new() });
+            }
+            _ => {}
+        }
+        false
+    }
+}
+
+/// `user@host: ~`, which is also what a shell typically puts in the window title.
+pub fn title() -> String {
+    let user = std::env::var("USER").or_else(|_| std::env::var("USERNAME")).unwrap_or_else(|_| "user".to_string());
+    let host = std::fs::read_to_string("/etc/hostname")
+        .ok()
+        .or_else(|| std::env::var("COMPUTERNAME").ok())
+        .unwrap_or_else(|| "localhost".to_string());
+    format!("{}@{}: ~", user, host.trim())
+}
+
+fn prompt() -> String {
+    format!("{}$ ", title().replace(": ", ":"))
+}
+
+/// Fills the whole screen with an idle shell prompt.
+pub fn render(frame: &mut Frame, disguise: &Disguise) {
+    let prompt = prompt();
+    let mut lines: Vec<Line> = disguise.history.iter()
+        .map(|line| Line::raw(format!("{}{}", prompt, line)))
+        .collect();
+    let typed = if disguise.echo { disguise.typed.as_str() } else { "" };
+    lines.push(Line::raw(format!("{}{}█", prompt, typed)));
+
+    let height = frame.area().height as usize;
+    let skip = lines.len().saturating_sub(height);
+    frame.render_widget(Paragraph::new(lines.split_off(skip)), frame.area());
+}
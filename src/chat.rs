@@ -0,0 +1,511 @@
+use std::collections::HashSet;
+
+use chrono::{DateTime, Utc};
+use unicode_width::UnicodeWidthStr;
+use ratatui::{
+    prelude::*,
+    widgets::{Block, BorderType, Borders, Clear, Padding, Paragraph, Wrap},
+};
+
+use crate::{centered, clock, expiry, files, i18n::t, markup, mouse, reactions, rich, rooms::Room, voice, AppState, ChatMessage, Origin, Quote};
+
+/// How tightly the history is laid out; F3 cycles through them.
+#[derive(Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum Density {
+    /// IRC style: everything left-aligned with `[time] name:` inline, no padding.
+    Compact,
+    Normal,
+    /// Consecutive messages from one sender grouped under a single name, groups spaced apart.
+    Cozy,
+}
+
+impl Density {
+    pub fn next(self) -> Self {
+        match self {
+            Density::Compact => Density::Normal,
+            Density::Normal => Density::Cozy,
+            Density::Cozy => Density::Compact,
+        }
+    }
+
+    pub fn name(self) -> String {
+        match self {
+            Density::Compact => t!("density-compact"),
+            Density::Normal => t!("density-normal"),
+            Density::Cozy => t!("density-cozy"),
+        }
+    }
+}
+
+/// How message times are shown.
+#[derive(Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum TimeStyle {
+    Clock,
+    Relative,
+}
+
+/// A message's time in `style`. Relative times are recomputed on every draw, so they stay fresh.
+pub fn stamp(time: &DateTime<Utc>, style: TimeStyle) -> String {
+    if style == TimeStyle::Clock {
+        return clock::show(*time).format("%H:%M").to_string();
+    }
+    // A sender's clock running ahead of ours shows as "just now", not in the future.
+    match (Utc::now() - *time).num_seconds().max(0) {
+        s if s < 45 => t!("just-now"),
+        s if s < 3600 => t!("ago-minutes", n = (s + 30) / 60),
+        s if s < 86400 => t!("ago-hours", n = s / 3600),
+        _ => clock::show(*time).format("%b %e").to_string(),
+    }
+}
+
+/// Whether `b` continues the run of messages `a` belongs to.
+fn same_group(a: &ChatMessage, b: &ChatMessage) -> bool {
+    a.origin != Origin::System && a.origin == b.origin && a.sender == b.sender
+}
+
+/// Renders one room's history, newest at the bottom, honouring its scroll offset.
+/// Search and selection only apply to the focused (active) room.
+pub fn render(frame: &mut Frame, area: Rect, state: &AppState, room: usize) {
+    let available_height = area.height as usize;
+    let focused = room == state.active;
+    let room = &state.rooms[room];
+    let visible = if focused {
+        state.chat_height.set(available_height);
+        state.visible()
+    } else {
+        room.view()
+    };
+    let end = visible.len().saturating_sub(room.scroll);
+
+    let mut names: Vec<&str> = room.peer_names.values().map(String::as_str).collect();
+    names.push(&state.my_name);
+
+    // Walk backwards from the bottom until the pane is full, then flip.
+    let mut rendered = Vec::new();
+    let mut used = 0;
+    let cozy = state.density == Density::Cozy;
+    let padding = if state.density == Density::Compact { 0 } else { 2 };
+    let width = area.width.saturating_sub(2 * padding) as usize;
+    for (pos, &i) in visible[..end].iter().enumerate().rev() {
+        if used >= available_height {
+            break;
+        }
+        let msg = &room.messages[i];
+        let grouped = cozy && pos > 0 && same_group(&room.messages[visible[pos - 1]], msg);
+        let mut lines = match msg.announcement {
+            true => banner_lines(state, room, msg, focused && state.selected == Some(i), width),
+            false => message_lines(state, room, focused, i, &names, grouped),
+        };
+        // The gap goes below a group so an image caption stays on the message's first row.
+        if cozy && visible.get(pos + 1).is_some_and(|&n| !same_group(msg, &room.messages[n])) {
+            lines.push(Line::raw(""));
+        }
+        used += lines.len();
+        rendered.push((i, lines));
+    }
+    rendered.reverse();
+
+    // The oldest message may only partly fit; its image is skipped rather than clipped.
+    let mut overflow = used.saturating_sub(available_height);
+    let mut chat_lines = Vec::new();
+    let mut row_messages = Vec::new();
+    let mut previews = Vec::new();
+    let mut shown = Vec::new();
+    for (i, lines) in rendered {
+        let skip = overflow.min(lines.len());
+        overflow -= skip;
+        if skip == 0 {
+            if let Some(preview) = room.messages[i].image.as_ref().and_then(|img| img.preview.as_ref()) {
+                previews.push((chat_lines.len() + 1, i, preview));
+            }
+        }
+        if skip < lines.len() {
+            shown.push(i);
+        }
+        row_messages.resize(row_messages.len() + lines.len() - skip, i);
+        chat_lines.extend(lines.into_iter().skip(skip));
+    }
+
+    let chat_area = Paragraph::new(chat_lines)
+        .block(Block::default().padding(Padding::new(padding, padding, 0, 0)));
+
+    frame.render_widget(chat_area, area);
+
+    // Keep what the focused pane shows, so clicks and drags can find their message and text.
+    if focused {
+        let buf = frame.buffer_mut();
+        let rows = (0..area.height)
+            .map(|dy| {
+                let cells = (area.x..area.right()).map(|x| buf[(x, area.y + dy)].symbol().to_string()).collect();
+                (row_messages.get(dy as usize).copied(), cells)
+            })
+            .collect();
+        *state.chat_rows.borrow_mut() = rows;
+        *state.shown.borrow_mut() = shown;
+    }
+
+    for (row, i, preview) in previews {
+        let size = preview.size();
+        let x = match room.messages[i].origin {
+            _ if state.density == Density::Compact => area.x + 2,
+            Origin::Me => area.right().saturating_sub(size.width + 2),
+            _ => area.x + 4,
+        };
+        let rect = Rect::new(x, area.y + row as u16, size.width, size.height).intersection(area);
+        frame.render_widget(ratatui_image::Image::new(preview), rect);
+    }
+
+    let below = room.new_below();
+    if below > 0 {
+        let text = format!(" ↓ {} ", t!("new-below", count = below));
+        let width = (text.chars().count() as u16).min(area.width);
+        let pill = Rect::new(area.x + (area.width - width) / 2, area.bottom().saturating_sub(1), width, 1);
+        frame.render_widget(Clear, pill);
+        frame.render_widget(Line::styled(text, Style::default().fg(Color::Black).bg(Color::Cyan).add_modifier(Modifier::BOLD)), pill);
+    }
+
+    if let Some(drag) = state.drag.filter(|_| focused) {
+        let buf = frame.buffer_mut();
+        for (y, from, to) in mouse::span(drag, area) {
+            for x in from..to.min(area.width) {
+                buf[(area.x + x, area.y + y)].modifier.insert(Modifier::REVERSED);
+            }
+        }
+    }
+}
+
+/// The thread panel's title: whose message it hangs off, and how to get back.
+pub fn thread_title(room: &Room) -> String {
+    let root = room.thread.as_ref().and_then(|id| room.messages.iter().find(|m| m.id == *id));
+    match root {
+        Some(msg) => {
+            let quote = Quote::of(msg);
+            t!("thread-title", sender = quote.sender, snippet = quote.snippet)
+        }
+        None => t!("thread-title-unknown"),
+    }
+}
+
+/// The line under a file: how fetching it is going, or for a voice message playing, how far it has got.
+pub fn file_status(state: &AppState, msg: &ChatMessage, file: &files::Offer) -> String {
+    match &state.playing {
+        Some((id, playback)) if *id == msg.id => {
+            let (done, total) = playback.progress();
+            format!("▶ {} {} / {}", files::bar(done.into(), total.into()), voice::clock(done), voice::clock(total))
+        }
+        _ => file.status(),
+    }
+}
+
+/// Every screen line of one message: Markdown blocks, then mentions, links and search hits on top.
+/// `grouped` (cozy mode) drops the sender name when the previous message was theirs too.
+fn message_lines(state: &AppState, room: &Room, focused: bool, index: usize, names: &[&str], grouped: bool) -> Vec<Line<'static>> {
+    let msg = &room.messages[index];
+    let search = state.search.as_ref().filter(|_| focused);
+    let term = search.map(|s| s.term()).unwrap_or_default();
+    let current = search.and_then(|s| s.current);
+    let hit = Style::default().fg(Color::Black).bg(Color::Yellow);
+    let hit = if current == Some(index) { hit.add_modifier(Modifier::BOLD) } else { hit.bg(Color::DarkGray) };
+
+    let (base, alignment) = match msg.origin {
+        Origin::System => (Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC), Alignment::Center),
+        Origin::Me => (Style::default().fg(Color::White), Alignment::Right),
+        Origin::Peer => (Style::default().fg(Color::Gray), Alignment::Left),
+    };
+    let compact = state.density == Density::Compact;
+    let alignment = if compact { Alignment::Left } else { alignment };
+
+    let blocks = if let Some(image) = &msg.image {
+        // The caption line, plus blank rows the graphic is drawn over after the text pass.
+        let caption = match &image.preview {
+            Some(_) => format!("[{}]", t!("image-caption", name = image.name.as_str())),
+            None => format!("[{}]", t!("image-caption-open", name = image.name.as_str())),
+        };
+        let mut blocks = vec![markup::Block::Text(markup::Styled { text: caption, styles: Vec::new() })];
+        let rows = image.preview.as_ref().map_or(0, |p| p.size().height);
+        blocks.extend((0..rows).map(|_| markup::Block::Text(markup::Styled { text: String::new(), styles: Vec::new() })));
+        if let Some(original) = &msg.file {
+            let text = format!("{} · {}", files::size(original.size), file_status(state, msg, original));
+            blocks.push(markup::Block::Text(markup::Styled { text, styles: Vec::new() }));
+        }
+        blocks
+    } else if let Some(file) = &msg.file {
+        let title = match file.voice {
+            Some(ms) => format!("🎤 {} · {}", t!("voice-clip", length = voice::clock(ms)), files::size(file.size)),
+            None => format!("📎 {} · {}", file.name, files::size(file.size)),
+        };
+        [title, file_status(state, msg, file)].into_iter()
+            .map(|text| markup::Block::Text(markup::Styled { text, styles: Vec::new() }))
+            .collect()
+    } else if let Some(place) = &msg.location {
+        let title = match msg.text.is_empty() {
+            true => format!("📍 {}", t!("location-shared")),
+            false => format!("📍 {}", msg.text),
+        };
+        [title, format!("{} · {}", place.plus_code(), place.coordinates()), place.osm()].into_iter()
+            .map(|text| markup::Block::Text(markup::Styled { text, styles: Vec::new() }))
+            .collect()
+    } else if let Some(poll) = &msg.poll {
+        let mut lines = vec![format!("📊 {}", msg.text)];
+        lines.extend(poll.lines(&state.node_id));
+        lines.push(poll.status());
+        lines.into_iter().map(|text| markup::Block::Text(markup::Styled { text, styles: Vec::new() })).collect()
+    } else if msg.origin == Origin::System {
+        // System notices are ours, so they skip Markdown.
+        vec![markup::Block::Text(markup::Styled { text: msg.text.clone(), styles: Vec::new() })]
+    } else if !msg.rich.is_empty() {
+        rich::blocks(&msg.rich, state.node_id)
+    } else {
+        markup::markdown(&msg.text)
+    };
+    // Without spans from the sender, mentions and links are picked out of the text here.
+    let guess = msg.rich.is_empty();
+
+    let mut lines: Vec<Line<'static>> = Vec::new();
+    for block in blocks {
+        match block {
+            markup::Block::Text(styled) => {
+                let mut overlays = styled.styles;
+                if guess {
+                    overlays.extend(markup::mentions(&styled.text, names).into_iter().map(|(range, name)| {
+                        let color = if name == state.my_name { Color::Yellow } else { Color::Cyan };
+                        (range, Style::default().fg(color).add_modifier(Modifier::BOLD))
+                    }));
+                    overlays.extend(markup::urls(&styled.text).into_iter()
+                        .map(|range| (range, Style::default().fg(Color::LightBlue).add_modifier(Modifier::UNDERLINED))));
+                }
+                overlays.extend(markup::find_all(&styled.text, &term).into_iter().map(|range| (range, hit)));
+
+                let spans = markup::paint(&styled.text, base, &overlays).into_iter()
+                    .map(|span| Span::styled(span.content.into_owned(), span.style))
+                    .collect::<Vec<_>>();
+                lines.push(Line::from(spans));
+            }
+            markup::Block::Code(code) => {
+                // Pad to a common width so the background reads as one block.
+                let width = code.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+                for line in code {
+                    lines.push(Line::styled(format!(" {:<width$} ", line, width = width), markup::code_style()));
+                }
+            }
+        }
+    }
+
+    let time = stamp(&msg.time, state.time_style);
+    let sender = room.sender(msg);
+    if compact {
+        decorate_compact(msg, &sender, &time, &mut lines);
+    } else {
+        decorate(msg, &sender, &time, &mut lines, grouped);
+    }
+    if let Some(first) = lines.first_mut().filter(|_| msg.urgent) {
+        first.spans.insert(0, Span::styled(format!("‼ {} ", t!("urgent")), Style::default().fg(Color::LightRed).add_modifier(Modifier::BOLD)));
+    }
+    if let Some(quote) = &msg.quote {
+        let style = Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC);
+        let indent = if msg.origin == Origin::Peer || compact { "  " } else { "" };
+        lines.insert(0, Line::from(vec![
+            Span::raw(indent),
+            Span::styled(format!("│ {}: {}", quote.sender, quote.snippet), style),
+        ]));
+    }
+    if let Some(forwarded) = &msg.forwarded {
+        let style = Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC);
+        let indent = if msg.origin == Origin::Peer || compact { "  " } else { "" };
+        lines.insert(0, Line::from(vec![
+            Span::raw(indent),
+            Span::styled(format!("↪ {}", t!("forwarded-from", sender = forwarded.sender.as_str(), room = forwarded.room.as_str())), style),
+        ]));
+    }
+    if let Some(preview) = &msg.preview {
+        let indent = if msg.origin == Origin::Peer || compact { "  " } else { "" };
+        let bar = Style::default().fg(Color::LightBlue);
+        let mut card = vec![(preview.title.clone(), Style::default().fg(Color::White).add_modifier(Modifier::BOLD))];
+        if !preview.description.is_empty() {
+            card.push((preview.description.clone(), Style::default().fg(Color::Gray)));
+        }
+        card.push((preview.site().to_string(), Style::default().fg(Color::DarkGray)));
+        for (text, style) in card {
+            lines.push(Line::from(vec![Span::raw(indent), Span::styled("┃ ", bar), Span::styled(text, style)]));
+        }
+    }
+    if !msg.reactions.is_empty() {
+        let mut counts = reactions::line(&msg.reactions, &state.node_id);
+        if msg.origin == Origin::Peer || compact {
+            counts.spans.insert(0, Span::raw("  "));
+        }
+        lines.push(counts);
+    }
+    let replies = if room.thread.is_none() { room.replies(&msg.id) } else { 0 };
+    if replies > 0 {
+        let indent = if msg.origin == Origin::Peer || compact { "  " } else { "" };
+        lines.push(Line::from(vec![
+            Span::raw(indent),
+            Span::styled(format!("💬 {}", t!("thread-replies", count = replies)), Style::default().fg(Color::Cyan)),
+        ]));
+    }
+    let mut lines: Vec<Line<'static>> = lines.into_iter().map(|line| line.alignment(alignment)).collect();
+
+    if focused && state.selected == Some(index) {
+        lines = lines.into_iter().map(|l| l.patch_style(Style::default().bg(Color::Indexed(237)))).collect();
+    } else if msg.urgent {
+        lines = lines.into_iter().map(|l| l.patch_style(Style::default().bg(Color::Indexed(52)))).collect();
+    } else if msg.mentions_me {
+        lines = lines.into_iter().map(|l| l.patch_style(Style::default().bg(Color::Indexed(53)))).collect();
+    }
+    lines
+}
+
+/// An `/announce`: who put it up and when, then its text, centered on a band across the pane.
+fn banner_lines(state: &AppState, room: &Room, msg: &ChatMessage, selected: bool, width: usize) -> Vec<Line<'static>> {
+    let band = Style::default().fg(Color::Black).bg(if selected { Color::LightYellow } else { Color::Yellow });
+    let title = format!("📢 {}  [{}]", t!("announcement-from", sender = room.sender(msg)), stamp(&msg.time, state.time_style));
+    let mut lines = vec![Line::styled(band_text(&title, width), band.add_modifier(Modifier::BOLD))];
+    lines.extend(msg.text.lines().map(|line| Line::styled(band_text(line, width), band)));
+    lines
+}
+
+/// `text` centered in `width` columns, padded with spaces on both sides so the band is full.
+fn band_text(text: &str, width: usize) -> String {
+    let left = width.saturating_sub(text.width()) / 2;
+    let right = width.saturating_sub(text.width() + left);
+    format!("{}{}{}", " ".repeat(left), text, " ".repeat(right))
+}
+
+/// What follows the time: whether the message was edited or only relayed to us, how many
+/// copies of it came, and how long it has left.
+pub fn marks(msg: &ChatMessage) -> String {
+    let mut marks = if msg.edited { format!(" {}", t!("edited")) } else { String::new() };
+    if msg.relayed.is_some() {
+        marks.push_str(&format!(" {}", t!("relayed")));
+    }
+    if msg.repeats > 0 {
+        marks.push_str(&format!(" ×{}", msg.repeats + 1));
+    }
+    if let Some(expires) = msg.expires {
+        marks.push_str(&format!(" ⏳{}", expiry::left(expires)));
+    }
+    marks
+}
+
+/// Adds the sender prefix and timestamp around the first line, plus a hanging indent for the rest.
+fn decorate(msg: &ChatMessage, sender: &str, time: &str, lines: &mut [Line<'static>], grouped: bool) {
+    let edited = marks(msg);
+    let time = Span::styled(format!("  [{}]{}", time, edited), Style::default().fg(Color::DarkGray));
+    match msg.origin {
+        Origin::System => {}
+        Origin::Me => {
+            if let Some(first) = lines.first_mut() {
+                first.spans.push(time);
+                first.spans.extend(msg.receipts.mark());
+            }
+        }
+        Origin::Peer => {
+            for line in lines.iter_mut().skip(1) {
+                line.spans.insert(0, Span::raw("  "));
+            }
+            if let Some(first) = lines.first_mut() {
+                if grouped {
+                    first.spans.insert(0, Span::raw("  "));
+                } else {
+                    first.spans.insert(0, Span::styled(sender.to_string(), Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)));
+                    first.spans.insert(1, Span::raw(": "));
+                }
+                first.spans.push(time);
+            }
+        }
+    }
+}
+
+/// Compact mode: `[time] name: ` in front of the first line, continuation lines indented.
+fn decorate_compact(msg: &ChatMessage, sender: &str, time: &str, lines: &mut [Line<'static>]) {
+    let edited = marks(msg);
+    let mut prefix = vec![Span::styled(format!("[{}] ", time), Style::default().fg(Color::DarkGray))];
+    match msg.origin {
+        Origin::System => prefix.push(Span::raw("* ")),
+        Origin::Me => prefix.push(Span::styled(format!("{}: ", sender), Style::default().fg(Color::White).add_modifier(Modifier::BOLD))),
+        Origin::Peer => prefix.push(Span::styled(format!("{}: ", sender), Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD))),
+    }
+    for line in lines.iter_mut().skip(1) {
+        line.spans.insert(0, Span::raw("  "));
+    }
+    if let Some(first) = lines.first_mut() {
+        first.spans.splice(0..0, prefix);
+        first.spans.push(Span::styled(edited, Style::default().fg(Color::DarkGray)));
+        if msg.origin == Origin::Me {
+            first.spans.extend(msg.receipts.mark());
+        }
+    }
+}
+
+/// The `i` popup in selection mode: everything we know about one message.
+pub fn render_info(frame: &mut Frame, state: &AppState, index: usize) {
+    let key = Style::default().fg(Color::White).add_modifier(Modifier::BOLD);
+    let dim = Style::default().fg(Color::Gray);
+    let room = state.room();
+    let msg = &room.messages[index];
+
+    let from = match (&msg.origin, msg.from) {
+        (Origin::Me, _) => t!("you-suffix", name = state.my_name.as_str()),
+        (Origin::System, _) => "GhostTerm".to_string(),
+        (Origin::Peer, Some(id)) => format!("{} · {}", msg.sender, id.fmt_short()),
+        (Origin::Peer, None) => msg.sender.clone(),
+    };
+    let mut rows = vec![
+        (t!("info-from"), from),
+        (t!("info-sent"), format!("{} ({})", clock::show(msg.time).format("%Y-%m-%d %H:%M:%S %:z"), stamp(&msg.time, TimeStyle::Relative))),
+        (t!("info-id"), if msg.id.is_empty() { t!("info-id-none") } else { msg.id.clone() }),
+    ];
+    if msg.edited {
+        rows.push((t!("info-edited"), t!("yes")));
+    }
+    if let Some(quote) = &msg.quote {
+        rows.push((t!("info-reply-to"), format!("{}: {}", quote.sender, quote.snippet)));
+    }
+    if let Some(forwarded) = &msg.forwarded {
+        let sent = clock::show(forwarded.time).format("%Y-%m-%d %H:%M:%S %:z");
+        rows.push((t!("info-forwarded"), format!("{} · {} · {}", forwarded.sender, forwarded.room, sent)));
+    }
+    if let Some(image) = &msg.image {
+        rows.push((t!("info-image"), format!("{} ({} KiB)", image.name, image.data.len().div_ceil(1024))));
+    }
+    if let Some(file) = &msg.file {
+        rows.push((t!("info-file"), format!("{} ({})", file.name, files::size(file.size))));
+        rows.push((t!("info-hash"), file.hash.to_string()));
+    }
+    let names = |ids: &HashSet<iroh::NodeId>| ids.iter()
+        .map(|id| if *id == state.node_id { t!("you") } else { room.peer_names.get(id).cloned().unwrap_or_else(|| t!("unknown-peer")) })
+        .collect::<Vec<_>>()
+        .join(", ");
+    for r in &msg.reactions {
+        rows.push((t!("info-reaction"), format!("{} {}", r.emoji, names(&r.from))));
+    }
+    if let Some(id) = msg.relayed {
+        let name = room.peer_names.get(&id).cloned().unwrap_or_else(|| t!("unknown-peer"));
+        rows.push((t!("info-relayed"), format!("{} · {}", name, id.fmt_short())));
+    }
+    if !msg.receipts.delivered.is_empty() {
+        rows.push((t!("info-delivered"), names(&msg.receipts.delivered)));
+    }
+    if !msg.receipts.read.is_empty() {
+        rows.push((t!("info-read"), names(&msg.receipts.read)));
+    }
+
+    let lines: Vec<Line> = rows.into_iter()
+        .map(|(label, value)| Line::from(vec![Span::styled(format!("{:<10}", label), key), Span::styled(value, dim)]))
+        .collect();
+
+    let area = centered(frame.area(), 64, lines.len() as u16 + 4);
+    let popup = Paragraph::new(lines)
+        .wrap(Wrap { trim: false })
+        .block(Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .border_style(Style::default().fg(Color::Cyan))
+            .title(format!(" {} ", t!("info-title")))
+            .title_bottom(Line::from(format!(" {} ", t!("esc-to-close"))).right_aligned())
+            .padding(Padding::new(1, 1, 1, 0)));
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(popup, area);
+}
@@ -0,0 +1,91 @@
+use std::{borrow::Cow, collections::HashMap, time::{Duration, Instant}};
+
+use bincode::Options;
+use serde::{Deserialize, Serialize};
+
+/// The most one gossip message carries, leaving room for gossip's own framing under the
+/// 64 KiB it's built with; anything bigger goes out in parts this size.
+const CHUNK: usize = 60 * 1024;
+const MAX_PARTS: usize = 16;
+/// The biggest message we send or put back together.
+pub const MAX_MESSAGE: usize = CHUNK * MAX_PARTS;
+/// The longest text we let a message have, leaving room for a reply quote and ids.
+pub const MAX_TEXT: usize = MAX_MESSAGE - 4 * 1024;
+/// How long the rest of a message may take to arrive before what came is thrown away.
+const TIMEOUT: Duration = Duration::from_secs(30);
+/// Messages being put back together at once; one more drops the oldest.
+const MAX_PENDING: usize = 8;
+/// Starts every part; whole messages start with `{` or `protocol::MAGIC`.
+const MAGIC: &[u8; 2] = b"GP";
+
+/// Part `index` of `count` of message `id`, a random number the sender picked for it.
+#[derive(Serialize, Deserialize)]
+struct Part {
+    id: u64,
+    index: u16,
+    count: u16,
+    data: Vec<u8>,
+}
+
+fn binary() -> impl Options {
+    bincode::DefaultOptions::new().with_limit(CHUNK as u64 + 64)
+}
+
+/// `bytes` as it goes on gossip: as is if it fits in one message, otherwise in parts.
+/// `None` if it's bigger than `MAX_MESSAGE`.
+pub fn split(bytes: Vec<u8>) -> Option<Vec<Vec<u8>>> {
+    if bytes.len() <= CHUNK {
+        return Some(vec![bytes]);
+    }
+    if bytes.len() > MAX_MESSAGE {
+        return None;
+    }
+    let id = rand::random();
+    let count = bytes.len().div_ceil(CHUNK) as u16;
+    bytes.chunks(CHUNK).enumerate().map(|(index, data)| {
+        let mut frame = MAGIC.to_vec();
+        let part = Part { id, index: index as u16, count, data: data.to_vec() };
+        binary().serialize_into(&mut frame, &part).ok()?;
+        Some(frame)
+    }).collect()
+}
+
+struct Partial {
+    parts: Vec<Option<Vec<u8>>>,
+    started: Instant,
+}
+
+/// The parts of messages still coming in one room.
+#[derive(Default)]
+pub struct Chunks {
+    pending: HashMap<u64, Partial>,
+}
+
+impl Chunks {
+    /// The message `raw` finishes: `raw` itself unless it's a part, the whole message once
+    /// its last part is in, and `None` while parts are missing or for parts that make no sense.
+    pub fn receive<'a>(&mut self, raw: &'a [u8]) -> Option<Cow<'a, [u8]>> {
+        let Some(frame) = raw.strip_prefix(MAGIC) else { return Some(Cow::Borrowed(raw)) };
+        let part: Part = binary().deserialize(frame).ok()?;
+        let count = part.count as usize;
+        if !(2..=MAX_PARTS).contains(&count) || part.index as usize >= count || part.data.len() > CHUNK {
+            return None;
+        }
+        self.pending.retain(|_, partial| partial.started.elapsed() < TIMEOUT);
+        if !self.pending.contains_key(&part.id) && self.pending.len() >= MAX_PENDING {
+            let oldest = self.pending.iter().min_by_key(|(_, partial)| partial.started).map(|(id, _)| *id)?;
+            self.pending.remove(&oldest);
+        }
+        let partial = self.pending.entry(part.id)
+            .or_insert_with(|| Partial { parts: vec![None; count], started: Instant::now() });
+        if partial.parts.len() != count {
+            return None;
+        }
+        partial.parts[part.index as usize] = Some(part.data);
+        if partial.parts.iter().any(Option::is_none) {
+            return None;
+        }
+        let partial = self.pending.remove(&part.id)?;
+        Some(Cow::Owned(partial.parts.into_iter().flatten().flatten().collect()))
+    }
+}
@@ -0,0 +1,387 @@
+//! A session without any interface: host or join one room, post to it, and read what
+//! happens there as typed events.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use anyhow::{bail, Context as _, Result};
+use futures_lite::Stream;
+use serde_json::json;
+use iroh::{protocol::Router, Endpoint, NodeId, RelayMap, RelayMode, RelayUrl, SecretKey};
+use iroh_blobs::store::GcConfig;
+use iroh_gossip::{net::{Gossip, GossipEvent}, proto::TopicId};
+use tokio::sync::mpsc;
+
+use crate::{broadcast, chunks, clock, files, location, new_id, notify, presence, protocol, rich, rooms, transport::{self, Transport}, Forwarded, Message, Quote};
+
+/// How to reach the network. The default is a new identity, n0's relays, and both DNS
+/// and local-network discovery.
+#[derive(Clone, Default)]
+pub struct Options {
+    /// Who we are; a new key when left out.
+    pub secret_key: Option<SecretKey>,
+    /// A relay server to use instead of the public n0 ones.
+    pub relay: Option<RelayUrl>,
+    /// Never relay traffic; only direct connections will work.
+    pub no_relay: bool,
+    pub no_local_discovery: bool,
+    pub no_dns_discovery: bool,
+    /// Where to keep the files being sent and fetched, each run in a directory of its own;
+    /// the system's temporary directory when left out.
+    pub blob_dir: Option<PathBuf>,
+}
+
+/// How often the blob store drops what no tag holds on to.
+const BLOB_GC_EVERY: Duration = Duration::from_secs(60);
+
+/// The endpoint and what runs on it: gossip for rooms, blobs for files.
+pub struct Network {
+    pub endpoint: Endpoint,
+    pub gossip: Gossip,
+    pub blobs: iroh_blobs::net_protocol::Blobs<iroh_blobs::store::fs::Store>,
+    pub router: Router,
+    /// Where `blobs` keeps its data, until this is dropped.
+    pub blob_dir: files::BlobDir,
+}
+
+impl Network {
+    /// Binds an endpoint as `options` say and starts accepting gossip and blob transfers.
+    pub async fn bind(options: Options) -> Result<Self> {
+        let mut builder = Endpoint::builder();
+        if let Some(key) = options.secret_key {
+            builder = builder.secret_key(key);
+        }
+        if !options.no_dns_discovery {
+            builder = builder.discovery_n0();
+        }
+        if !options.no_local_discovery {
+            builder = builder.discovery_local_network();
+        }
+        if options.no_relay {
+            builder = builder.relay_mode(RelayMode::Disabled);
+        } else if let Some(url) = options.relay {
+            builder = builder.relay_mode(RelayMode::Custom(RelayMap::from_url(url)));
+        }
+        let endpoint = builder.bind().await?;
+        // Room for inline image thumbnails on top of plain text.
+        let gossip = Gossip::builder().max_message_size(64 * 1024).spawn(endpoint.clone()).await?;
+        // Files go over their own QUIC streams; gossip only carries the offer. They're kept on
+        // disk, not in memory, and dropped once nothing tags them.
+        let parent = options.blob_dir.unwrap_or_else(|| std::env::temp_dir().join("ghost-blobs"));
+        let blob_dir = files::BlobDir::create(&parent).with_context(|| format!("creating a blob store in {}", parent.display()))?;
+        let blobs = iroh_blobs::net_protocol::Blobs::persistent(blob_dir.path()).await?.build(&endpoint);
+        blobs.start_gc(GcConfig { period: BLOB_GC_EVERY, done_callback: None })?;
+        let router = Router::builder(endpoint.clone())
+            .accept(iroh_gossip::ALPN, gossip.clone())
+            .accept(iroh_blobs::ALPN, blobs.clone())
+            .spawn()
+            .await?;
+        Ok(Network { endpoint, gossip, blobs, router, blob_dir })
+    }
+
+    /// Rooms over this endpoint's gossip.
+    pub fn transport(&self) -> transport::Iroh {
+        transport::Iroh::new(&self.endpoint, &self.gossip)
+    }
+}
+
+/// Something that happened in the room.
+#[derive(Debug)]
+pub enum ChatEvent {
+    /// A text message, or an `/announce` from the host or a moderator.
+    Message {
+        id: String,
+        from: NodeId,
+        sender: String,
+        text: String,
+        /// Its sender's formatting; empty from clients that only send `text`.
+        rich: Vec<rich::Span>,
+        /// The message it replies to, as its sender quoted it.
+        reply: Option<Quote>,
+        /// The id of the message whose thread it was posted in.
+        thread: Option<String>,
+        /// Rings through a muted room; only once per `rooms::URGENT_EVERY` from the same sender.
+        urgent: bool,
+        announcement: bool,
+        /// Seconds it lasts, as sent with `/tmp`; forget it once they're up.
+        ttl: Option<u32>,
+        forwarded: Option<Forwarded>,
+        /// It @mentions us, by name or by key.
+        mentions_me: bool,
+    },
+    /// `id` here and below is the envelope's; `target` the message it's about.
+    Edit { id: String, target: String, from: NodeId, text: String, rich: Vec<rich::Span> },
+    Reaction { id: String, target: String, from: NodeId, emoji: String, remove: bool },
+    /// Message `target` deleted for everyone. Sent for anyone's message: it only counts from
+    /// its sender, or when `moderator` is set.
+    Redact { id: String, target: String, from: NodeId, moderator: bool },
+    /// Peers' delivery and read receipts for our messages.
+    Receipt { targets: Vec<String>, from: NodeId, read: bool },
+    /// A peer said who they are, for the first time (`old` is `None`) or under a new name.
+    Peer { node: NodeId, name: String, old: Option<String> },
+    /// A peer's client can't show some of the message types ours sends, going by what it
+    /// said it speaks.
+    Outdated { node: NodeId, info: protocol::Info },
+    /// A peer went away, got busy or came back, with what they said about it.
+    Presence { node: NodeId, status: presence::Status },
+    NeighborUp(NodeId),
+    NeighborDown(NodeId),
+    /// A message type only a newer version can read.
+    Unsupported { from: NodeId, kind: String, version: u32 },
+    /// The messages `node` numbered `first` to `last` never came; they've been asked for
+    /// again, and any that do come arrive as usual.
+    Gap { node: NodeId, first: u64, last: u64 },
+    /// `node` went over the flood limit; what it posts is dropped for a while.
+    Muted(NodeId),
+    /// The host or a moderator put `node` out of the room, for ten minutes with `kick`;
+    /// `name` is what it went by here.
+    Banned { node: NodeId, name: Option<String>, kick: bool },
+    /// The host set the room's name and topic line, or a moderator the topic line; they were `old`.
+    Info { info: rooms::Info, old: rooms::Info },
+    /// Someone joined a room we host with its ticket, naming the relay they're reachable on
+    /// if it looks like a host name.
+    Redeemed { node: NodeId, relay: Option<String> },
+    /// The host destroyed the room; nothing more will come.
+    Destroyed,
+    /// Anything else, as it came: pictures, files, polls, votes, pins, roles and the mode.
+    /// Roles and the mode have been applied by the time it's seen; polls, locations and link
+    /// previews that don't make sense never get here.
+    Other { id: String, from: NodeId, sender: String, message: Message },
+    /// Something went wrong without ending the room: events lost to lag, or a message
+    /// nobody could read.
+    Error(String),
+}
+
+impl ChatEvent {
+    /// The event as `pipe` prints it from `room`, for bindings and gateways that speak JSON.
+    /// Message types `pipe` doesn't report are named by their type only.
+    pub fn to_json(&self, room: TopicId) -> serde_json::Value {
+        let room = room.to_string();
+        let time = clock::now().to_rfc3339();
+        match self {
+            ChatEvent::Message { id, from, sender, text, rich, reply, thread, urgent, announcement, ttl, forwarded, .. } => json!({
+                "event": if *announcement { "announcement" } else { "message" }, "room": room, "id": id, "from": from.to_string(),
+                "sender": sender, "text": text, "time": time, "reply": reply.as_ref().map(|q| &q.id), "thread": thread,
+                "urgent": urgent, "rich": rich, "ttl": ttl, "forwarded": forwarded,
+            }),
+            ChatEvent::Edit { id, target, from, text, rich } => json!({
+                "event": "edit", "room": room, "id": id, "target": target, "from": from.to_string(), "text": text, "rich": rich,
+            }),
+            ChatEvent::Reaction { id, target, from, emoji, remove } => json!({
+                "event": "reaction", "room": room, "id": id, "target": target, "from": from.to_string(), "emoji": emoji, "remove": remove,
+            }),
+            ChatEvent::Redact { id, target, from, moderator } => json!({
+                "event": "redact", "room": room, "id": id, "target": target, "from": from.to_string(), "moderator": moderator,
+            }),
+            ChatEvent::Receipt { targets, from, read } => json!({
+                "event": "receipt", "room": room, "targets": targets, "from": from.to_string(), "read": read,
+            }),
+            ChatEvent::Peer { node, name, old } => json!({ "event": "peer", "room": room, "node": node.to_string(), "name": name, "old": old }),
+            ChatEvent::Outdated { node, info } => json!({
+                "event": "outdated", "room": room, "node": node.to_string(), "version": info.version, "missing": info.missing(),
+            }),
+            ChatEvent::Presence { node, status } => json!({
+                "event": "status", "room": room, "node": node.to_string(), "status": status.presence, "text": status.text,
+            }),
+            ChatEvent::NeighborUp(node) => json!({ "event": "neighbor_up", "room": room, "node": node.to_string() }),
+            ChatEvent::NeighborDown(node) => json!({ "event": "neighbor_down", "room": room, "node": node.to_string() }),
+            ChatEvent::Unsupported { from, kind, version } => json!({
+                "event": "unsupported", "room": room, "from": from.to_string(), "type": kind, "version": version,
+            }),
+            ChatEvent::Gap { node, first, last } => json!({ "event": "gap", "room": room, "from": node.to_string(), "first": first, "last": last }),
+            ChatEvent::Muted(node) => json!({ "event": "muted", "room": room, "node": node.to_string() }),
+            ChatEvent::Banned { node, kick, .. } => json!({ "event": "ban", "room": room, "node": node.to_string(), "kick": kick }),
+            ChatEvent::Info { info, .. } => json!({ "event": "room_info", "room": room, "name": info.name, "topic": info.topic }),
+            ChatEvent::Redeemed { node, relay } => json!({ "event": "redeemed", "room": room, "from": node.to_string(), "relay": relay }),
+            ChatEvent::Destroyed => json!({ "event": "destroyed", "room": room }),
+            ChatEvent::Other { id, from, sender, message } => {
+                let from = from.to_string();
+                match message {
+                    Message::Image { name, id, original, .. } => json!({
+                        "event": "image", "room": room, "id": id, "from": from, "sender": sender, "name": name, "time": time,
+                        "original": original,
+                    }),
+                    Message::FileOffer { id, name, size, hash, node, voice } => json!({
+                        "event": "file", "room": room, "id": id, "from": from, "sender": sender, "name": name, "size": size,
+                        "hash": hash.to_string(), "node": node.to_string(), "voice": voice, "time": time,
+                    }),
+                    Message::Poll { id, question, options, closes } => json!({
+                        "event": "poll", "room": room, "id": id, "from": from, "sender": sender, "question": question,
+                        "options": options, "closes": closes.to_rfc3339(), "time": time,
+                    }),
+                    Message::Vote { target, option, remove } => json!({
+                        "event": "vote", "room": room, "id": id, "target": target, "from": from, "option": option, "remove": remove,
+                    }),
+                    Message::Location { id, lat, lon, label } => {
+                        let place = location::Location::new(*lat, *lon);
+                        json!({
+                            "event": "location", "room": room, "id": id, "from": from, "sender": sender, "lat": lat, "lon": lon,
+                            "label": label, "plus_code": place.as_ref().map(|p| p.plus_code()), "url": place.as_ref().map(|p| p.osm()),
+                            "time": time,
+                        })
+                    }
+                    Message::LinkPreview { target, preview } => json!({
+                        "event": "preview", "room": room, "id": id, "target": target, "from": from,
+                        "url": preview.url, "title": preview.title, "description": preview.description,
+                    }),
+                    Message::Extension { id, kind, body } => json!({
+                        "event": "extension", "room": room, "id": id, "from": from, "sender": sender, "type": kind,
+                        "body": serde_json::from_str::<serde_json::Value>(body).unwrap_or_else(|_| serde_json::Value::String(body.clone())),
+                    }),
+                    Message::Pin { quote, remove } => json!({
+                        "event": "pin", "room": room, "id": id, "target": quote.id, "from": from, "remove": remove,
+                    }),
+                    Message::Role { node, role, .. } => json!({ "event": "role", "room": room, "node": node.to_string(), "role": role }),
+                    Message::Mode { broadcast, .. } => json!({ "event": "mode", "room": room, "broadcast": broadcast }),
+                    message => json!({ "event": "other", "room": room, "from": from, "type": protocol::kind(message) }),
+                }
+            }
+            ChatEvent::Error(message) => json!({ "event": "error", "room": room, "message": message }),
+        }
+    }
+}
+
+/// One room, hosted or joined, with no interface attached.
+pub struct GhostClient {
+    /// `None` when the client was handed a transport rather than binding one.
+    network: Option<Network>,
+    transport: Arc<dyn Transport>,
+    room: rooms::Room,
+    name: String,
+    events: mpsc::UnboundedReceiver<(TopicId, iroh_gossip::net::Event)>,
+    /// Decoded from gossip but not yet handed out.
+    pending: VecDeque<ChatEvent>,
+}
+
+impl GhostClient {
+    /// Hosts a new room as `name`; hand out `ticket()` to let others in.
+    pub async fn host(name: &str, options: Options) -> Result<Self> {
+        let network = Network::bind(options).await?;
+        let mut client = GhostClient::host_on(Arc::new(network.transport()), name).await?;
+        client.network = Some(network);
+        Ok(client)
+    }
+
+    /// Joins the room `ticket` invites to as `name`, giving up after 30 seconds.
+    pub async fn join(ticket: &str, name: &str, options: Options) -> Result<Self> {
+        let network = Network::bind(options).await?;
+        let mut client = GhostClient::join_on(Arc::new(network.transport()), ticket, name).await?;
+        client.network = Some(network);
+        Ok(client)
+    }
+
+    /// Like `host`, over `transport` instead of a network of our own: a
+    /// [`Loopback`](transport::Loopback) node, say, to run clients side by side in one process.
+    pub async fn host_on(transport: Arc<dyn Transport>, name: &str) -> Result<Self> {
+        let channel = rooms::host(transport.as_ref(), "").await?;
+        Ok(GhostClient::open(transport, channel, name))
+    }
+
+    /// Like `join`, over `transport`.
+    pub async fn join_on(transport: Arc<dyn Transport>, ticket: &str, name: &str) -> Result<Self> {
+        let channel = rooms::join(transport.as_ref(), ticket).await?;
+        Ok(GhostClient::open(transport, channel, name))
+    }
+
+    fn open(transport: Arc<dyn Transport>, channel: rooms::Channel, name: &str) -> Self {
+        let (events_tx, events) = mpsc::unbounded_channel();
+        let room = rooms::Room::spawn(channel, name.to_string(), notify::Alert::Off, false, events_tx);
+        GhostClient { network: None, transport, room, name: name.to_string(), events, pending: VecDeque::new() }
+    }
+
+    /// The room's ticket, as `[Ghost:...]`.
+    pub fn ticket(&self) -> &str {
+        &self.room.ticket
+    }
+
+    pub fn topic(&self) -> TopicId {
+        self.room.topic
+    }
+
+    pub fn node_id(&self) -> NodeId {
+        self.transport.secret_key().public()
+    }
+
+    /// Names of the peers heard from so far.
+    pub fn peers(&self) -> &HashMap<NodeId, String> {
+        &self.room.peer_names
+    }
+
+    /// The room underneath, for what this API doesn't cover.
+    pub fn room(&mut self) -> &mut rooms::Room {
+        &mut self.room
+    }
+
+    /// Posts `text`, formatted as Markdown with `@name` mentions, and returns its id.
+    pub async fn send(&mut self, text: &str) -> Result<String> {
+        if self.room.destroyed() {
+            bail!("the room was destroyed");
+        }
+        if text.len() > chunks::MAX_TEXT {
+            bail!("message too long");
+        }
+        let id = new_id();
+        let rich = rich::parse(text, &self.room.peer_names, self.node_id(), &self.name);
+        let chat = Message::Chat { text: text.to_string(), id: id.clone(), reply: None, thread: None, urgent: false, rich, ttl: None, forwarded: None };
+        broadcast(&self.room.sender, &chat).await;
+        Ok(id)
+    }
+
+    /// Leaves the room and closes the endpoint, if it's ours.
+    pub async fn shutdown(self) -> Result<()> {
+        drop(self.room);
+        match self.network {
+            Some(network) => network.router.shutdown().await,
+            None => Ok(()),
+        }
+    }
+
+    fn apply(&mut self, event: iroh_gossip::net::Event) {
+        match event {
+            iroh_gossip::net::Event::Gossip(GossipEvent::Received(msg)) => {
+                // Only what happens while we're here is reported, as with `pipe`.
+                let events = self.room.receive(msg, &HashSet::new(), None).into_iter()
+                    .filter(|event| !matches!(event, ChatEvent::Other { message: Message::HistoryRequest {} | Message::History { .. }, .. }));
+                self.pending.extend(events);
+            }
+            iroh_gossip::net::Event::Gossip(GossipEvent::Joined(peers)) => self.room.neighbors.extend(peers),
+            iroh_gossip::net::Event::Gossip(GossipEvent::NeighborUp(id)) => {
+                self.room.neighbors.insert(id);
+                self.room.introduce();
+                self.pending.push_back(ChatEvent::NeighborUp(id));
+            }
+            iroh_gossip::net::Event::Gossip(GossipEvent::NeighborDown(id)) => {
+                self.room.neighbors.remove(&id);
+                self.pending.push_back(ChatEvent::NeighborDown(id));
+            }
+            iroh_gossip::net::Event::Lagged => {
+                tracing::warn!(topic = %self.room.topic, "gossip lagged; events were lost");
+                self.pending.push_back(ChatEvent::Error("gossip lagged; events were lost".to_string()));
+            }
+        }
+    }
+}
+
+/// Everything that happens in the room, in order, ending once it's been left or destroyed:
+/// poll it with `StreamExt::next`, or hand it to `select!` or a bridge.
+impl Stream for GhostClient {
+    type Item = ChatEvent;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<ChatEvent>> {
+        let client = self.get_mut();
+        loop {
+            if let Some(event) = client.pending.pop_front() {
+                return Poll::Ready(Some(event));
+            }
+            match client.events.poll_recv(cx) {
+                Poll::Ready(Some((_, event))) => client.apply(event),
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
@@ -0,0 +1,52 @@
+use std::io::Write;
+
+use anyhow::Result;
+use base64::Engine;
+
+use crate::i18n::t;
+
+/// System clipboard access with an OSC 52 fallback for SSH sessions and headless boxes.
+/// The arboard handle is kept alive for the whole session because on X11 the
+/// clipboard contents vanish as soon as their owner is dropped.
+#[derive(Default)]
+pub struct Clipboard {
+    native: Option<arboard::Clipboard>,
+}
+
+impl Clipboard {
+    /// Copies `text`, returning a short description of where it went.
+    pub fn copy(&mut self, text: &str) -> Result<String> {
+        // Over SSH the "system" clipboard belongs to the remote host, which is useless to the user.
+        if std::env::var_os("SSH_CONNECTION").is_none() && std::env::var_os("SSH_TTY").is_none() {
+            if self.native.is_none() {
+                self.native = arboard::Clipboard::new()
+                    .inspect_err(|err| tracing::debug!(%err, "no system clipboard"))
+                    .ok();
+            }
+            if let Some(native) = self.native.as_mut() {
+                if native.set_text(text).is_ok() {
+                    return Ok(t!("clipboard-system"));
+                }
+            }
+        }
+
+        osc52(text)?;
+        Ok(t!("clipboard-osc52"))
+    }
+}
+
+/// Asks the terminal emulator itself to set the clipboard.
+/// Inside tmux the sequence has to be wrapped in a DCS passthrough.
+fn osc52(text: &str) -> Result<()> {
+    let payload = base64::engine::general_purpose::STANDARD.encode(text);
+    let sequence = if std::env::var_os("TMUX").is_some() {
+        format!("\x1bPtmux;\x1b\x1b]52;c;{}\x07\x1b\\", payload)
+    } else {
+        format!("\x1b]52;c;{}\x07", payload)
+    };
+
+    let mut stdout = std::io::stdout();
+    stdout.write_all(sequence.as_bytes())?;
+    stdout.flush()?;
+    Ok(())
+}
@@ -0,0 +1,71 @@
+use std::sync::OnceLock;
+
+use chrono::{DateTime, FixedOffset, Local, Offset, Utc};
+use chrono_tz::Tz;
+
+/// The zone times are shown in. Messages keep their time in UTC and are converted only
+/// for display, so times from other machines line up with ours whatever zone they use.
+#[derive(Clone, Copy, Debug)]
+pub enum Zone {
+    Local,
+    Fixed(FixedOffset),
+    Named(Tz),
+}
+
+static ZONE: OnceLock<Zone> = OnceLock::new();
+
+/// Validates `--timezone`: `local`, `utc`, an offset like `+05:30`, or a name like `Europe/Berlin`.
+pub fn parse(zone: &str) -> Result<Zone, String> {
+    let zone = zone.trim();
+    match zone.to_lowercase().as_str() {
+        "local" => return Ok(Zone::Local),
+        "utc" | "gmt" | "z" => return Ok(Zone::Fixed(Utc.fix())),
+        _ => {}
+    }
+    if let Some(offset) = offset(zone) {
+        return Ok(Zone::Fixed(offset));
+    }
+    zone.parse::<Tz>()
+        .map(Zone::Named)
+        .map_err(|_| format!("unknown time zone '{}' (try local, utc, +05:30 or Europe/Berlin)", zone))
+}
+
+/// `+05:30`, `-0800` or `+2`.
+fn offset(zone: &str) -> Option<FixedOffset> {
+    let sign = match zone.chars().next()? {
+        '+' => 1,
+        '-' => -1,
+        _ => return None,
+    };
+    let digits = zone[1..].replace(':', "");
+    if digits.is_empty() || digits.len() > 4 || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let (hours, minutes) = match digits.len() {
+        1 | 2 => (digits.parse::<i32>().ok()?, 0),
+        n => (digits[..n - 2].parse::<i32>().ok()?, digits[n - 2..].parse::<i32>().ok()?),
+    };
+    if hours > 14 || minutes > 59 {
+        return None;
+    }
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+}
+
+/// Sets the display zone; without one, the system's local time (which honours `TZ`).
+pub fn init(zone: Option<Zone>) {
+    let _ = ZONE.set(zone.unwrap_or(Zone::Local));
+}
+
+/// `time` as it reads in the display zone.
+pub fn show(time: DateTime<Utc>) -> DateTime<FixedOffset> {
+    match ZONE.get().copied().unwrap_or(Zone::Local) {
+        Zone::Local => time.with_timezone(&Local).fixed_offset(),
+        Zone::Fixed(offset) => time.with_timezone(&offset),
+        Zone::Named(tz) => time.with_timezone(&tz).fixed_offset(),
+    }
+}
+
+/// The current time in the display zone.
+pub fn now() -> DateTime<FixedOffset> {
+    show(Utc::now())
+}
@@ -0,0 +1,273 @@
+use clap::ValueEnum;
+use ratatui::layout::Direction;
+
+use crate::{expiry, export::Redact, i18n::t, location, moderation, names, notify::Alert, polls, presence};
+
+/// Slash commands typed into the input bar.
+pub enum Command {
+    Help,
+    /// `/ticket` prints the room ticket, `/ticket copy` puts it on the clipboard.
+    Ticket { copy: bool },
+    Links,
+    /// `/img <path>` sends a picture.
+    Image { path: String },
+    /// `/sendfile <path>` offers a file of any size for peers to fetch.
+    SendFile { path: String },
+    /// `/voice` starts recording a clip, and again stops and sends it; `/voice cancel` drops it.
+    Voice { cancel: bool },
+    /// `/join <ticket>` opens another room in a new tab.
+    Join { ticket: String },
+    /// `/host` creates a new room in a new tab.
+    Host,
+    /// `/leave` closes the current tab.
+    Leave,
+    /// `/split h|v` shows the next tab alongside this one, `/split off` goes back to one pane.
+    Split(Option<Direction>),
+    /// `/quit` asks for confirmation, then exits.
+    Quit,
+    /// `/notify off|bell|flash|both` sets this room's new-message alert.
+    Notify(Alert),
+    /// `/receipts on|off` sets whether this room sends read receipts.
+    Receipts(bool),
+    /// `/export <path> [--no-names] [--no-times]` saves this room's history to a file.
+    Export { path: String, redact: Redact },
+    /// `/kick <name>` and `/ban <name>` put a peer out of the room, for a while or for good;
+    /// host only. `who` is a name or the start of a NodeId.
+    Ban { who: String, kick: bool },
+    /// `/role <name> moderator|member|read-only` changes what a peer may do here; host only.
+    Role { who: String, role: moderation::Role },
+    /// `/broadcast on|off` lets only the host and peers with a role post here; host only.
+    Broadcast(bool),
+    /// `/destroy` asks for confirmation, then wipes the room for everyone in it; host only.
+    Destroy,
+    /// `/urgent <text>` sends a message that rings through where the room is muted.
+    Urgent { text: String },
+    /// `/tmp <seconds> <text>` sends a message that every client drops after `ttl` seconds.
+    Temporary { ttl: u32, text: String },
+    /// `/loc 48.85,2.35 ["label"]` shares a point on the map.
+    Location { place: location::Location, label: String },
+    /// `/poll [10m] "question" yes no ...` asks the room, taking votes for that long.
+    Poll { question: String, options: Vec<String>, open: chrono::Duration },
+    /// `/topic [text]` sets the room's topic line for everyone, or clears it; host only.
+    Topic { text: String },
+    /// `/announce [--pin] <text>` puts a banner in front of everyone, pinned with `--pin`;
+    /// host and moderators only.
+    Announce { text: String, pin: bool },
+    /// `/nick <name>` renames us in every room and saves the name as the default.
+    Nick { name: String },
+    /// `/away [text]`, `/busy [text]` and `/back` tell every room whether we're around.
+    Status(presence::Status),
+}
+
+/// Every command with the message id of its description, in the order the help overlay lists them.
+pub const COMMANDS: &[(&str, &str)] = &[
+    ("/help", "cmd-help"),
+    ("/ticket [copy]", "cmd-ticket"),
+    ("/links", "cmd-links"),
+    ("/img <path>", "cmd-img"),
+    ("/sendfile <path>", "cmd-sendfile"),
+    ("/voice [cancel]", "cmd-voice"),
+    ("/join <ticket>", "cmd-join"),
+    ("/host", "cmd-host"),
+    ("/leave", "cmd-leave"),
+    ("/quit", "cmd-quit"),
+    ("/split h|v|off", "cmd-split"),
+    ("/notify <mode>", "cmd-notify"),
+    ("/receipts on|off", "cmd-receipts"),
+    ("/export <file>", "cmd-export"),
+    ("/nick <name>", "cmd-nick"),
+    ("/away [text]", "cmd-away"),
+    ("/busy [text]", "cmd-busy"),
+    ("/back", "cmd-back"),
+    ("/urgent <text>", "cmd-urgent"),
+    ("/tmp <seconds> <text>", "cmd-tmp"),
+    ("/loc <lat>,<lon> [label]", "cmd-loc"),
+    ("/poll [10m] \"question\" <options>", "cmd-poll"),
+    ("/topic [text]", "cmd-topic"),
+    ("/announce [--pin] <text>", "cmd-announce"),
+    ("/kick <name>", "cmd-kick"),
+    ("/ban <name>", "cmd-ban"),
+    ("/role <name> <role>", "cmd-role"),
+    ("/broadcast on|off", "cmd-broadcast"),
+    ("/destroy", "cmd-destroy"),
+];
+
+/// Splits `s` at whitespace, keeping "double-quoted runs" together without the quotes.
+fn quoted(s: &str) -> Vec<String> {
+    let (mut words, mut word, mut quoting) = (Vec::new(), String::new(), false);
+    for c in s.chars() {
+        match c {
+            '"' => {
+                if quoting && !word.is_empty() {
+                    words.push(std::mem::take(&mut word));
+                }
+                quoting = !quoting;
+            }
+            c if c.is_whitespace() && !quoting => {
+                if !word.is_empty() {
+                    words.push(std::mem::take(&mut word));
+                }
+            }
+            c => word.push(c),
+        }
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+/// Parses `/name args...` from the input bar.
+/// Returns `None` for ordinary chat text, `Some(Err(..))` for an unknown or malformed command.
+pub fn parse(input: &str) -> Option<Result<Command, String>> {
+    let rest = input.trim().strip_prefix('/')?;
+    let mut words = rest.split_whitespace();
+    let name = words.next().unwrap_or("");
+
+    let command = match name {
+        "help" => Command::Help,
+        "links" => Command::Links,
+        "host" => Command::Host,
+        "leave" => Command::Leave,
+        "destroy" => Command::Destroy,
+        "quit" | "exit" => Command::Quit,
+        "join" => match words.next() {
+            Some(ticket) => Command::Join { ticket: ticket.to_string() },
+            None => return Some(Err(t!("usage", usage = "/join <ticket>"))),
+        },
+        "split" => match words.next() {
+            Some("h") => Command::Split(Some(Direction::Vertical)),
+            Some("v") => Command::Split(Some(Direction::Horizontal)),
+            Some("off") => Command::Split(None),
+            _ => return Some(Err(t!("usage", usage = "/split h|v|off"))),
+        },
+        "notify" => match words.next().map(|mode| Alert::from_str(mode, true)) {
+            Some(Ok(alert)) => Command::Notify(alert),
+            _ => return Some(Err(t!("usage", usage = "/notify off|bell|flash|both"))),
+        },
+        "receipts" => match words.next() {
+            Some("on") => Command::Receipts(true),
+            Some("off") => Command::Receipts(false),
+            _ => return Some(Err(t!("usage", usage = "/receipts on|off"))),
+        },
+        "broadcast" => match words.next() {
+            Some("on") => Command::Broadcast(true),
+            Some("off") => Command::Broadcast(false),
+            _ => return Some(Err(t!("usage", usage = "/broadcast on|off"))),
+        },
+        "img" => {
+            // Paths may contain spaces, so take everything after the command name.
+            let path = rest[name.len()..].trim().trim_matches('"');
+            if path.is_empty() {
+                return Some(Err(t!("usage", usage = "/img <path>")));
+            }
+            Command::Image { path: path.to_string() }
+        }
+        "voice" => match words.next() {
+            None => Command::Voice { cancel: false },
+            Some("cancel") => Command::Voice { cancel: true },
+            Some(other) => return Some(Err(format!("{} ({})", t!("usage", usage = "/voice [cancel]"), other))),
+        },
+        "sendfile" => {
+            let path = rest[name.len()..].trim().trim_matches('"');
+            if path.is_empty() {
+                return Some(Err(t!("usage", usage = "/sendfile <path>")));
+            }
+            Command::SendFile { path: path.to_string() }
+        }
+        "export" => {
+            let mut redact = Redact::default();
+            let mut path = Vec::new();
+            for word in words.by_ref() {
+                match word {
+                    "--no-names" => redact.names = true,
+                    "--no-times" => redact.times = true,
+                    _ => path.push(word),
+                }
+            }
+            let path = path.join(" ");
+            let path = path.trim_matches('"');
+            if path.is_empty() {
+                return Some(Err(t!("usage", usage = "/export <path> [--no-names] [--no-times]")));
+            }
+            Command::Export { path: path.to_string(), redact }
+        }
+        "kick" | "ban" => match rest[name.len()..].trim() {
+            "" => return Some(Err(t!("usage", usage = format!("/{} <name>", name)))),
+            who => Command::Ban { who: who.to_string(), kick: name == "kick" },
+        },
+        "role" => {
+            let usage = || t!("usage", usage = "/role <name> moderator|member|read-only");
+            let Some((who, role)) = rest[name.len()..].trim().rsplit_once(char::is_whitespace) else { return Some(Err(usage())) };
+            match moderation::Role::from_str(role, true) {
+                Ok(role) if role != moderation::Role::Host => Command::Role { who: who.trim().to_string(), role },
+                _ => return Some(Err(usage())),
+            }
+        }
+        "loc" | "location" => {
+            let mut words = quoted(&rest[name.len()..]);
+            // `48.85, 2.35` arrives as two words.
+            if words.len() > 1 && words[0].ends_with(',') {
+                let lon = words.remove(1);
+                words[0].push_str(&lon);
+            }
+            match words.first().and_then(|w| location::parse(w)) {
+                Some(place) => Command::Location { place, label: location::label(&words[1..].join(" ")) },
+                None => return Some(Err(t!("usage", usage = "/loc <lat>,<lon> [\"label\"]"))),
+            }
+        }
+        "poll" => {
+            let mut words = quoted(&rest[name.len()..]);
+            let open = match words.first().and_then(|w| polls::duration(w)) {
+                Some(open) => {
+                    words.remove(0);
+                    open
+                }
+                None => polls::DEFAULT,
+            };
+            let usage = t!("usage", usage = format!("/poll [10m] \"question\" <2-{} options>", polls::MAX_OPTIONS));
+            match (!words.is_empty()).then(|| polls::sanitize(&words[0], &words[1..])).flatten() {
+                Some((question, options)) => Command::Poll { question, options, open },
+                None => return Some(Err(usage)),
+            }
+        }
+        "urgent" => match rest[name.len()..].trim() {
+            "" => return Some(Err(t!("usage", usage = "/urgent <text>"))),
+            text => Command::Urgent { text: text.to_string() },
+        },
+        "tmp" => {
+            let rest = rest[name.len()..].trim();
+            let (ttl, text) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+            match (expiry::parse(ttl), text.trim()) {
+                (Some(ttl), text) if !text.is_empty() => Command::Temporary { ttl, text: text.to_string() },
+                _ => return Some(Err(t!("usage", usage = "/tmp <seconds|10m|2h> <text>"))),
+            }
+        }
+        "away" => Command::Status(presence::Status::received(presence::Presence::Away, &rest[name.len()..])),
+        "busy" => Command::Status(presence::Status::received(presence::Presence::Busy, &rest[name.len()..])),
+        "back" => Command::Status(presence::Status::default()),
+        "topic" => Command::Topic { text: rest[name.len()..].trim().to_string() },
+        "announce" => {
+            let text = rest[name.len()..].trim();
+            let (text, pin) = match text.strip_prefix("--pin") {
+                Some(after) if after.is_empty() || after.starts_with(char::is_whitespace) => (after.trim(), true),
+                _ => (text, false),
+            };
+            match text {
+                "" => return Some(Err(t!("usage", usage = "/announce [--pin] <text>"))),
+                text => Command::Announce { text: text.to_string(), pin },
+            }
+        }
+        "nick" => match names::parse(&rest[name.len()..]) {
+            Ok(nick) => Command::Nick { name: nick },
+            Err(err) => return Some(Err(format!("{} ({})", t!("usage", usage = "/nick <name>"), err))),
+        },
+        "ticket" => match words.next() {
+            None => Command::Ticket { copy: false },
+            Some("copy") => Command::Ticket { copy: true },
+            Some(other) => return Some(Err(format!("{} ({})", t!("usage", usage = "/ticket [copy]"), other))),
+        },
+        _ => return Some(Err(t!("unknown-command", name = name))),
+    };
+    Some(Ok(command))
+}
@@ -0,0 +1,155 @@
+use std::{ffi::OsString, path::{Path, PathBuf}};
+
+use anyhow::{bail, Context, Result};
+use clap::CommandFactory;
+
+use crate::Cli;
+
+/// `ghost` under the platform's config directory (`$XDG_CONFIG_HOME`, `~/.config`, `%APPDATA%`),
+/// or `ghost/profiles/<name>` for a named profile, which has its own config and identity.
+pub fn dir(profile: Option<&str>) -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("APPDATA").filter(|_| cfg!(windows)).map(PathBuf::from))
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+    let dir = base.join("ghost");
+    Some(match profile {
+        Some(name) => dir.join("profiles").join(name),
+        None => dir,
+    })
+}
+
+/// Validates a `--profile` name or saved room alias, which for profiles becomes a directory name.
+pub fn profile_name(name: &str) -> Result<String, String> {
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_')) {
+        return Err("use letters, digits, '-' and '_'".to_string());
+    }
+    Ok(name.to_string())
+}
+
+/// Turns one setting into the flag it stands for; `false` and empty lists add nothing.
+fn flag(long: &str, value: &toml::Value, out: &mut Vec<OsString>) -> Result<()> {
+    match value {
+        toml::Value::Boolean(true) => out.push(format!("--{}", long).into()),
+        toml::Value::Boolean(false) => {}
+        toml::Value::String(s) => out.extend([format!("--{}", long).into(), s.into()]),
+        toml::Value::Integer(n) => out.extend([format!("--{}", long).into(), n.to_string().into()]),
+        toml::Value::Array(items) => {
+            for item in items {
+                flag(long, item, out)?;
+            }
+        }
+        _ => bail!("`{}` must be a string, number or true/false", long),
+    }
+    Ok(())
+}
+
+/// The config file in use: `--config`, or `config.toml` in the profile's directory.
+pub fn file(path: Option<&Path>, profile: Option<&str>) -> Option<PathBuf> {
+    path.map(Path::to_path_buf).or_else(|| dir(profile).map(|dir| dir.join("config.toml")))
+}
+
+/// Reads the config file, if there is one. A missing default file is fine; a missing
+/// `--config` file is an error.
+fn load(path: Option<&Path>, profile: Option<&str>) -> Result<Option<(PathBuf, toml::value::Table)>> {
+    let explicit = path.is_some();
+    let Some(path) = file(path, profile) else { return Ok(None) };
+    let text = match std::fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(err) if !explicit && err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err).with_context(|| format!("reading {}", path.display())),
+    };
+    let table = toml::from_str(&text).with_context(|| format!("parsing {}", path.display()))?;
+    Ok(Some((path, table)))
+}
+
+/// Saves one top-level setting, editing just its line so comments and layout survive.
+/// Without `overwrite`, an existing value is kept. Returns whether the file changed.
+pub fn remember(file: &Path, key: &str, value: impl Into<toml::Value>, overwrite: bool) -> Result<bool> {
+    let text = match std::fs::read_to_string(file) {
+        Ok(text) => text,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(err) => return Err(err).with_context(|| format!("reading {}", file.display())),
+    };
+    let line = format!("{} = {}", key, value.into());
+    let mut lines: Vec<&str> = text.lines().collect();
+    // Only keys before the first `[table]` are top-level.
+    let top = lines.iter().position(|l| l.trim_start().starts_with('[')).unwrap_or(lines.len());
+    let existing = lines[..top].iter().position(|l| {
+        l.split_once('=').is_some_and(|(k, _)| k.trim().replace('-', "_") == key.replace('-', "_"))
+    });
+    match existing {
+        Some(_) if !overwrite => return Ok(false),
+        Some(i) => lines[i] = &line,
+        None => lines.insert(top, &line),
+    }
+    if let Some(parent) = file.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut out = lines.join("\n");
+    out.push('\n');
+    std::fs::write(file, out).with_context(|| format!("writing {}", file.display()))?;
+    Ok(true)
+}
+
+/// Flags for `cmd`'s options set through `GHOST_<FLAG>` variables (`GHOST_BOSS_KEY` for
+/// `--boss-key`). Switches count as set for `1`, `true`, `yes` or `on`.
+fn env_flags(cmd: &clap::Command, out: &mut Vec<OsString>) {
+    for arg in cmd.get_arguments() {
+        // `--config` and `--profile` are read by clap itself, before the config file.
+        let Some(long) = arg.get_long().filter(|l| !matches!(*l, "config" | "profile")) else { continue };
+        let Ok(value) = std::env::var(format!("GHOST_{}", long.replace('-', "_").to_uppercase())) else { continue };
+        if arg.get_action().takes_values() {
+            out.extend([format!("--{}", long).into(), value.into()]);
+        } else if matches!(value.to_lowercase().as_str(), "1" | "true" | "yes" | "on") {
+            out.push(format!("--{}", long).into());
+        }
+    }
+}
+
+/// The command line with defaults spliced in ahead of the user's own flags: first the
+/// config file's, then environment variables', so the environment beats the file and
+/// anything typed beats both. Every config key is a flag name (`boss_key` or `boss-key`).
+/// `subcommands` is the path to the one being run, like `["rooms", "join"]`.
+pub fn apply(argv: Vec<OsString>, path: Option<&Path>, profile: Option<&str>, subcommands: &[&str]) -> Result<Vec<OsString>> {
+    let command = Cli::command();
+    let has = |cmd: &clap::Command, long: &str| cmd.get_arguments().any(|a| a.get_long() == Some(long));
+    fn anywhere(cmd: &clap::Command, long: &str) -> bool {
+        cmd.get_subcommands().any(|sub| sub.get_arguments().any(|a| a.get_long() == Some(long)) || anywhere(sub, long))
+    }
+    let target = subcommands.iter().try_fold(&command, |cmd, name| cmd.find_subcommand(name)).filter(|_| !subcommands.is_empty());
+    let (mut global, mut local) = (Vec::new(), Vec::new());
+    if let Some((path, table)) = load(path, profile)? {
+        for (key, value) in &table {
+            let long = key.replace('_', "-");
+            let result = if matches!(long.as_str(), "config" | "profile") {
+                bail!("{}: `{}` can only be given on the command line", path.display(), key);
+            } else if has(&command, &long) {
+                flag(&long, value, &mut global)
+            } else if target.is_some_and(|cmd| has(cmd, &long)) {
+                flag(&long, value, &mut local)
+            } else if anywhere(&command, &long) {
+                // Meant for another subcommand, like `cover` while joining.
+                Ok(())
+            } else {
+                bail!("{}: unknown setting `{}`", path.display(), key);
+            };
+            result.with_context(|| path.display().to_string())?;
+        }
+    }
+    env_flags(&command, &mut global);
+    if let Some(cmd) = target {
+        env_flags(cmd, &mut local);
+    }
+
+    // Local flags go right after the last subcommand's name.
+    let at = subcommands.iter().try_fold(1, |from, name| argv[from..].iter().position(|a| a == name).map(|i| from + i + 1));
+    let at = at.unwrap_or(argv.len());
+    let mut out = argv[..1].to_vec();
+    out.extend(global);
+    out.extend(argv[1..at].iter().cloned());
+    out.extend(local);
+    out.extend(argv[at..].iter().cloned());
+    Ok(out)
+}
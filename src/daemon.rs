@@ -0,0 +1,223 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail, Context, Result};
+use iroh::Endpoint;
+use iroh_gossip::net::Gossip;
+use serde_json::{json, Value};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader},
+    sync::{broadcast, mpsc},
+    task::JoinHandle,
+};
+
+use crate::{config, hooks::Hooks, i18n::t, pipe, rooms};
+
+/// Either end of the daemon's socket (or named pipe).
+trait Stream: AsyncRead + AsyncWrite + Unpin + Send + 'static {}
+impl<T: AsyncRead + AsyncWrite + Unpin + Send + 'static> Stream for T {}
+
+/// Where the profile's daemon listens: `daemon.sock` in its config directory, or a named
+/// pipe on Windows.
+pub fn address(profile: Option<&str>) -> Option<PathBuf> {
+    if cfg!(windows) {
+        return Some(PathBuf::from(format!(r"\\.\pipe\ghostterm-{}", profile.unwrap_or("default"))));
+    }
+    config::dir(profile).map(|dir| dir.join("daemon.sock"))
+}
+
+/// Shuttles one client's command lines to the session and every event back to it.
+async fn client(stream: impl Stream, lines: mpsc::UnboundedSender<String>, mut events: broadcast::Receiver<String>) {
+    let (read, mut write) = tokio::io::split(stream);
+    let mut read = BufReader::new(read).lines();
+    loop {
+        tokio::select! {
+            line = read.next_line() => match line {
+                Ok(Some(line)) => {
+                    if lines.send(line).is_err() {
+                        break;
+                    }
+                }
+                _ => break,
+            },
+            event = events.recv() => match event {
+                Ok(event) => {
+                    if write.write_all(format!("{}\n", event).as_bytes()).await.is_err() {
+                        break;
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(missed)) => tracing::warn!(missed, "daemon client lagged"),
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+        }
+    }
+}
+
+#[cfg(unix)]
+fn listen(addr: &Path, lines: mpsc::UnboundedSender<String>, events: broadcast::Sender<String>) -> Result<JoinHandle<()>> {
+    use std::os::unix::fs::PermissionsExt;
+
+    if addr.exists() {
+        if std::os::unix::net::UnixStream::connect(addr).is_ok() {
+            bail!("a daemon is already listening on {}", addr.display());
+        }
+        // Left behind by a daemon that didn't shut down cleanly.
+        std::fs::remove_file(addr).with_context(|| format!("removing {}", addr.display()))?;
+    }
+    if let Some(parent) = addr.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let listener = tokio::net::UnixListener::bind(addr).with_context(|| format!("listening on {}", addr.display()))?;
+    // Anyone who can connect can speak in our rooms as us.
+    std::fs::set_permissions(addr, std::fs::Permissions::from_mode(0o600))?;
+    Ok(tokio::spawn(async move {
+        loop {
+            match listener.accept().await {
+                Ok((stream, _)) => {
+                    tokio::spawn(client(stream, lines.clone(), events.subscribe()));
+                }
+                Err(err) => tracing::warn!(%err, "daemon accept failed"),
+            }
+        }
+    }))
+}
+
+#[cfg(windows)]
+fn listen(addr: &Path, lines: mpsc::UnboundedSender<String>, events: broadcast::Sender<String>) -> Result<JoinHandle<()>> {
+    use tokio::net::windows::named_pipe::ServerOptions;
+
+    let addr = addr.to_path_buf();
+    let mut server = ServerOptions::new()
+        .first_pipe_instance(true)
+        .create(&addr)
+        .with_context(|| format!("listening on {} (is a daemon already running?)", addr.display()))?;
+    Ok(tokio::spawn(async move {
+        loop {
+            if let Err(err) = server.connect().await {
+                tracing::warn!(%err, "daemon accept failed");
+                continue;
+            }
+            // Each client takes over the connected instance; the next one waits on a fresh one.
+            let next = match ServerOptions::new().create(&addr) {
+                Ok(next) => next,
+                Err(err) => {
+                    tracing::error!(%err, "could not reopen the daemon pipe");
+                    break;
+                }
+            };
+            tokio::spawn(client(std::mem::replace(&mut server, next), lines.clone(), events.subscribe()));
+        }
+    }))
+}
+
+#[cfg(unix)]
+async fn connect(addr: &Path) -> Option<Box<dyn Stream>> {
+    let stream = tokio::net::UnixStream::connect(addr).await.ok()?;
+    Some(Box::new(stream))
+}
+
+#[cfg(windows)]
+async fn connect(addr: &Path) -> Option<Box<dyn Stream>> {
+    let stream = tokio::net::windows::named_pipe::ClientOptions::new().open(addr).ok()?;
+    Some(Box::new(stream))
+}
+
+/// Whether a daemon is running at `addr`.
+pub async fn running(addr: &Path) -> bool {
+    connect(addr).await.is_some()
+}
+
+/// `ghost daemon`: keeps rooms open in the background and speaks the `pipe` protocol to
+/// every client attached to `addr`. All clients see all events; `quit` stops the daemon.
+/// With `tray` (Windows only) it also shows a tray icon and toasts new messages.
+pub async fn run(
+    endpoint: Endpoint,
+    gossip: Gossip,
+    my_name: String,
+    addr: &Path,
+    tray: bool,
+    profile: Option<&str>,
+    hooks: &Hooks,
+) -> Result<()> {
+    let (lines_tx, lines) = mpsc::unbounded_channel();
+    let (events, _) = broadcast::channel::<String>(1024);
+    #[cfg(windows)]
+    let tray = tray.then(|| crate::tray::Tray::spawn(lines_tx.clone(), profile.map(str::to_string))).transpose()?;
+    #[cfg(not(windows))]
+    if tray {
+        let _ = profile;
+        bail!("--tray is only available on Windows");
+    }
+    let listener = listen(addr, lines_tx, events.clone())?;
+    eprintln!("{}", t!("daemon-listening", addr = addr.display().to_string()));
+    tracing::info!(addr = %addr.display(), "daemon started");
+
+    let out = move |event: Value| {
+        #[cfg(windows)]
+        if let Some(tray) = &tray {
+            tray.event(&event);
+        }
+        // Nobody attached is fine; the rooms stay open.
+        let _ = events.send(event.to_string());
+    };
+    let result = pipe::serve(endpoint, gossip, None, my_name, lines, hooks, &out).await;
+    listener.abort();
+    if cfg!(unix) {
+        let _ = std::fs::remove_file(addr);
+    }
+    result
+}
+
+/// `ghost attach`: the `pipe` protocol on stdin/stdout, served by the running daemon.
+pub async fn attach(addr: &Path) -> Result<()> {
+    let stream = connect(addr).await.ok_or_else(|| anyhow!("no daemon on {}; start one with `ghostterm daemon`", addr.display()))?;
+    let (mut read, mut write) = tokio::io::split(stream);
+    let to_daemon = async {
+        tokio::io::copy(&mut tokio::io::stdin(), &mut write).await?;
+        anyhow::Ok(())
+    };
+    let from_daemon = async {
+        tokio::io::copy(&mut read, &mut tokio::io::stdout()).await?;
+        anyhow::Ok(())
+    };
+    // Either side closing ends the session; the daemon and its rooms carry on.
+    tokio::select! {
+        result = to_daemon => result,
+        result = from_daemon => result,
+    }
+}
+
+/// `ghost send` through a running daemon: reuses (or joins) the ticket's room there and
+/// posts into it. Returns false if no daemon is running.
+pub async fn send(addr: &Path, ticket: &str, text: &str, urgent: bool) -> Result<bool> {
+    let Some(stream) = connect(addr).await else { return Ok(false) };
+    let topic = rooms::topic(ticket)?.to_string();
+    let (read, mut write) = tokio::io::split(stream);
+    let mut events = BufReader::new(read).lines();
+    let command = |cmd: Value| format!("{}\n", cmd).into_bytes();
+
+    write.write_all(&command(json!({ "cmd": "join", "ticket": ticket }))).await?;
+    let mut joined = false;
+    while let Some(line) = events.next_line().await? {
+        let event: Value = serde_json::from_str(&line).context("bad event from the daemon")?;
+        if event["room"] != topic.as_str() && event["event"] != "error" {
+            continue;
+        }
+        match event["event"].as_str() {
+            Some("ready") if !joined => {
+                joined = true;
+                write.write_all(&command(json!({ "cmd": "send", "room": topic, "text": text, "urgent": urgent }))).await?;
+            }
+            Some("sent") => {
+                let neighbors = event["neighbors"].as_u64().unwrap_or_default();
+                if neighbors == 0 {
+                    bail!(t!("daemon-no-peers"));
+                }
+                println!("{}", t!("daemon-sent", id = event["id"].as_str().unwrap_or_default(), count = neighbors));
+                return Ok(true);
+            }
+            Some("error") => bail!(t!("daemon-error", error = event["message"].as_str().unwrap_or_default())),
+            _ => {}
+        }
+    }
+    bail!(t!("daemon-closed"))
+}
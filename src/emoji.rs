@@ -0,0 +1,96 @@
+use ratatui::{
+    prelude::*,
+    widgets::{Block, BorderType, Borders, Clear, Padding, Paragraph},
+};
+
+use crate::{centered, i18n::t};
+
+/// `:shortcode:` names and what they expand to, roughly by popularity.
+pub const SHORTCODES: &[(&str, &str)] = &[
+    ("smile", "😄"), ("joy", "😂"), ("heart", "❤️"), ("thumbsup", "👍"), ("+1", "👍"),
+    ("thumbsdown", "👎"), ("-1", "👎"), ("wink", "😉"), ("grin", "😁"), ("laughing", "😆"),
+    ("slightly_smiling_face", "🙂"), ("upside_down_face", "🙃"), ("blush", "😊"), ("heart_eyes", "😍"), ("kiss", "😘"),
+    ("thinking", "🤔"), ("neutral_face", "😐"), ("expressionless", "😑"), ("unamused", "😒"), ("roll_eyes", "🙄"),
+    ("grimacing", "😬"), ("relieved", "😌"), ("pensive", "😔"), ("sleepy", "😪"), ("sleeping", "😴"),
+    ("mask", "😷"), ("nerd", "🤓"), ("sunglasses", "😎"), ("confused", "😕"), ("worried", "😟"),
+    ("open_mouth", "😮"), ("astonished", "😲"), ("flushed", "😳"), ("cry", "😢"), ("sob", "😭"),
+    ("scream", "😱"), ("angry", "😠"), ("rage", "😡"), ("skull", "💀"), ("ghost", "👻"),
+    ("clown", "🤡"), ("robot", "🤖"), ("alien", "👽"), ("poop", "💩"), ("see_no_evil", "🙈"),
+    ("wave", "👋"), ("ok_hand", "👌"), ("v", "✌️"), ("crossed_fingers", "🤞"), ("point_up", "☝️"),
+    ("clap", "👏"), ("raised_hands", "🙌"), ("pray", "🙏"), ("muscle", "💪"), ("eyes", "👀"),
+    ("fire", "🔥"), ("sparkles", "✨"), ("star", "⭐"), ("tada", "🎉"), ("100", "💯"),
+    ("check", "✅"), ("x", "❌"), ("warning", "⚠️"), ("question", "❓"), ("exclamation", "❗"),
+    ("zap", "⚡"), ("boom", "💥"), ("rocket", "🚀"), ("bug", "🐛"), ("lock", "🔒"),
+    ("key", "🔑"), ("bulb", "💡"), ("coffee", "☕"), ("beer", "🍺"), ("pizza", "🍕"),
+    ("cake", "🍰"), ("gift", "🎁"), ("sun", "☀️"), ("moon", "🌙"), ("rainbow", "🌈"),
+    ("cat", "🐱"), ("dog", "🐶"), ("shrug", "🤷"), ("facepalm", "🤦"), ("broken_heart", "💔"),
+];
+
+fn lookup(name: &str) -> Option<&'static str> {
+    SHORTCODES.iter().find(|(code, _)| *code == name).map(|(_, emoji)| *emoji)
+}
+
+/// Replaces every known `:shortcode:` in `text`; unknown ones are left as typed.
+pub fn expand(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(':') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let name_len = after.find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '_' | '+' | '-'))).unwrap_or(after.len());
+        match lookup(&after[..name_len]).filter(|_| after[name_len..].starts_with(':')) {
+            Some(emoji) => {
+                out.push_str(emoji);
+                rest = &after[name_len + 1..];
+            }
+            None => {
+                out.push(':');
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Shortcodes containing `query`, best (prefix) matches first.
+pub fn search(query: &str) -> Vec<(&'static str, &'static str)> {
+    let query = query.to_lowercase();
+    let mut found: Vec<_> = SHORTCODES.iter().copied().filter(|(code, _)| code.contains(&query)).collect();
+    found.sort_by_key(|(code, _)| !code.starts_with(&query));
+    found
+}
+
+/// Renders the Ctrl+E picker: the query, then a page of matches around the selection.
+pub fn render(frame: &mut Frame, query: &str, selected: usize) {
+    let matches = search(query);
+    let rows = 10;
+    let first = selected.saturating_sub(rows - 1);
+
+    let mut lines = vec![Line::from(vec![
+        Span::styled(format!("{} ", t!("emoji-search")), Style::default().fg(Color::DarkGray)),
+        Span::styled(format!("{}█", query), Style::default().fg(Color::White)),
+    ])];
+    for (i, (code, emoji)) in matches.iter().enumerate().skip(first).take(rows) {
+        let style = if i == selected {
+            Style::default().fg(Color::Black).bg(Color::Cyan)
+        } else {
+            Style::default().fg(Color::Gray)
+        };
+        lines.push(Line::styled(format!("{}  :{}:", emoji, code), style));
+    }
+    if matches.is_empty() {
+        lines.push(Line::styled(t!("no-matches"), Style::default().fg(Color::DarkGray)));
+    }
+
+    let area = centered(frame.area(), 40, rows as u16 + 3);
+    let popup = Paragraph::new(lines).block(Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(Color::Cyan))
+        .title(format!(" {} ", t!("emoji-title")))
+        .padding(Padding::horizontal(1)));
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(popup, area);
+}
@@ -0,0 +1,35 @@
+use chrono::{DateTime, Utc};
+
+/// The longest a message may be set to last, in seconds: a week.
+pub const LONGEST: u32 = 7 * 24 * 3600;
+
+/// A `/tmp` lifetime: seconds (`60`) or a number with a unit (`10m`, `2h`, `1d`), up to
+/// `LONGEST`.
+pub fn parse(s: &str) -> Option<u32> {
+    let seconds = match s.parse::<u32>() {
+        Ok(seconds) => seconds,
+        Err(_) => crate::polls::duration(s)?.num_seconds().try_into().ok()?,
+    };
+    (1..=LONGEST).contains(&seconds).then_some(seconds)
+}
+
+/// A lifetime as sent, kept to `LONGEST`; 0 counts as none.
+pub fn received(ttl: Option<u32>) -> Option<u32> {
+    ttl.filter(|ttl| *ttl > 0).map(|ttl| ttl.min(LONGEST))
+}
+
+/// When a message that lasts `ttl` seconds goes from our screen: counted from when it
+/// reaches us, so a sender's clock that's off can't keep it around or make it vanish unread.
+pub fn at(ttl: u32) -> DateTime<Utc> {
+    Utc::now() + chrono::Duration::seconds(ttl as i64)
+}
+
+/// The time left until `expires`, in its largest unit: `42s`, `5m`, `2h`, `3d`.
+pub fn left(expires: DateTime<Utc>) -> String {
+    match (expires - Utc::now()).num_seconds().max(0) {
+        s if s < 60 => format!("{}s", s),
+        s if s < 3600 => format!("{}m", (s + 59) / 60),
+        s if s < 86400 => format!("{}h", (s + 3599) / 3600),
+        s => format!("{}d", (s + 86399) / 86400),
+    }
+}
@@ -0,0 +1,136 @@
+use std::{collections::{HashMap, HashSet}, io::BufRead, path::Path};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+
+use crate::clock;
+
+/// What `/export` and `ghost export` write.
+#[derive(Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum Format {
+    /// Markdown, for meeting notes.
+    Md,
+    Json,
+    Txt,
+}
+
+impl Format {
+    /// Guessed from the file extension; plain text if it's unfamiliar.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("md" | "markdown") => Format::Md,
+            Some("json") => Format::Json,
+            _ => Format::Txt,
+        }
+    }
+}
+
+/// What to leave out of an export.
+#[derive(Clone, Copy, Default)]
+pub struct Redact {
+    /// Replace every name (and `@mention` of it) with "Person 1", "Person 2", ...
+    pub names: bool,
+    pub times: bool,
+}
+
+/// One exported message.
+pub struct Entry {
+    pub time: DateTime<Utc>,
+    pub sender: String,
+    pub text: String,
+}
+
+pub fn render(entries: &[Entry], format: Format, redact: Redact) -> String {
+    let mut aliases: HashMap<&str, String> = HashMap::new();
+    if redact.names {
+        for entry in entries {
+            let next = format!("Person {}", aliases.len() + 1);
+            aliases.entry(&entry.sender).or_insert(next);
+        }
+    }
+    // Longest first, so `@alice` isn't caught by a rule for `@al`.
+    let mut mentions: Vec<(&str, &str)> = aliases.iter().map(|(name, alias)| (*name, alias.as_str())).collect();
+    mentions.sort_by_key(|(name, _)| std::cmp::Reverse(name.len()));
+    let rows: Vec<(Option<DateTime<Utc>>, &str, String)> = entries
+        .iter()
+        .map(|e| {
+            let sender = aliases.get(e.sender.as_str()).map_or(e.sender.as_str(), String::as_str);
+            let text = mentions.iter().fold(e.text.clone(), |text, (name, alias)| text.replace(&format!("@{}", name), &format!("@{}", alias)));
+            ((!redact.times).then_some(e.time), sender, text)
+        })
+        .collect();
+
+    let stamp = |time: &DateTime<Utc>| clock::show(*time).format("%Y-%m-%d %H:%M").to_string();
+    let mut out = String::new();
+    match format {
+        Format::Json => {
+            let rows: Vec<Value> = rows
+                .iter()
+                .map(|(time, sender, text)| json!({ "time": time.map(|t| clock::show(t).to_rfc3339()), "sender": sender, "text": text }))
+                .collect();
+            out = serde_json::to_string_pretty(&rows).expect("JSON values serialize");
+            out.push('\n');
+        }
+        Format::Md => {
+            out.push_str("# Chat export\n\n");
+            for (time, sender, text) in &rows {
+                let time = time.as_ref().map(|t| format!(" _{}_", stamp(t))).unwrap_or_default();
+                out.push_str(&format!("- **{}**{}: {}\n", sender, time, text.replace('\n', "\n  ")));
+            }
+        }
+        Format::Txt => {
+            for (time, sender, text) in &rows {
+                let time = time.as_ref().map(|t| format!("[{}] ", stamp(t))).unwrap_or_default();
+                out.push_str(&format!("{}{}: {}\n", time, sender, text.replace('\n', "\n    ")));
+            }
+        }
+    }
+    out
+}
+
+/// Messages from a `pipe` or `attach` event log, with later edits by their authors applied,
+/// and without those their authors or a moderator deleted.
+pub fn from_events(input: impl BufRead) -> Result<Vec<Entry>> {
+    let mut entries = Vec::new();
+    let mut ids = HashMap::new();
+    let mut deleted = HashSet::new();
+    for (n, line) in input.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let event: Value = serde_json::from_str(&line).with_context(|| format!("line {}: not a JSON event", n + 1))?;
+        let text = |key: &str| event[key].as_str().unwrap_or_default().to_string();
+        let time = || {
+            DateTime::parse_from_rfc3339(event["time"].as_str().unwrap_or_default())
+                .map_or_else(|_| Utc::now(), |t| t.with_timezone(&Utc))
+        };
+        match event["event"].as_str() {
+            Some("message") => {
+                ids.insert(text("id"), (entries.len(), text("from")));
+                entries.push(Entry { time: time(), sender: text("sender"), text: text("text") });
+            }
+            Some("image") => {
+                ids.insert(text("id"), (entries.len(), text("from")));
+                entries.push(Entry { time: time(), sender: text("sender"), text: format!("[image: {}]", text("name")) });
+            }
+            Some("edit") => {
+                match ids.get(&text("target")) {
+                    Some((i, from)) if *from == text("from") => entries[*i].text = text("text"),
+                    _ => {}
+                }
+            }
+            Some("redact") => {
+                match ids.get(&text("target")) {
+                    Some((i, from)) if *from == text("from") || event["moderator"].as_bool() == Some(true) => {
+                        deleted.insert(*i);
+                    }
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(entries.into_iter().enumerate().filter(|(i, _)| !deleted.contains(i)).map(|(_, entry)| entry).collect())
+}
@@ -0,0 +1,264 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use futures_lite::StreamExt;
+use iroh::NodeId;
+use iroh_blobs::{
+    get::db::DownloadProgress,
+    net_protocol::DownloadMode,
+    rpc::client::blobs::{DownloadOptions, MemClient, WrapOption},
+    store::{ExportFormat, ExportMode},
+    util::SetTagOption,
+    BlobFormat, Hash, Tag,
+};
+use iroh_gossip::proto::TopicId;
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+
+use crate::{i18n::t, images};
+
+/// A file offered in a room, and how far fetching it has got.
+pub struct Offer {
+    pub name: String,
+    pub size: u64,
+    pub hash: Hash,
+    /// The node serving it: the sender, who has to stay online until it's fetched.
+    pub node: NodeId,
+    /// A voice clip's length in milliseconds.
+    pub voice: Option<u32>,
+    pub state: State,
+}
+
+/// What a published file is offered as.
+pub enum Kind {
+    File,
+    /// With the inline thumbnail that goes out in the `Image` message.
+    Image(Vec<u8>),
+    /// A recorded clip of this many milliseconds, read from a temporary file that is deleted
+    /// once it's copied into the blob store.
+    Voice(u32),
+}
+
+/// One run's blob store, in a directory of its own since a store can't be shared between
+/// processes. Removed when dropped.
+pub struct BlobDir(PathBuf);
+
+impl BlobDir {
+    /// A directory for this process in `parent`, emptied of anything a crashed run with the
+    /// same process id left.
+    pub fn create(parent: &Path) -> std::io::Result<Self> {
+        let dir = parent.join(std::process::id().to_string());
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir)?;
+        Ok(BlobDir(dir))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for BlobDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Where to fetch the full-size file behind an inline image.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Original {
+    pub size: u64,
+    pub hash: Hash,
+    pub node: NodeId,
+}
+
+pub enum State {
+    /// Ours, served to whoever asks.
+    Shared,
+    Offered,
+    /// Bytes received so far.
+    Fetching(u64),
+    Saved(PathBuf),
+    Failed(String),
+}
+
+/// What background transfers report back to the UI.
+pub enum Event {
+    /// Our file is hashed and ready to serve, kept in the store by `tag`: offer it in `topic`.
+    Published { topic: TopicId, name: String, size: u64, hash: Hash, tag: Tag, kind: Kind },
+    PublishFailed { topic: TopicId, error: String },
+    /// Progress on fetching the offer in message `id`.
+    Fetching { topic: TopicId, id: String, done: u64 },
+    Saved { topic: TopicId, id: String, path: PathBuf },
+    Failed { topic: TopicId, id: String, error: String },
+}
+
+/// The blob store files are served from and fetched into, and where they are saved.
+pub struct Files {
+    client: MemClient,
+    events: mpsc::UnboundedSender<Event>,
+    pub dir: PathBuf,
+    /// What keeps each file we offer in the store; untagged data is collected as garbage.
+    shared: HashMap<Hash, Tag>,
+}
+
+/// `~/Downloads` if there is one, else the current directory.
+pub fn default_dir() -> PathBuf {
+    std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE"))
+        .map(|home| PathBuf::from(home).join("Downloads"))
+        .filter(|dir| dir.is_dir())
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+/// `12.3 MiB` and the like.
+pub fn size(bytes: u64) -> String {
+    let mut value = bytes as f64;
+    for unit in ["B", "KiB", "MiB", "GiB"] {
+        if value < 1024.0 || unit == "GiB" {
+            return if unit == "B" { format!("{} B", bytes) } else { format!("{:.1} {}", value, unit) };
+        }
+        value /= 1024.0;
+    }
+    unreachable!()
+}
+
+/// A ten-cell bar with the percentage after it.
+pub fn bar(done: u64, total: u64) -> String {
+    let percent = (done.min(total) * 100).checked_div(total).unwrap_or(100) as usize;
+    format!("[{}{}] {}%", "█".repeat(percent / 10), "░".repeat(10 - percent / 10), percent)
+}
+
+impl Offer {
+    /// The line under the file name: what can be done with it, or how the fetch is going.
+    pub fn status(&self) -> String {
+        match &self.state {
+            State::Shared => t!("file-shared"),
+            State::Offered => t!("file-offered"),
+            State::Fetching(done) => format!("{} {}", bar(*done, self.size), t!("file-fetching", size = size(self.size))),
+            State::Saved(_) if self.voice.is_some() => t!("voice-saved"),
+            State::Saved(path) => t!("file-saved", path = path.display().to_string()),
+            State::Failed(error) => t!("file-failed", error = error.as_str()),
+        }
+    }
+
+    /// Whether pressing `a` should (re)start fetching it.
+    pub fn fetchable(&self) -> bool {
+        matches!(self.state, State::Offered | State::Failed(_))
+    }
+}
+
+/// A path in `dir` for `name` that doesn't overwrite anything: `notes.txt`, `notes (1).txt`, ...
+fn free_path(dir: &Path, name: &str) -> PathBuf {
+    let path = dir.join(name);
+    if !path.exists() {
+        return path;
+    }
+    let (stem, ext) = match name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => (stem, format!(".{}", ext)),
+        _ => (name, String::new()),
+    };
+    (1..).map(|n| dir.join(format!("{} ({}){}", stem, n, ext))).find(|p| !p.exists()).unwrap_or(path)
+}
+
+impl Files {
+    pub fn new(client: MemClient, events: mpsc::UnboundedSender<Event>, dir: PathBuf) -> Self {
+        Files { client, events, dir, shared: HashMap::new() }
+    }
+
+    /// Keeps serving `hash` until `release` finds it no longer offered.
+    pub fn share(&mut self, hash: Hash, tag: Tag) {
+        self.shared.insert(hash, tag);
+    }
+
+    /// Stops serving the files we offered that aren't in `offered` any more, as when their
+    /// message was deleted or their room left, so the store can let go of them.
+    pub fn release(&mut self, offered: &HashSet<Hash>) {
+        let gone: Vec<Hash> = self.shared.keys().filter(|hash| !offered.contains(*hash)).copied().collect();
+        for hash in gone {
+            let Some(tag) = self.shared.remove(&hash) else { continue };
+            let tags = self.client.tags();
+            tokio::spawn(async move {
+                if let Err(err) = tags.delete(tag).await {
+                    tracing::warn!(%err, %hash, "could not untag a file");
+                }
+            });
+        }
+    }
+
+    /// Hashes `path` into the blob store in the background, then reports it `Published`.
+    pub fn publish(&self, topic: TopicId, path: PathBuf, kind: Kind) {
+        let (client, events) = (self.client.clone(), self.events.clone());
+        tokio::spawn(async move {
+            let fallback = match kind {
+                Kind::File => "file",
+                Kind::Image(_) => "image",
+                Kind::Voice(_) => "voice.opus",
+            };
+            let name = images::file_name(&path.to_string_lossy(), fallback);
+            let added = async {
+                let path = std::path::absolute(&path)?;
+                anyhow::ensure!(path.is_file(), t!("file-not-a-file", path = path.display().to_string()));
+                // Served from where it is, except voice clips, whose file is about to go.
+                let in_place = !matches!(kind, Kind::Voice(_));
+                client.add_from_path(path, in_place, SetTagOption::Auto, WrapOption::NoWrap).await?.finish().await
+            };
+            let added = added.await;
+            if let Kind::Voice(_) = kind {
+                let _ = std::fs::remove_file(&path);
+            }
+            let _ = events.send(match added {
+                Ok(outcome) => Event::Published { topic, name, size: outcome.size, hash: outcome.hash, tag: outcome.tag, kind },
+                Err(err) => Event::PublishFailed { topic, error: format!("{:#}", err) },
+            });
+        });
+    }
+
+    /// Fetches the offer in message `id` straight from its node, saving it in `dir`. The
+    /// store only holds it until it's written out.
+    pub fn fetch(&self, topic: TopicId, id: String, offer: &Offer) {
+        let (client, events) = (self.client.clone(), self.events.clone());
+        let (hash, node, dest) = (offer.hash, offer.node, free_path(&self.dir, &offer.name));
+        let tag = Tag::from(format!("fetch-{}-{}", topic, id));
+        tokio::spawn(async move {
+            let fetched = async {
+                let options = DownloadOptions {
+                    format: BlobFormat::Raw,
+                    nodes: vec![node.into()],
+                    tag: SetTagOption::Named(tag.clone()),
+                    mode: DownloadMode::Queued,
+                };
+                let mut progress = client.download_with_opts(hash, options).await?;
+                while let Some(item) = progress.next().await {
+                    if let DownloadProgress::Progress { offset, .. } = item? {
+                        let _ = events.send(Event::Fetching { topic, id: id.clone(), done: offset });
+                    }
+                }
+                client.export(hash, dest.clone(), ExportFormat::Blob, ExportMode::Copy).await?.finish().await
+                    .with_context(|| format!("writing {}", dest.display()))?;
+                Ok::<_, anyhow::Error>(dest)
+            };
+            let fetched = fetched.await;
+            if let Err(err) = client.tags().delete(tag).await {
+                tracing::debug!(%err, %hash, "could not untag a fetched file");
+            }
+            let _ = events.send(match fetched {
+                Ok(path) => Event::Saved { topic, id, path },
+                Err(err) => Event::Failed { topic, id, error: format!("{:#}", err) },
+            });
+        });
+    }
+}
+
+impl Original {
+    pub fn offer(&self, name: &str) -> Offer {
+        offer(name, self.size, self.hash, self.node)
+    }
+}
+
+/// What a peer offered, with the name cut down to a plain file name.
+pub fn offer(name: &str, size: u64, hash: Hash, node: NodeId) -> Offer {
+    Offer { name: images::file_name(name, "file"), size, hash, node, voice: None, state: State::Offered }
+}
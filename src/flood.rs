@@ -0,0 +1,81 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    time::{Duration, Instant},
+};
+
+use iroh::NodeId;
+
+use crate::Message;
+
+/// The same text from the same sender within this long of the last copy is counted on
+/// that one ("×12") rather than shown again.
+pub const BURST: Duration = Duration::from_secs(60);
+
+/// `--flood-limit`, `--flood-window` and `--flood-mute`.
+#[derive(Clone, Copy)]
+pub struct Limits {
+    /// How many posts a peer may make per `window`; 0 turns muting off.
+    pub posts: usize,
+    pub window: Duration,
+    /// How long a peer that goes over stays muted.
+    pub mute: Duration,
+}
+
+/// What becomes of one post.
+pub enum Verdict {
+    Pass,
+    /// Over the limit: dropped, and its sender muted from now on.
+    Muted,
+    /// Dropped while its sender is muted.
+    Dropped,
+}
+
+/// What counts against the limit: anything that takes a line in the chat.
+pub fn counted(msg: &Message) -> bool {
+    matches!(msg, Message::Chat { .. } | Message::Image { .. } | Message::FileOffer { .. } | Message::Poll { .. }
+        | Message::Location { .. } | Message::Extension { .. })
+}
+
+/// Recent posts per peer, and who is muted until when. Kept across rooms, so a peer
+/// flooding several at once is only let through once.
+pub struct Flood {
+    limits: Limits,
+    recent: HashMap<NodeId, VecDeque<Instant>>,
+    muted: HashMap<NodeId, Instant>,
+}
+
+impl Flood {
+    pub fn new(limits: Limits) -> Self {
+        Flood { limits, recent: HashMap::new(), muted: HashMap::new() }
+    }
+
+    /// Counts one post from `from`.
+    pub fn post(&mut self, from: NodeId) -> Verdict {
+        if self.limits.posts == 0 {
+            return Verdict::Pass;
+        }
+        let now = Instant::now();
+        match self.muted.get(&from) {
+            Some(until) if now < *until => return Verdict::Dropped,
+            Some(_) => {
+                self.muted.remove(&from);
+            }
+            None => {}
+        }
+        let recent = self.recent.entry(from).or_default();
+        while recent.front().is_some_and(|at| now.duration_since(*at) >= self.limits.window) {
+            recent.pop_front();
+        }
+        if recent.len() < self.limits.posts {
+            recent.push_back(now);
+            return Verdict::Pass;
+        }
+        recent.clear();
+        self.muted.insert(from, now + self.limits.mute);
+        Verdict::Muted
+    }
+
+    pub fn mute(&self) -> Duration {
+        self.limits.mute
+    }
+}
@@ -0,0 +1,440 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    convert::Infallible,
+    net::SocketAddr,
+    path::PathBuf,
+    sync::{atomic::{AtomicU64, Ordering}, Arc, Mutex},
+    time::Duration,
+};
+
+use anyhow::{Context, Result};
+use futures_util::{SinkExt, StreamExt};
+use http_body_util::{combinators::UnsyncBoxBody, BodyExt, Full, Limited, StreamBody};
+use hyper::{
+    body::{Bytes, Frame as Chunk, Incoming},
+    header::{self, HeaderValue},
+    Method, Request, Response, StatusCode,
+};
+use hyper_util::rt::TokioIo;
+use iroh::Endpoint;
+use iroh_gossip::net::Gossip;
+use serde_json::{json, Map, Value};
+use sha2::{Digest, Sha256};
+use tokio::{
+    net::{TcpListener, TcpStream},
+    sync::{broadcast, mpsc},
+};
+use tokio_tungstenite::{
+    tungstenite::{handshake::derive_accept_key, protocol::Role, Message as Frame},
+    WebSocketStream,
+};
+
+use crate::{config, hooks::Hooks, pipe};
+
+/// The `pipe` commands a client may call by name, each answered by exactly one event. `quit`
+/// isn't one of them: the node isn't any one client's to stop.
+const FORWARDED: &[&str] = &["send", "poll", "location", "announce", "broadcast", "extension", "redact", "vote", "join", "host", "peers", "leave", "destroy"];
+
+/// JSON-RPC's own error codes, and ours for a command the node turned down.
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const FAILED: i64 = -32000;
+
+/// Every open room's `ready` event, by topic, so `rooms` and `ticket` are answered here.
+type Rooms = Arc<Mutex<BTreeMap<String, Value>>>;
+
+/// The token clients authenticate with: `given`, or the one kept in `gateway.token` in the
+/// profile's config directory, made on first use. Returns where it's kept, if anywhere.
+pub fn token(given: Option<String>, profile: Option<&str>) -> Result<(String, Option<PathBuf>)> {
+    if let Some(token) = given {
+        return Ok((token, None));
+    }
+    let fresh = || hex::encode(rand::random::<[u8; 32]>());
+    let Some(path) = config::dir(profile).map(|dir| dir.join("gateway.token")) else { return Ok((fresh(), None)) };
+    match std::fs::read_to_string(&path) {
+        Ok(token) if !token.trim().is_empty() => Ok((token.trim().to_string(), Some(path))),
+        Ok(_) => Ok((fresh(), None)),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            let token = fresh();
+            ghost_messaging::identity::save(&path, &token)?;
+            Ok((token, Some(path)))
+        }
+        Err(err) => Err(err).with_context(|| format!("reading {}", path.display())),
+    }
+}
+
+/// How long an HTTP request waits for the node to answer its command.
+const ANSWER: Duration = Duration::from_secs(30);
+/// The largest message body taken over HTTP; a message's text is shorter still.
+const MAX_BODY: usize = 64 << 10;
+
+type Body = UnsyncBoxBody<Bytes, Infallible>;
+
+/// What every connection shares.
+struct Shared {
+    token: String,
+    lines: mpsc::UnboundedSender<String>,
+    events: broadcast::Sender<Value>,
+    rooms: Rooms,
+    /// Numbers the `ref`s of the commands sent for clients.
+    next: AtomicU64,
+}
+
+impl Shared {
+    fn reference(&self) -> String {
+        self.next.fetch_add(1, Ordering::Relaxed).to_string()
+    }
+
+    /// The topic of the open room starting with `prefix`, and its `ready` event.
+    fn room(&self, prefix: &str) -> Option<(String, Value)> {
+        let rooms = self.rooms.lock().unwrap_or_else(|e| e.into_inner());
+        rooms.iter().find(|(topic, _)| topic.starts_with(prefix)).map(|(topic, ready)| (topic.clone(), ready.clone()))
+    }
+
+    /// Runs `command` and waits for the event answering it, or `None` if that takes too long.
+    async fn command(&self, mut command: Map<String, Value>) -> Option<Value> {
+        let reference = self.reference();
+        command.insert("ref".into(), json!(reference));
+        // Subscribed before it's sent, so the answer can't slip past.
+        let mut events = self.events.subscribe();
+        self.lines.send(Value::Object(command).to_string()).ok()?;
+        let answer = async {
+            loop {
+                match events.recv().await {
+                    Ok(mut event) if event["ref"] == reference.as_str() => {
+                        if let Some(event) = event.as_object_mut() {
+                            event.remove("ref");
+                        }
+                        return Some(event);
+                    }
+                    Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {}
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        };
+        tokio::time::timeout(ANSWER, answer).await.ok().flatten()
+    }
+}
+
+/// Whether the request carries `token`, as `Authorization: Bearer <token>` or, for browsers,
+/// which can't set headers on a WebSocket or an `EventSource`, as `?token=<token>`.
+fn authorized<B>(request: &Request<B>, token: &str) -> bool {
+    let header = request.headers().get(header::AUTHORIZATION).and_then(|v| v.to_str().ok()).and_then(|v| v.strip_prefix("Bearer "));
+    let query = request.uri().query().into_iter().flat_map(|q| q.split('&')).find_map(|pair| pair.strip_prefix("token="));
+    // Compared as digests so the time taken says nothing about how much of it matched.
+    let expected = Sha256::digest(token.as_bytes());
+    header.into_iter().chain(query).any(|given| Sha256::digest(given.as_bytes()) == expected)
+}
+
+fn respond(status: StatusCode, body: Value) -> Response<Body> {
+    let mut response = Response::new(Full::new(Bytes::from(body.to_string())).boxed_unsync());
+    *response.status_mut() = status;
+    response.headers_mut().insert(header::CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    response
+}
+
+fn refuse(status: StatusCode, message: &str) -> Response<Body> {
+    respond(status, json!({ "error": message }))
+}
+
+fn reply(id: &Value, result: Value) -> Frame {
+    Frame::text(json!({ "jsonrpc": "2.0", "id": id, "result": result }).to_string())
+}
+
+fn failure(id: &Value, code: i64, message: impl std::fmt::Display) -> Frame {
+    Frame::text(json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message.to_string() } }).to_string())
+}
+
+/// Whether a client gets `event` as a notification, given what it `subscribed` to: `None`
+/// for nothing yet, an empty list for every room, else prefixes of the topics it wants.
+fn wanted(subscribed: &Option<Vec<String>>, event: &Value) -> bool {
+    match (subscribed, event["room"].as_str()) {
+        (None, _) => false,
+        (Some(prefixes), Some(room)) if !prefixes.is_empty() => prefixes.iter().any(|p| room.starts_with(p.as_str())),
+        // Events for no room in particular, like errors, go to every subscriber.
+        (Some(_), _) => true,
+    }
+}
+
+/// One TCP connection, speaking HTTP until it asks to become a WebSocket.
+async fn connection(stream: TcpStream, peer: SocketAddr, shared: Arc<Shared>) {
+    let service = hyper::service::service_fn(move |request| {
+        let shared = shared.clone();
+        async move { Ok::<_, Infallible>(handle(request, peer, shared).await) }
+    });
+    let served = hyper::server::conn::http1::Builder::new().serve_connection(TokioIo::new(stream), service).with_upgrades();
+    if let Err(err) = served.await {
+        tracing::debug!(%peer, %err, "gateway connection failed");
+    }
+}
+
+/// Answers one HTTP request: the WebSocket handshake, or one of the REST routes.
+async fn handle(mut request: Request<Incoming>, peer: SocketAddr, shared: Arc<Shared>) -> Response<Body> {
+    if !authorized(&request, &shared.token) {
+        tracing::info!(%peer, "gateway request without a valid token");
+        return refuse(StatusCode::UNAUTHORIZED, "a valid token is needed");
+    }
+    let upgrade = request.headers().get(header::UPGRADE).is_some_and(|v| v.as_bytes().eq_ignore_ascii_case(b"websocket"));
+    if upgrade {
+        let Some(key) = request.headers().get(header::SEC_WEBSOCKET_KEY).map(|key| derive_accept_key(key.as_bytes())) else {
+            return refuse(StatusCode::BAD_REQUEST, "no Sec-WebSocket-Key");
+        };
+        let upgrading = hyper::upgrade::on(&mut request);
+        tokio::spawn(async move {
+            match upgrading.await {
+                Ok(upgraded) => session(WebSocketStream::from_raw_socket(TokioIo::new(upgraded), Role::Server, None).await, peer, shared).await,
+                Err(err) => tracing::info!(%peer, %err, "gateway upgrade failed"),
+            }
+        });
+        let mut response = Response::new(Body::default());
+        *response.status_mut() = StatusCode::SWITCHING_PROTOCOLS;
+        let headers = response.headers_mut();
+        headers.insert(header::CONNECTION, HeaderValue::from_static("Upgrade"));
+        headers.insert(header::UPGRADE, HeaderValue::from_static("websocket"));
+        if let Ok(key) = HeaderValue::from_str(&key) {
+            headers.insert(header::SEC_WEBSOCKET_ACCEPT, key);
+        }
+        return response;
+    }
+
+    let path: Vec<String> = request.uri().path().trim_matches('/').split('/').map(str::to_string).collect();
+    match (request.method(), path.iter().map(String::as_str).collect::<Vec<_>>().as_slice()) {
+        (&Method::GET, ["rooms"]) => {
+            let rooms: Vec<Value> = shared.rooms.lock().unwrap_or_else(|e| e.into_inner()).values().cloned().collect();
+            respond(StatusCode::OK, json!(rooms))
+        }
+        (&Method::POST, ["rooms", room, "messages"]) => match shared.room(room) {
+            Some((topic, _)) => post(request, topic, &shared).await,
+            None => refuse(StatusCode::NOT_FOUND, "no such room"),
+        },
+        (&Method::GET, ["rooms", room, "events"]) => match shared.room(room) {
+            Some((topic, _)) => events(topic, &shared),
+            None => refuse(StatusCode::NOT_FOUND, "no such room"),
+        },
+        (_, ["rooms"] | ["rooms", _, "messages" | "events"]) => refuse(StatusCode::METHOD_NOT_ALLOWED, "wrong method for this path"),
+        _ => refuse(StatusCode::NOT_FOUND, "no such path"),
+    }
+}
+
+/// `POST /rooms/{id}/messages`: the body is the text, or with `Content-Type: application/json`
+/// the params of `pipe`'s `send`. Answers with its `sent` event.
+async fn post(request: Request<Incoming>, topic: String, shared: &Shared) -> Response<Body> {
+    let json = request.headers().get(header::CONTENT_TYPE).and_then(|v| v.to_str().ok()).is_some_and(|v| v.starts_with("application/json"));
+    let body = match Limited::new(request.into_body(), MAX_BODY).collect().await {
+        Ok(body) => body.to_bytes(),
+        Err(err) => return refuse(StatusCode::BAD_REQUEST, &format!("could not read the body: {}", err)),
+    };
+    let mut command = match (json, std::str::from_utf8(&body)) {
+        (true, _) => match serde_json::from_slice(&body) {
+            Ok(Value::Object(params)) => params,
+            _ => return refuse(StatusCode::BAD_REQUEST, r#"the body is a JSON object, like {"text":"hi"}"#),
+        },
+        (false, Ok(text)) => Map::from_iter([("text".to_string(), json!(text.trim_end()))]),
+        (false, Err(_)) => return refuse(StatusCode::BAD_REQUEST, "the body is not UTF-8"),
+    };
+    command.insert("cmd".into(), json!("send"));
+    command.insert("room".into(), json!(topic));
+    match shared.command(command).await {
+        Some(event) if event["event"] == "error" => refuse(StatusCode::BAD_REQUEST, event["message"].as_str().unwrap_or_default()),
+        Some(event) => respond(StatusCode::OK, event),
+        None => refuse(StatusCode::GATEWAY_TIMEOUT, "the node did not answer"),
+    }
+}
+
+/// `GET /rooms/{id}/events`: the room's events as server-sent events, one JSON object per
+/// `data:` line, until the room is left or destroyed.
+fn events(topic: String, shared: &Shared) -> Response<Body> {
+    let stream = futures_util::stream::unfold(Some(shared.events.subscribe()), move |events| {
+        let topic = topic.clone();
+        async move {
+            let mut events = events?;
+            loop {
+                match events.recv().await {
+                    Ok(mut event) if event["room"] == topic.as_str() => {
+                        if let Some(event) = event.as_object_mut() {
+                            event.remove("ref");
+                        }
+                        let last = matches!(event["event"].as_str(), Some("left" | "destroyed"));
+                        let chunk = Chunk::data(Bytes::from(format!("data: {}\n\n", event)));
+                        return Some((Ok(chunk), (!last).then_some(events)));
+                    }
+                    Ok(_) => {}
+                    Err(broadcast::error::RecvError::Lagged(missed)) => tracing::warn!(missed, "gateway event stream lagged"),
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        }
+    });
+    let mut response = Response::new(StreamBody::new(stream).boxed_unsync());
+    let headers = response.headers_mut();
+    headers.insert(header::CONTENT_TYPE, HeaderValue::from_static("text/event-stream"));
+    headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+    response
+}
+
+/// One WebSocket client: its requests become `pipe` commands tagged with a `ref`, the events
+/// answering them its responses, and the rest notifications once it subscribes.
+async fn session(mut socket: WebSocketStream<TokioIo<hyper::upgrade::Upgraded>>, peer: SocketAddr, shared: Arc<Shared>) {
+    tracing::info!(%peer, "gateway client connected");
+    let mut events = shared.events.subscribe();
+    let mut subscribed: Option<Vec<String>> = None;
+    // The JSON-RPC id of every request still waiting on its event, by `ref`.
+    let mut pending = HashMap::new();
+
+    loop {
+        let frame = tokio::select! {
+            frame = socket.next() => match frame {
+                Some(Ok(Frame::Text(text))) => {
+                    match request(&text, &mut pending, &mut subscribed, &shared) {
+                        Some(frame) => frame,
+                        None => continue,
+                    }
+                }
+                Some(Ok(Frame::Binary(_))) => failure(&Value::Null, PARSE_ERROR, "requests are text frames"),
+                Some(Ok(Frame::Close(_))) | None => break,
+                Some(Ok(_)) => continue,
+                Some(Err(err)) => {
+                    tracing::info!(%peer, %err, "gateway client dropped");
+                    break;
+                }
+            },
+            event = events.recv() => match event {
+                Ok(mut event) => {
+                    let answer = event.as_object_mut().and_then(|e| e.remove("ref")).and_then(|r| pending.remove(r.as_str()?));
+                    match answer {
+                        Some(id) if event["event"] == "error" => failure(&id, FAILED, event["message"].as_str().unwrap_or_default()),
+                        Some(id) => reply(&id, event),
+                        None if wanted(&subscribed, &event) => Frame::text(json!({ "jsonrpc": "2.0", "method": "event", "params": event }).to_string()),
+                        None => continue,
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    tracing::warn!(missed, "gateway client lagged");
+                    continue;
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+        };
+        if socket.send(frame).await.is_err() {
+            break;
+        }
+    }
+    tracing::info!(%peer, "gateway client disconnected");
+}
+
+/// Handles one request: the frame to answer with now, or `None` when the answer is an event
+/// still to come (or the request was a notification, which gets none).
+fn request(text: &str, pending: &mut HashMap<String, Value>, subscribed: &mut Option<Vec<String>>, shared: &Shared) -> Option<Frame> {
+    let request: Value = match serde_json::from_str(text) {
+        Ok(request) => request,
+        Err(err) => return Some(failure(&Value::Null, PARSE_ERROR, err)),
+    };
+    let id = request.get("id").cloned();
+    let answer = |frame: Frame| id.is_some().then_some(frame);
+    let id_or_null = id.clone().unwrap_or(Value::Null);
+    let Some(method) = request["method"].as_str().filter(|_| request["jsonrpc"] == "2.0") else {
+        return Some(failure(&id_or_null, INVALID_REQUEST, "not a JSON-RPC 2.0 request"));
+    };
+    let mut params = match request.get("params") {
+        None | Some(Value::Null) => Map::new(),
+        Some(Value::Object(params)) => params.clone(),
+        Some(_) => return answer(failure(&id_or_null, INVALID_PARAMS, "params go by name, as an object")),
+    };
+    // `ref`s are ours to hand out; one picked by a client could claim another's answer.
+    params.remove("ref");
+    match method {
+        "subscribe" => {
+            let prefixes = params.get("rooms").and_then(Value::as_array).map(|rooms| rooms.iter().filter_map(|r| r.as_str().map(str::to_string)).collect());
+            *subscribed = Some(prefixes.unwrap_or_default());
+            answer(reply(&id_or_null, json!(true)))
+        }
+        "unsubscribe" => {
+            *subscribed = None;
+            answer(reply(&id_or_null, json!(true)))
+        }
+        "rooms" => {
+            let rooms: Vec<Value> = shared.rooms.lock().unwrap_or_else(|e| e.into_inner()).values().cloned().collect();
+            answer(reply(&id_or_null, json!(rooms)))
+        }
+        "ticket" => {
+            match shared.room(params.get("room").and_then(Value::as_str).unwrap_or_default()) {
+                Some((topic, ready)) => answer(reply(&id_or_null, json!({ "room": topic, "ticket": ready["ticket"] }))),
+                None => answer(failure(&id_or_null, FAILED, "no such room")),
+            }
+        }
+        // Answers nothing in `pipe`, so it's acknowledged as soon as it's passed on.
+        "status" => {
+            let mut command = params;
+            command.insert("cmd".into(), json!("status"));
+            let _ = shared.lines.send(Value::Object(command).to_string());
+            answer(reply(&id_or_null, json!(true)))
+        }
+        method if FORWARDED.contains(&method) => {
+            let mut command = params;
+            command.insert("cmd".into(), json!(method));
+            // A notification's event is nobody's answer, so it goes out to subscribers as it is.
+            if let Some(id) = id {
+                let reference = shared.reference();
+                command.insert("ref".into(), json!(reference));
+                pending.insert(reference, id);
+            }
+            let _ = shared.lines.send(Value::Object(command).to_string());
+            None
+        }
+        _ => answer(failure(&id_or_null, METHOD_NOT_FOUND, format!("no method {}", method))),
+    }
+}
+
+/// `ghost gateway`: keeps rooms open like the daemon and serves them on `listen`, as JSON-RPC
+/// 2.0 to WebSocket clients and as a small REST API over plain HTTP, until interrupted. Every
+/// client needs `token`.
+pub async fn run(endpoint: Endpoint, gossip: Gossip, my_name: String, listen: SocketAddr, token: String, hooks: &Hooks) -> Result<()> {
+    let listener = TcpListener::bind(listen).await.with_context(|| format!("listening on {}", listen))?;
+    if !listen.ip().is_loopback() {
+        eprintln!("Warning: {} is reachable from other machines, and the gateway speaks plain HTTP", listen);
+    }
+    eprintln!("Gateway listening on ws://{0} and http://{0}", listener.local_addr()?);
+    tracing::info!(%listen, "gateway started");
+
+    let (lines_tx, lines) = mpsc::unbounded_channel();
+    let (events, _) = broadcast::channel::<Value>(1024);
+    let rooms = Rooms::default();
+    let shared = Arc::new(Shared { token, lines: lines_tx, events: events.clone(), rooms: rooms.clone(), next: AtomicU64::new(1) });
+    let accept = tokio::spawn(async move {
+        loop {
+            match listener.accept().await {
+                Ok((stream, peer)) => {
+                    tokio::spawn(connection(stream, peer, shared.clone()));
+                }
+                Err(err) => tracing::warn!(%err, "gateway accept failed"),
+            }
+        }
+    });
+
+    let out = move |event: Value| {
+        let room = event["room"].as_str().unwrap_or_default().to_string();
+        match event["event"].as_str() {
+            Some("ready") => {
+                let mut ready = event.clone();
+                if let Some(ready) = ready.as_object_mut() {
+                    ready.remove("ref");
+                }
+                rooms.lock().unwrap_or_else(|e| e.into_inner()).insert(room, ready);
+            }
+            Some("left" | "destroyed") => {
+                rooms.lock().unwrap_or_else(|e| e.into_inner()).remove(&room);
+            }
+            _ => {}
+        }
+        // Nobody connected is fine; the rooms stay open.
+        let _ = events.send(event);
+    };
+    let result = tokio::select! {
+        result = pipe::serve(endpoint, gossip, None, my_name, lines, hooks, &out) => result,
+        _ = tokio::signal::ctrl_c() => Ok(()),
+    };
+    accept.abort();
+    result
+}
@@ -0,0 +1,399 @@
+use std::{collections::BTreeMap, sync::mpsc as channel};
+
+use anyhow::{anyhow, Result};
+use eframe::egui::{self, Color32, RichText};
+use futures_lite::StreamExt;
+use ghost_messaging::{client, clock, names, presence, rich, ChatEvent, GhostClient};
+use iroh::NodeId;
+use iroh_gossip::proto::TopicId;
+use tokio::sync::mpsc;
+
+use crate::i18n::t;
+
+/// What the window asks of the network task.
+enum Command {
+    /// Joins the room `ticket` invites to, or hosts a new one.
+    Open { name: String, ticket: Option<String> },
+    Send(String),
+    Leave,
+}
+
+/// What the network task tells the window.
+enum Update {
+    Opened { ticket: String, topic: TopicId, me: NodeId },
+    Failed(String),
+    Event(Box<ChatEvent>),
+    Sent { id: String, text: String },
+    /// Left, or the room ended; back to the start.
+    Closed,
+}
+
+/// One line of the conversation: a message, or a notice when `from` is `None`.
+struct Line {
+    id: Option<String>,
+    from: Option<NodeId>,
+    time: String,
+    sender: String,
+    text: String,
+    /// The sender's formatting; dropped once the text is edited.
+    rich: Vec<rich::Span>,
+    urgent: bool,
+    edited: bool,
+    deleted: bool,
+    /// Who reacted with what.
+    reactions: BTreeMap<String, Vec<NodeId>>,
+}
+
+impl Line {
+    fn notice(text: String) -> Self {
+        Line {
+            id: None, from: None, time: clock::now().format("%H:%M").to_string(), sender: String::new(), text, rich: Vec::new(),
+            urgent: false, edited: false, deleted: false, reactions: BTreeMap::new(),
+        }
+    }
+}
+
+/// An open room, as the window shows it.
+struct Room {
+    ticket: String,
+    me: NodeId,
+    lines: Vec<Line>,
+    peers: BTreeMap<NodeId, (String, presence::Status)>,
+    input: String,
+}
+
+impl Room {
+    fn name(&self, node: NodeId) -> String {
+        self.peers.get(&node).map_or_else(|| t!("unknown-peer"), |(name, _)| name.clone())
+    }
+
+    fn line(&mut self, id: &str) -> Option<&mut Line> {
+        self.lines.iter_mut().find(|line| line.id.as_deref() == Some(id))
+    }
+
+    fn apply(&mut self, event: ChatEvent) {
+        match event {
+            ChatEvent::Message { id, from, sender, text, rich, urgent, announcement, .. } => {
+                let sender = if announcement { format!("📣 {}", sender) } else { sender };
+                let time = clock::now().format("%H:%M").to_string();
+                self.lines.push(Line { id: Some(id), from: Some(from), time, sender, text, rich, urgent, edited: false, deleted: false, reactions: BTreeMap::new() });
+            }
+            ChatEvent::Edit { target, from, text, .. } => {
+                if let Some(line) = self.line(&target).filter(|line| line.from == Some(from)) {
+                    line.text = text;
+                    line.rich.clear();
+                    line.edited = true;
+                }
+            }
+            ChatEvent::Reaction { target, from, emoji, remove, .. } => {
+                if let Some(line) = self.line(&target) {
+                    let nodes = line.reactions.entry(emoji.clone()).or_default();
+                    nodes.retain(|node| *node != from);
+                    if !remove {
+                        nodes.push(from);
+                    }
+                    if nodes.is_empty() {
+                        line.reactions.remove(&emoji);
+                    }
+                }
+            }
+            ChatEvent::Redact { target, from, moderator, .. } => {
+                if let Some(line) = self.line(&target).filter(|line| moderator || line.from == Some(from)) {
+                    line.deleted = true;
+                }
+            }
+            ChatEvent::Peer { node, name, old } => {
+                let notice = match old {
+                    Some(old) => t!("peer-renamed", old = old, name = name.as_str()),
+                    None => t!("plain-joined", name = name.as_str()),
+                };
+                self.peers.entry(node).or_default().0 = name;
+                self.lines.push(Line::notice(notice));
+            }
+            ChatEvent::Presence { node, status } => {
+                self.peers.entry(node).or_default().1 = status;
+            }
+            ChatEvent::Destroyed => self.lines.push(Line::notice(t!("gui-destroyed"))),
+            ChatEvent::Error(message) => self.lines.push(Line::notice(message)),
+            ChatEvent::Receipt { .. } | ChatEvent::Outdated { .. } | ChatEvent::NeighborUp(_) | ChatEvent::NeighborDown(_) | ChatEvent::Unsupported { .. }
+            | ChatEvent::Gap { .. } | ChatEvent::Muted(_) | ChatEvent::Banned { .. } | ChatEvent::Info { .. } | ChatEvent::Redeemed { .. }
+            | ChatEvent::Other { .. } => {}
+        }
+    }
+
+    /// The message list, newest at the bottom.
+    fn messages(&self, ui: &mut egui::Ui) {
+        egui::ScrollArea::vertical().auto_shrink([false, false]).stick_to_bottom(true).show(ui, |ui| {
+            for line in &self.lines {
+                ui.horizontal_wrapped(|ui| {
+                    ui.label(RichText::new(&line.time).weak().monospace());
+                    let Some(from) = line.from else {
+                        ui.label(RichText::new(&line.text).italics().weak());
+                        return;
+                    };
+                    let sender = if from == self.me { t!("you-suffix", name = line.sender.as_str()) } else { line.sender.clone() };
+                    ui.label(RichText::new(format!("{}:", sender)).strong().color(color(from)));
+                    if line.deleted {
+                        ui.label(RichText::new(t!("message-deleted")).italics().weak());
+                        return;
+                    }
+                    if line.urgent {
+                        ui.label(RichText::new("❗").color(Color32::RED));
+                    }
+                    if line.rich.is_empty() {
+                        ui.label(&line.text);
+                    }
+                    for span in &line.rich {
+                        let mut text = RichText::new(&span.text);
+                        if span.bold {
+                            text = text.strong();
+                        }
+                        if span.italic {
+                            text = text.italics();
+                        }
+                        if span.code || span.block {
+                            text = text.code();
+                        }
+                        match (&span.link, span.mention) {
+                            (Some(url), _) => {
+                                ui.hyperlink_to(text, url);
+                            }
+                            (None, Some(node)) => {
+                                ui.label(text.color(color(node)).strong());
+                            }
+                            (None, None) => {
+                                ui.label(text);
+                            }
+                        }
+                    }
+                    if line.edited {
+                        ui.label(RichText::new(t!("edited")).weak());
+                    }
+                    for (emoji, nodes) in &line.reactions {
+                        ui.label(RichText::new(format!("{} {}", emoji, nodes.len())).small()).on_hover_text(
+                            nodes.iter().map(|node| self.name(*node)).collect::<Vec<_>>().join(", "),
+                        );
+                    }
+                });
+            }
+        });
+    }
+
+    /// The sidebar: us, then everyone heard from, each with their presence.
+    fn peers(&self, ui: &mut egui::Ui, my_name: &str) {
+        ui.heading(t!("sidebar-title"));
+        ui.label(RichText::new(t!("sidebar-you", name = my_name)).strong().color(color(self.me)));
+        let mut peers: Vec<_> = self.peers.iter().collect();
+        peers.sort_by_key(|(_, (name, _))| name.to_lowercase());
+        for (node, (name, status)) in peers {
+            ui.horizontal(|ui| {
+                ui.label(RichText::new("●").color(presence(status.presence)));
+                ui.label(RichText::new(if name.is_empty() { t!("unknown-peer") } else { name.clone() }).color(color(*node)))
+                    .on_hover_text(format!("{}\n{}", status.describe(), node.fmt_short()));
+            });
+        }
+    }
+}
+
+/// Each sender keeps a colour of their own, picked from their node id.
+fn color(node: NodeId) -> Color32 {
+    let hue = f32::from(node.as_bytes()[0]) / 255.0;
+    egui::ecolor::Hsva::new(hue, 0.55, 0.85, 1.0).into()
+}
+
+fn presence(presence: presence::Presence) -> Color32 {
+    match presence {
+        presence::Presence::Online => Color32::GREEN,
+        presence::Presence::Away => Color32::YELLOW,
+        presence::Presence::Busy => Color32::RED,
+    }
+}
+
+enum Screen {
+    /// Asking for a name and a ticket, with why the last attempt failed.
+    Start { error: Option<String> },
+    Connecting,
+    Chat(Room),
+}
+
+struct App {
+    name: String,
+    ticket: String,
+    screen: Screen,
+    commands: mpsc::UnboundedSender<Command>,
+    updates: channel::Receiver<Update>,
+}
+
+impl App {
+    fn open(&mut self) {
+        match names::parse(&self.name) {
+            Ok(name) => {
+                let ticket = Some(self.ticket.trim().to_string()).filter(|ticket| !ticket.is_empty());
+                let _ = self.commands.send(Command::Open { name, ticket });
+                self.screen = Screen::Connecting;
+            }
+            Err(err) => self.screen = Screen::Start { error: Some(err) },
+        }
+    }
+
+    fn update(&mut self, update: Update) {
+        match (update, &mut self.screen) {
+            (Update::Opened { ticket, topic, me }, _) => {
+                let lines = vec![Line::notice(t!("gui-joined", room = format!("#{}", &topic.to_string()[..8])))];
+                self.screen = Screen::Chat(Room { ticket, me, lines, peers: BTreeMap::new(), input: String::new() });
+            }
+            (Update::Failed(err), _) => self.screen = Screen::Start { error: Some(t!("gui-failed", error = err)) },
+            (Update::Event(event), Screen::Chat(room)) => room.apply(*event),
+            (Update::Sent { id, text }, Screen::Chat(room)) => {
+                let me = room.me;
+                let time = clock::now().format("%H:%M").to_string();
+                room.lines.push(Line { id: Some(id), from: Some(me), time, sender: self.name.clone(), text, rich: Vec::new(), urgent: false, edited: false, deleted: false, reactions: BTreeMap::new() });
+            }
+            (Update::Closed, Screen::Chat(_) | Screen::Connecting) => self.screen = Screen::Start { error: None },
+            _ => {}
+        }
+    }
+
+    fn start(&mut self, ui: &mut egui::Ui, error: Option<String>) {
+        egui::CentralPanel::default_margins().show(ui, |ui| {
+            ui.vertical_centered(|ui| {
+                ui.add_space(40.0);
+                ui.heading("👻 GhostTerm");
+                ui.add_space(20.0);
+                ui.label(t!("gui-name"));
+                ui.text_edit_singleline(&mut self.name);
+                ui.add_space(10.0);
+                ui.label(t!("gui-ticket"));
+                ui.add(egui::TextEdit::singleline(&mut self.ticket).hint_text(t!("gui-ticket-hint")).desired_width(400.0));
+                ui.add_space(10.0);
+                let label = if self.ticket.trim().is_empty() { t!("gui-host") } else { t!("gui-join") };
+                if ui.button(label).clicked() {
+                    self.open();
+                }
+                if let Some(error) = error {
+                    ui.add_space(10.0);
+                    ui.colored_label(Color32::RED, error);
+                }
+            });
+        });
+    }
+
+    fn chat(ui: &mut egui::Ui, room: &mut Room, my_name: &str, commands: &mpsc::UnboundedSender<Command>) {
+        egui::Panel::top("room").show(ui, |ui| {
+            ui.horizontal(|ui| {
+                if ui.button(t!("gui-copy-ticket")).clicked() {
+                    ui.ctx().copy_text(room.ticket.clone());
+                }
+                if ui.button(t!("gui-leave")).clicked() {
+                    let _ = commands.send(Command::Leave);
+                }
+            });
+        });
+        egui::Panel::right("peers").default_size(180.0).show(ui, |ui| room.peers(ui, my_name));
+        egui::Panel::bottom("input").show(ui, |ui| {
+            ui.horizontal(|ui| {
+                let input = ui.add(egui::TextEdit::singleline(&mut room.input).hint_text(t!("gui-input-hint")).desired_width(ui.available_width() - 80.0));
+                let entered = input.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                if (ui.button(t!("gui-send")).clicked() || entered) && !room.input.trim().is_empty() {
+                    let _ = commands.send(Command::Send(std::mem::take(&mut room.input)));
+                }
+                if entered {
+                    input.request_focus();
+                }
+            });
+        });
+        egui::CentralPanel::default_margins().show(ui, |ui| room.messages(ui));
+    }
+}
+
+impl eframe::App for App {
+    fn logic(&mut self, _ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        while let Ok(update) = self.updates.try_recv() {
+            self.update(update);
+        }
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, _frame: &mut eframe::Frame) {
+        match &mut self.screen {
+            Screen::Start { error } => {
+                let error = error.clone();
+                self.start(ui, error);
+            }
+            Screen::Connecting => {
+                egui::CentralPanel::default_margins().show(ui, |ui| {
+                    ui.vertical_centered(|ui| {
+                        ui.add_space(40.0);
+                        ui.spinner();
+                        ui.label(t!("connecting"));
+                    });
+                });
+            }
+            Screen::Chat(room) => App::chat(ui, room, &self.name, &self.commands),
+        }
+    }
+}
+
+/// Runs the window's commands against one room at a time, passing back everything that happens.
+async fn network(options: client::Options, mut commands: mpsc::UnboundedReceiver<Command>, updates: channel::Sender<Update>, ctx: egui::Context) {
+    let update = |update: Update| {
+        let _ = updates.send(update);
+        ctx.request_repaint();
+    };
+    while let Some(command) = commands.recv().await {
+        let Command::Open { name, ticket } = command else { continue };
+        let opened = match &ticket {
+            Some(ticket) => GhostClient::join(ticket, &name, options.clone()).await,
+            None => GhostClient::host(&name, options.clone()).await,
+        };
+        let mut client = match opened {
+            Ok(client) => client,
+            Err(err) => {
+                update(Update::Failed(format!("{:#}", err)));
+                continue;
+            }
+        };
+        update(Update::Opened { ticket: client.ticket().to_string(), topic: client.topic(), me: client.node_id() });
+        loop {
+            tokio::select! {
+                command = commands.recv() => match command {
+                    Some(Command::Send(text)) => match client.send(&text).await {
+                        Ok(id) => update(Update::Sent { id, text }),
+                        Err(err) => update(Update::Event(Box::new(ChatEvent::Error(err.to_string())))),
+                    },
+                    Some(Command::Open { .. }) => {}
+                    Some(Command::Leave) | None => break,
+                },
+                event = client.next() => match event {
+                    Some(event) => update(Update::Event(Box::new(event))),
+                    // The room was destroyed; its last event said so.
+                    None => break,
+                },
+            }
+        }
+        if let Err(err) = client.shutdown().await {
+            tracing::warn!(%err, "could not shut the node down cleanly");
+        }
+        update(Update::Closed);
+    }
+}
+
+/// `ghost gui`: the chat in a desktop window instead of the terminal, on the same library.
+/// With a `ticket` it joins that room straight away; otherwise it asks.
+pub async fn run(name: String, ticket: Option<String>, options: client::Options) -> Result<()> {
+    let ctx = egui::Context::default();
+    let (commands, receiver) = mpsc::unbounded_channel();
+    let (updates, updates_rx) = channel::channel();
+    let task = tokio::spawn(network(options, receiver, updates, ctx.clone()));
+    let mut app = App { name, ticket: ticket.clone().unwrap_or_default(), screen: Screen::Start { error: None }, commands: commands.clone(), updates: updates_rx };
+    if ticket.is_some() {
+        app.open();
+    }
+    let viewport = egui::ViewportBuilder::default().with_title("GhostTerm").with_inner_size([900.0, 600.0]).with_min_inner_size([480.0, 320.0]);
+    let options = eframe::NativeOptions { viewport, ..Default::default() };
+    let shown = tokio::task::block_in_place(|| eframe::run_native_ext("GhostTerm", options, Some(ctx), Box::new(|_| Ok(Box::new(app)))));
+    // The window closed: leave the room properly before the runtime goes away.
+    let _ = commands.send(Command::Leave);
+    drop(commands);
+    let _ = tokio::time::timeout(std::time::Duration::from_secs(5), task).await;
+    shown.map_err(|err| anyhow!("could not open a window: {}", err))
+}
@@ -0,0 +1,98 @@
+use ratatui::{
+    prelude::*,
+    widgets::{Block, BorderType, Borders, Clear, Padding, Paragraph},
+};
+
+use crate::{commands::COMMANDS, centered, i18n::t, store, AppState};
+
+/// Keybindings shown in the help overlay, with the message id of their description.
+const KEYS: &[(&str, &str)] = &[
+    ("Enter", "key-send"),
+    ("Alt+Enter", "key-newline"),
+    ("←/→ Home/End", "key-cursor"),
+    ("↑ (no input)", "key-edit"),
+    ("Ctrl+E", "key-emoji"),
+    ("Ctrl+F", "key-search"),
+    ("PgUp / PgDn", "key-scroll"),
+    ("Ctrl+S", "key-select"),
+    ("Mouse", "key-mouse"),
+    ("Ctrl+P", "key-peers"),
+    ("Alt+1..9", "key-tabs"),
+    ("F2", "key-sidebar"),
+    ("F3", "key-density"),
+    ("F4", "key-pins"),
+    ("F6", "key-focus"),
+    ("F12", "key-boss"),
+    ("F1", "key-help"),
+    ("Esc", "key-esc"),
+    ("Ctrl+Q", "key-quit"),
+];
+
+/// Extra keys in the `--keys vim` normal mode.
+const VIM_KEYS: &[(&str, &str)] = &[
+    ("i a I A", "vim-insert"),
+    (":", "vim-command"),
+    ("j / k", "vim-scroll"),
+    ("gg / G", "vim-ends"),
+    ("/", "vim-search"),
+    ("v", "key-select"),
+    ("h l 0 $ x dd", "vim-edit"),
+];
+
+/// Renders the F1 / `/help` overlay on top of the chat.
+pub fn render(frame: &mut Frame, state: &AppState) {
+    let heading = Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD);
+    let key = Style::default().fg(Color::White).add_modifier(Modifier::BOLD);
+    let dim = Style::default().fg(Color::Gray);
+
+    let mut lines = vec![Line::styled(t!("help-keys"), heading)];
+    for (k, desc) in KEYS {
+        lines.push(Line::from(vec![Span::styled(format!("  {:<14}", k), key), Span::styled(t!(desc), dim)]));
+    }
+
+    if state.vim.is_some() {
+        lines.push(Line::raw(""));
+        lines.push(Line::styled(t!("help-vim"), heading));
+        for (k, desc) in VIM_KEYS {
+            lines.push(Line::from(vec![Span::styled(format!("  {:<14}", k), key), Span::styled(t!(desc), dim)]));
+        }
+    }
+
+    lines.push(Line::raw(""));
+    lines.push(Line::styled(t!("help-commands"), heading));
+    for (cmd, desc) in COMMANDS {
+        lines.push(Line::from(vec![Span::styled(format!("  {:<14}", cmd), key), Span::styled(t!(desc), dim)]));
+    }
+    for (cmd, desc) in state.plugins.commands() {
+        lines.push(Line::from(vec![Span::styled(format!("  {:<14}", cmd), key), Span::styled(desc, dim)]));
+    }
+
+    lines.push(Line::raw(""));
+    lines.push(Line::styled(t!("help-security"), heading));
+    let room = state.room();
+    let topic = room.topic.to_string();
+    let security = [
+        (t!("security-transport"), t!("security-transport-value")),
+        (t!("security-room"), topic[..16].to_string()),
+        (t!("security-node-id"), state.node_id.fmt_short()),
+        (t!("security-peers"), room.peer_names.len().to_string()),
+        (t!("security-history"), state.archive.as_ref().map_or_else(|| t!("security-history-value"), store::Archive::summary)),
+        (t!("security-names"), t!("security-names-value")),
+        (t!("security-previews"), if state.previews.is_some() { t!("security-previews-on") } else { t!("security-previews-off") }),
+    ];
+    for (label, value) in security {
+        lines.push(Line::from(vec![Span::styled(format!("  {:<14}", label), key), Span::styled(value, dim)]));
+    }
+
+    let area = centered(frame.area(), 64, lines.len() as u16 + 4);
+    let popup = Paragraph::new(lines).block(Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(Color::Cyan))
+        .title(format!(" {} ", t!("help-title")))
+        .title_bottom(Line::from(format!(" {} ", t!("esc-to-close"))).right_aligned())
+        .padding(Padding::new(1, 1, 1, 0)));
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(popup, area);
+}
@@ -0,0 +1,63 @@
+use std::{io::Write, process::{Command, Stdio}};
+
+use clap::Args;
+use serde_json::Value;
+
+/// Programs run on chat events, each handed the event as one line of JSON on stdin (the
+/// same events `pipe` prints). Usually set in the config file, like `on_mention = "..."`.
+#[derive(Args, Clone, Default)]
+pub struct Hooks {
+    /// Shell command to run for every message or image from someone else
+    #[arg(long, global = true, value_name = "COMMAND")]
+    on_message: Option<String>,
+    /// Shell command to run for messages that @mention you
+    #[arg(long, global = true, value_name = "COMMAND")]
+    on_mention: Option<String>,
+    /// Shell command to run when a peer first introduces itself in a room
+    #[arg(long, global = true, value_name = "COMMAND")]
+    on_join: Option<String>,
+}
+
+impl Hooks {
+    /// For a `message` or `image` event.
+    pub fn message(&self, event: &Value, mentions_me: bool) {
+        spawn(self.on_message.as_deref(), event);
+        if mentions_me {
+            spawn(self.on_mention.as_deref(), event);
+        }
+    }
+
+    /// For the `peer` event of a peer's first introduction.
+    pub fn join(&self, event: &Value) {
+        spawn(self.on_join.as_deref(), event);
+    }
+}
+
+/// Starts `command` through the shell without waiting for it. Its output is discarded, since
+/// it would garble the TUI or the `pipe` event stream.
+fn spawn(command: Option<&str>, event: &Value) {
+    let Some(command) = command else { return };
+    let mut shell = if cfg!(windows) { Command::new("cmd") } else { Command::new("sh") };
+    shell.args([if cfg!(windows) { "/C" } else { "-c" }, command]);
+    shell.stdin(Stdio::piped()).stdout(Stdio::null()).stderr(Stdio::null());
+    let mut child = match shell.spawn() {
+        Ok(child) => child,
+        Err(err) => {
+            tracing::warn!(%err, command, "could not start hook");
+            return;
+        }
+    };
+    let line = format!("{}\n", event);
+    let command = command.to_string();
+    std::thread::spawn(move || {
+        if let Some(mut stdin) = child.stdin.take() {
+            // A hook that ignores its input may exit before reading it.
+            let _ = stdin.write_all(line.as_bytes());
+        }
+        match child.wait() {
+            Ok(status) if !status.success() => tracing::debug!(%status, command, "hook failed"),
+            Ok(_) => {}
+            Err(err) => tracing::warn!(%err, command, "hook vanished"),
+        }
+    });
+}
@@ -0,0 +1,103 @@
+use std::sync::OnceLock;
+
+use fluent_bundle::{concurrent::FluentBundle, FluentResource};
+#[doc(hidden)]
+pub use fluent_bundle::FluentArgs;
+use unic_langid::LanguageIdentifier;
+
+/// Bundled translations of the interface, by language code. Anything missing from one
+/// falls back to English.
+const LOCALES: &[(&str, &str)] = &[
+    ("en", include_str!("../locales/en.ftl")),
+    ("de", include_str!("../locales/de.ftl")),
+    ("es", include_str!("../locales/es.ftl")),
+];
+
+struct Catalog {
+    chosen: Option<FluentBundle<FluentResource>>,
+    english: FluentBundle<FluentResource>,
+}
+
+static CATALOG: OnceLock<Catalog> = OnceLock::new();
+
+/// `de_DE.UTF-8` or `de-AT` -> `de`, if it's a bundled language.
+fn normalize(locale: &str) -> Option<&'static str> {
+    let code = locale.split(['_', '-', '.', '@']).next()?.to_lowercase();
+    LOCALES.iter().map(|(c, _)| *c).find(|c| *c == code)
+}
+
+/// Validates `--lang`.
+pub fn language(code: &str) -> Result<String, String> {
+    match normalize(code) {
+        Some(code) => Ok(code.to_string()),
+        None => {
+            let known: Vec<&str> = LOCALES.iter().map(|(c, _)| *c).collect();
+            Err(format!("no translation for '{}' (available: {})", code, known.join(", ")))
+        }
+    }
+}
+
+/// The language the environment asks for, the way gettext looks it up.
+fn from_env() -> Option<&'static str> {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.is_empty())
+        .and_then(|value| normalize(&value))
+}
+
+fn bundle(code: &str) -> Option<FluentBundle<FluentResource>> {
+    let (_, source) = LOCALES.iter().find(|(c, _)| *c == code)?;
+    let lang: LanguageIdentifier = code.parse().ok()?;
+    let resource = FluentResource::try_new(source.to_string()).unwrap_or_else(|(resource, errors)| {
+        tracing::warn!(code, ?errors, "translation has syntax errors");
+        resource
+    });
+    let mut bundle = FluentBundle::new_concurrent(vec![lang]);
+    // Unicode isolation marks show up as junk in many terminals.
+    bundle.set_use_isolating(false);
+    bundle.add_resource_overriding(resource);
+    Some(bundle)
+}
+
+fn catalog(code: Option<&str>) -> Catalog {
+    let code = code.and_then(normalize).or_else(from_env).filter(|c| *c != "en");
+    Catalog { chosen: code.and_then(bundle), english: bundle("en").expect("English is bundled") }
+}
+
+/// Picks the interface language: `lang` (from `--lang`), else the locale environment, else
+/// English. Call before anything is translated.
+pub fn init(lang: Option<&str>) {
+    let _ = CATALOG.set(catalog(lang));
+}
+
+/// The text for message `id`; use [`t!`] rather than calling this.
+pub fn text(id: &str, args: Option<&FluentArgs>) -> String {
+    let catalog = CATALOG.get_or_init(|| catalog(None));
+    for bundle in catalog.chosen.iter().chain([&catalog.english]) {
+        if let Some(pattern) = bundle.get_message(id).and_then(|m| m.value()) {
+            let mut errors = Vec::new();
+            let text = bundle.format_pattern(pattern, args, &mut errors);
+            if !errors.is_empty() {
+                tracing::debug!(id, ?errors, "translation failed");
+            }
+            return text.into_owned();
+        }
+    }
+    tracing::debug!(id, "untranslated message");
+    id.to_string()
+}
+
+/// Translated interface text: `t!("quit-title")` or `t!("status-peers", count = n)`.
+#[macro_export]
+macro_rules! t {
+    ($id:expr) => {
+        $crate::i18n::text($id, None)
+    };
+    ($id:expr, $($name:ident = $value:expr),+ $(,)?) => {{
+        let mut args = $crate::i18n::FluentArgs::new();
+        $(args.set(stringify!($name), $value);)+
+        $crate::i18n::text($id, Some(&args))
+    }};
+}
+pub use crate::t;
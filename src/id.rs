@@ -0,0 +1,141 @@
+use std::{io::Write, path::{Path, PathBuf}};
+
+use anyhow::{anyhow, bail, Context, Result};
+use crossterm::{
+    event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    terminal::{disable_raw_mode, enable_raw_mode},
+};
+use iroh::SecretKey;
+
+use crate::{i18n::t, identity};
+
+/// `ghost id ...`
+#[derive(clap::Subcommand)]
+pub enum Action {
+    /// Create a new identity (a new NodeId)
+    New {
+        /// Replace the existing identity; peers who verified it will see a stranger
+        #[arg(long)]
+        force: bool,
+    },
+    /// Print the NodeId and fingerprint peers see
+    Show,
+    /// Write the identity file (still encrypted, if it is) to a file or stdout
+    Export {
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+    /// Restore an identity file made by `export`
+    Import {
+        file: PathBuf,
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+/// Reads a line from the terminal without echoing it. Esc or Ctrl+C cancels.
+pub fn prompt_hidden(prompt: &str) -> Result<String> {
+    print!("{}", prompt);
+    std::io::stdout().flush()?;
+    enable_raw_mode()?;
+    let mut line = String::new();
+    let result = loop {
+        match event::read() {
+            Ok(Event::Key(key)) if key.kind == KeyEventKind::Press => match key.code {
+                KeyCode::Enter => break Ok(()),
+                KeyCode::Esc => break Err(anyhow!("cancelled")),
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => break Err(anyhow!("cancelled")),
+                KeyCode::Backspace => {
+                    line.pop();
+                }
+                KeyCode::Char(c) => line.push(c),
+                _ => {}
+            },
+            Ok(_) => {}
+            Err(err) => break Err(err.into()),
+        }
+    };
+    // Back to a normal terminal whatever happened, before reporting either error.
+    let restored = disable_raw_mode();
+    println!();
+    result?;
+    restored?;
+    Ok(line)
+}
+
+/// Asks for the passphrase of an identity file.
+fn unlock() -> Result<String> {
+    prompt_hidden(&format!("{}: ", t!("identity-passphrase")))
+}
+
+/// The key file's contents for `key`, asking for a new passphrase twice when `protect` is set.
+fn encode(key: &SecretKey, protect: bool) -> Result<String> {
+    if !protect {
+        return Ok(identity::encode(key, None));
+    }
+    let passphrase = prompt_hidden(&format!("{}: ", t!("identity-new-passphrase")))?;
+    if passphrase.is_empty() {
+        bail!(t!("identity-empty-passphrase"));
+    }
+    if prompt_hidden(&format!("{}: ", t!("identity-repeat-passphrase")))? != passphrase {
+        bail!(t!("identity-mismatch"));
+    }
+    Ok(identity::encode(key, Some(&passphrase)))
+}
+
+/// This node's long-lived key from `dir`, created on first run, so the NodeId peers
+/// have verified stays the same across restarts.
+pub fn load_or_create(dir: &Path, protect: bool) -> Result<SecretKey> {
+    if let Some(key) = identity::load(dir, unlock)? {
+        return Ok(key);
+    }
+    let path = identity::path(dir);
+    let key = SecretKey::generate(rand::rngs::OsRng);
+    identity::save(&path, &encode(&key, protect)?)?;
+    eprintln!("{}", t!("identity-created", id = key.public().fmt_short(), path = path.display().to_string()));
+    Ok(key)
+}
+
+fn describe(key: &SecretKey) {
+    let id = key.public();
+    println!("{:<13}{}", format!("{}:", t!("ticket-node-id")), id);
+    println!("{:<13}{}", format!("{}:", t!("peer-fingerprint")), identity::fingerprint(&id));
+}
+
+/// Runs a `ghost id` subcommand against the identity kept in `dir`.
+pub fn run(action: &Action, dir: Option<PathBuf>, protect: bool) -> Result<()> {
+    let path = identity::path(&dir.ok_or_else(|| anyhow!("no config directory (set HOME or XDG_CONFIG_HOME)"))?);
+    let refuse = |force: bool| {
+        if path.exists() && !force {
+            bail!(t!("identity-exists", path = path.display().to_string()));
+        }
+        Ok(())
+    };
+    match action {
+        Action::New { force } => {
+            refuse(*force)?;
+            let key = SecretKey::generate(rand::rngs::OsRng);
+            identity::save(&path, &encode(&key, protect)?)?;
+            describe(&key);
+        }
+        Action::Show => {
+            let text = std::fs::read_to_string(&path).with_context(|| t!("identity-missing", path = path.display().to_string()))?;
+            describe(&identity::decode(&text, unlock)?);
+        }
+        Action::Export { out } => {
+            let text = std::fs::read_to_string(&path).with_context(|| t!("identity-missing", path = path.display().to_string()))?;
+            match out {
+                Some(out) => identity::save(out, &text)?,
+                None => println!("{}", text.trim()),
+            }
+        }
+        Action::Import { file, force } => {
+            refuse(*force)?;
+            let text = std::fs::read_to_string(file).with_context(|| format!("reading {}", file.display()))?;
+            let key = identity::decode(&text, unlock)?;
+            identity::save(&path, text.trim())?;
+            describe(&key);
+        }
+    }
+    Ok(())
+}
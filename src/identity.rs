@@ -0,0 +1,139 @@
+use std::{io::Write, path::{Path, PathBuf}};
+
+use anyhow::{anyhow, bail, Context, Result};
+use crypto_secretbox::{aead::{Aead, KeyInit}, XSalsa20Poly1305};
+use hmac::{Hmac, Mac};
+use iroh::{NodeId, SecretKey};
+use sha2::Sha256;
+
+use crate::i18n::t;
+
+
+const FILE: &str = "identity.key";
+
+/// Prefix of a passphrase-protected key file; plain files are just the key in hex.
+const ENCRYPTED: &str = "encrypted:";
+const ROUNDS: u32 = 210_000;
+
+/// PBKDF2-HMAC-SHA256, one 32-byte block.
+fn derive(passphrase: &str, salt: &[u8]) -> [u8; 32] {
+    let mut out = [0; 32];
+    pbkdf2::pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), salt, ROUNDS, &mut out);
+    out
+}
+
+/// Encrypts `plain` under `key`, as nonce ‖ ciphertext.
+pub fn seal(key: &[u8; 32], plain: &[u8]) -> Vec<u8> {
+    let nonce: [u8; 24] = rand::random();
+    let sealed = XSalsa20Poly1305::new(key.into()).encrypt(&nonce.into(), plain).expect("in-memory encryption");
+    [&nonce[..], &sealed].concat()
+}
+
+/// Reverses [`seal`].
+pub fn open(key: &[u8; 32], data: &[u8]) -> Result<Vec<u8>> {
+    if data.len() < 24 {
+        bail!("truncated");
+    }
+    let (nonce, sealed) = data.split_at(24);
+    XSalsa20Poly1305::new(key.into()).decrypt(nonce.into(), sealed).map_err(|_| anyhow!("wrong key"))
+}
+
+/// A key for encrypting local files, derived from the identity so they need no
+/// passphrase of their own and are exactly as protected as the identity is.
+pub fn subkey(identity: &SecretKey, purpose: &str) -> [u8; 32] {
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(&identity.to_bytes()).expect("HMAC takes any key length");
+    mac.update(purpose.as_bytes());
+    mac.finalize().into_bytes().into()
+}
+
+/// `bytes` signed with the identity, in hex, as announcements carry it.
+pub fn sign(key: &SecretKey, bytes: &[u8]) -> String {
+    hex::encode(key.sign(bytes).to_bytes())
+}
+
+/// Whether `signature`, as `sign` writes it, is `signer`'s over `bytes`.
+pub fn verify(signer: NodeId, bytes: &[u8], signature: &str) -> bool {
+    let Some(signature) = hex::decode(signature).ok().and_then(|b| <[u8; 64]>::try_from(b).ok()) else { return false };
+    signer.verify(bytes, &ed25519_dalek::Signature::from_bytes(&signature)).is_ok()
+}
+
+fn encrypt(key: &SecretKey, passphrase: &str) -> String {
+    let salt: [u8; 16] = rand::random();
+    let sealed = seal(&derive(passphrase, &salt), &key.to_bytes());
+    format!("{}{}", ENCRYPTED, hex::encode([&salt[..], &sealed].concat()))
+}
+
+fn decrypt(data: &str, passphrase: &str) -> Result<SecretKey> {
+    let bytes = hex::decode(data).context("corrupt identity file")?;
+    if bytes.len() < 16 {
+        bail!("corrupt identity file");
+    }
+    let (salt, sealed) = bytes.split_at(16);
+    let plain = open(&derive(passphrase, salt), sealed).map_err(|_| anyhow!(t!("identity-wrong-passphrase")))?;
+    let bytes: [u8; 32] = plain.try_into().map_err(|_| anyhow!("corrupt identity file"))?;
+    Ok(SecretKey::from_bytes(&bytes))
+}
+
+/// Parses a key file's contents, calling `passphrase` for it if it is protected.
+pub fn decode(text: &str, passphrase: impl FnOnce() -> Result<String>) -> Result<SecretKey> {
+    let text = text.trim();
+    match text.strip_prefix(ENCRYPTED) {
+        Some(data) => decrypt(data, &passphrase()?),
+        None => {
+            let bytes: [u8; 32] = hex::decode(text).ok().and_then(|b| b.try_into().ok())
+                .ok_or_else(|| anyhow!("corrupt identity file"))?;
+            Ok(SecretKey::from_bytes(&bytes))
+        }
+    }
+}
+
+/// The key file's contents, protected with `passphrase` if there is one.
+pub fn encode(key: &SecretKey, passphrase: Option<&str>) -> String {
+    match passphrase {
+        Some(passphrase) => encrypt(key, passphrase),
+        None => hex::encode(key.to_bytes()),
+    }
+}
+
+/// Writes a key file readable only by the current user.
+pub fn save(path: &Path, contents: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options.open(path)?.write_all(contents.as_bytes()).with_context(|| format!("writing {}", path.display()))
+}
+
+/// Where `dir` keeps its identity.
+pub fn path(dir: &Path) -> PathBuf {
+    dir.join(FILE)
+}
+
+/// Whether `dir` holds an identity yet.
+pub fn exists(dir: &Path) -> bool {
+    path(dir).exists()
+}
+
+/// This node's long-lived key from `dir`, calling `passphrase` for it if it's protected;
+/// `None` before the first run has made one.
+pub fn load(dir: &Path, passphrase: impl FnOnce() -> Result<String>) -> Result<Option<SecretKey>> {
+    let path = path(dir);
+    match std::fs::read_to_string(&path) {
+        Ok(text) => decode(&text, passphrase).with_context(|| format!("loading {}", path.display())).map(Some),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err).with_context(|| format!("reading {}", path.display())),
+    }
+}
+
+/// The key as a short, readable string to compare out of band: 8 groups of 4 hex digits.
+pub fn fingerprint(id: &NodeId) -> String {
+    let hex: String = id.as_bytes()[..16].iter().map(|b| format!("{:02X}", b)).collect();
+    hex.as_bytes()
+        .chunks(4)
+        .map(|group| std::str::from_utf8(group).unwrap_or(""))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
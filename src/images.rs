@@ -0,0 +1,95 @@
+use std::{io::Read, path::{Path, PathBuf}};
+
+use anyhow::{Context, Result};
+use image::{codecs::jpeg::JpegEncoder, DynamicImage};
+use ratatui::layout::Size;
+use ratatui_image::{picker::Picker, protocol::Protocol, Resize};
+
+use crate::i18n::t;
+
+/// Largest encoded payload we put inline on the gossip topic (before base64).
+const MAX_INLINE: usize = 20 * 1024;
+
+/// Cell budget for an inline preview.
+const PREVIEW_CELLS: Size = Size { width: 32, height: 10 };
+
+/// An image attached to a chat message.
+pub struct Image {
+    pub name: String,
+    pub data: Vec<u8>,
+    /// Terminal graphics for the thumbnail, when the terminal speaks kitty, iTerm2 or sixel.
+    pub preview: Option<Protocol>,
+}
+
+impl Image {
+    /// Wraps received bytes, rejecting anything that isn't a decodable image.
+    pub fn new(name: &str, data: Vec<u8>, picker: Option<&Picker>) -> Result<Self> {
+        let decoded = image::load_from_memory(&data).with_context(|| t!("image-not-an-image"))?;
+        let preview = picker.and_then(|p| p.new_protocol(decoded, PREVIEW_CELLS, Resize::Fit(None)).ok());
+        Ok(Image { name: sanitize(name), data, preview })
+    }
+
+    /// Writes the image to the temp dir and hands it to the system viewer.
+    pub fn open(&self) -> Result<PathBuf> {
+        // The extension comes from the bytes, never from the sender-supplied name,
+        // so a "cat.png.exe" can't be launched through the viewer.
+        let format = image::guess_format(&self.data)?;
+        let ext = format.extensions_str().first().copied().unwrap_or("img");
+        let stem = Path::new(&self.name).file_stem().and_then(|s| s.to_str()).unwrap_or("image");
+
+        let path = std::env::temp_dir().join(format!("ghost-{:08x}-{}.{}", rand::random::<u32>(), stem, ext));
+        std::fs::write(&path, &self.data)?;
+        crate::opener::launch(&path)?;
+        Ok(path)
+    }
+}
+
+/// Hands a downloaded original to the system viewer, once its bytes show it really is the
+/// kind of image its name says.
+pub fn open_original(path: &Path) -> Result<()> {
+    let mut head = Vec::new();
+    std::fs::File::open(path)?.take(64).read_to_end(&mut head)?;
+    let format = image::guess_format(&head).with_context(|| t!("image-not-an-image"))?;
+    let ext = path.extension().and_then(|e| e.to_str()).unwrap_or_default().to_lowercase();
+    anyhow::ensure!(format.extensions_str().contains(&ext.as_str()), t!("image-not-an-image"));
+    crate::opener::launch(path)
+}
+
+/// Graphics support for this terminal, or `None` when only placeholders make sense.
+pub fn detect() -> Option<Picker> {
+    let picker = Picker::from_query_stdio().ok()?;
+    (picker.protocol_type() != ratatui_image::picker::ProtocolType::Halfblocks).then_some(picker)
+}
+
+/// Loads `path` and shrinks it into a JPEG small enough to travel inline.
+pub fn thumbnail(path: &Path) -> Result<Vec<u8>> {
+    let img = image::open(path).with_context(|| t!("image-unreadable", path = path.display().to_string()))?;
+    for (edge, quality) in [(320, 80), (256, 70), (192, 60), (128, 50)] {
+        let bytes = encode(&img.thumbnail(edge, edge), quality)?;
+        if bytes.len() <= MAX_INLINE {
+            return Ok(bytes);
+        }
+    }
+    anyhow::bail!(t!("image-too-detailed"))
+}
+
+fn encode(img: &DynamicImage, quality: u8) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    DynamicImage::ImageRgb8(img.to_rgb8()).write_with_encoder(JpegEncoder::new_with_quality(&mut bytes, quality))?;
+    Ok(bytes)
+}
+
+fn sanitize(name: &str) -> String {
+    file_name(name, "image")
+}
+
+/// Keeps only a plain file name from whatever the sender claimed, or `fallback` if
+/// nothing usable is left.
+pub fn file_name(name: &str, fallback: &str) -> String {
+    let base = Path::new(name).file_name().and_then(|s| s.to_str()).unwrap_or(fallback);
+    let clean: String = base.chars()
+        .map(|c| if c.is_alphanumeric() || matches!(c, '.' | '-' | '_') { c } else { '_' })
+        .take(64)
+        .collect();
+    if clean.is_empty() || clean.chars().all(|c| c == '.') { fallback.to_string() } else { clean }
+}
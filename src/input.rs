@@ -0,0 +1,128 @@
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// The message being typed, edited a grapheme cluster at a time so emoji,
+/// combining marks and CJK text never get split.
+#[derive(Default)]
+pub struct Input {
+    text: String,
+    /// Byte offset of the cursor, always on a grapheme boundary.
+    cursor: usize,
+}
+
+impl Input {
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    /// Replaces the contents, leaving the cursor at the end.
+    pub fn set(&mut self, text: String) {
+        self.cursor = text.len();
+        self.text = text;
+    }
+
+    pub fn clear(&mut self) {
+        self.set(String::new());
+    }
+
+    /// Empties the input, returning what was in it.
+    pub fn take(&mut self) -> String {
+        self.cursor = 0;
+        std::mem::take(&mut self.text)
+    }
+
+    pub fn insert(&mut self, c: char) {
+        self.text.insert(self.cursor, c);
+        self.cursor += c.len_utf8();
+    }
+
+    /// Inserts pasted text as-is, apart from normalising line endings and tabs.
+    pub fn insert_str(&mut self, s: &str) {
+        let clean = s.replace("\r\n", "\n").replace('\r', "\n").replace('\t', "    ");
+        self.text.insert_str(self.cursor, &clean);
+        self.cursor += clean.len();
+    }
+
+    fn prev_boundary(&self) -> Option<usize> {
+        self.text[..self.cursor].grapheme_indices(true).next_back().map(|(i, _)| i)
+    }
+
+    fn next_boundary(&self) -> Option<usize> {
+        self.text[self.cursor..].graphemes(true).next().map(|g| self.cursor + g.len())
+    }
+
+    pub fn backspace(&mut self) {
+        if let Some(start) = self.prev_boundary() {
+            self.text.replace_range(start..self.cursor, "");
+            self.cursor = start;
+        }
+    }
+
+    pub fn delete(&mut self) {
+        if let Some(end) = self.next_boundary() {
+            self.text.replace_range(self.cursor..end, "");
+        }
+    }
+
+    pub fn left(&mut self) {
+        self.cursor = self.prev_boundary().unwrap_or(0);
+    }
+
+    pub fn right(&mut self) {
+        self.cursor = self.next_boundary().unwrap_or(self.text.len());
+    }
+
+    pub fn home(&mut self) {
+        self.cursor = self.text[..self.cursor].rfind('\n').map_or(0, |i| i + 1);
+    }
+
+    pub fn end(&mut self) {
+        self.cursor = self.text[self.cursor..].find('\n').map_or(self.text.len(), |i| self.cursor + i);
+    }
+
+    /// Wraps the text to `width` display columns, returning the screen lines and
+    /// the cursor's (column, row) within them.
+    pub fn layout(&self, width: usize) -> (Vec<String>, (usize, usize)) {
+        let width = width.max(1);
+        let mut lines = vec![String::new()];
+        let mut col = 0;
+        let mut cursor = None;
+
+        for (i, g) in self.text.grapheme_indices(true) {
+            if i == self.cursor {
+                cursor = Some((col, lines.len() - 1));
+            }
+            if g == "\n" || g == "\r\n" {
+                lines.push(String::new());
+                col = 0;
+                continue;
+            }
+            let w = g.width();
+            if col + w > width && col > 0 {
+                lines.push(String::new());
+                col = 0;
+                if i == self.cursor {
+                    cursor = Some((0, lines.len() - 1));
+                }
+            }
+            if let Some(line) = lines.last_mut() {
+                line.push_str(g);
+            }
+            col += w;
+        }
+
+        let cursor = cursor.unwrap_or_else(|| {
+            if col >= width {
+                lines.push(String::new());
+                (0, lines.len() - 1)
+            } else {
+                (col, lines.len() - 1)
+            }
+        });
+        (lines, cursor)
+    }
+}
@@ -0,0 +1,401 @@
+//! The chat itself, without an interface: the wire format, rooms and their history,
+//! moderation, and [`GhostClient`](client::GhostClient) for hosting or joining a room from
+//! other programs. The `ghostterm` binary is a terminal frontend on top of it.
+
+pub mod backfill;
+pub mod chunks;
+pub mod client;
+pub mod clock;
+pub mod expiry;
+pub mod files;
+pub mod flood;
+pub mod i18n;
+pub mod identity;
+pub mod images;
+pub mod input;
+pub mod location;
+pub mod markup;
+pub mod moderation;
+pub mod names;
+pub mod notify;
+pub mod opener;
+pub mod plugins;
+pub mod polls;
+pub mod presence;
+pub mod preview;
+pub mod protocol;
+pub mod reactions;
+pub mod receipts;
+pub mod rich;
+pub mod rooms;
+pub mod saved;
+pub mod scripts;
+pub mod sequence;
+pub mod stego;
+pub mod store;
+pub mod transport;
+pub mod voice;
+pub mod wasm;
+pub mod whereabouts;
+
+use std::{fmt, str::FromStr};
+
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use iroh::NodeAddr;
+use iroh_gossip::proto::TopicId;
+use serde::{Deserialize, Serialize};
+
+pub use client::{ChatEvent, GhostClient};
+
+/// An invite: the room's topic and the nodes to reach it through, the host first.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Ticket {
+    pub topic: TopicId,
+    pub nodes: Vec<NodeAddr>,
+}
+
+impl fmt::Display for Ticket {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let binary_data = bincode::serialize(self).map_err(|_| fmt::Error)?;
+        let s = base64::engine::general_purpose::STANDARD_NO_PAD.encode(binary_data);
+        write!(f, "{}", s)
+    }
+}
+
+impl FromStr for Ticket {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let clean_s = s.trim();
+        let binary_data = base64::engine::general_purpose::STANDARD_NO_PAD.decode(clean_s)?;
+        let ticket = bincode::deserialize(&binary_data)?;
+        Ok(ticket)
+    }
+}
+
+/// Everything sent over gossip. The binary format numbers variants in order, so new ones go
+/// at the end.
+#[derive(Debug, Serialize, Deserialize)]
+pub enum Message {
+    AboutMe {
+        name: String,
+        /// Missing from clients older than the compatibility report.
+        #[serde(default)]
+        protocol: Option<protocol::Info>,
+        /// Missing from clients older than signed names, whose names are taken on trust as
+        /// those of whoever passed them on.
+        #[serde(default)]
+        claim: Option<names::Claim>,
+    },
+    Chat {
+        text: String,
+        /// Random per-message id that replies point at. Empty from older clients.
+        #[serde(default)]
+        id: String,
+        #[serde(default)]
+        reply: Option<Quote>,
+        /// Id of the message whose thread this was posted in.
+        #[serde(default)]
+        thread: Option<String>,
+        /// Sent with `/urgent`: rings through a muted room, a few times an hour at most.
+        #[serde(default)]
+        urgent: bool,
+        /// `text` as the sender formatted it. Empty from older clients, whose Markdown the
+        /// receiver reads itself; `text` is always there for whoever can't use this.
+        #[serde(default)]
+        rich: Vec<rich::Span>,
+        /// Sent with `/tmp`: seconds the message lasts after it arrives, when every
+        /// client that knows the field drops it.
+        #[serde(default)]
+        ttl: Option<u32>,
+        /// Set on a copy of a message from another room.
+        #[serde(default)]
+        forwarded: Option<Forwarded>,
+    },
+    /// A small picture sent inline; `data` is base64 of the encoded file.
+    Image {
+        name: String,
+        data: String,
+        #[serde(default)]
+        id: String,
+        /// The full-size picture, fetched like a `FileOffer`. Missing from older clients.
+        #[serde(default)]
+        original: Option<files::Original>,
+    },
+    /// Replaces the text of our own earlier message `target`.
+    Edit {
+        target: String,
+        text: String,
+        /// As in `Chat`.
+        #[serde(default)]
+        rich: Vec<rich::Span>,
+    },
+    /// Pins (or with `remove`, unpins) a message for the whole room.
+    Pin {
+        quote: Quote,
+        #[serde(default)]
+        remove: bool,
+    },
+    /// Adds (or with `remove`, takes back) an emoji on the message with id `target`.
+    Reaction {
+        target: String,
+        emoji: String,
+        #[serde(default)]
+        remove: bool,
+    },
+    /// The room's name and topic line, sent when they change and to each peer that turns up.
+    /// Ignored from anyone but the host and moderators, and only the host's changes the name.
+    RoomInfo {
+        #[serde(default)]
+        name: String,
+        #[serde(default)]
+        topic: String,
+    },
+    /// A `/poll`: the question and its two to nine options, taking votes until `closes`.
+    Poll {
+        id: String,
+        question: String,
+        options: Vec<String>,
+        closes: DateTime<Utc>,
+    },
+    /// Votes for option `option` (from 0) of poll `target`, or with `remove`, takes it back.
+    Vote {
+        target: String,
+        option: usize,
+        #[serde(default)]
+        remove: bool,
+    },
+    /// A `/loc` point on the map, with what it's for.
+    Location {
+        id: String,
+        lat: f64,
+        lon: f64,
+        #[serde(default)]
+        label: String,
+    },
+    /// The title and description of a link in our message `target`, looked up after sending it.
+    LinkPreview { target: String, preview: preview::Preview },
+    /// Announces a `/nick` change; peers without it pick up the new name from `AboutMe`.
+    NameChange {
+        old: String,
+        name: String,
+        /// As in `AboutMe`.
+        #[serde(default)]
+        claim: Option<names::Claim>,
+    },
+    /// Acknowledges our messages `targets`: they arrived, or with `read`, were on screen.
+    Receipt {
+        targets: Vec<String>,
+        #[serde(default)]
+        read: bool,
+    },
+    /// Sent to our neighbors on joining, asking for the messages from before we came.
+    HistoryRequest {},
+    /// The answer: recent messages, oldest first.
+    History { messages: Vec<backfill::Past> },
+    /// The host, or moderator `by`, putting `node` out of the room for `KICK` from time `at`
+    /// (Unix milliseconds) or for good; `signature` is theirs over what `moderation::announce` signs.
+    Ban {
+        node: iroh::NodeId,
+        #[serde(default)]
+        kick: bool,
+        #[serde(default)]
+        by: Option<iroh::NodeId>,
+        #[serde(default)]
+        at: i64,
+        signature: String,
+    },
+    /// The host giving `node` a role from time `at` (Unix milliseconds) on, signed like `Ban`.
+    Role { node: iroh::NodeId, role: moderation::Role, at: i64, signature: String },
+    /// A file too big for gossip, served by `node` over iroh-blobs for peers to fetch.
+    FileOffer {
+        #[serde(default)]
+        id: String,
+        name: String,
+        size: u64,
+        hash: iroh_blobs::Hash,
+        node: iroh::NodeId,
+        /// Set on a `/voice` clip: its length in milliseconds.
+        #[serde(default)]
+        voice: Option<u32>,
+    },
+    /// A banner from the host or a moderator; ignored from anyone else.
+    Announcement { id: String, text: String },
+    /// The host making the room a broadcast channel from time `at` on, or with `broadcast`
+    /// false an ordinary room again; signed like `Role`.
+    Mode { broadcast: bool, at: i64, signature: String },
+    /// A type from outside GhostTerm, defined by a plugin or bridge and named
+    /// `namespace/type`; `body` is JSON that only whatever defined it reads. Clients
+    /// without it show that something came they can't show, and go on.
+    Extension { id: String, kind: String, body: String },
+    /// Asking `node` to send again what it numbered `from` to `to` in `session`, which
+    /// never reached us.
+    Resend { node: iroh::NodeId, session: u32, from: u64, to: u64 },
+    /// Messages sent again after a `Resend`, each encoded as it first went out.
+    Resent { messages: Vec<Vec<u8>> },
+    /// Whether we're around, set with `/away`, `/busy` and `/back` or on going idle.
+    Status {
+        presence: presence::Presence,
+        #[serde(default)]
+        text: String,
+    },
+    /// The host's `/destroy`: everyone wipes the room and leaves it. Signed like `Ban`.
+    RoomClose { signature: String },
+    /// Deletes message `target` for everyone; from its sender, or the host or a moderator.
+    Redact { target: String },
+    /// Sent once on joining with a ticket, so its host hears the invite was used; `relay`
+    /// is the joiner's home relay, by host name.
+    Redeem { relay: Option<String> },
+}
+
+/// The message a reply answers, carried along so every client can show it
+/// even if it never saw the original.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Quote {
+    pub id: String,
+    pub sender: String,
+    pub snippet: String,
+}
+
+impl Quote {
+    pub fn of(msg: &ChatMessage) -> Self {
+        Quote::new(msg.id.clone(), msg.sender.clone(), &msg.text)
+    }
+
+    /// A quote of message `id` from `sender`, with the start of its `text`.
+    pub fn new(id: String, sender: String, text: &str) -> Self {
+        let first = text.lines().next().unwrap_or("");
+        let mut snippet: String = first.chars().take(80).collect();
+        if snippet.len() < text.len() {
+            snippet.push('…');
+        }
+        Quote { id, sender, snippet }
+    }
+}
+
+/// Where a forwarded message was first posted, shown above the copy. Like a `Quote`, the
+/// forwarder's word only; forwarding a copy again keeps the first.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Forwarded {
+    pub sender: String,
+    /// The label of the room it came from.
+    pub room: String,
+    pub time: DateTime<Utc>,
+}
+
+/// A ULID: unique without coordination, and sorting by id sorts by time sent.
+pub fn new_id() -> String {
+    ulid::Ulid::new().to_string()
+}
+
+/// Who a `ChatMessage` is from.
+#[derive(PartialEq)]
+pub enum Origin {
+    Me,
+    Peer,
+    /// Local notices (command errors etc.), never sent on the wire.
+    System,
+}
+
+/// One line of a room's history, as the interface shows it.
+pub struct ChatMessage {
+    pub id: String,
+    /// Who sent it, for peers' messages; edits are only accepted from the same node.
+    pub from: Option<iroh::NodeId>,
+    pub sender: String,
+    pub text: String,
+    /// In UTC; shown in the `--timezone` zone.
+    pub time: DateTime<Utc>,
+    pub origin: Origin,
+    /// Someone wrote `@<my name>` in this message.
+    pub mentions_me: bool,
+    pub image: Option<images::Image>,
+    /// A file offered with this message, and how fetching it is going.
+    pub file: Option<files::Offer>,
+    /// The message this one replies to.
+    pub quote: Option<Quote>,
+    /// The root of the thread it was posted in; shown in the thread panel, not the main view.
+    pub thread: Option<String>,
+    /// Set on a `/poll`, whose question is the text.
+    pub poll: Option<polls::Poll>,
+    /// The card for a link in it, as its sender looked it up.
+    pub preview: Option<preview::Preview>,
+    /// Set on a `/loc`, whose label is the text.
+    pub location: Option<location::Location>,
+    /// An `/announce` from the host or a moderator, shown as a banner across the pane.
+    pub announcement: bool,
+    /// An `/urgent` message, shown highlighted; from peers, only while under the rate limit.
+    pub urgent: bool,
+    /// The text's formatting as its sender sent it; when empty, the text is read as Markdown.
+    pub rich: Vec<rich::Span>,
+    pub reactions: Vec<reactions::Reaction>,
+    pub edited: bool,
+    /// Copies of it its sender posted straight after, collapsed into this one.
+    pub repeats: u32,
+    /// Set on a `/tmp` message: when it goes from the screen. Never kept on disk or passed on.
+    pub expires: Option<DateTime<Utc>>,
+    /// Deleted for everyone by its sender or a moderator; what's left is the placeholder,
+    /// never kept, passed on or exported.
+    pub redacted: bool,
+    /// Set on a copy posted with `f` in selection mode: who wrote the original, and where.
+    pub forwarded: Option<Forwarded>,
+    /// Peers' receipts, for our own messages.
+    pub receipts: receipts::Receipts,
+    /// A peer's message has been on screen, so its read receipt went out (or was withheld).
+    pub seen: bool,
+    /// Who passed this on in a history backfill; the sender is their word only.
+    pub relayed: Option<iroh::NodeId>,
+}
+
+impl ChatMessage {
+    pub fn system(text: impl Into<String>) -> Self {
+        ChatMessage {
+            id: String::new(),
+            from: None,
+            sender: String::new(),
+            text: text.into(),
+            time: Utc::now(),
+            origin: Origin::System,
+            mentions_me: false,
+            image: None,
+            file: None,
+            quote: None,
+            thread: None,
+            poll: None,
+            preview: None,
+            location: None,
+            announcement: false,
+            urgent: false,
+            rich: Vec::new(),
+            reactions: Vec::new(),
+            edited: false,
+            repeats: 0,
+            expires: None,
+            redacted: false,
+            forwarded: None,
+            receipts: Default::default(),
+            seen: false,
+            relayed: None,
+        }
+    }
+
+    /// Our own message `id`, sent just now as `sender`; set what else it carries on the result.
+    pub fn mine(id: String, sender: String, text: impl Into<String>) -> Self {
+        ChatMessage { id, sender, origin: Origin::Me, ..Self::system(text) }
+    }
+
+    /// Peer `from`'s message `id`, sent as `sender` at `time`.
+    pub fn peer(id: String, from: iroh::NodeId, sender: String, text: impl Into<String>, time: DateTime<Utc>) -> Self {
+        ChatMessage { id, from: Some(from), sender, time, origin: Origin::Peer, ..Self::system(text) }
+    }
+}
+
+/// Sends `msg` to everyone in the room behind `sender`.
+pub async fn broadcast(sender: &sequence::Sender, msg: &Message) {
+    sender.send(msg, false).await
+}
+
+/// Like `broadcast`, but only to our direct neighbors, who don't pass it on.
+pub async fn broadcast_neighbors(sender: &sequence::Sender, msg: &Message) {
+    sender.send(msg, true).await
+}
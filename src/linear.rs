@@ -0,0 +1,148 @@
+use ratatui::{prelude::*, widgets::Paragraph};
+use unicode_width::UnicodeWidthChar;
+
+use crate::{chat, files, i18n::t, voice, AppState, ChatMessage, Origin};
+
+/// Plain transcript lines for one message, in reading order.
+fn message_text(state: &AppState, msg: &ChatMessage, time: &str, selected: bool) -> Vec<String> {
+    let marker = if selected { "» " } else { "" };
+    let mut edited = chat::marks(msg);
+    if let Some(receipt) = msg.receipts.text().filter(|_| msg.origin == Origin::Me) {
+        edited.push_str(&format!(" ({})", receipt));
+    }
+    if msg.urgent {
+        edited.push_str(&format!(" ({})", t!("urgent")));
+    }
+    let mut out = Vec::new();
+    if let Some(forwarded) = &msg.forwarded {
+        out.push(format!("{}{}", marker, t!("forwarded-from", sender = forwarded.sender.as_str(), room = forwarded.room.as_str())));
+    }
+    if let Some(quote) = &msg.quote {
+        out.push(format!("{}{}", marker, t!("linear-reply", sender = quote.sender.as_str(), snippet = quote.snippet.as_str())));
+    }
+    let body = match (&msg.image, &msg.file) {
+        (Some(image), None) => t!("linear-image", name = image.name.as_str()),
+        (Some(image), Some(original)) => {
+            format!("{}\n{} · {}", t!("linear-image", name = image.name.as_str()), files::size(original.size), chat::file_status(state, msg, original))
+        }
+        (None, Some(file)) => {
+            let title = match file.voice {
+                Some(ms) => t!("linear-voice", length = voice::clock(ms)),
+                None => t!("linear-file", name = file.name.as_str(), size = files::size(file.size)),
+            };
+            format!("{}\n{}", title, chat::file_status(state, msg, file))
+        }
+        (None, None) => match (&msg.poll, &msg.location) {
+            (Some(poll), _) => {
+                let mut lines = vec![t!("linear-poll", question = msg.text.as_str())];
+                lines.extend(poll.options.iter().zip(poll.counts()).enumerate()
+                    .map(|(i, (option, count))| t!("linear-poll-option", number = i + 1, option = option.as_str(), votes = count)));
+                lines.push(poll.status());
+                lines.join("\n")
+            }
+            (None, Some(place)) => {
+                let place = t!("linear-location", code = place.plus_code(), coordinates = place.coordinates(), url = place.osm());
+                if msg.text.is_empty() { place } else { format!("{}\n{}", msg.text, place) }
+            }
+            (None, None) if msg.announcement => t!("linear-announcement", text = msg.text.as_str()),
+            (None, None) => msg.text.clone(),
+        },
+    };
+    let who = match msg.origin {
+        Origin::System => t!("linear-notice"),
+        Origin::Me => t!("you-suffix", name = msg.sender.as_str()),
+        Origin::Peer => state.room().sender(msg),
+    };
+    for (i, line) in body.lines().enumerate() {
+        match i {
+            0 => out.push(format!("{}[{}] {}: {}{}", marker, time, who, line, edited)),
+            _ => out.push(format!("{}  {}", marker, line)),
+        }
+    }
+    if body.is_empty() {
+        out.push(format!("{}[{}] {}:{}", marker, time, who, edited));
+    }
+    if let Some(preview) = &msg.preview {
+        out.push(format!("{}  {}", marker, t!("linear-preview", title = preview.title.as_str(), site = preview.site())));
+        if !preview.description.is_empty() {
+            out.push(format!("{}  {}", marker, preview.description));
+        }
+    }
+    if !msg.reactions.is_empty() {
+        let counts: Vec<String> = msg.reactions.iter().map(|r| format!("{} {}", r.emoji, r.from.len())).collect();
+        out.push(format!("{}  {}", marker, t!("linear-reactions", reactions = counts.join(", "))));
+    }
+    let replies = if state.room().thread.is_none() { state.room().replies(&msg.id) } else { 0 };
+    if replies > 0 {
+        out.push(format!("{}  {}", marker, t!("thread-replies", count = replies)));
+    }
+    out
+}
+
+/// Breaks `line` into pieces no wider than `width` columns.
+fn wrap(line: &str, width: usize) -> Vec<String> {
+    let mut pieces = vec![String::new()];
+    let mut col = 0;
+    for c in line.chars() {
+        let w = c.width().unwrap_or(0);
+        if col + w > width && col > 0 {
+            pieces.push(String::new());
+            col = 0;
+        }
+        if let Some(piece) = pieces.last_mut() {
+            piece.push(c);
+        }
+        col += w;
+    }
+    pieces
+}
+
+/// Screen-reader layout: a status line, the transcript as plain left-aligned lines,
+/// then the input on the last line with the cursor at its end.
+pub fn render(frame: &mut Frame, state: &AppState) {
+    let area = frame.area();
+    let width = area.width.max(1) as usize;
+    let room = state.room();
+
+    let mut status = t!("linear-status", room = room.label(), peers = room.peer_names.len(), connected = if room.neighbors.is_empty() { "no" } else { "yes" });
+    if room.thread.is_some() {
+        status = format!("{}, {}", status, chat::thread_title(room));
+    }
+
+    let mut transcript = Vec::new();
+    let mut owners = Vec::new();
+    let visible = state.visible();
+    let end = visible.len().saturating_sub(room.scroll);
+    for &i in &visible[..end] {
+        let msg = &room.messages[i];
+        for line in message_text(state, msg, &chat::stamp(&msg.time, state.time_style), state.selected == Some(i)) {
+            let pieces = wrap(&line, width);
+            owners.resize(owners.len() + pieces.len(), i);
+            transcript.extend(pieces);
+        }
+    }
+
+    let prompt = match &state.search {
+        Some(search) => t!("linear-search", query = search.query.as_str()),
+        None => t!("linear-message", text = room.input.as_str()),
+    };
+    let prompt = wrap(&prompt.replace('\n', " / "), width);
+
+    let rows = (area.height as usize).saturating_sub(1 + prompt.len());
+    let skip = transcript.len().saturating_sub(rows);
+    let mut shown: Vec<usize> = owners.into_iter().skip(skip).collect();
+    shown.dedup();
+    *state.shown.borrow_mut() = shown;
+    let mut lines = vec![Line::raw(status)];
+    lines.extend(transcript.into_iter().skip(skip).map(Line::raw));
+    lines.resize(1 + rows, Line::raw(""));
+    let prompt_row = lines.len();
+    lines.extend(prompt.iter().cloned().map(Line::raw));
+    frame.render_widget(Paragraph::new(lines), area);
+
+    if state.overlay.is_none() && state.selected.is_none() {
+        let last = prompt.last().map_or(0, |l| l.chars().map(|c| c.width().unwrap_or(0)).sum::<usize>());
+        let row = prompt_row + prompt.len() - 1;
+        frame.set_cursor_position((area.x + last as u16, area.y + row as u16));
+    }
+}
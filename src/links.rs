@@ -0,0 +1,44 @@
+use ratatui::{
+    prelude::*,
+    widgets::{Block, BorderType, Borders, Clear, Padding, Paragraph, Wrap},
+};
+
+use crate::{centered, i18n::t};
+
+/// Renders the link picker, or the open confirmation once a link has been chosen.
+pub fn render(frame: &mut Frame, urls: &[String], selected: usize, confirm: bool) {
+    let (title, lines) = if confirm {
+        let lines = vec![
+            Line::styled(t!("link-confirm"), Style::default().fg(Color::White)),
+            Line::raw(""),
+            Line::styled(urls[selected].as_str(), Style::default().fg(Color::LightBlue).add_modifier(Modifier::UNDERLINED)),
+            Line::raw(""),
+            Line::styled(t!("link-confirm-keys"), Style::default().fg(Color::DarkGray)),
+        ];
+        (t!("link-open-title"), lines)
+    } else {
+        let lines = urls.iter().enumerate().map(|(i, url)| {
+            let style = if i == selected {
+                Style::default().fg(Color::Black).bg(Color::LightBlue)
+            } else {
+                Style::default().fg(Color::LightBlue)
+            };
+            Line::styled(url.as_str(), style)
+        }).collect();
+        (t!("links-title"), lines)
+    };
+
+    let width = urls.iter().map(|u| u.chars().count()).max().unwrap_or(0).max(48) as u16 + 4;
+    let area = centered(frame.area(), width, lines.len() as u16 + 2);
+    let popup = Paragraph::new(lines)
+        .wrap(Wrap { trim: false })
+        .block(Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .border_style(Style::default().fg(Color::LightBlue))
+            .title(format!(" {} ", title))
+            .padding(Padding::horizontal(1)));
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(popup, area);
+}
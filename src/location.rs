@@ -0,0 +1,70 @@
+use serde::{Deserialize, Serialize};
+
+/// The Open Location Code alphabet: digits and letters that can't spell words or be misread.
+const ALPHABET: &[u8; 20] = b"23456789CFGHJMPQRVWX";
+/// A 10-digit plus code is a cell 1/8000 of a degree on each side, about 14 m.
+const CELLS: f64 = 8000.0;
+const MAX_LABEL: usize = 200;
+
+/// A point shared with `/loc`, in degrees.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Location {
+    pub lat: f64,
+    pub lon: f64,
+}
+
+/// Parses `48.85,2.35` (a space after the comma is fine).
+pub fn parse(s: &str) -> Option<Location> {
+    let (lat, lon) = s.split_once(',')?;
+    Location::new(lat.trim().parse().ok()?, lon.trim().parse().ok()?)
+}
+
+/// A place's label on one line, cut to `MAX_LABEL` characters.
+pub fn label(text: &str) -> String {
+    text.chars().map(|c| if c.is_control() { ' ' } else { c }).collect::<String>().trim().chars().take(MAX_LABEL).collect()
+}
+
+impl Location {
+    /// `None` unless it's a real point on the map.
+    pub fn new(lat: f64, lon: f64) -> Option<Self> {
+        ((-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lon)).then_some(Location { lat, lon })
+    }
+
+    /// The 10-digit plus code for it, like `8FVC9G8F+6X`, which maps and phones understand.
+    pub fn plus_code(&self) -> String {
+        let lat = (((self.lat + 90.0) * CELLS) as u32).min(180 * CELLS as u32 - 1);
+        let lon = ((self.lon + 180.0).rem_euclid(360.0) * CELLS) as u32;
+        let digits = |mut n: u32| {
+            let mut out = [0u8; 5];
+            for d in out.iter_mut().rev() {
+                *d = ALPHABET[(n % 20) as usize];
+                n /= 20;
+            }
+            out
+        };
+        let (lat, lon) = (digits(lat), digits(lon));
+        let mut code = String::with_capacity(11);
+        for i in 0..5 {
+            if i == 4 {
+                code.push('+');
+            }
+            code.push(lat[i] as char);
+            code.push(lon[i] as char);
+        }
+        code
+    }
+
+    /// The point on OpenStreetMap, marked and zoomed to street level.
+    pub fn osm(&self) -> String {
+        format!(
+            "https://www.openstreetmap.org/?mlat={lat:.5}&mlon={lon:.5}#map=17/{lat:.5}/{lon:.5}",
+            lat = self.lat,
+            lon = self.lon,
+        )
+    }
+
+    /// `48.85000, 2.35000`.
+    pub fn coordinates(&self) -> String {
+        format!("{:.5}, {:.5}", self.lat, self.lon)
+    }
+}
@@ -0,0 +1,41 @@
+use std::{fs::OpenOptions, path::PathBuf, sync::Mutex};
+
+use anyhow::{Context, Result};
+use clap::Args;
+use tracing_subscriber::EnvFilter;
+
+#[derive(Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum Format {
+    Pretty,
+    Json,
+}
+
+/// Diagnostics go to a file only; the terminal belongs to the TUI.
+#[derive(Args)]
+pub struct LogArgs {
+    /// Append logs to this file (nothing is logged without it)
+    #[arg(long, global = true)]
+    pub log_file: Option<PathBuf>,
+    /// Minimum level, or a filter like `debug,iroh=warn`
+    #[arg(long, global = true, default_value = "info")]
+    log_level: String,
+    #[arg(long, global = true, value_enum, default_value_t = Format::Pretty)]
+    log_format: Format,
+}
+
+/// Starts writing logs if `--log-file` was given.
+pub fn init(args: &LogArgs) -> Result<()> {
+    let Some(path) = &args.log_file else { return Ok(()) };
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("opening log file {}", path.display()))?;
+    let filter = EnvFilter::try_new(&args.log_level).with_context(|| format!("bad --log-level `{}`", args.log_level))?;
+    let builder = tracing_subscriber::fmt().with_env_filter(filter).with_writer(Mutex::new(file)).with_ansi(false);
+    match args.log_format {
+        Format::Pretty => builder.init(),
+        Format::Json => builder.json().init(),
+    }
+    Ok(())
+}
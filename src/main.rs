@@ -1,143 +1,691 @@
-mod stego;
+mod boss;
+mod chat;
+mod clipboard;
+mod commands;
+mod config;
+mod daemon;
+mod emoji;
+mod export;
+mod gateway;
+#[cfg(feature = "gui")]
+mod gui;
+mod help;
+mod hooks;
+mod id;
+mod linear;
+mod links;
+mod logging;
+mod mouse;
+mod peers;
+mod pipe;
+mod plain;
+mod search;
+mod service;
+mod status;
+mod theme;
+#[cfg(windows)]
+mod tray;
+mod update;
+mod vim;
+mod wizard;
 
-use anyhow::{Context, Result};
-use clap::{Parser, Subcommand};
-use futures_lite::StreamExt;
-use iroh::{Endpoint, NodeAddr, protocol::Router};
+use anyhow::{anyhow, bail, Context, Result};
+use clap::{Args, CommandFactory, Parser, Subcommand};
+use iroh::{Endpoint, RelayUrl};
 use iroh_gossip::{net::{Gossip, GossipEvent}, proto::TopicId};
-use serde::{Deserialize, Serialize};
-use std::{collections::{HashMap, HashSet}, fmt, str::FromStr, time::Duration};
+use std::{cell::{Cell, RefCell}, collections::HashSet, path::{Path, PathBuf}, time::{Duration, Instant}};
 use base64::Engine; 
-use chrono::Local;
+use chrono::Utc;
+use futures_lite::StreamExt;
+use i18n::t;
+use tokio::sync::mpsc;
+
+// The chat itself lives in the library; this lets the frontend's modules reach it as before.
+use ghost_messaging::{
+    backfill, broadcast, broadcast_neighbors, chunks, client, clock, expiry, files, flood, i18n, identity, images, location, markup,
+    moderation, names, new_id, notify, opener, plugins, polls, presence, preview, protocol, reactions, rich, rooms, saved, scripts, store,
+    transport, voice, wasm, whereabouts, ChatEvent, ChatMessage, Forwarded, Message, Origin, Quote,
+};
 
 // --- UI Imports ---
 use crossterm::{
-    event::{self, Event, KeyCode, KeyEventKind},
+    event::{DisableBracketedPaste, DisableFocusChange, DisableMouseCapture, EnableBracketedPaste, EnableFocusChange, EnableMouseCapture, Event, EventStream, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, KeyboardEnhancementFlags, MouseEventKind, PushKeyboardEnhancementFlags, PopKeyboardEnhancementFlags},
     execute,
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+    terminal::{disable_raw_mode, enable_raw_mode, supports_keyboard_enhancement, EnterAlternateScreen, LeaveAlternateScreen, SetTitle},
 };
 use ratatui::{
     prelude::*,
-    widgets::{Block, Borders, BorderType, Paragraph, List, ListItem, Padding},
+    widgets::{Block, Borders, Paragraph, List, ListItem, Padding, Tabs},
 };
 
-// --- DATA STRUCTURES ---
-#[derive(Debug, Serialize, Deserialize)]
-struct Ticket {
-    topic: TopicId,
-    nodes: Vec<NodeAddr>,
-}
-
-impl fmt::Display for Ticket {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let binary_data = bincode::serialize(self).map_err(|_| fmt::Error)?;
-        let s = base64::engine::general_purpose::STANDARD_NO_PAD.encode(binary_data);
-        write!(f, "{}", s)
-    }
-}
-
-impl FromStr for Ticket {
-    type Err = anyhow::Error;
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let clean_s = s.trim();
-        let binary_data = base64::engine::general_purpose::STANDARD_NO_PAD.decode(clean_s)?;
-        let ticket = bincode::deserialize(&binary_data)?;
-        Ok(ticket)
-    }
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-enum Message {
-    AboutMe { name: String },
-    Chat { text: String },
-}
-
+/// Serverless, ephemeral peer-to-peer chat in the terminal.
 #[derive(Parser)]
+#[command(args_override_self = true)]
 struct Cli {
+    /// Settings file (default: ~/.config/ghost/config.toml); flags given here still win
+    #[arg(long, global = true, env = "GHOST_CONFIG")]
+    config: Option<PathBuf>,
+    /// Separate persona with its own config and identity, unlinkable by NodeId
+    #[arg(long, global = true, env = "GHOST_PROFILE", value_parser = config::profile_name)]
+    profile: Option<String>,
+    /// Use a throwaway identity: a new NodeId this run, nothing saved
+    #[arg(long, global = true)]
+    ephemeral: bool,
+    /// Protect a newly created identity with a passphrase, asked for on every start
+    #[arg(long, global = true)]
+    encrypt_identity: bool,
+    /// Don't look for new releases in the background or mention them on startup
+    #[arg(long, global = true, env = "GHOST_NO_UPDATE_CHECK")]
+    no_update_check: bool,
+    /// Interface language (en, de, es); default: from LC_ALL / LC_MESSAGES / LANG
+    #[arg(long, global = true, env = "GHOST_LANG", value_parser = i18n::language)]
+    lang: Option<String>,
+    /// Time zone for message times: local (default), utc, an offset like +05:30, or a name like Europe/Berlin
+    #[arg(long, global = true, env = "GHOST_TIMEZONE", value_parser = clock::parse)]
+    timezone: Option<clock::Zone>,
+    /// Send messages as JSON instead of the compact binary format, to read them in a packet dump
+    #[arg(long, global = true, env = "GHOST_JSON_WIRE")]
+    json_wire: bool,
+    #[command(flatten)]
+    net: NetArgs,
+    #[command(flatten)]
+    log: logging::LogArgs,
+    #[command(flatten)]
+    hooks: hooks::Hooks,
     #[command(subcommand)]
     command: Commands,
 }
 
 #[derive(Subcommand)]
+#[command(args_override_self = true)]
 enum Commands {
+    /// Open a new room and print its ticket
     Host {
-        #[arg(short, long, default_value = "Ghost")]
+        #[arg(short, long, default_value = "Ghost", value_parser = names::parse)]
         name: String,
         #[arg(short, long, default_value = "Hello World")]
         cover: String, 
+        /// What peers see the room called, in tabs and the status bar
+        #[arg(long, value_parser = names::parse)]
+        room_name: Option<String>,
+        /// Don't put the new ticket on the clipboard
+        #[arg(long)]
+        no_clipboard: bool,
+        /// Only you, and peers you give a role with `/role`, may post; everyone else reads along
+        #[arg(long)]
+        broadcast: bool,
+        #[command(flatten)]
+        ui: UiArgs,
     },
+    /// Join a room from its ticket
     Join {
+        #[command(flatten)]
+        ticket: TicketArgs,
+        #[arg(short, long, default_value = "Ghost", value_parser = names::parse)]
+        name: String,
+        #[command(flatten)]
+        ui: UiArgs,
+    },
+    /// No TUI: JSON events on stdout, JSON commands on stdin (joins `--ticket`, or hosts)
+    Pipe {
+        #[command(flatten)]
+        ticket: TicketArgs,
+        #[arg(short, long, default_value = "Ghost", value_parser = names::parse)]
+        name: String,
+    },
+    /// Join a room, post one message and exit (for cron jobs and alerts); uses the daemon if one runs
+    Send {
+        #[command(flatten)]
+        ticket: TicketArgs,
+        #[arg(short, long)]
+        message: String,
+        #[arg(short, long, default_value = "Ghost", value_parser = names::parse)]
+        name: String,
+        /// Seconds to stay connected afterwards so the message spreads
+        #[arg(long, default_value_t = 2)]
+        linger: u64,
+        /// Send it as `/urgent` does, ringing through rooms peers have muted
+        #[arg(long)]
+        urgent: bool,
+    },
+    /// Stay in rooms in the background, serving the `pipe` protocol on a local socket
+    Daemon {
+        #[arg(short, long, default_value = "Ghost", value_parser = names::parse)]
+        name: String,
+        /// Show a tray icon with the unread count, and toast new messages (Windows)
+        #[arg(long)]
+        tray: bool,
+    },
+    /// Talk to the running daemon: `pipe` commands on stdin, its events on stdout
+    Attach,
+    /// Chat in a desktop window instead of the terminal (joins `--ticket`, or asks)
+    #[cfg(feature = "gui")]
+    Gui {
+        #[command(flatten)]
+        ticket: TicketArgs,
+        #[arg(short, long, default_value = "Ghost", value_parser = names::parse)]
+        name: String,
+    },
+    /// Stay in rooms like the daemon, serving JSON-RPC over a local WebSocket and a small REST API
+    Gateway {
+        #[arg(short, long, default_value = "Ghost", value_parser = names::parse)]
+        name: String,
+        /// Where to listen; anything but loopback exposes it unencrypted
+        #[arg(long, default_value = "127.0.0.1:7780")]
+        listen: std::net::SocketAddr,
+        /// The token clients must present (default: one made once and kept in `gateway.token`)
+        #[arg(long, env = "GHOST_GATEWAY_TOKEN", hide_env_values = true)]
+        token: Option<String>,
+    },
+    /// Keep the daemon running as a systemd user unit, launchd agent or Windows service
+    Service {
+        #[command(subcommand)]
+        action: service::Action,
+    },
+    /// Turn an event log from `pipe` or `attach` into notes
+    Export {
+        /// The log (default: stdin)
+        #[arg(long)]
+        input: Option<PathBuf>,
+        /// Where to write (default: stdout)
+        #[arg(short, long)]
+        out: Option<PathBuf>,
+        /// Default: from --out's extension, else txt
+        #[arg(long, value_enum)]
+        format: Option<export::Format>,
+        /// Replace names (and @mentions of them) with "Person 1", "Person 2", ...
         #[arg(long)]
-        ticket: String,
-        #[arg(short, long, default_value = "Ghost")]
+        no_names: bool,
+        #[arg(long)]
+        no_times: bool,
+    },
+    /// Install the latest release for this platform after checking its signature
+    Update {
+        /// Only say whether there is a newer release
+        #[arg(long)]
+        check: bool,
+    },
+    /// Ask the first-run questions again: name, colour scheme, and whether to keep an identity
+    Setup,
+    /// Print the version; with `--protocol`, the wire format and message types it speaks
+    Version {
+        #[arg(long)]
+        protocol: bool,
+    },
+    /// Print a shell completion script, e.g. `ghostterm completions bash > /etc/bash_completion.d/ghostterm`
+    Completions { shell: clap_complete::Shell },
+    /// Tickets saved under short names, so regulars needn't paste them every day
+    Rooms {
+        #[command(subcommand)]
+        action: RoomsAction,
+    },
+    /// Work with room tickets offline
+    Ticket {
+        #[command(subcommand)]
+        action: TicketAction,
+    },
+    /// Manage this profile's identity keypair
+    Id {
+        #[command(subcommand)]
+        action: id::Action,
+    },
+}
+
+#[derive(Subcommand)]
+enum TicketAction {
+    /// Print a ticket's topic, nodes, addresses and relays without connecting
+    Inspect {
+        #[command(flatten)]
+        ticket: TicketArgs,
+    },
+}
+
+#[derive(Subcommand)]
+enum RoomsAction {
+    /// Show the saved rooms
+    List,
+    /// Save a ticket under a short name (replacing any with that name)
+    Add {
+        #[arg(value_parser = config::profile_name)]
+        alias: String,
+        #[command(flatten)]
+        ticket: TicketArgs,
+    },
+    /// Forget a saved room
+    Remove { alias: String },
+    /// Join a saved room
+    Join {
+        alias: String,
+        #[arg(short, long, default_value = "Ghost", value_parser = names::parse)]
         name: String,
+        #[command(flatten)]
+        ui: UiArgs,
     },
 }
 
+/// A room ticket, typed out or read from somewhere that survives shell quoting better.
+#[derive(Args)]
+struct TicketArgs {
+    /// The ticket, or `-` to read it from stdin
+    #[arg(value_name = "TICKET")]
+    positional: Option<String>,
+    /// Same as the positional ticket (`-` reads stdin)
+    #[arg(long)]
+    ticket: Option<String>,
+    /// Read the ticket from a file; binary files like images are searched for `[Ghost:...]`
+    #[arg(long, value_name = "PATH")]
+    ticket_file: Option<PathBuf>,
+}
+
+impl Commands {
+    fn ticket(&self) -> Option<&TicketArgs> {
+        match self {
+            Commands::Join { ticket, .. } | Commands::Pipe { ticket, .. } | Commands::Send { ticket, .. } => Some(ticket),
+            Commands::Ticket { action: TicketAction::Inspect { ticket } } => Some(ticket),
+            Commands::Rooms { action: RoomsAction::Add { ticket, .. } } => Some(ticket),
+            #[cfg(feature = "gui")]
+            Commands::Gui { ticket, .. } => Some(ticket),
+            _ => None,
+        }
+    }
+}
+
+impl TicketArgs {
+    /// The ticket, if one was given: the positional one beats `--ticket-file`, which beats
+    /// `--ticket`, so a ticket typed out overrides one from the config file.
+    fn get(&self) -> Result<Option<String>> {
+        let text = match (&self.positional, &self.ticket_file, &self.ticket) {
+            (Some(text), _, _) | (None, None, Some(text)) => text,
+            (None, Some(path), _) => return rooms::read(path).map(Some),
+            (None, None, None) => return Ok(None),
+        };
+        match text.as_str() {
+            "-" => rooms::read(Path::new("-")).map(Some),
+            _ => Ok(Some(text.clone())),
+        }
+    }
+}
+
+/// The ticket a command needs, or how to give one.
+fn required(ticket: Option<&str>) -> Result<String> {
+    ticket.map(str::to_string).ok_or_else(|| anyhow!("no ticket: pass it, `-` for stdin, or --ticket-file <path>"))
+}
+
+/// How this node reaches and is found by peers.
+#[derive(Args)]
+struct NetArgs {
+    /// Use this relay server instead of the public n0 ones
+    #[arg(long, global = true)]
+    relay: Option<RelayUrl>,
+    /// Never relay traffic; only direct connections will work
+    #[arg(long, global = true)]
+    no_relay: bool,
+    /// Don't look for peers on the local network (mDNS)
+    #[arg(long, global = true)]
+    no_local_discovery: bool,
+    /// Don't publish or resolve addresses through n0's DNS discovery
+    #[arg(long, global = true)]
+    no_dns_discovery: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, clap::ValueEnum)]
+enum Pins {
+    /// Only the room's host can pin.
+    Host,
+    Anyone,
+}
+
+/// Interface options shared by every subcommand that opens the chat.
+#[derive(Args)]
+struct UiArgs {
+    /// Key that instantly swaps the chat for a fake shell prompt (F1..F24 or Ctrl/Alt+<key>)
+    #[arg(long, default_value = "F12")]
+    boss_key: boss::Hotkey,
+    /// Require typing this (then Enter) at the fake prompt to bring the chat back
+    #[arg(long)]
+    unhide_passphrase: Option<String>,
+    /// Width of the peer sidebar in columns
+    #[arg(long, default_value_t = 25)]
+    sidebar_width: u16,
+    /// How tightly messages are laid out (F3 cycles)
+    #[arg(long, value_enum, default_value_t = chat::Density::Normal)]
+    density: chat::Density,
+    /// Default alert for new messages while scrolled up or unfocused (per room: /notify)
+    #[arg(long, value_enum, default_value_t = notify::Alert::Off)]
+    alert: notify::Alert,
+    /// Don't tell senders when their messages have been on screen (per room: /receipts)
+    #[arg(long)]
+    no_read_receipts: bool,
+    /// Don't look up titles for links you send; previews come from the sender, so this is
+    /// the only request they cause
+    #[arg(long)]
+    no_link_previews: bool,
+    /// How many recent messages to pass on to someone who just joined (0: none)
+    #[arg(long, default_value_t = 50)]
+    backfill: usize,
+    /// Keep each room's messages on disk: none, forever, or a number of days like 30d
+    #[arg(long, default_value = "none", value_parser = store::retention)]
+    history: store::Retention,
+    /// Encrypt kept history with a key derived from your identity
+    #[arg(long)]
+    encrypt_history: bool,
+    /// Where fetched files are saved [default: ~/Downloads, else the current directory]
+    #[arg(long)]
+    download_dir: Option<PathBuf>,
+    /// Colour scheme
+    #[arg(long, value_enum, default_value_t = theme::Theme::Default)]
+    theme: theme::Theme,
+    /// Plain sequential layout without boxes or alignment, for screen readers
+    #[arg(long)]
+    screen_reader: bool,
+    /// Show message times as a clock (14:05) or relative ("2m ago")
+    #[arg(long, value_enum, default_value_t = chat::TimeStyle::Clock)]
+    time: chat::TimeStyle,
+    /// Keymap for the main view; `vim` adds normal/insert modes
+    #[arg(long, value_enum, default_value_t = vim::Keymap::Default)]
+    keys: vim::Keymap,
+    /// Leave the mouse to the terminal, for its native text selection
+    #[arg(long)]
+    no_mouse: bool,
+    /// Whose pins to honour, and whether you may pin yourself
+    #[arg(long, value_enum, default_value_t = Pins::Host)]
+    pins: Pins,
+    /// Hide the sidebar automatically when the terminal is narrower than this (F2 overrides)
+    #[arg(long, default_value_t = 80)]
+    sidebar_min_cols: u16,
+    /// Mute a peer for a while when they post more than this many messages within
+    /// --flood-window (0: never)
+    #[arg(long, default_value_t = 10)]
+    flood_limit: usize,
+    /// Seconds over which --flood-limit counts a peer's posts
+    #[arg(long, default_value_t = 10)]
+    flood_window: u64,
+    /// Seconds a flooding peer stays muted
+    #[arg(long, default_value_t = 60)]
+    flood_mute: u64,
+    /// Show as away after this many minutes without a key pressed (0: never)
+    #[arg(long, default_value_t = 10)]
+    away_after: u64,
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
-    let args = Cli::parse();
-
-    let endpoint = Endpoint::builder()
-        .discovery_n0()
-        .discovery_local_network()
-        .bind()
-        .await?;
-    let gossip = Gossip::builder().spawn(endpoint.clone()).await?;
-    let router = Router::builder(endpoint.clone())
-        .accept(iroh_gossip::ALPN, gossip.clone())
-        .spawn()
-        .await?;
+    // A lenient first pass finds the config file and subcommand, even when a required
+    // flag (like `--ticket`) is left to the config or environment.
+    let early = Cli::command().ignore_errors(true).get_matches();
+    let mut subcommands = Vec::new();
+    let mut matches = &early;
+    while let Some((name, sub)) = matches.subcommand() {
+        subcommands.push(name);
+        matches = sub;
+    }
+    let typed_name = matches.ids().any(|id| id == "name") && matches.value_source("name") == Some(clap::parser::ValueSource::CommandLine);
+    // Nothing saved yet: ask the basics before the config file is read, so the answers count now.
+    let mut first_key = None;
+    let chatting = matches!(subcommands.first(), Some(&"host" | &"join"));
+    let ephemeral = early.get_one::<bool>("ephemeral").copied().unwrap_or(false);
+    let profile = early.get_one::<String>("profile").map(String::as_str);
+    if let (true, false, None, Some(dir)) = (chatting, ephemeral, early.get_one::<PathBuf>("config"), config::dir(profile)) {
+        let file = dir.join("config.toml");
+        if wizard::due(&file, &dir) {
+            i18n::init(early.get_one::<String>("lang").map(String::as_str));
+            let name = matches.get_one::<String>("name").filter(|_| typed_name).map(String::as_str);
+            first_key = wizard::run(&file, &dir, name)?;
+            println!();
+        }
+    }
+    let argv = config::apply(
+        std::env::args_os().collect(),
+        early.get_one::<PathBuf>("config").map(PathBuf::as_path),
+        early.get_one::<String>("profile").map(String::as_str),
+        &subcommands,
+    )?;
+    let mut args = Cli::parse_from(argv);
+    if let Commands::Service { action: service::Action::Run { .. } } = &args.command {
+        args.log.log_file = args.log.log_file.take().or_else(|| service::default_log(args.profile.as_deref()));
+    }
+    logging::init(&args.log)?;
+    i18n::init(args.lang.as_deref());
+    clock::init(args.timezone);
+    protocol::init(args.json_wire);
+    #[cfg(windows)]
+    if let Commands::Service { action: service::Action::Run { .. } } = &args.command {
+        let log_file = args.log.log_file.clone();
+        if tokio::task::block_in_place(|| service::dispatch(args.profile.as_deref(), log_file))? {
+            return Ok(());
+        }
+    }
+    // Read once up front: `-` consumes a line of stdin.
+    let mut ticket = args.command.ticket().map(TicketArgs::get).transpose()?.flatten();
+    let daemon = daemon::address(args.profile.as_deref());
+    let daemon_at = || daemon.as_deref().ok_or_else(|| anyhow!("no config directory (set HOME or XDG_CONFIG_HOME)"));
+    if let (Commands::Send { message, urgent, .. }, Some(daemon)) = (&args.command, &daemon) {
+        if !args.ephemeral && daemon::send(daemon, &required(ticket.as_deref())?, message, *urgent).await? {
+            return Ok(());
+        }
+    }
+
+    // Saved rooms are encrypted with the identity, so it's loaded once here for both.
+    let mut secret = first_key;
+    if let Commands::Rooms { action } = &args.command {
+        let dir = config::dir(args.profile.as_deref()).ok_or_else(|| anyhow!("no config directory (set HOME or XDG_CONFIG_HOME)"))?;
+        let key = id::load_or_create(&dir, args.encrypt_identity)?;
+        let mut saved = saved::Saved::load(&dir, &key)?;
+        match action {
+            RoomsAction::List => {
+                if saved.rooms.is_empty() {
+                    println!("{}", t!("rooms-none"));
+                }
+                for (alias, ticket) in &saved.rooms {
+                    let Ok(topic) = rooms::topic(ticket) else {
+                        println!("{:<16} {}", alias, t!("rooms-invalid"));
+                        continue;
+                    };
+                    let info = saved.info(topic).cloned().unwrap_or_default();
+                    let name = if info.name.is_empty() { format!("#{}", &topic.to_string()[..8]) } else { info.name };
+                    match info.topic.is_empty() {
+                        true => println!("{:<16} {}", alias, name),
+                        false => println!("{:<16} {} — {}", alias, name, info.topic),
+                    }
+                }
+                return Ok(());
+            }
+            RoomsAction::Add { alias, .. } => {
+                let ticket = required(ticket.as_deref())?;
+                rooms::topic(&ticket)?;
+                let updated = saved.rooms.insert(alias.clone(), ticket).is_some();
+                saved.save()?;
+                println!("{}", if updated { t!("rooms-updated", alias = alias.as_str()) } else { t!("rooms-saved", alias = alias.as_str()) });
+                return Ok(());
+            }
+            RoomsAction::Remove { alias } => {
+                if saved.rooms.remove(alias).is_none() {
+                    bail!(t!("rooms-unknown", alias = alias.as_str()));
+                }
+                saved.save()?;
+                println!("{}", t!("rooms-removed", alias = alias.as_str()));
+                return Ok(());
+            }
+            RoomsAction::Join { alias, .. } => {
+                ticket = Some(saved.get(alias)?.to_string());
+                secret = Some(key);
+            }
+        }
+    }
+
+    let daemon_running = match &daemon {
+        Some(daemon) if !args.ephemeral => daemon::running(daemon).await,
+        _ => false,
+    };
 
     match &args.command {
-        Commands::Host { name, cover } => {
-            let topic = TopicId::from_bytes(rand::random());
-            let mut me = endpoint.node_addr().await?;
-            let mut unique_ports = HashSet::new();
-            for addr in &me.direct_addresses { unique_ports.insert(addr.port()); }
-            for port in unique_ports {
-                let localhost = std::net::SocketAddr::from_str(&format!("127.0.0.1:{}", port))?;
-                me.direct_addresses.insert(localhost);
+        Commands::Id { action } => return id::run(action, config::dir(args.profile.as_deref()), args.encrypt_identity),
+        Commands::Ticket { .. } => return rooms::inspect(&required(ticket.as_deref())?),
+        Commands::Completions { shell } => {
+            clap_complete::generate(*shell, &mut Cli::command(), "ghostterm", &mut std::io::stdout());
+            return Ok(());
+        }
+        Commands::Attach => return daemon::attach(daemon_at()?).await,
+        Commands::Setup => {
+            let dir = config::dir(args.profile.as_deref()).ok_or_else(|| anyhow!("no config directory (set HOME or XDG_CONFIG_HOME)"))?;
+            let file = config::file(args.config.as_deref(), args.profile.as_deref()).unwrap_or_else(|| dir.join("config.toml"));
+            wizard::run(&file, &dir, None)?;
+            return Ok(());
+        }
+        Commands::Version { protocol } => {
+            protocol::report(*protocol);
+            return Ok(());
+        }
+        Commands::Update { check } => return update::run(*check).await,
+        Commands::Service { action: service::Action::Install { name } } => return service::install(args.profile.as_deref(), name),
+        Commands::Service { action: service::Action::Uninstall } => return service::uninstall(args.profile.as_deref()),
+        Commands::Export { input, out, format, no_names, no_times } => {
+            let entries = match input {
+                Some(path) => export::from_events(std::io::BufReader::new(std::fs::File::open(path).with_context(|| format!("opening {}", path.display()))?))?,
+                None => export::from_events(std::io::stdin().lock())?,
+            };
+            let format = format.unwrap_or_else(|| out.as_deref().map_or(export::Format::Txt, export::Format::from_path));
+            let text = export::render(&entries, format, export::Redact { names: *no_names, times: *no_times });
+            match out {
+                Some(path) => std::fs::write(path, text).with_context(|| format!("writing {}", path.display()))?,
+                None => print!("{}", text),
             }
+            return Ok(());
+        }
+        // A second endpoint with the daemon's identity would fight it for the same NodeId.
+        _ if daemon_running => {
+            bail!("a daemon is running for this profile; use `ghostterm attach`, or --ephemeral for a separate identity")
+        }
+        _ => {}
+    }
 
-            let ticket = Ticket { topic, nodes: vec![me] };
-            let ghost_ticket = stego::hide(cover, &ticket.to_string());
+    // Only chat sessions mention updates; scripts and services shouldn't get surprise output.
+    let interactive = matches!(args.command, Commands::Host { .. } | Commands::Join { .. } | Commands::Rooms { .. });
+    // The first name typed for a chat becomes the default, so it needn't be typed every time.
+    let chat_name = match &args.command {
+        Commands::Host { name, .. } | Commands::Join { name, .. } | Commands::Rooms { action: RoomsAction::Join { name, .. } } => Some(name),
+        _ => None,
+    };
+    if let (Some(name), Some(file)) = (chat_name.filter(|_| typed_name && !args.ephemeral), config::file(args.config.as_deref(), args.profile.as_deref())) {
+        match config::remember(&file, "name", name.as_str(), false) {
+            Ok(true) => println!("{}", t!("name-saved", name = name.as_str(), path = file.display().to_string())),
+            Ok(false) => {}
+            Err(err) => println!("{}", t!("name-not-saved", error = format!("{:#}", err))),
+        }
+    }
+    if let Some(dir) = config::dir(args.profile.as_deref()).filter(|_| interactive && !args.no_update_check) {
+        if let Some(notice) = update::notice(&dir) {
+            println!("{}", notice);
+        }
+        update::spawn_check(&dir);
+    }
+
+    let secret_key = match config::dir(args.profile.as_deref()).filter(|_| !args.ephemeral) {
+        Some(dir) => match secret.take() {
+            Some(key) => Some(key),
+            None => Some(id::load_or_create(&dir, args.encrypt_identity)?),
+        },
+        None => None,
+    };
+    let options = client::Options {
+        secret_key,
+        relay: args.net.relay.clone(),
+        no_relay: args.net.no_relay,
+        no_local_discovery: args.net.no_local_discovery,
+        no_dns_discovery: args.net.no_dns_discovery,
+        blob_dir: config::dir(args.profile.as_deref()).filter(|_| !args.ephemeral).map(|dir| dir.join("blobs")),
+    };
+    // The window binds a node of its own for each room it opens.
+    #[cfg(feature = "gui")]
+    if let Commands::Gui { name, .. } = &args.command {
+        return gui::run(name.clone(), ticket, options).await;
+    }
+    let client::Network { endpoint, gossip, blobs, router, blob_dir: _blob_dir } = client::Network::bind(options).await?;
+    let iroh = transport::Iroh::new(&endpoint, &gossip);
+
+    match &args.command {
+        Commands::Host { name, cover, room_name, no_clipboard, broadcast, ui } => {
+            let mut channel = rooms::host(&iroh, cover).await?;
+            channel.info.name = room_name.clone().unwrap_or_default();
+            if *broadcast {
+                if let Message::Mode { at, signature, .. } = moderation::mode(endpoint.secret_key(), channel.topic, true) {
+                    channel.roles.set_mode(channel.host, channel.topic, true, at, signature);
+                }
+            }
 
-            println!("\n--- 👻 GHOST TICKET ---");
-            println!("{}", ghost_ticket);
+            println!("\n--- 👻 {} ---", t!("ticket-banner"));
+            println!("{}", channel.ticket);
             println!("-----------------------\n");
-            println!("Press ENTER to Initialize...");
-            
-            let mut line = String::new();
-            std::io::stdin().read_line(&mut line)?;
+            // Kept for the whole session: on X11 the copy lasts only as long as its owner.
+            let mut clipboard = clipboard::Clipboard::default();
+            if !no_clipboard {
+                match clipboard.copy(&channel.ticket) {
+                    Ok(target) => println!("{}", t!("ticket-copied", target = target)),
+                    Err(err) => println!("{}", t!("ticket-not-copied", error = err.to_string())),
+                }
+            }
+            if !plain::supported() {
+                plain::run(endpoint.clone(), gossip.clone(), channel, name.clone(), &args.hooks).await?;
+            } else {
+                println!("{}", t!("press-enter"));
 
-            let (sender, receiver) = gossip.subscribe(topic, vec![])?.split();
-            run_tui(sender, receiver, name.clone()).await?;
+                let mut line = String::new();
+                std::io::stdin().read_line(&mut line)?;
+
+                run_tui(endpoint.clone(), gossip.clone(), blobs.client().clone(), channel, name.clone(), clipboard, ui, &args).await?;
+            }
         }
         
-        Commands::Join { ticket, name } => {
-            let decoded = match stego::reveal(ticket) {
-                Ok(s) => s,
-                Err(_) => ticket.clone(),
-            };
-            let ticket = Ticket::from_str(&decoded).context("Invalid Ticket")?;
-            
-            let peer_ids: Vec<iroh::NodeId> = ticket.nodes.iter().map(|addr| addr.node_id).collect();
-            for addr in ticket.nodes { endpoint.add_node_addr(addr)?; }
-
-            println!("Connecting...");
-            let connect_future = gossip.subscribe_and_join(ticket.topic, peer_ids);
-            let topic_source = match tokio::time::timeout(Duration::from_secs(30), connect_future).await {
-                Ok(res) => res?,
-                Err(_) => {
-                    println!("Connection Failed (Timeout)");
+        Commands::Join { name, ui, .. } | Commands::Rooms { action: RoomsAction::Join { name, ui, .. } } => {
+            println!("{}", t!("connecting"));
+            let ticket = required(ticket.as_deref())?;
+            let channel = match rooms::join(&iroh, &ticket).await {
+                Ok(channel) => channel,
+                Err(err) => {
+                    println!("{}", err);
                     return Ok(());
                 }
             };
 
-            let (sender, receiver) = topic_source.split();
-            run_tui(sender, receiver, name.clone()).await?;
+            if !plain::supported() {
+                plain::run(endpoint.clone(), gossip.clone(), channel, name.clone(), &args.hooks).await?;
+            } else {
+                run_tui(endpoint.clone(), gossip.clone(), blobs.client().clone(), channel, name.clone(), clipboard::Clipboard::default(), ui, &args).await?;
+            }
+        }
+
+        Commands::Pipe { name, .. } => {
+            let channel = match &ticket {
+                Some(ticket) => rooms::join(&iroh, ticket).await?,
+                None => rooms::host(&iroh, "").await?,
+            };
+            pipe::run(endpoint.clone(), gossip.clone(), channel, name.clone(), &args.hooks).await?;
+        }
+
+        Commands::Send { message, name, linger, urgent, .. } => {
+            let channel = rooms::join(&iroh, &required(ticket.as_deref())?).await?;
+            pipe::send(channel, name.clone(), message.clone(), *linger, *urgent).await?;
+        }
+
+        Commands::Service { action: service::Action::Run { name } } => {
+            daemon::run(endpoint.clone(), gossip.clone(), name.clone(), daemon_at()?, false, args.profile.as_deref(), &args.hooks).await?
         }
+        Commands::Daemon { name, tray } => {
+            daemon::run(endpoint.clone(), gossip.clone(), name.clone(), daemon_at()?, *tray, args.profile.as_deref(), &args.hooks).await?
+        }
+        Commands::Gateway { name, listen, token } => {
+            let (token, kept) = gateway::token(token.clone(), args.profile.as_deref())?;
+            match kept {
+                Some(path) => eprintln!("Clients authenticate with the token in {}", path.display()),
+                None if std::env::var_os("GHOST_GATEWAY_TOKEN").is_none() => eprintln!("Clients authenticate with the token {}", token),
+                None => {}
+            }
+            gateway::run(endpoint.clone(), gossip.clone(), name.clone(), *listen, token, &args.hooks).await?
+        }
+
+        Commands::Completions { .. } | Commands::Ticket { .. } | Commands::Id { .. } | Commands::Attach | Commands::Rooms { .. } | Commands::Export { .. } | Commands::Service { .. } | Commands::Update { .. } | Commands::Version { .. } | Commands::Setup => {}
+        #[cfg(feature = "gui")]
+        Commands::Gui { .. } => {}
     }
 
     router.shutdown().await?;
@@ -146,198 +694,2337 @@ async fn main() -> Result<()> {
 
 // --- MODERN UI LOGIC ---
 
-struct ChatMessage {
-    sender: String,
-    text: String,
-    time: String,
-    is_me: bool,
+enum Overlay {
+    Help,
+    /// Link picker; `confirm` is set once a link is chosen and awaits y/n.
+    Links { urls: Vec<String>, selected: usize, confirm: bool },
+    /// Details for one peer, opened from the sidebar.
+    Peer(iroh::NodeId),
+    /// Ctrl+E picker; the chosen emoji is appended to the input.
+    Emoji { query: String, selected: usize },
+    /// "Really quit?" prompt, from Ctrl+Q or `/quit`.
+    Quit,
+    /// Details of one message of the active room (`i` in selection mode).
+    Info(usize),
+    /// Emoji picker for the message at index `target` of the active room.
+    React { target: usize, selected: usize },
+    /// Room picker for forwarding the message at index `target` of the active room;
+    /// `selected` counts the other rooms, in tab order.
+    Forward { target: usize, selected: usize },
+    /// "Really destroy?" prompt, from `/destroy`.
+    Destroy,
+    /// Says a room was destroyed, in `notice`. When it was the `last` one open, this is all
+    /// that's left on screen, and any key quits.
+    Destroyed { notice: String, last: bool },
 }
 
 struct AppState {
-    messages: Vec<ChatMessage>, 
-    input: String,
-    peer_names: HashMap<iroh::NodeId, String>,
+    /// One entry per tab; never empty.
+    rooms: Vec<rooms::Room>,
+    active: usize,
     my_name: String,
+    node_id: iroh::NodeId,
+    endpoint: Endpoint,
+    /// Height of the chat pane at the last draw, used to keep the selection on screen.
+    chat_height: Cell<usize>,
+    /// Pane positions and chat rows at the last draw, for the mouse.
+    areas: Cell<mouse::Areas>,
+    chat_rows: RefCell<Vec<mouse::Row>>,
+    /// Messages of the active room on screen at the last draw, for read receipts.
+    shown: RefCell<Vec<usize>>,
+    /// Mouse drag across the chat: where it started and where the pointer is now.
+    drag: Option<(Position, Position)>,
+    search: Option<search::Search>,
+    /// Message highlighted in selection mode (Ctrl+S).
+    selected: Option<usize>,
+    overlay: Option<Overlay>,
+    clipboard: clipboard::Clipboard,
+    /// Terminal graphics protocol, if the terminal supports inline images.
+    picker: Option<ratatui_image::picker::Picker>,
+    /// Sidebar row highlighted while browsing peers (Ctrl+P).
+    peer_cursor: Option<usize>,
+    /// Peers whose fingerprint was checked this session.
+    verified: HashSet<iroh::NodeId>,
+    /// Peers whose messages are dropped this session.
+    blocked: HashSet<iroh::NodeId>,
+    /// Peers posting too fast, muted for a while.
+    flood: flood::Flood,
+    /// Ours, in every room.
+    status: presence::Status,
+    /// Set when going idle made us away, so the next key brings us back.
+    idle_away: bool,
+    /// When a key was last pressed, for `--away-after`.
+    last_key: Instant,
+    away_after: Option<Duration>,
+    /// Set while the boss key has the chat hidden behind a fake prompt.
+    disguise: Option<boss::Disguise>,
+    density: chat::Density,
+    theme: theme::Theme,
+    screen_reader: bool,
+    time_style: chat::TimeStyle,
+    /// Modal state when the vim keymap is selected.
+    vim: Option<vim::Vim>,
+    sidebar_width: u16,
+    sidebar_min_cols: u16,
+    /// F2 choice; `None` means shown only when the terminal is wide enough.
+    sidebar_shown: Option<bool>,
+    /// Whether the sidebar was drawn last frame, so F2 can flip what the user actually sees.
+    sidebar_visible: Cell<bool>,
+    /// Alert for rooms opened later.
+    alert: notify::Alert,
+    /// Whether rooms opened later send read receipts.
+    read_receipts: bool,
+    /// `--backfill`: how much history late joiners get from us.
+    backfill: usize,
+    /// Where rooms keep their history on disk; `None` with `--ephemeral`.
+    archive: Option<store::Archive>,
+    /// Files we serve and fetch.
+    files: files::Files,
+    /// A `/voice` clip being recorded, for the room it goes to.
+    recording: Option<(TopicId, voice::Recording)>,
+    /// The voice message playing, by message id.
+    playing: Option<(String, voice::Playback)>,
+    /// Whether the terminal window has focus (for terminals that report it).
+    window_focused: bool,
+    /// The input border is highlighted until then, for `flash` alerts.
+    flash_until: Option<Instant>,
+    /// Looks up previews for links we send; `None` with `--no-link-previews`.
+    previews: Option<preview::Fetcher>,
+    pins: Pins,
+    /// Whether the pinned strip lists every pin (F4) or just the latest.
+    pins_expanded: bool,
+    /// Set once quitting is confirmed; the event loop exits on the next turn.
+    quit: bool,
+    /// Second chat pane: how the chat area is divided and which room it shows.
+    split: Option<(Direction, usize)>,
+    hooks: hooks::Hooks,
+    plugins: plugins::Registry,
+    /// What plugins asked for while gossip was handled, done on the next turn of the loop.
+    plugin_actions: Vec<(TopicId, plugins::Action)>,
+    /// Config file `/nick` saves the new name to; `None` with `--ephemeral`.
+    name_file: Option<PathBuf>,
+    /// The profile's directory, whose saved rooms a destroyed room is dropped from; `None`
+    /// with `--ephemeral`.
+    config_dir: Option<PathBuf>,
 }
 
+impl AppState {
+    fn room(&self) -> &rooms::Room {
+        &self.rooms[self.active]
+    }
+
+    fn room_mut(&mut self) -> &mut rooms::Room {
+        &mut self.rooms[self.active]
+    }
+
+    /// Adds a message to the active room.
+    fn push_message(&mut self, msg: ChatMessage) {
+        self.room_mut().push(msg);
+    }
+
+    /// Makes `status` ours in every room.
+    fn set_status(&mut self, status: presence::Status) {
+        for room in &mut self.rooms {
+            room.set_status(status.clone());
+        }
+        self.status = status;
+        self.idle_away = false;
+    }
+
+    /// False, after saying so, when the host made us read-only in the active room.
+    fn may_post(&mut self) -> bool {
+        let posts = self.room().role(&self.node_id).posts();
+        if !posts {
+            self.push_message(ChatMessage::system(t!("read-only-you")));
+        }
+        posts
+    }
+
+    /// Indices of the messages the chat pane currently displays (honours `from:` filters).
+    fn visible(&self) -> Vec<usize> {
+        let messages = &self.room().messages;
+        self.room().view().into_iter()
+            .filter(|&i| self.search.as_ref().is_none_or(|s| s.shows(&messages[i])))
+            .collect()
+    }
+
+    /// Scrolls so that the message at `index` sits at the bottom of the chat pane.
+    fn scroll_to(&mut self, index: usize) {
+        let visible = self.visible();
+        if let Some(pos) = visible.iter().position(|&i| i == index) {
+            self.room_mut().scroll = visible.len() - 1 - pos;
+        }
+    }
+
+    /// Scrolls the minimum amount needed to bring the message at `index` on screen.
+    fn reveal(&mut self, index: usize) {
+        let visible = self.visible();
+        let Some(pos) = visible.iter().position(|&i| i == index) else { return };
+        let from_bottom = visible.len() - 1 - pos;
+        let height = self.chat_height.get().max(1);
+        let room = self.room_mut();
+        if from_bottom < room.scroll {
+            room.scroll = from_bottom;
+        } else if from_bottom >= room.scroll + height {
+            room.scroll = from_bottom + 1 - height;
+        }
+    }
+
+    fn copy(&mut self, text: &str) {
+        let notice = match self.clipboard.copy(text) {
+            Ok(target) => t!("copied", target = target),
+            Err(err) => t!("copy-failed", error = err.to_string()),
+        };
+        self.push_message(ChatMessage::system(notice));
+    }
+
+    fn show_links(&mut self, urls: Vec<String>) {
+        if urls.is_empty() {
+            self.push_message(ChatMessage::system(t!("no-links")));
+        } else {
+            let confirm = urls.len() == 1;
+            self.overlay = Some(Overlay::Links { urls, selected: 0, confirm });
+        }
+    }
+
+    fn scroll_by(&mut self, delta: isize) {
+        let max = self.visible().len().saturating_sub(1);
+        let room = self.room_mut();
+        match room.scroll.saturating_add_signed(delta).min(max) {
+            0 => room.jump_to_bottom(),
+            scroll => room.scroll = scroll,
+        }
+    }
+
+    /// Starts selection mode on the newest visible message.
+    fn enter_selection(&mut self) {
+        self.selected = self.visible().last().copied();
+        self.room_mut().jump_to_bottom();
+    }
+
+    /// Makes tab `index` the active one. Search and selection belong to the old tab's view.
+    /// Picking the room in the other split pane swaps the panes instead.
+    fn switch_room(&mut self, index: usize) {
+        if index < self.rooms.len() {
+            if let Some((_, other)) = self.split.as_mut() {
+                if *other == index {
+                    *other = self.active;
+                }
+            }
+            self.active = index;
+            self.search = None;
+            self.selected = None;
+            self.peer_cursor = None;
+            self.room_mut().unread = 0;
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 async fn run_tui(
-    sender: iroh_gossip::net::GossipSender,
-    mut receiver: iroh_gossip::net::GossipReceiver,
+    endpoint: Endpoint,
+    gossip: Gossip,
+    blobs: iroh_blobs::rpc::client::blobs::MemClient,
+    channel: rooms::Channel,
     my_name: String,
+    clipboard: clipboard::Clipboard,
+    ui_args: &UiArgs,
+    cli: &Cli,
 ) -> Result<()> {
     enable_raw_mode()?;
     let mut stdout = std::io::stdout();
-    execute!(stdout, EnterAlternateScreen)?;
+    // Pastes arrive as one event instead of a burst of keys (which would send on every newline).
+    execute!(stdout, EnterAlternateScreen, EnableBracketedPaste, EnableFocusChange)?;
+    status::save_title(&mut stdout)?;
+    if !ui_args.no_mouse {
+        execute!(stdout, EnableMouseCapture)?;
+    }
+    // Lets terminals that support it report Ctrl+Tab distinctly from Tab.
+    let enhanced_keys = supports_keyboard_enhancement().unwrap_or(false);
+    if enhanced_keys {
+        execute!(stdout, PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES))?;
+    }
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
+    // Must run before we start reading key events: it talks to the terminal over stdio.
+    // Inline graphics ignore the theme and mean nothing to a screen reader, so those get placeholders.
+    let picker = if ui_args.theme == theme::Theme::Default && !ui_args.screen_reader { images::detect() } else { None };
+
+    let (events_tx, mut events) = mpsc::unbounded_channel();
+    let (opened_tx, mut opened) = mpsc::unbounded_channel();
+    let (files_tx, mut file_events) = mpsc::unbounded_channel();
+    let (previews_tx, mut previews) = mpsc::unbounded_channel();
+    let node_id = endpoint.node_id();
+    let net = rooms::Net { endpoint: endpoint.clone(), gossip, events: events_tx, opened: opened_tx };
 
+    let (plugins, plugin_notices) = load_plugins(cli.profile.as_deref());
+    let config_dir = config::dir(cli.profile.as_deref()).filter(|_| !cli.ephemeral);
+    let archive = config_dir.as_ref().map(|dir| store::Archive::new(dir, endpoint.secret_key(), ui_args.history, ui_args.encrypt_history));
     let mut state = AppState {
-        messages: Vec::new(),
-        input: String::new(),
-        peer_names: HashMap::new(),
-        my_name: my_name.clone(),
+        rooms: vec![rooms::Room::spawn(channel, my_name.clone(), ui_args.alert, !ui_args.no_read_receipts, net.events.clone())],
+        active: 0,
+        my_name,
+        node_id,
+        endpoint,
+        chat_height: Cell::new(0),
+        areas: Cell::new(mouse::Areas::default()),
+        chat_rows: RefCell::new(Vec::new()),
+        shown: RefCell::new(Vec::new()),
+        drag: None,
+        search: None,
+        selected: None,
+        overlay: None,
+        clipboard,
+        picker,
+        peer_cursor: None,
+        verified: HashSet::new(),
+        blocked: HashSet::new(),
+        flood: flood::Flood::new(flood::Limits {
+            posts: ui_args.flood_limit,
+            window: Duration::from_secs(ui_args.flood_window),
+            mute: Duration::from_secs(ui_args.flood_mute),
+        }),
+        status: presence::Status::default(),
+        idle_away: false,
+        last_key: Instant::now(),
+        away_after: (ui_args.away_after > 0).then(|| Duration::from_secs(ui_args.away_after * 60)),
+        disguise: None,
+        density: ui_args.density,
+        theme: ui_args.theme,
+        screen_reader: ui_args.screen_reader,
+        time_style: ui_args.time,
+        vim: (ui_args.keys == vim::Keymap::Vim).then(vim::Vim::default),
+        sidebar_width: ui_args.sidebar_width,
+        sidebar_min_cols: ui_args.sidebar_min_cols,
+        sidebar_shown: None,
+        sidebar_visible: Cell::new(true),
+        alert: ui_args.alert,
+        read_receipts: !ui_args.no_read_receipts,
+        backfill: ui_args.backfill,
+        archive,
+        files: files::Files::new(blobs, files_tx, ui_args.download_dir.clone().unwrap_or_else(files::default_dir)),
+        recording: None,
+        playing: None,
+        window_focused: true,
+        flash_until: None,
+        previews: match ui_args.no_link_previews {
+            true => None,
+            false => preview::Fetcher::new(previews_tx).inspect_err(|err| tracing::warn!(%err, "link previews off")).ok(),
+        },
+        pins: ui_args.pins,
+        pins_expanded: false,
+        quit: false,
+        split: None,
+        hooks: cli.hooks.clone(),
+        plugins,
+        plugin_actions: Vec::new(),
+        name_file: config::file(cli.config.as_deref(), cli.profile.as_deref()).filter(|_| !cli.ephemeral),
+        config_dir,
     };
+    open_history(&mut state.rooms[0], state.archive.as_ref());
+    state.room().ask_history();
+    for notice in plugin_notices {
+        state.push_message(ChatMessage::system(notice));
+    }
 
-    // --- HEARTBEAT SYSTEM (Fixes "Unknown" Name Bug) ---
-    // Sends "AboutMe" every 3 seconds so new peers learn our name immediately.
-    let gossip_tx = sender.clone();
-    let heartbeat_name = my_name.clone();
-    tokio::spawn(async move {
-        loop {
-            let msg = Message::AboutMe { name: heartbeat_name.clone() };
-            if let Ok(bytes) = serde_json::to_vec(&msg) {
-                let _ = gossip_tx.broadcast(bytes.into()).await;
+    let mut title = String::new();
+    // Redraw only after something changed; the tick covers the clock, relative times and flash.
+    let mut dirty = true;
+    let mut tick = tokio::time::interval(Duration::from_secs(1));
+    tick.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+    let mut input = EventStream::new();
+    while !state.quit {
+        if dirty {
+            let wanted = status::window_title(&state);
+            if wanted != title {
+                execute!(terminal.backend_mut(), SetTitle(&wanted))?;
+                title = wanted;
             }
-            tokio::time::sleep(Duration::from_secs(3)).await;
+            terminal.draw(|f| ui(f, &state))?;
+            dirty = false;
+            mark_read(&mut state);
         }
-    });
-
-    loop {
-        terminal.draw(|f| ui(f, &state))?;
 
         tokio::select! {
-            event = receiver.next() => {
-                if let Some(Ok(iroh_gossip::net::Event::Gossip(GossipEvent::Received(msg)))) = event {
-                    let from_id = msg.delivered_from;
-                    if let Ok(decoded) = serde_json::from_slice::<Message>(&msg.content) {
-                        match decoded {
-                            Message::AboutMe { name } => {
-                                state.peer_names.insert(from_id, name.clone());
-                            }
-                            Message::Chat { text } => {
-                                let name = state.peer_names.get(&from_id).map(|s| s.as_str()).unwrap_or("Unknown");
-                                let time = Local::now().format("%H:%M").to_string();
-                                state.messages.push(ChatMessage {
-                                    sender: name.to_string(),
-                                    text,
-                                    time,
-                                    is_me: false,
-                                });
-                            }
-                        }
+            Some((topic, event)) = events.recv() => {
+                handle_gossip(&mut state, topic, event);
+                run_plugins(&mut state).await;
+                dirty = true;
+            }
+
+            _ = tick.tick() => {
+                for room in &mut state.rooms {
+                    room.send_receipts();
+                }
+                if state.recording.as_ref().is_some_and(|(_, r)| r.elapsed() >= voice::MAX) {
+                    send_voice(&mut state);
+                }
+                if state.playing.as_ref().is_some_and(|(_, p)| p.done()) {
+                    state.playing = None;
+                }
+                expire(&mut state);
+                let idle = state.away_after.is_some_and(|after| state.last_key.elapsed() >= after);
+                if idle && state.status == presence::Status::default() {
+                    state.set_status(presence::Status { presence: presence::Presence::Away, text: String::new() });
+                    state.idle_away = true;
+                }
+                dirty = true;
+            }
+
+            Some(event) = file_events.recv() => {
+                file_event(&mut state, event).await;
+                dirty = true;
+            }
+
+            Some(preview::Fetched { topic, id, preview }) = previews.recv() => {
+                if let Some(room) = state.rooms.iter_mut().find(|r| r.topic == topic) {
+                    if let Some(msg) = room.messages.iter_mut().rev().find(|m| m.origin == Origin::Me && m.id == id) {
+                        msg.preview = Some(preview.clone());
+                        room.keep(&id);
+                        broadcast(&room.sender, &Message::LinkPreview { target: id, preview }).await;
+                        dirty = true;
                     }
                 }
             }
 
-            _ = tokio::time::sleep(Duration::from_millis(10)) => {
-                if event::poll(Duration::from_millis(0))? {
-                    if let Event::Key(key) = event::read()? {
-                        if key.kind == KeyEventKind::Press {
-                            match key.code {
-                                KeyCode::Enter => {
-                                    if !state.input.is_empty() {
-                                        let text = state.input.drain(..).collect::<String>();
-                                        let msg = Message::Chat { text: text.clone() };
-                                        if let Ok(bytes) = serde_json::to_vec(&msg) {
-                                            let _ = sender.broadcast(bytes.into()).await;
+            Some(result) = opened.recv() => {
+                dirty = true;
+                match result {
+                    Ok(channel) if state.rooms.iter().any(|r| r.topic == channel.topic) => {
+                        let index = state.rooms.iter().position(|r| r.topic == channel.topic).unwrap_or(state.active);
+                        state.switch_room(index);
+                        state.push_message(ChatMessage::system(t!("already-in-room")));
+                    }
+                    Ok(channel) => {
+                        let mut room = rooms::Room::spawn(channel, state.my_name.clone(), state.alert, state.read_receipts, net.events.clone());
+                        let label = room.label();
+                        open_history(&mut room, state.archive.as_ref());
+                        room.ask_history();
+                        room.set_status(state.status.clone());
+                        state.rooms.push(room);
+                        state.switch_room(state.rooms.len() - 1);
+                        state.push_message(ChatMessage::system(t!("joined-room", room = label)));
+                    }
+                    Err(err) => state.push_message(ChatMessage::system(t!("open-room-failed", error = err.to_string()))),
+                }
+            }
+
+            _ = tokio::time::sleep_until(state.flash_until.unwrap_or_else(Instant::now).into()), if state.flash_until.is_some() => {
+                state.flash_until = None;
+                dirty = true;
+            }
+
+            Some(event) = input.next() => {
+                let event = event?;
+                // Key releases and bare pointer motion change nothing on screen.
+                let idle = match &event {
+                    Event::Key(key) => key.kind != KeyEventKind::Press,
+                    Event::Mouse(mouse) => mouse.kind == MouseEventKind::Moved,
+                    _ => false,
+                };
+                if idle {
+                    continue;
+                }
+                dirty = true;
+                match event {
+                    Event::FocusGained => {
+                        state.window_focused = true;
+                        let visible = state.split.map(|(_, other)| other);
+                        for room in [Some(state.active), visible].into_iter().flatten() {
+                            state.rooms[room].unread = 0;
+                        }
+                    }
+                    Event::FocusLost => state.window_focused = false,
+                    _ => {}
+                }
+                if let Event::Paste(text) = &event {
+                    if let Some(search) = state.search.as_mut() {
+                        search.query.push_str(text.trim());
+                        search.reset(&state.rooms[state.active].messages);
+                    } else if state.overlay.is_none() && state.disguise.is_none() {
+                        state.room_mut().input.insert_str(text);
+                    }
+                }
+                if let Event::Mouse(mouse) = event {
+                    if state.disguise.is_none() {
+                        mouse::handle(&mut state, mouse);
+                    }
+                }
+                if let Event::Key(key) = event {
+                    state.last_key = Instant::now();
+                    if state.idle_away {
+                        state.set_status(presence::Status::default());
+                    }
+                    let passphrase = ui_args.unhide_passphrase.as_deref();
+                    if let Some(disguise) = state.disguise.as_mut() {
+                        let unlocked = match passphrase {
+                            Some(_) => disguise.key(key, passphrase),
+                            None => ui_args.boss_key.matches(&key) || disguise.key(key, None),
+                        };
+                        if unlocked {
+                            state.disguise = None;
+                        }
+                        continue;
+                    }
+                    if ui_args.boss_key.matches(&key) {
+                        state.disguise = Some(boss::Disguise::new(passphrase.is_none()));
+                        // A full repaint, so no inline image graphics linger on screen.
+                        terminal.clear()?;
+                        continue;
+                    }
+                    if state.overlay.is_some() {
+                        handle_overlay_key(&mut state, key).await;
+                        continue;
+                    }
+                    if state.search.is_some() {
+                        handle_search_key(&mut state, key);
+                        continue;
+                    }
+                    if state.selected.is_some() {
+                        handle_select_key(&mut state, key).await;
+                        continue;
+                    }
+                    if state.peer_cursor.is_some() {
+                        handle_peer_key(&mut state, key);
+                        continue;
+                    }
+                    if vim::key(&mut state, key) {
+                        continue;
+                    }
+                    let alt = key.modifiers.contains(KeyModifiers::ALT);
+                    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+                    match key.code {
+                        KeyCode::Enter if alt => state.room_mut().input.insert('\n'),
+                        KeyCode::Enter if !state.room().input.is_empty() => {
+                            let text = state.room_mut().input.take();
+                            match commands::parse(&text) {
+                                Some(Ok(command)) => run_command(&mut state, &net, command).await,
+                                Some(Err(err)) => {
+                                    let topic = state.room().topic;
+                                    match state.plugins.command(topic, &text) {
+                                        Some(actions) => {
+                                            state.plugin_actions.extend(actions.into_iter().map(|action| (topic, action)));
+                                            run_plugins(&mut state).await;
                                         }
-                                        let time = Local::now().format("%H:%M").to_string();
-                                        state.messages.push(ChatMessage {
-                                            sender: state.my_name.clone(),
-                                            text,
-                                            time,
-                                            is_me: true,
-                                        });
+                                        None => state.push_message(ChatMessage::system(err)),
                                     }
                                 }
-                                KeyCode::Char(c) => { state.input.push(c); }
-                                KeyCode::Backspace => { state.input.pop(); }
-                                KeyCode::Esc => { break; }
-                                _ => {}
+                                None if !state.room().role(&state.node_id).posts() => {
+                                    state.room_mut().input.set(text);
+                                    state.push_message(ChatMessage::system(t!("read-only-you")));
+                                }
+                                None if text.len() > chunks::MAX_TEXT => {
+                                    state.room_mut().input.set(text);
+                                    state.push_message(ChatMessage::system(t!("message-too-long", max = chunks::MAX_TEXT / 1024)));
+                                }
+                                None if state.room().editing.is_some() => {
+                                    let text = emoji::expand(&text);
+                                    let target = state.room_mut().editing.take().unwrap_or_default();
+                                    let rich = rich::parse(&text, &state.room().peer_names, state.node_id, &state.my_name);
+                                    broadcast(&state.room().sender, &Message::Edit { target: target.clone(), text: text.clone(), rich: rich.clone() }).await;
+                                    if let Some(msg) = state.room_mut().messages.iter_mut().rev().find(|m| m.id == target) {
+                                        msg.text = text;
+                                        msg.rich = rich;
+                                        msg.edited = true;
+                                    }
+                                    state.room().keep(&target);
+                                }
+                                None => post(&mut state, &text, false, None).await,
+                            }
+                        }
+                        KeyCode::F(1) => state.overlay = Some(Overlay::Help),
+                        KeyCode::F(2) => state.sidebar_shown = Some(!state.sidebar_visible.get()),
+                        KeyCode::F(3) => {
+                            state.density = state.density.next();
+                            state.push_message(ChatMessage::system(t!("density-changed", density = state.density.name())));
+                        }
+                        KeyCode::F(4) => state.pins_expanded = !state.pins_expanded,
+                        KeyCode::F(6) => {
+                            if let Some((_, other)) = state.split {
+                                state.switch_room(other);
+                            }
+                        }
+                        KeyCode::Char('f') if ctrl => {
+                            state.search = Some(search::Search::default());
+                        }
+                        KeyCode::Char('s') if ctrl => {
+                            state.enter_selection();
+                        }
+                        KeyCode::Char('q') if ctrl => state.overlay = Some(Overlay::Quit),
+                        KeyCode::Char('e') if ctrl => {
+                            state.overlay = Some(Overlay::Emoji { query: String::new(), selected: 0 });
+                        }
+                        KeyCode::Char('p') if ctrl => {
+                            if state.room().peer_names.is_empty() {
+                                state.push_message(ChatMessage::system(t!("no-peers")));
+                            } else {
+                                state.peer_cursor = Some(0);
                             }
                         }
+                        KeyCode::Char(c @ '1'..='9') if alt => {
+                            state.switch_room(c as usize - '1' as usize);
+                        }
+                        KeyCode::Tab if ctrl => state.switch_room((state.active + 1) % state.rooms.len()),
+                        KeyCode::Right if alt => state.switch_room((state.active + 1) % state.rooms.len()),
+                        KeyCode::BackTab if ctrl => state.switch_room((state.active + state.rooms.len() - 1) % state.rooms.len()),
+                        KeyCode::Left if alt => state.switch_room((state.active + state.rooms.len() - 1) % state.rooms.len()),
+                        KeyCode::Char(c) => state.room_mut().input.insert(c),
+                        KeyCode::Backspace => state.room_mut().input.backspace(),
+                        KeyCode::Delete => state.room_mut().input.delete(),
+                        KeyCode::Left => state.room_mut().input.left(),
+                        KeyCode::Right => state.room_mut().input.right(),
+                        KeyCode::Home => state.room_mut().input.home(),
+                        KeyCode::End if state.room().scroll > 0 => state.room_mut().jump_to_bottom(),
+                        KeyCode::End => state.room_mut().input.end(),
+                        KeyCode::PageUp => state.scroll_by(10),
+                        KeyCode::PageDown => state.scroll_by(-10),
+                        KeyCode::Up if state.room().input.is_empty() => {
+                            let room = state.room_mut();
+                            let last = room.messages.iter().rev()
+                                .find(|m| m.origin == Origin::Me && m.image.is_none() && m.poll.is_none() && m.location.is_none() && !m.redacted && !m.id.is_empty());
+                            if let Some(msg) = last {
+                                room.input.set(msg.text.clone());
+                                room.editing = Some(msg.id.clone());
+                            }
+                        }
+                        KeyCode::Esc if state.room().editing.is_some() => {
+                            let room = state.room_mut();
+                            room.editing = None;
+                            room.input.clear();
+                        }
+                        KeyCode::Esc if state.room().reply.is_some() => state.room_mut().reply = None,
+                        KeyCode::Esc if !state.room().input.is_empty() => state.room_mut().input.clear(),
+                        KeyCode::Esc if state.room().thread.is_some() => {
+                            let room = state.room_mut();
+                            room.thread = None;
+                            room.jump_to_bottom();
+                        }
+                        KeyCode::Esc => state.push_message(ChatMessage::system(t!("how-to-quit"))),
+                        _ => {}
                     }
                 }
             }
         }
     }
 
+    if enhanced_keys {
+        execute!(terminal.backend_mut(), PopKeyboardEnhancementFlags)?;
+    }
     disable_raw_mode()?;
-    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    status::restore_title(terminal.backend_mut())?;
+    if !ui_args.no_mouse {
+        execute!(terminal.backend_mut(), DisableMouseCapture)?;
+    }
+    execute!(terminal.backend_mut(), DisableFocusChange, DisableBracketedPaste, LeaveAlternateScreen)?;
     terminal.show_cursor()?;
     Ok(())
 }
 
-fn ui(frame: &mut Frame, state: &AppState) {
-    let main_layout = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([
-            Constraint::Length(25), // Sidebar (Left)
-            Constraint::Min(1),     // Chat (Right)
-        ])
-        .split(frame.area());
+/// Applies one gossip event to the room it belongs to.
+fn handle_gossip(state: &mut AppState, topic: TopicId, event: iroh_gossip::net::Event) {
+    let Some(index) = state.rooms.iter().position(|r| r.topic == topic) else { return };
+    let room = &mut state.rooms[index];
 
-    let chat_layout = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([
-            Constraint::Min(1),    // Messages
-            Constraint::Length(3), // Input
-        ])
-        .split(main_layout[1]);
+    match event {
+        iroh_gossip::net::Event::Gossip(GossipEvent::Received(msg)) => {
+            for event in room.receive(msg, &state.blocked, Some(&mut state.flood)) {
+                show_event(state, index, event);
+            }
+        }
+        iroh_gossip::net::Event::Gossip(GossipEvent::Joined(peers)) => {
+            room.neighbors.extend(peers);
+        }
+        iroh_gossip::net::Event::Gossip(GossipEvent::NeighborUp(id)) => {
+            room.neighbors.insert(id);
+            room.introduce();
+            if !room.backfilled {
+                room.ask_history();
+            }
+        }
+        iroh_gossip::net::Event::Gossip(GossipEvent::NeighborDown(id)) => {
+            room.neighbors.remove(&id);
+        }
+        iroh_gossip::net::Event::Lagged => {
+            room.push(ChatMessage::system(t!("lagged")));
+        }
+    }
+}
 
-    // --- SIDEBAR (PEERS) ---
-    let mut peers: Vec<ListItem> = state.peer_names.values().map(|name| {
-        ListItem::new(Line::from(vec![
-            Span::styled(" ● ", Style::default().fg(Color::Cyan)), 
-            Span::raw(name),
-        ]))
-    }).collect();
-    
-    peers.insert(0, ListItem::new(Line::from(vec![
-        Span::styled(" ● ", Style::default().fg(Color::Green)), 
-        Span::styled(format!("{} (You)", state.my_name), Style::default().add_modifier(Modifier::BOLD)),
-    ])));
+/// Shows what a message did to room `index`, once the room has taken it in: notices, new
+/// lines in the chat and changes to old ones, with the alerts, hooks and plugins they set off.
+fn show_event(state: &mut AppState, index: usize, event: ChatEvent) {
+    let is_active = index == state.active || state.split.is_some_and(|(_, other)| other == index);
+    let blocked = &state.blocked;
+    let pins = state.pins;
+    let hidden = state.disguise.is_some();
+    let my_name = state.my_name.clone();
+    let me = state.node_id;
+    let mute = state.flood.mute();
+    let picker = state.picker.as_ref();
+    let hooks = &state.hooks;
+    let plugins = &mut state.plugins;
+    let plugin_actions = &mut state.plugin_actions;
+    let endpoint = &state.endpoint;
+    let config_dir = state.config_dir.as_deref();
+    let room = &mut state.rooms[index];
+    let topic = room.topic;
+    let time = Utc::now();
 
-    let sidebar = List::new(peers)
-        .block(Block::default()
-            .borders(Borders::RIGHT) 
-            .title(" Network ")
-            .padding(Padding::new(1, 1, 1, 1)))
-        .style(Style::default().fg(Color::DarkGray));
-            
-    frame.render_widget(sidebar, main_layout[0]);
+    let chat = match event {
+        ChatEvent::Unsupported { from, kind, version } => {
+            newer_message(room, from, &kind, version);
+            if !is_active || !state.window_focused {
+                room.unread += 1;
+            }
+            return;
+        }
+        ChatEvent::Gap { node, .. } => {
+            let name = room.peer_names.get(&node).cloned().unwrap_or_else(|| node.fmt_short());
+            room.push(ChatMessage::system(t!("messages-missing", name = name)));
+            return;
+        }
+        ChatEvent::Muted(node) => {
+            let name = room.peer_names.get(&node).cloned().unwrap_or_else(|| node.fmt_short());
+            room.push(ChatMessage::system(t!("flood-muted", name = name, seconds = mute.as_secs())));
+            return;
+        }
+        ChatEvent::Peer { node, name, old } => {
+            match old {
+                Some(old) => room.push(ChatMessage::system(t!("peer-renamed", old = old, name = name.as_str()))),
+                None => {
+                    hooks.join(&serde_json::json!({ "event": "peer", "room": topic.to_string(), "node": node.to_string(), "name": name }));
+                    plugin_actions.extend(plugins.peer_join(topic, node, &name).into_iter().map(|action| (topic, action)));
+                }
+            }
+            warn_lookalike(room, node, &name, &my_name);
+            return;
+        }
+        ChatEvent::Outdated { node, info } => {
+            let name = room.peer_names.get(&node).cloned().unwrap_or_else(|| node.fmt_short());
+            room.push(ChatMessage::system(t!("protocol-older", name = name.as_str(), version = info.version, types = info.missing().join(", "))));
+            return;
+        }
+        ChatEvent::Destroyed => return destroyed(state, index),
+        ChatEvent::Redeemed { node, relay } => {
+            let place = whereabouts::describe(endpoint, node, relay.as_deref());
+            room.push(ChatMessage::system(t!("ticket-redeemed", fingerprint = identity::fingerprint(&node), place = place)));
+            if !is_active || !state.window_focused {
+                room.unread += 1;
+            }
+            return;
+        }
+        ChatEvent::Banned { node, name, kick } => {
+            let name = name.unwrap_or_else(|| node.fmt_short());
+            let notice = match (node == me, kick) {
+                (true, true) => t!("you-kicked"),
+                (true, false) => t!("you-banned"),
+                (false, true) => t!("peer-kicked", name = name),
+                (false, false) => t!("peer-banned", name = name),
+            };
+            room.push(ChatMessage::system(notice));
+            return;
+        }
+        ChatEvent::Info { info, old } => {
+            if info.topic != old.topic && !info.topic.is_empty() {
+                room.push(ChatMessage::system(t!("topic-changed", topic = info.topic.as_str())));
+            }
+            remember_info(config_dir, endpoint.secret_key(), topic, &info);
+            return;
+        }
+        ChatEvent::Redact { target, from, .. } => {
+            room.redact(&target, from);
+            return;
+        }
+        ChatEvent::Edit { target, from, text, rich, .. } => {
+            let Some((text, rich)) = filtered(plugins, topic, text, rich) else { return };
+            let own = |m: &&mut ChatMessage| !target.is_empty() && m.id == target && m.from == Some(from) && m.image.is_none() && !m.redacted;
+            if let Some(msg) = room.messages.iter_mut().rev().find(own) {
+                msg.text = text;
+                msg.rich = rich;
+                msg.edited = true;
+                room.keep(&target);
+            }
+            return;
+        }
+        ChatEvent::Reaction { target, from, emoji, remove, .. } => {
+            if let Some(msg) = room.messages.iter_mut().rev().find(|m| !target.is_empty() && m.id == target && !m.redacted) {
+                reactions::apply(&mut msg.reactions, &emoji, from, remove);
+                room.keep(&target);
+            }
+            return;
+        }
+        ChatEvent::Receipt { targets, from, read } => {
+            for msg in room.messages.iter_mut().filter(|m| m.origin == Origin::Me && targets.contains(&m.id)) {
+                msg.receipts.apply(from, read);
+            }
+            for target in &targets {
+                room.keep(target);
+            }
+            return;
+        }
+        ChatEvent::Presence { .. } | ChatEvent::NeighborUp(_) | ChatEvent::NeighborDown(_) | ChatEvent::Error(_) => return,
+        ChatEvent::Message { id, from, sender, text, rich, reply, thread, urgent, announcement: false, ttl, forwarded, mentions_me } => {
+            let Some((text, rich)) = filtered(plugins, topic, text, rich) else { return };
+            // The same text again straight after is counted on the first copy.
+            let repeat = room.messages.last_mut().filter(|last| {
+                last.origin == Origin::Peer && last.from == Some(from) && last.text == text && last.thread == thread
+                    && reply.is_none() && last.quote.is_none() && forwarded.is_none() && last.forwarded.is_none() && !last.announcement
+                    && last.image.is_none() && last.file.is_none() && last.poll.is_none() && last.location.is_none()
+                    && last.expires.is_none() && ttl.is_none()
+                    && (time - last.time).to_std().is_ok_and(|since| since < flood::BURST)
+            });
+            if let Some(last) = repeat {
+                last.repeats += 1;
+                let id = last.id.clone();
+                room.keep(&id);
+                return;
+            }
+            hooks.message(&serde_json::json!({
+                "event": "message", "room": topic.to_string(), "id": id, "from": from.to_string(), "sender": sender,
+                "text": text, "time": clock::show(time).to_rfc3339(), "reply": reply.as_ref().map(|q| &q.id), "thread": thread,
+                "urgent": urgent, "rich": rich, "ttl": ttl, "forwarded": forwarded,
+            }), mentions_me || urgent);
+            let incoming = plugins::Incoming { room: topic, id: &id, from, sender: &sender, text: &text, mentions_me };
+            plugin_actions.extend(plugins.message_in(&incoming).into_iter().map(|action| (topic, action)));
+            ChatMessage {
+                mentions_me, quote: reply, thread, urgent, rich, expires: ttl.map(expiry::at), forwarded,
+                ..ChatMessage::peer(id, from, sender, text, time)
+            }
+        }
+        ChatEvent::Message { id, from, sender, text, .. } => {
+            let Some(text) = plugins.filter_in(topic, text) else { return };
+            let mentions_me = !markup::mentions(&text, &[&my_name]).is_empty();
+            hooks.message(&serde_json::json!({
+                "event": "announcement", "room": topic.to_string(), "id": id, "from": from.to_string(), "sender": sender,
+                "text": text, "time": clock::show(time).to_rfc3339(),
+            }), mentions_me);
+            let incoming = plugins::Incoming { room: topic, id: &id, from, sender: &sender, text: &text, mentions_me };
+            plugin_actions.extend(plugins.message_in(&incoming).into_iter().map(|action| (topic, action)));
+            ChatMessage { mentions_me, announcement: true, ..ChatMessage::peer(id, from, sender, text, time) }
+        }
+        ChatEvent::Other { from, sender, message, .. } => match message {
+            Message::Role { node, role, .. } => {
+                let notice = match node == me {
+                    true => t!("role-yours", role = role.name()),
+                    false => {
+                        let name = room.peer_names.get(&node).cloned().unwrap_or_else(|| node.fmt_short());
+                        t!("role-changed", name = name, role = role.name())
+                    }
+                };
+                room.push(ChatMessage::system(notice));
+                return;
+            }
+            Message::Mode { broadcast, .. } => {
+                let notice = if broadcast { t!("mode-broadcast") } else { t!("mode-open") };
+                room.push(ChatMessage::system(notice));
+                return;
+            }
+            Message::Extension { kind, .. } => {
+                tracing::debug!(%from, kind, "extension message");
+                let mut placeholder = ChatMessage::system(t!("extension-message", kind = kind.as_str()));
+                placeholder.from = Some(from);
+                placeholder.sender = sender;
+                placeholder.origin = Origin::Peer;
+                room.push(placeholder);
+                return;
+            }
+            Message::Pin { quote, remove } => {
+                if pins == Pins::Host && !room.role(&from).moderates() {
+                    return;
+                }
+                let Quote { id, sender, snippet } = quote;
+                // The pinner only vouches for the id; show our own copy of the text if we have one.
+                let quote = match room.messages.iter().find(|m| !id.is_empty() && m.id == id) {
+                    Some(msg) => Quote::of(msg),
+                    None => Quote { id, sender, snippet },
+                };
+                room.pins.retain(|p| p.id != quote.id);
+                if !remove {
+                    room.pins.push(quote);
+                }
+                return;
+            }
+            Message::LinkPreview { target, preview } => {
+                // Only for a link the sender really put in their own message.
+                let own = |m: &&mut ChatMessage| !target.is_empty() && m.id == target && m.from == Some(from);
+                if let Some(msg) = room.messages.iter_mut().rev().find(own) {
+                    if msg.text.contains(&preview.url) {
+                        msg.preview = Some(preview);
+                        room.keep(&target);
+                    }
+                }
+                return;
+            }
+            Message::Vote { target, option, remove } => {
+                let poll = room.messages.iter_mut().rev().find(|m| !target.is_empty() && m.id == target).and_then(|m| m.poll.as_mut());
+                if poll.is_some_and(|poll| poll.vote(from, option, remove)) {
+                    room.keep(&target);
+                }
+                return;
+            }
+            Message::HistoryRequest {} => {
+                if let Some(history) = backfill::history(&room.messages, me, state.backfill) {
+                    let sender = room.sender.clone();
+                    tokio::spawn(async move { broadcast_neighbors(&sender, &history).await });
+                }
+                return;
+            }
+            Message::History { messages } => {
+                room.backfilled = true;
+                let mut anchors = Vec::new();
+                if is_active {
+                    anchors.extend(state.selected.as_mut());
+                    match &mut state.overlay {
+                        Some(Overlay::Info(i)) | Some(Overlay::React { target: i, .. }) | Some(Overlay::Forward { target: i, .. }) => anchors.push(i),
+                        _ => {}
+                    }
+                }
+                let added = backfill::merge(&mut room.messages, messages, me, from, |id| !blocked.contains(id) && !room.bans.contains(id), &mut anchors);
+                for id in &added {
+                    room.keep(id);
+                }
+                if !added.is_empty() {
+                    room.push(ChatMessage::system(t!("backfilled", count = added.len(), name = sender)));
+                }
+                return;
+            }
+            Message::Poll { id, question, options, closes } => {
+                hooks.message(&serde_json::json!({
+                    "event": "poll", "room": topic.to_string(), "id": id, "from": from.to_string(), "sender": sender,
+                    "question": question, "options": options, "closes": closes.to_rfc3339(), "time": clock::show(time).to_rfc3339(),
+                }), false);
+                ChatMessage { poll: Some(polls::Poll::new(options, closes)), ..ChatMessage::peer(id, from, sender, question, time) }
+            }
+            Message::Location { id, lat, lon, label } => {
+                let Some(place) = location::Location::new(lat, lon) else { return };
+                hooks.message(&serde_json::json!({
+                    "event": "location", "room": topic.to_string(), "id": id, "from": from.to_string(), "sender": sender,
+                    "lat": lat, "lon": lon, "label": label, "plus_code": place.plus_code(), "time": clock::show(time).to_rfc3339(),
+                }), false);
+                ChatMessage { location: Some(place), ..ChatMessage::peer(id, from, sender, label, time) }
+            }
+            Message::Image { name, data, id, original } => {
+                let image = match base64::engine::general_purpose::STANDARD.decode(&data) {
+                    Ok(bytes) => images::Image::new(&name, bytes, picker),
+                    Err(err) => Err(err.into()),
+                };
+                let image = match image {
+                    Ok(image) => image,
+                    Err(err) => {
+                        tracing::warn!(%err, %from, name, "dropped unreadable image");
+                        return;
+                    }
+                };
+                hooks.message(&serde_json::json!({
+                    "event": "image", "room": topic.to_string(), "id": id, "from": from.to_string(), "sender": sender,
+                    "name": image.name, "time": clock::show(time).to_rfc3339(),
+                }), false);
+                let original = original.map(|o| o.offer(&image.name));
+                let text = image.name.clone();
+                ChatMessage { image: Some(image), file: original, ..ChatMessage::peer(id, from, sender, text, time) }
+            }
+            Message::FileOffer { id, name, size, hash, node, voice } => {
+                let mut offer = files::offer(&name, size, hash, node);
+                offer.voice = voice;
+                hooks.message(&serde_json::json!({
+                    "event": "file", "room": topic.to_string(), "id": id, "from": from.to_string(), "sender": sender,
+                    "name": offer.name, "size": size, "voice": voice, "time": clock::show(time).to_rfc3339(),
+                }), false);
+                let text = offer.name.clone();
+                ChatMessage { file: Some(offer), ..ChatMessage::peer(id, from, sender, text, time) }
+            }
+            _ => return,
+        },
+    };
+    // Unread also counts what arrives while the window is in the background, for the title.
+    if !is_active || !state.window_focused {
+        room.unread += 1;
+    }
+    let away = !is_active || room.scroll > 0 || !state.window_focused;
+    let alert = match chat.urgent {
+        // Rings through `/notify off` and while watching the room.
+        true => notify::Alert::Both,
+        false if away => room.alert,
+        false => notify::Alert::Off,
+    };
+    let mentions_me = chat.mentions_me;
+    room.push(chat);
 
-    // --- CHAT MESSAGES (SMS Layout) ---
-    let available_height = chat_layout[0].height as usize;
-    let message_count = state.messages.len();
-    let skip = if message_count > available_height { message_count - available_height } else { 0 };
+    if !hidden && (mentions_me || alert.bell()) {
+        notify::bell();
+    }
+    if alert.flash() {
+        state.flash_until = Some(Instant::now() + Duration::from_millis(600));
+    }
+}
 
-    let mut chat_lines = Vec::new();
-    
-    for msg in state.messages.iter().skip(skip) {
-        if msg.is_me {
-            // RIGHT ALIGN (My Messages)
-            let content = Line::from(vec![
-                Span::styled(&msg.text, Style::default().fg(Color::White)),
-                Span::styled(format!("  [{}]", msg.time), Style::default().fg(Color::DarkGray)),
-            ]).alignment(Alignment::Right); 
-            chat_lines.push(content);
+/// Drops `/tmp` messages whose time is up, moving the selection and overlays along with
+/// the messages they point at, or dropping them with theirs. Files we offered stop being
+/// served once their message is gone, deleted or left behind with its room.
+fn expire(state: &mut AppState) {
+    for index in 0..state.rooms.len() {
+        let gone = state.rooms[index].expire();
+        if gone.is_empty() || index != state.active {
+            continue;
+        }
+        let shift = |i: usize| gone.binary_search(&i).err().map(|before| i - before);
+        state.selected = state.selected.and_then(shift);
+        let open = match &mut state.overlay {
+            Some(Overlay::Info(i)) | Some(Overlay::React { target: i, .. }) | Some(Overlay::Forward { target: i, .. }) => {
+                shift(*i).map(|moved| *i = moved).is_some()
+            }
+            _ => true,
+        };
+        if !open {
+            state.overlay = None;
+        }
+    }
+    let offered = state.rooms.iter().flat_map(|room| &room.messages)
+        .filter(|msg| msg.origin == Origin::Me)
+        .filter_map(|msg| Some(msg.file.as_ref()?.hash))
+        .collect();
+    state.files.release(&offered);
+}
+
+/// Keeps room `topic`'s name and topic line with its saved tickets, if any, for `rooms list`.
+fn remember_info(config_dir: Option<&Path>, key: &iroh::SecretKey, topic: TopicId, info: &rooms::Info) {
+    let Some(dir) = config_dir else { return };
+    let remembered = saved::Saved::load(dir, key)
+        .and_then(|mut saved| if saved.remember(topic, info) { saved.save() } else { Ok(()) });
+    if let Err(err) = remembered {
+        tracing::warn!(%err, "could not remember a saved room's name and topic");
+    }
+}
+
+/// Acts on the host's `RoomClose` for room `index`, ours or theirs: wipes the room, its
+/// history on disk and the saved rooms leading to it, and leaves it. The last room stays
+/// open, empty, behind the final screen, since there's always one.
+fn destroyed(state: &mut AppState, index: usize) {
+    let room = &mut state.rooms[index];
+    let topic = room.topic;
+    let notice = match room.host == state.node_id {
+        true => t!("destroyed-yours", room = room.label()),
+        false => t!("destroyed-text", room = room.label()),
+    };
+    tracing::info!(%topic, "room destroyed by its host");
+    if let Some(Err(err)) = state.archive.as_ref().map(|archive| archive.remove(topic)) {
+        tracing::warn!(%err, "could not delete the history of a destroyed room");
+    }
+    if let Some(dir) = &state.config_dir {
+        let forgot = saved::Saved::load(dir, state.endpoint.secret_key())
+            .and_then(|mut saved| if saved.forget(topic) > 0 { saved.save() } else { Ok(()) });
+        if let Err(err) = forgot {
+            tracing::warn!(%err, "could not drop a destroyed room from the saved rooms");
+        }
+    }
+    let last = state.rooms.len() == 1;
+    if last {
+        state.search = None;
+        state.selected = None;
+        state.peer_cursor = None;
+    } else {
+        state.rooms.remove(index);
+        state.split = None;
+        match index.cmp(&state.active) {
+            std::cmp::Ordering::Equal => state.switch_room(state.active.min(state.rooms.len() - 1)),
+            std::cmp::Ordering::Less => state.active -= 1,
+            std::cmp::Ordering::Greater => {}
+        }
+    }
+    state.overlay = Some(Overlay::Destroyed { notice, last });
+}
+
+/// The sidebar's note on anyone who isn't a plain member.
+fn role_tag(role: moderation::Role) -> Span<'static> {
+    match role {
+        moderation::Role::Member => Span::raw(""),
+        role => Span::styled(format!(" ({})", role.name()), Style::default().fg(Color::DarkGray)),
+    }
+}
+
+/// The sidebar's note on anyone away or busy, with what they said about it.
+fn status_tag(status: &presence::Status) -> Span<'static> {
+    match status.presence {
+        presence::Presence::Online => Span::raw(""),
+        _ => Span::styled(format!(" – {}", status.describe()), Style::default().fg(Color::DarkGray)),
+    }
+}
+
+/// The one peer in `room` called `who` (any case), or whose NodeId starts with it; else
+/// the notice saying why not.
+fn find_peer(room: &rooms::Room, who: &str) -> Result<iroh::NodeId, String> {
+    let matches: Vec<iroh::NodeId> = room.peer_names.iter()
+        .filter(|(id, name)| name.eq_ignore_ascii_case(who) || id.to_string().starts_with(&who.to_lowercase()))
+        .map(|(id, _)| *id)
+        .collect();
+    match matches[..] {
+        [node] => Ok(node),
+        [] => Err(t!("moderation-no-peer", name = who)),
+        _ => Err(t!("moderation-ambiguous", name = who)),
+    }
+}
+
+/// Stops the `/voice` recording and offers the clip in the room it was started in.
+fn send_voice(state: &mut AppState) {
+    let Some((topic, recording)) = state.recording.take() else { return };
+    match recording.finish() {
+        Ok((path, ms)) => state.files.publish(topic, path, files::Kind::Voice(ms)),
+        Err(err) => state.push_message(ChatMessage::system(t!("voice-failed", error = format!("{:#}", err)))),
+    }
+}
+
+/// Offers a file (image, voice clip) once it's ready to serve, and tracks fetches on their messages.
+async fn file_event(state: &mut AppState, event: files::Event) {
+    let (topic, id, file_state) = match event {
+        files::Event::Published { topic, name, size, hash, tag, kind } => {
+            state.files.share(hash, tag);
+            let Some(room) = state.rooms.iter_mut().find(|r| r.topic == topic) else { return };
+            let id = new_id();
+            let node = state.node_id;
+            let (image, voice) = match kind {
+                files::Kind::File => (None, None),
+                files::Kind::Image(thumbnail) => match images::Image::new(&name, thumbnail, state.picker.as_ref()) {
+                    Ok(image) => (Some(image), None),
+                    Err(err) => {
+                        room.push(ChatMessage::system(t!("send-image-failed", error = format!("{:#}", err))));
+                        return;
+                    }
+                },
+                files::Kind::Voice(ms) => (None, Some(ms)),
+            };
+            let sent = match &image {
+                Some(image) => Message::Image {
+                    name: name.clone(),
+                    data: base64::engine::general_purpose::STANDARD.encode(&image.data),
+                    id: id.clone(),
+                    original: Some(files::Original { size, hash, node }),
+                },
+                None => Message::FileOffer { id: id.clone(), name: name.clone(), size, hash, node, voice },
+            };
+            broadcast(&room.sender, &sent).await;
+            room.jump_to_bottom();
+            room.push(ChatMessage {
+                image,
+                file: Some(files::Offer { name: name.clone(), size, hash, node, voice, state: files::State::Shared }),
+                ..ChatMessage::mine(id, state.my_name.clone(), name)
+            });
+            return;
+        }
+        files::Event::PublishFailed { topic, error } => {
+            if let Some(room) = state.rooms.iter_mut().find(|r| r.topic == topic) {
+                room.push(ChatMessage::system(t!("send-file-failed", error = error)));
+            }
+            return;
+        }
+        files::Event::Fetching { topic, id, done } => (topic, id, files::State::Fetching(done)),
+        files::Event::Saved { topic, id, path } => (topic, id, files::State::Saved(path)),
+        files::Event::Failed { topic, id, error } => (topic, id, files::State::Failed(error)),
+    };
+    let offer = state.rooms.iter_mut().find(|r| r.topic == topic)
+        .and_then(|room| room.messages.iter_mut().find(|m| m.id == id))
+        .and_then(|msg| msg.file.as_mut());
+    if let Some(offer) = offer {
+        offer.state = file_state;
+    }
+}
+
+/// Gives `room` its database, if history is kept, and shows what earlier sessions left.
+fn open_history(room: &mut rooms::Room, archive: Option<&store::Archive>) {
+    let Some(archive) = archive else { return };
+    let store = match archive.open(room.topic) {
+        Ok(Some(store)) => store,
+        Ok(None) => return,
+        Err(err) => return room.push(ChatMessage::system(t!("history-failed", error = format!("{:#}", err)))),
+    };
+    match store.load() {
+        Ok(earlier) if !earlier.is_empty() => {
+            let count = earlier.len();
+            room.messages.splice(0..0, earlier);
+            room.push(ChatMessage::system(t!("history-loaded", count = count)));
+        }
+        Ok(_) => {}
+        Err(err) => room.push(ChatMessage::system(t!("history-failed", error = format!("{:#}", err)))),
+    }
+    room.store = Some(store);
+}
+
+/// Marks the peers' messages on screen in the active room as seen, queueing their read
+/// receipts. Nothing counts while the window is unfocused or disguised.
+fn mark_read(state: &mut AppState) {
+    if !state.window_focused || state.disguise.is_some() {
+        return;
+    }
+    let shown = state.shown.take();
+    let room = state.room_mut();
+    for i in shown {
+        let Some(msg) = room.messages.get_mut(i).filter(|m| m.origin == Origin::Peer && !m.seen) else { continue };
+        msg.seen = true;
+        let id = msg.id.clone();
+        room.acknowledge(&id, true);
+    }
+}
+
+/// Points out a peer whose new name could pass for ours or another peer's.
+fn warn_lookalike(room: &mut rooms::Room, id: iroh::NodeId, name: &str, my_name: &str) {
+    let others = room.peer_names.iter().filter(|(other, _)| **other != id).map(|(_, n)| n.as_str());
+    let notice = match names::lookalike(name, std::iter::once(my_name).chain(others)) {
+        Some(other) if other == my_name => format!("⚠ {}", t!("lookalike-you", name = name, id = id.fmt_short())),
+        Some(other) => format!("⚠ {}", t!("lookalike-peer", name = name, id = id.fmt_short(), other = other)),
+        None => return,
+    };
+    room.push(ChatMessage::system(notice));
+}
+
+/// Stands in for a message only a newer version can show, and the first time a peer sends
+/// that kind, says how to see it.
+fn newer_message(room: &mut rooms::Room, id: iroh::NodeId, kind: &str, version: u32) {
+    let name = room.peer_names.get(&id).cloned().unwrap_or_else(|| t!("unknown-peer"));
+    if room.unsupported(id, kind) {
+        let notice = if version > protocol::VERSION {
+            t!("protocol-newer", name = name.as_str(), kind = kind, version = version, ours = protocol::VERSION)
         } else {
-            // LEFT ALIGN (Their Messages)
-            let content = Line::from(vec![
-                Span::styled(&msg.sender, Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
-                Span::raw(": "),
-                Span::styled(&msg.text, Style::default().fg(Color::Gray)),
-                Span::styled(format!("  [{}]", msg.time), Style::default().fg(Color::DarkGray)),
-            ]).alignment(Alignment::Left);
-            chat_lines.push(content);
+            t!("protocol-unknown-type", name = name.as_str(), kind = kind)
+        };
+        room.push(ChatMessage::system(notice));
+    }
+    let mut placeholder = ChatMessage::system(t!("newer-message"));
+    placeholder.from = Some(id);
+    placeholder.sender = name;
+    placeholder.origin = Origin::Peer;
+    room.push(placeholder);
+}
+
+async fn run_command(state: &mut AppState, net: &rooms::Net, command: commands::Command) {
+    match command {
+        commands::Command::Help => state.overlay = Some(Overlay::Help),
+        commands::Command::Ticket { copy: false } => {
+            let notice = ChatMessage::system(t!("room-ticket", ticket = state.room().ticket.as_str()));
+            state.push_message(notice);
+        }
+        commands::Command::Ticket { copy: true } => {
+            let ticket = state.room().ticket.clone();
+            state.copy(&ticket);
+        }
+        commands::Command::Links => {
+            let mut urls: Vec<String> = Vec::new();
+            for msg in &state.room().messages {
+                let found = markup::urls(&msg.text).into_iter().map(|range| msg.text[range].to_string());
+                for url in found.chain(msg.location.map(|l| l.osm())) {
+                    if !urls.contains(&url) {
+                        urls.push(url);
+                    }
+                }
+            }
+            state.show_links(urls);
+        }
+        commands::Command::Image { .. } | commands::Command::Voice { cancel: false } | commands::Command::SendFile { .. }
+            if !state.may_post() => {}
+        commands::Command::Image { path } => {
+            // The thumbnail travels inline; the original is offered alongside it once hashed.
+            let path = PathBuf::from(path);
+            match images::thumbnail(&path) {
+                Ok(thumbnail) => {
+                    let topic = state.room().topic;
+                    state.files.publish(topic, path, files::Kind::Image(thumbnail));
+                }
+                Err(err) => state.push_message(ChatMessage::system(t!("send-image-failed", error = format!("{:#}", err)))),
+            }
+        }
+        commands::Command::Voice { cancel: true } => {
+            if state.recording.take().is_some() {
+                state.push_message(ChatMessage::system(t!("voice-cancelled")));
+            }
+        }
+        commands::Command::Voice { cancel: false } if state.recording.is_some() => send_voice(state),
+        commands::Command::Voice { cancel: false } => match voice::record() {
+            Ok(recording) => {
+                state.recording = Some((state.room().topic, recording));
+                state.push_message(ChatMessage::system(t!("voice-recording")));
+            }
+            Err(err) => state.push_message(ChatMessage::system(t!("voice-failed", error = format!("{:#}", err)))),
+        },
+        commands::Command::SendFile { path } => {
+            let topic = state.room().topic;
+            state.files.publish(topic, PathBuf::from(path), files::Kind::File);
+        }
+        commands::Command::Export { path, redact } => {
+            let path = std::path::PathBuf::from(path);
+            let entries: Vec<export::Entry> = state.room().messages.iter()
+                .filter(|m| m.origin != Origin::System && !m.redacted)
+                .map(|m| export::Entry {
+                    time: m.time,
+                    sender: m.sender.clone(),
+                    text: if m.image.is_some() {
+                        format!("[image: {}]", m.text)
+                    } else if m.file.is_some() {
+                        format!("[file: {}]", m.text)
+                    } else {
+                        m.text.clone()
+                    },
+                })
+                .collect();
+            let notice = match std::fs::write(&path, export::render(&entries, export::Format::from_path(&path), redact)) {
+                Ok(()) => t!("exported", count = entries.len(), path = path.display().to_string()),
+                Err(err) => t!("export-failed", error = err.to_string()),
+            };
+            state.push_message(ChatMessage::system(notice));
+        }
+        commands::Command::Nick { name } => {
+            let others = state.rooms.iter().flat_map(|r| r.peer_names.values().map(String::as_str));
+            if let Some(other) = names::lookalike(&name, others) {
+                let notice = t!("nick-lookalike", name = name, other = other);
+                state.push_message(ChatMessage::system(notice));
+                return;
+            }
+            let old = std::mem::replace(&mut state.my_name, name.clone());
+            for room in &mut state.rooms {
+                room.rename(&old, &name).await;
+                room.relabel(None, &name);
+            }
+            let notice = match &state.name_file {
+                Some(file) => match config::remember(file, "name", name.as_str(), true) {
+                    Ok(_) => t!("nick-saved", name = name.as_str(), path = file.display().to_string()),
+                    Err(err) => t!("nick-not-saved", name = name.as_str(), error = format!("{:#}", err)),
+                },
+                None => t!("nick-changed", name = name.as_str()),
+            };
+            state.push_message(ChatMessage::system(notice));
+        }
+        commands::Command::Join { ticket } => {
+            state.push_message(ChatMessage::system(t!("joining-room")));
+            let (iroh, opened) = (transport::Iroh::new(&net.endpoint, &net.gossip), net.opened.clone());
+            tokio::spawn(async move {
+                let _ = opened.send(rooms::join(&iroh, &ticket).await);
+            });
+        }
+        commands::Command::Host => {
+            let (iroh, opened) = (transport::Iroh::new(&net.endpoint, &net.gossip), net.opened.clone());
+            tokio::spawn(async move {
+                let _ = opened.send(rooms::host(&iroh, "").await);
+            });
+        }
+        commands::Command::Ban { who, kick } => {
+            let me = state.room().role(&state.node_id);
+            if !me.moderates() {
+                state.push_message(ChatMessage::system(t!("moderation-host-only")));
+                return;
+            }
+            let node = match find_peer(state.room(), &who) {
+                Ok(node) => node,
+                Err(notice) => return state.push_message(ChatMessage::system(notice)),
+            };
+            let room = state.room();
+            let name = room.peer_names.get(&node).cloned().unwrap_or_default();
+            if !me.outranks(room.role(&node)) {
+                return state.push_message(ChatMessage::system(t!("moderation-outranked", name = name)));
+            }
+            let by = (me != moderation::Role::Host).then_some(state.node_id);
+            let ban = moderation::announce(net.endpoint.secret_key(), room.topic, node, kick, by);
+            broadcast(&room.sender, &ban).await;
+            if let Message::Ban { at, signature, .. } = ban {
+                state.room_mut().ban(node, kick, by, at, signature);
+            }
+            let notice = if kick { t!("peer-kicked", name = name) } else { t!("peer-banned", name = name) };
+            state.push_message(ChatMessage::system(notice));
+        }
+        commands::Command::Role { who, role } => {
+            if state.room().host != state.node_id {
+                state.push_message(ChatMessage::system(t!("role-host-only")));
+                return;
+            }
+            let node = match find_peer(state.room(), &who) {
+                Ok(node) => node,
+                Err(notice) => return state.push_message(ChatMessage::system(notice)),
+            };
+            let room = state.room();
+            let name = room.peer_names.get(&node).cloned().unwrap_or_default();
+            let assignment = moderation::assign(net.endpoint.secret_key(), room.topic, node, role);
+            broadcast(&room.sender, &assignment).await;
+            if let Message::Role { at, signature, .. } = assignment {
+                state.room_mut().assign(node, role, at, signature);
+            }
+            state.push_message(ChatMessage::system(t!("role-changed", name = name, role = role.name())));
+        }
+        commands::Command::Broadcast(on) => {
+            if state.room().host != state.node_id {
+                state.push_message(ChatMessage::system(t!("mode-host-only")));
+                return;
+            }
+            let room = state.room();
+            let mode = moderation::mode(net.endpoint.secret_key(), room.topic, on);
+            broadcast(&room.sender, &mode).await;
+            if let Message::Mode { at, signature, .. } = mode {
+                state.room_mut().set_mode(on, at, signature);
+            }
+            state.push_message(ChatMessage::system(if on { t!("mode-broadcast") } else { t!("mode-open") }));
+        }
+        commands::Command::Destroy => {
+            if state.room().host != state.node_id {
+                state.push_message(ChatMessage::system(t!("destroy-host-only")));
+                return;
+            }
+            state.overlay = Some(Overlay::Destroy);
+        }
+        commands::Command::Urgent { .. } if !state.may_post() => {}
+        commands::Command::Urgent { text } => {
+            let me = state.node_id;
+            if !state.room_mut().urgent(me) {
+                state.push_message(ChatMessage::system(t!("urgent-too-soon", minutes = rooms::URGENT_EVERY.as_secs() / 60)));
+                return;
+            }
+            post(state, &text, true, None).await;
+        }
+        commands::Command::Status(status) => {
+            let notice = match status == presence::Status::default() {
+                true => t!("status-back"),
+                false => t!("status-set", status = status.describe()),
+            };
+            state.set_status(status);
+            state.push_message(ChatMessage::system(notice));
+        }
+        commands::Command::Temporary { .. } if !state.may_post() => {}
+        commands::Command::Temporary { ttl, text } => post(state, &text, false, Some(ttl)).await,
+        commands::Command::Location { .. } if !state.may_post() => {}
+        commands::Command::Location { place, label } => {
+            let id = new_id();
+            let shared = Message::Location { id: id.clone(), lat: place.lat, lon: place.lon, label: label.clone() };
+            broadcast(&state.room().sender, &shared).await;
+            state.room_mut().jump_to_bottom();
+            state.push_message(ChatMessage { location: Some(place), ..ChatMessage::mine(id, state.my_name.clone(), label) });
+        }
+        commands::Command::Poll { .. } if !state.may_post() => {}
+        commands::Command::Poll { question, options, open } => {
+            let id = new_id();
+            let closes = Utc::now() + open;
+            let poll = Message::Poll { id: id.clone(), question: question.clone(), options: options.clone(), closes };
+            broadcast(&state.room().sender, &poll).await;
+            state.room_mut().jump_to_bottom();
+            state.push_message(ChatMessage { poll: Some(polls::Poll::new(options, closes)), ..ChatMessage::mine(id, state.my_name.clone(), question) });
+        }
+        commands::Command::Topic { text } => {
+            if !state.room().role(&state.node_id).moderates() {
+                state.push_message(ChatMessage::system(t!("topic-host-only")));
+                return;
+            }
+            let topic = rooms::topic_line(&text);
+            let notice = if topic.is_empty() { t!("topic-cleared") } else { t!("topic-set", topic = topic.as_str()) };
+            let room = state.room_mut();
+            room.info.topic = topic;
+            // An empty `RoomInfo` still has to go out, to clear it for everyone.
+            broadcast(&room.sender, &Message::RoomInfo { name: room.info.name.clone(), topic: room.info.topic.clone() }).await;
+            let (topic, info) = (room.topic, room.info.clone());
+            remember_info(state.config_dir.as_deref(), state.endpoint.secret_key(), topic, &info);
+            state.push_message(ChatMessage::system(notice));
+        }
+        commands::Command::Announce { text, pin } => {
+            if !state.room().role(&state.node_id).moderates() {
+                state.push_message(ChatMessage::system(t!("announce-host-only")));
+                return;
+            }
+            let text = emoji::expand(&text);
+            let id = new_id();
+            broadcast(&state.room().sender, &Message::Announcement { id: id.clone(), text: text.clone() }).await;
+            let msg = ChatMessage { announcement: true, ..ChatMessage::mine(id, state.my_name.clone(), text) };
+            let quote = Quote::of(&msg);
+            state.room_mut().jump_to_bottom();
+            state.push_message(msg);
+            if pin {
+                let room = state.room_mut();
+                room.pins.push(quote.clone());
+                broadcast(&room.sender, &Message::Pin { quote, remove: false }).await;
+            }
+        }
+        commands::Command::Notify(alert) => {
+            state.room_mut().alert = alert;
+            let notice = t!("alerts-set", room = state.room().label(), alert = alert.name());
+            state.push_message(ChatMessage::system(notice));
+        }
+        commands::Command::Receipts(on) => {
+            state.room_mut().read_receipts = on;
+            let notice = t!("receipts-set", room = state.room().label(), on = if on { t!("yes") } else { t!("no") });
+            state.push_message(ChatMessage::system(notice));
+        }
+        commands::Command::Split(None) => state.split = None,
+        commands::Command::Split(Some(direction)) => {
+            if state.rooms.len() == 1 {
+                state.push_message(ChatMessage::system(t!("split-needs-room")));
+            } else {
+                let other = state.split.map_or((state.active + 1) % state.rooms.len(), |(_, other)| other);
+                state.split = Some((direction, other));
+                state.rooms[other].unread = 0;
+            }
+        }
+        commands::Command::Quit => state.overlay = Some(Overlay::Quit),
+        commands::Command::Leave => {
+            if state.rooms.len() == 1 {
+                state.push_message(ChatMessage::system(t!("last-room")));
+            } else {
+                state.rooms.remove(state.active);
+                state.split = None;
+                state.switch_room(state.active.min(state.rooms.len() - 1));
+            }
         }
     }
+}
 
-    let chat_area = Paragraph::new(chat_lines)
-        .block(Block::default().padding(Padding::new(2, 2, 0, 0))); 
-        
-    frame.render_widget(chat_area, chat_layout[0]);
+/// Keys while a popup is open. Esc always backs out.
+async fn handle_overlay_key(state: &mut AppState, key: KeyEvent) {
+    match state.overlay.as_mut() {
+        Some(Overlay::Help) => {
+            if matches!(key.code, KeyCode::Esc | KeyCode::F(1)) {
+                state.overlay = None;
+            }
+        }
+        Some(Overlay::Links { urls, selected, confirm }) if !*confirm => match key.code {
+            KeyCode::Up => *selected = selected.saturating_sub(1),
+            KeyCode::Down => *selected = (*selected + 1).min(urls.len() - 1),
+            KeyCode::Enter | KeyCode::Char('o') => *confirm = true,
+            KeyCode::Esc => state.overlay = None,
+            _ => {}
+        },
+        Some(Overlay::Links { urls, selected, confirm }) => match key.code {
+            KeyCode::Char('y') | KeyCode::Enter => {
+                let url = urls[*selected].clone();
+                state.overlay = None;
+                let notice = match opener::open(&url) {
+                    Ok(()) => t!("opened", target = url.as_str()),
+                    Err(err) => t!("open-failed", target = url.as_str(), error = err.to_string()),
+                };
+                state.push_message(ChatMessage::system(notice));
+            }
+            KeyCode::Char('n') | KeyCode::Esc => {
+                if urls.len() > 1 {
+                    *confirm = false;
+                } else {
+                    state.overlay = None;
+                }
+            }
+            _ => {}
+        },
+        Some(Overlay::Peer(id)) => match key.code {
+            KeyCode::Char('v') => {
+                let id = *id;
+                if !state.verified.remove(&id) {
+                    state.verified.insert(id);
+                }
+            }
+            KeyCode::Char('b') => {
+                let id = *id;
+                if !state.blocked.remove(&id) {
+                    state.blocked.insert(id);
+                }
+            }
+            KeyCode::Esc | KeyCode::Enter => state.overlay = None,
+            _ => {}
+        },
+        Some(Overlay::Quit) => match key.code {
+            KeyCode::Char('y') | KeyCode::Char('q') => state.quit = true,
+            KeyCode::Char('n') | KeyCode::Esc => state.overlay = None,
+            _ => {}
+        },
+        Some(Overlay::Destroy) => match key.code {
+            KeyCode::Char('y') => {
+                state.overlay = None;
+                let close = moderation::close(state.endpoint.secret_key(), state.room().topic);
+                broadcast(&state.room().sender, &close).await;
+                if let Message::RoomClose { signature } = close {
+                    if state.room_mut().destroy(&signature) {
+                        destroyed(state, state.active);
+                    }
+                }
+            }
+            KeyCode::Char('n') | KeyCode::Esc => state.overlay = None,
+            _ => {}
+        },
+        Some(Overlay::Destroyed { last: true, .. }) => state.quit = true,
+        Some(Overlay::Destroyed { .. }) => {
+            if matches!(key.code, KeyCode::Esc | KeyCode::Enter) {
+                state.overlay = None;
+            }
+        }
+        Some(Overlay::Info(_)) => {
+            if matches!(key.code, KeyCode::Esc | KeyCode::Enter | KeyCode::Char('i')) {
+                state.overlay = None;
+            }
+        }
+        Some(Overlay::Emoji { query, selected }) => match key.code {
+            KeyCode::Up => *selected = selected.saturating_sub(1),
+            KeyCode::Down => *selected = (*selected + 1).min(emoji::search(query).len().saturating_sub(1)),
+            KeyCode::Backspace => {
+                query.pop();
+                *selected = 0;
+            }
+            KeyCode::Char(c) => {
+                query.push(c);
+                *selected = 0;
+            }
+            KeyCode::Enter => {
+                let pick = emoji::search(query).get(*selected).map(|(_, e)| *e);
+                state.overlay = None;
+                if let Some(pick) = pick {
+                    state.room_mut().input.insert_str(pick);
+                }
+            }
+            KeyCode::Esc => state.overlay = None,
+            _ => {}
+        },
+        Some(Overlay::React { target, selected }) => {
+            let pick = match key.code {
+                KeyCode::Left => { *selected = selected.saturating_sub(1); None }
+                KeyCode::Right => { *selected = (*selected + 1).min(reactions::EMOJI.len() - 1); None }
+                KeyCode::Enter => Some(*selected),
+                KeyCode::Char(c @ '1'..='9') => Some(c as usize - '1' as usize).filter(|&i| i < reactions::EMOJI.len()),
+                _ => None,
+            };
+            let target = *target;
+            if key.code == KeyCode::Esc {
+                state.overlay = None;
+            } else if let Some(pick) = pick {
+                state.overlay = None;
+                react(state, target, reactions::EMOJI[pick]).await;
+            }
+        }
+        Some(Overlay::Forward { target, selected }) => match key.code {
+            KeyCode::Up => *selected = selected.saturating_sub(1),
+            KeyCode::Down => *selected = (*selected + 1).min(state.rooms.len() - 2),
+            KeyCode::Enter => {
+                let target = *target;
+                // The picker leaves out the active room.
+                let to = if *selected < state.active { *selected } else { *selected + 1 };
+                state.overlay = None;
+                forward(state, target, to).await;
+            }
+            KeyCode::Esc => state.overlay = None,
+            _ => {}
+        },
+        None => {}
+    }
+}
 
-    // --- INPUT BAR ---
-    let input_border_color = if state.input.is_empty() { Color::DarkGray } else { Color::White };
+/// Sends `text` to the active room, as a reply or in a thread if one is set up there, and
+/// shows it.
+async fn post(state: &mut AppState, text: &str, urgent: bool, ttl: Option<u32>) {
+    let topic = state.room().topic;
+    let Some(text) = state.plugins.message_out(topic, emoji::expand(text)) else {
+        state.push_message(ChatMessage::system(t!("plugin-held")));
+        return;
+    };
+    let id = new_id();
+    let reply = state.room_mut().reply.take();
+    let thread = state.room().thread.clone();
+    let rich = rich::parse(&text, &state.room().peer_names, state.node_id, &state.my_name);
+    let chat = Message::Chat { text: text.clone(), id: id.clone(), reply: reply.clone(), thread: thread.clone(), urgent, rich: rich.clone(), ttl, forwarded: None };
+    broadcast(&state.room().sender, &chat).await;
+    if let Some(previews) = &state.previews {
+        previews.fetch(state.room().topic, id.clone(), &text);
+    }
+    let time = Utc::now();
+    state.room_mut().jump_to_bottom();
+    state.push_message(ChatMessage {
+        time, quote: reply, thread, urgent, rich, expires: ttl.map(expiry::at),
+        ..ChatMessage::mine(id, state.my_name.clone(), text)
+    });
+}
+
+/// The plugins built into this binary, registered ahead of any loaded from libraries. A
+/// custom build adds its own here.
+fn builtin_plugins() -> Vec<Box<dyn plugins::Plugin>> {
+    Vec::new()
+}
+
+/// Every plugin for this run, compiled in, from libraries and in WebAssembly, then the
+/// scripts, with a line to show for each one that didn't load and, when there are any,
+/// one naming them all.
+fn load_plugins(profile: Option<&str>) -> (plugins::Registry, Vec<String>) {
+    let mut registry = plugins::Registry::default();
+    let mut notices = Vec::new();
+    for plugin in builtin_plugins() {
+        registry.register(plugin);
+    }
+    let dir = config::dir(profile);
+    #[cfg(feature = "plugins")]
+    if let Some(dir) = &dir {
+        for (path, err) in registry.load(&dir.join("plugins")) {
+            notices.push(t!("plugin-failed", path = path.display().to_string(), err = err));
+        }
+    }
+    if let Some(dir) = &dir {
+        let (filters, failed) = wasm::load(&dir.join("plugins"));
+        for filter in filters {
+            registry.register(Box::new(filter));
+        }
+        for (path, err) in failed {
+            notices.push(t!("plugin-failed", path = path.display().to_string(), err = err));
+        }
+        let (loaded, failed) = scripts::load(&dir.join("scripts"));
+        for script in loaded {
+            registry.register(Box::new(script));
+        }
+        for (path, err) in failed {
+            notices.push(t!("script-failed", path = path.display().to_string(), err = err));
+        }
+    }
+    if !registry.is_empty() {
+        notices.push(t!("plugins-loaded", names = registry.names().join(", ")));
+    }
+    (registry, notices)
+}
+
+/// A message's text and formatting as plugins' `filter_in` leave them, or `None` when one
+/// hides it. The sender's formatting only fits their own text, so a changed one loses it.
+fn filtered(plugins: &mut plugins::Registry, topic: TopicId, text: String, rich: Vec<rich::Span>) -> Option<(String, Vec<rich::Span>)> {
+    let shown = plugins.filter_in(topic, text.clone())?;
+    match shown == text {
+        true => Some((text, rich)),
+        false => Some((shown, Vec::new())),
+    }
+}
+
+/// Carries out what plugins asked for, each in the room it was asked from.
+async fn run_plugins(state: &mut AppState) {
+    for (topic, action) in std::mem::take(&mut state.plugin_actions) {
+        let Some(index) = state.rooms.iter().position(|r| r.topic == topic) else { continue };
+        match action {
+            plugins::Action::Show(text) => state.rooms[index].push(ChatMessage::system(text)),
+            plugins::Action::Send(text) => plugin_post(state, index, text).await,
+        }
+    }
+}
+
+/// Posts a plugin's message to room `index` as ours. Unlike `post` it leaves the reply
+/// and thread being typed alone, since the plugin wasn't answering those.
+async fn plugin_post(state: &mut AppState, index: usize, text: String) {
+    let room = &mut state.rooms[index];
+    if room.destroyed() || !room.role(&state.node_id).posts() || text.is_empty() || text.len() > chunks::MAX_TEXT {
+        return;
+    }
+    let Some(text) = state.plugins.message_out(room.topic, text) else { return };
+    let id = new_id();
+    let rich = rich::parse(&text, &room.peer_names, state.node_id, &state.my_name);
+    let chat = Message::Chat { text: text.clone(), id: id.clone(), reply: None, thread: None, urgent: false, rich: rich.clone(), ttl: None, forwarded: None };
+    broadcast(&room.sender, &chat).await;
+    room.push(ChatMessage { rich, ..ChatMessage::mine(id, state.my_name.clone(), text) });
+}
+
+/// Whether a message can be forwarded: plain text someone posted. Pictures, files and
+/// polls only work where they were sent, and `/tmp` messages were meant to go.
+fn forwardable(msg: &ChatMessage) -> bool {
+    msg.origin != Origin::System && !msg.redacted && msg.image.is_none() && msg.file.is_none() && msg.poll.is_none()
+        && msg.location.is_none() && msg.expires.is_none()
+}
+
+/// Posts a copy of message `target` of the active room to room `to`, saying who wrote it
+/// and where, and shows it there.
+async fn forward(state: &mut AppState, target: usize, to: usize) {
+    let msg = &state.room().messages[target];
+    let forwarded = msg.forwarded.clone().unwrap_or_else(|| Forwarded { sender: msg.sender.clone(), room: state.room().label(), time: msg.time });
+    let (text, rich) = (msg.text.clone(), msg.rich.clone());
+    let room = &mut state.rooms[to];
+    if !room.role(&state.node_id).posts() {
+        let notice = t!("forward-read-only", room = room.label());
+        state.push_message(ChatMessage::system(notice));
+        return;
+    }
+    let id = new_id();
+    let chat = Message::Chat { text: text.clone(), id: id.clone(), reply: None, thread: None, urgent: false, rich: rich.clone(), ttl: None, forwarded: Some(forwarded.clone()) };
+    broadcast(&room.sender, &chat).await;
+    room.push(ChatMessage { rich, forwarded: Some(forwarded), ..ChatMessage::mine(id, state.my_name.clone(), text) });
+    let notice = t!("forward-done", room = room.label());
+    state.selected = None;
+    state.push_message(ChatMessage::system(notice));
+}
+
+/// Puts our vote on option `option` of the poll at `index`, or takes it back if it was
+/// there already, and tells the room.
+async fn vote(state: &mut AppState, index: usize, option: usize) {
+    if !state.may_post() {
+        return;
+    }
+    let me = state.node_id;
+    let msg = &mut state.rooms[state.active].messages[index];
+    let Some(poll) = msg.poll.as_mut() else { return };
+    let remove = poll.votes.get(&me) == Some(&option);
+    if !poll.vote(me, option, remove) {
+        let notice = if poll.open() { t!("poll-no-option", option = option + 1) } else { t!("poll-is-closed") };
+        state.push_message(ChatMessage::system(notice));
+        return;
+    }
+    let target = msg.id.clone();
+    state.room().keep(&target);
+    broadcast(&state.room().sender, &Message::Vote { target, option, remove }).await;
+}
+
+/// Toggles our `emoji` on the message at `index` and tells the room.
+async fn react(state: &mut AppState, index: usize, emoji: &str) {
+    if !state.may_post() {
+        return;
+    }
+    let me = state.node_id;
+    let msg = &mut state.rooms[state.active].messages[index];
+    let remove = msg.reactions.iter().any(|r| r.emoji == emoji && r.from.contains(&me));
+    reactions::apply(&mut msg.reactions, emoji, me, remove);
+    let target = msg.id.clone();
+    state.room().keep(&target);
+    broadcast(&state.room().sender, &Message::Reaction { target, emoji: emoji.to_string(), remove }).await;
+}
+
+/// Keys while browsing the sidebar (Ctrl+P).
+/// Up/Down move, Enter opens the peer's details, Esc goes back to the input.
+fn handle_peer_key(state: &mut AppState, key: KeyEvent) {
+    let peers = state.room().peers();
+    let Some(cursor) = state.peer_cursor else { return };
+    // Peers can leave while we browse.
+    let cursor = cursor.min(peers.len().saturating_sub(1));
+    match key.code {
+        KeyCode::Up => state.peer_cursor = Some(cursor.saturating_sub(1)),
+        KeyCode::Down => state.peer_cursor = Some((cursor + 1).min(peers.len().saturating_sub(1))),
+        KeyCode::Enter => {
+            if let Some((id, _)) = peers.get(cursor) {
+                state.overlay = Some(Overlay::Peer(*id));
+            }
+        }
+        KeyCode::Esc => state.peer_cursor = None,
+        _ => {}
+    }
+}
+
+/// Keys while a message is selected (Ctrl+S).
+/// Up/Down move the selection, y/Enter copy the message, Esc leaves selection mode.
+async fn handle_select_key(state: &mut AppState, key: KeyEvent) {
+    let Some(current) = state.selected else { return };
+    let visible = state.visible();
+    let pos = visible.iter().position(|&i| i == current).unwrap_or(visible.len().saturating_sub(1));
+    match key.code {
+        KeyCode::Esc => {
+            state.selected = None;
+            state.room_mut().jump_to_bottom();
+        }
+        KeyCode::Up => state.selected = visible.get(pos.saturating_sub(1)).copied(),
+        KeyCode::Down => state.selected = visible.get(pos + 1).or(visible.last()).copied(),
+        KeyCode::Char('o') if state.room().messages[current].file.as_ref().is_some_and(|f| matches!(f.state, files::State::Saved(_))) => {
+            if let Some(files::State::Saved(path)) = state.room().messages[current].file.as_ref().map(|f| &f.state) {
+                let path = path.clone();
+                let msg = &state.room().messages[current];
+                if msg.file.as_ref().is_some_and(|f| f.voice.is_some()) {
+                    // o again stops it.
+                    let id = msg.id.clone();
+                    if state.playing.take().is_none_or(|(playing, _)| playing != id) {
+                        match voice::play(&path) {
+                            Ok(playback) => state.playing = Some((id, playback)),
+                            Err(err) => state.push_message(ChatMessage::system(t!("voice-failed", error = format!("{:#}", err)))),
+                        }
+                    }
+                    return;
+                }
+                // Only pictures are launched; anything else could be a program, so its folder opens instead.
+                let opened = if msg.image.is_some() {
+                    images::open_original(&path)
+                } else {
+                    opener::launch(path.parent().unwrap_or(Path::new(".")))
+                };
+                if let Err(err) = opened {
+                    state.push_message(ChatMessage::system(t!("open-failed", target = path.display().to_string(), error = err.to_string())));
+                }
+            }
+        }
+        KeyCode::Char('o') if state.room().messages[current].image.is_some() => {
+            let opened = state.room().messages[current].image.as_ref().map(images::Image::open);
+            if let Some(Err(err)) = opened {
+                state.push_message(ChatMessage::system(t!("open-image-failed", error = err.to_string())));
+            }
+        }
+        KeyCode::Char('a') if state.room().messages[current].file.as_ref().is_some_and(files::Offer::fetchable) => {
+            let active = state.active;
+            let room = &mut state.rooms[active];
+            let msg = &mut room.messages[current];
+            if let Some(offer) = msg.file.as_mut() {
+                state.files.fetch(room.topic, msg.id.clone(), offer);
+                offer.state = files::State::Fetching(0);
+            }
+        }
+        KeyCode::Char('o') if state.room().messages[current].location.is_some() => {
+            let urls = state.room().messages[current].location.iter().map(location::Location::osm).collect();
+            state.show_links(urls);
+        }
+        KeyCode::Char('o') => {
+            let text = &state.room().messages[current].text;
+            let urls = markup::urls(text).into_iter().map(|r| text[r].to_string()).collect();
+            state.show_links(urls);
+        }
+        KeyCode::Char('r') if state.room().messages[current].origin != Origin::System => {
+            let quote = Quote::of(&state.room().messages[current]);
+            state.selected = None;
+            let room = state.room_mut();
+            room.jump_to_bottom();
+            room.reply = Some(quote);
+        }
+        KeyCode::Char('t') if state.room().messages[current].origin != Origin::System && !state.room().messages[current].id.is_empty() => {
+            let msg = &state.room().messages[current];
+            let root = msg.thread.clone().unwrap_or_else(|| msg.id.clone());
+            state.selected = None;
+            let room = state.room_mut();
+            room.thread = Some(root);
+            room.jump_to_bottom();
+        }
+        KeyCode::Char('i') => state.overlay = Some(Overlay::Info(current)),
+        KeyCode::Char(c @ '1'..='9') if state.room().messages[current].poll.is_some() => vote(state, current, c as usize - '1' as usize).await,
+        KeyCode::Char('p') if !state.room().messages[current].id.is_empty() => {
+            if state.pins == Pins::Host && !state.room().role(&state.node_id).moderates() {
+                state.push_message(ChatMessage::system(t!("pin-host-only")));
+            } else if state.may_post() {
+                let quote = Quote::of(&state.room().messages[current]);
+                let room = state.room_mut();
+                let remove = room.pins.iter().any(|p| p.id == quote.id);
+                room.pins.retain(|p| p.id != quote.id);
+                if !remove {
+                    room.pins.push(quote.clone());
+                }
+                broadcast(&state.room().sender, &Message::Pin { quote, remove }).await;
+            }
+        }
+        KeyCode::Char('d') if state.room().messages[current].origin != Origin::System && !state.room().messages[current].redacted => {
+            let id = state.room().messages[current].id.clone();
+            let me = state.node_id;
+            if state.room_mut().redact(&id, me) {
+                broadcast(&state.room().sender, &Message::Redact { target: id }).await;
+            } else {
+                state.push_message(ChatMessage::system(t!("redact-not-yours")));
+            }
+        }
+        KeyCode::Char('f') if forwardable(&state.room().messages[current]) => {
+            if state.rooms.len() < 2 {
+                state.push_message(ChatMessage::system(t!("forward-no-rooms")));
+            } else {
+                state.overlay = Some(Overlay::Forward { target: current, selected: 0 });
+            }
+        }
+        // Messages from older clients have no id to react to.
+        KeyCode::Char('e') if !state.room().messages[current].id.is_empty() && !state.room().messages[current].redacted => {
+            state.overlay = Some(Overlay::React { target: current, selected: 0 });
+        }
+        KeyCode::Char('y') | KeyCode::Enter => {
+            let text = state.room().messages[current].text.clone();
+            state.selected = None;
+            state.room_mut().jump_to_bottom();
+            state.copy(&text);
+        }
+        _ => {}
+    }
+    if let Some(index) = state.selected {
+        state.reveal(index);
+    }
+}
+
+/// Keys while the Ctrl+F search bar is open.
+/// Enter/Up walk to older matches, Down to newer ones, Esc closes the search.
+fn handle_search_key(state: &mut AppState, key: KeyEvent) {
+    let messages = &state.rooms[state.active].messages;
+    let Some(search) = state.search.as_mut() else { return };
+    match key.code {
+        KeyCode::Esc => {
+            state.search = None;
+            state.room_mut().jump_to_bottom();
+            return;
+        }
+        KeyCode::Enter | KeyCode::Up => search.older(messages),
+        KeyCode::Down => search.newer(messages),
+        KeyCode::Backspace => {
+            search.query.pop();
+            search.reset(messages);
+        }
+        KeyCode::Char(c) => {
+            search.query.push(c);
+            search.reset(messages);
+        }
+        _ => return,
+    }
+    match search.current {
+        Some(index) => state.scroll_to(index),
+        None => state.room_mut().jump_to_bottom(),
+    }
+}
+
+fn ui(frame: &mut Frame, state: &AppState) {
+    if let Some(disguise) = &state.disguise {
+        boss::render(frame, disguise);
+        return;
+    }
+    if state.screen_reader {
+        linear::render(frame, state);
+        overlays(frame, state);
+        theme::strip_boxes(frame.buffer_mut());
+    } else {
+        panes(frame, state);
+        overlays(frame, state);
+    }
+    state.theme.apply(frame.buffer_mut());
+}
+
+fn panes(frame: &mut Frame, state: &AppState) {
+    let screen = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(1),    // Body
+            Constraint::Length(1), // Status Bar
+        ])
+        .split(frame.area());
+
+    // Browsing peers needs the list on screen, whatever the width.
+    let show_sidebar = state.peer_cursor.is_some()
+        || state.sidebar_shown.unwrap_or(frame.area().width >= state.sidebar_min_cols);
+    state.sidebar_visible.set(show_sidebar);
+    let sidebar_width = if show_sidebar { state.sidebar_width } else { 0 };
+    let main_layout = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Length(sidebar_width), // Sidebar (Left)
+            Constraint::Min(1),                // Chat (Right)
+        ])
+        .split(screen[0]);
+
+    let tab_height = if state.rooms.len() > 1 { 1 } else { 0 };
+    let pins = &state.room().pins;
+    let pin_height = match pins.len() {
+        0 => 0,
+        n if state.pins_expanded => n.min(5) as u16 + 1,
+        _ => 1,
+    };
+    let (input_lines, cursor) = state.room().input.layout(main_layout[1].width as usize);
+    let input_height = input_lines.len().clamp(2, 5) as u16 + 1;
+    let chat_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(tab_height),   // Room Tabs
+            Constraint::Length(pin_height),   // Pinned
+            Constraint::Min(1),               // Messages
+            Constraint::Length(input_height), // Input
+        ])
+        .split(main_layout[1]);
+    let (tabs_area, pins_area, chat_area, input_area) = (chat_layout[0], chat_layout[1], chat_layout[2], chat_layout[3]);
+
+    // --- SIDEBAR (PEERS) ---
+    let mut peers: Vec<ListItem> = state.room().peers().into_iter().enumerate().map(|(i, (id, name))| {
+        let (color, mark) = if state.blocked.contains(&id) {
+            (Color::Red, " ✕")
+        } else if state.verified.contains(&id) {
+            (Color::Green, " ✓")
+        } else {
+            (Color::Cyan, "")
+        };
+        let status = state.room().statuses.get(&id).cloned().unwrap_or_default();
+        // Peers on another protocol version may miss, or send, what the other side can't show.
+        let version = match state.room().protocols.get(&id).map(protocol::Info::compare) {
+            Some(std::cmp::Ordering::Greater) => " ↑",
+            Some(std::cmp::Ordering::Less) => " ↓",
+            _ => "",
+        };
+        let item = ListItem::new(Line::from(vec![
+            Span::styled(" ● ", Style::default().fg(status.presence.color())),
+            Span::raw(state.room().named(&id, name)),
+            Span::styled(mark, Style::default().fg(color)),
+            Span::styled(version, Style::default().fg(Color::Yellow)),
+            role_tag(state.room().role(&id)),
+            status_tag(&status),
+        ]));
+        if state.peer_cursor == Some(i) {
+            item.style(Style::default().fg(Color::White).bg(Color::Indexed(237)))
+        } else {
+            item
+        }
+    }).collect();
     
-    let input = Paragraph::new(state.input.as_str())
-        .style(Style::default().fg(Color::White))
+    peers.insert(0, ListItem::new(Line::from(vec![
+        Span::styled(" ● ", Style::default().fg(state.status.presence.color())),
+        Span::styled(t!("sidebar-you", name = state.my_name.as_str()), Style::default().add_modifier(Modifier::BOLD)),
+        role_tag(state.room().role(&state.node_id)),
+        status_tag(&state.status),
+    ])));
+
+    let sidebar = List::new(peers)
         .block(Block::default()
-            .borders(Borders::TOP) 
-            .border_style(Style::default().fg(input_border_color))
-            .title(Span::styled(" Write a message ", Style::default().fg(Color::DarkGray))));
+            .borders(Borders::RIGHT) 
+            .title(format!(" {} ", t!("sidebar-title")))
+            .padding(Padding::new(1, 1, 1, 1)))
+        .style(Style::default().fg(Color::DarkGray));
             
-    frame.render_widget(input, chat_layout[1]);
+    if show_sidebar {
+        frame.render_widget(sidebar, main_layout[0]);
+    }
+
+    // --- ROOM TABS ---
+    if state.rooms.len() > 1 {
+        let titles = state.rooms.iter().enumerate().map(|(i, room)| {
+            let mut title = vec![Span::raw(format!("{} {}", i + 1, room.label()))];
+            if room.unread > 0 {
+                title.push(Span::styled(format!(" ({})", room.unread), Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)));
+            }
+            Line::from(title)
+        });
+        let tabs = Tabs::new(titles)
+            .select(state.active)
+            .style(Style::default().fg(Color::DarkGray))
+            .highlight_style(Style::default().fg(Color::White).add_modifier(Modifier::BOLD))
+            .divider("│");
+        frame.render_widget(tabs, tabs_area);
+    }
+
+    // --- PINNED STRIP ---
+    if let Some(latest) = pins.last() {
+        let pin = Style::default().fg(Color::Yellow);
+        let mut lines = vec![Line::from(vec![
+            Span::styled(format!(" 📌 {}", t!("pinned", count = pins.len())), pin.add_modifier(Modifier::BOLD)),
+            Span::styled(format!(" · {}: {}", latest.sender, latest.snippet), Style::default().fg(Color::Gray)),
+            Span::styled(format!("  ({})", if state.pins_expanded { t!("pins-collapse") } else { t!("pins-expand") }), Style::default().fg(Color::DarkGray)),
+        ])];
+        if state.pins_expanded {
+            for quote in pins.iter().rev().take(5) {
+                lines.push(Line::from(vec![
+                    Span::styled("    • ", pin),
+                    Span::styled(format!("{}: {}", quote.sender, quote.snippet), Style::default().fg(Color::Gray)),
+                ]));
+            }
+        }
+        frame.render_widget(Paragraph::new(lines).style(Style::default().bg(Color::Indexed(235))), pins_area);
+    }
+
+    // --- CHAT MESSAGES (SMS Layout) ---
+    let mut areas = mouse::Areas { input: input_area, ..Default::default() };
+    if show_sidebar {
+        areas.sidebar = main_layout[0];
+    }
+    match state.split {
+        None if state.room().thread.is_some() => {
+            let block = Block::default()
+                .borders(Borders::TOP)
+                .border_style(Style::default().fg(Color::Cyan))
+                .title(Span::styled(format!(" {} ", chat::thread_title(state.room())), Style::default().fg(Color::Cyan)));
+            frame.render_widget(&block, chat_area);
+            areas.chat = block.inner(chat_area);
+            chat::render(frame, areas.chat, state, state.active);
+        }
+        None => {
+            areas.chat = chat_area;
+            chat::render(frame, chat_area, state, state.active);
+        }
+        Some((direction, other)) => {
+            let panes = Layout::default()
+                .direction(direction)
+                .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .split(chat_area);
+            for (pane, room) in panes.iter().zip([state.active, other]) {
+                let color = if room == state.active { Color::Cyan } else { Color::DarkGray };
+                let title = match state.rooms[room].thread {
+                    Some(_) => format!("{} · {}", state.rooms[room].label(), chat::thread_title(&state.rooms[room])),
+                    None => state.rooms[room].label(),
+                };
+                let block = Block::default()
+                    .borders(Borders::TOP)
+                    .border_style(Style::default().fg(color))
+                    .title(Span::styled(format!(" {} ", title), Style::default().fg(color)));
+                frame.render_widget(&block, *pane);
+                let inner = block.inner(*pane);
+                if room == state.active {
+                    areas.chat = inner;
+                } else {
+                    areas.other = inner;
+                }
+                chat::render(frame, inner, state, room);
+            }
+        }
+    }
+    state.areas.set(areas);
+
+    // --- INPUT BAR ---
+    if let Some(search) = &state.search {
+        let matches = search.matches(&state.room().messages);
+        let position = search.current
+            .and_then(|cur| matches.iter().position(|&i| i == cur))
+            .map(|pos| format!("{}/{}", pos + 1, matches.len()))
+            .unwrap_or_else(|| t!("search-no-matches"));
+
+        let input = Paragraph::new(search.query.as_str())
+            .style(Style::default().fg(Color::Yellow))
+            .block(Block::default()
+                .borders(Borders::TOP)
+                .border_style(Style::default().fg(Color::Yellow))
+                .title(Span::styled(
+                    format!(" {} ", t!("search-title", position = position)),
+                    Style::default().fg(Color::DarkGray),
+                )));
+
+        frame.render_widget(input, input_area);
+    } else {
+        let flashing = state.flash_until.is_some_and(|until| Instant::now() < until);
+        let input_border_color = if flashing {
+            Color::Yellow
+        } else if state.room().input.is_empty() {
+            Color::DarkGray
+        } else {
+            Color::White
+        };
+        let title = match (&state.room().editing, &state.room().reply) {
+            _ if state.selected.is_some() => t!("input-selecting"),
+            _ if state.vim.as_ref().is_some_and(|v| v.mode == vim::Mode::Normal) => t!("input-vim-normal"),
+            (Some(_), _) => t!("input-editing"),
+            (None, Some(quote)) => t!("input-replying", sender = quote.sender.as_str(), snippet = quote.snippet.as_str()),
+            (None, None) if state.room().thread.is_some() => t!("input-thread"),
+            (None, None) => t!("input-write"),
+        };
+        let title = format!(" {} ", title);
+
+        // Keep the cursor's line in view when the draft is taller than the box.
+        let rows = input_area.height.saturating_sub(1) as usize;
+        let first = (cursor.1 + 1).saturating_sub(rows);
+        let lines: Vec<Line> = input_lines.into_iter().skip(first).take(rows).map(Line::raw).collect();
+
+        let input = Paragraph::new(lines)
+            .style(Style::default().fg(Color::White))
+            .block(Block::default()
+                .borders(Borders::TOP) 
+                .border_style(Style::default().fg(input_border_color))
+                .title(Span::styled(title, Style::default().fg(Color::DarkGray))));
+
+        frame.render_widget(input, input_area);
+        if state.overlay.is_none() && state.selected.is_none() && state.peer_cursor.is_none() {
+            let area = input_area;
+            frame.set_cursor_position((area.x + cursor.0 as u16, area.y + 1 + (cursor.1 - first) as u16));
+        }
+    }
+
+    // --- STATUS BAR ---
+    status::render(frame, screen[1], state);
+}
+
+fn overlays(frame: &mut Frame, state: &AppState) {
+    match &state.overlay {
+        Some(Overlay::Help) => help::render(frame, state),
+        Some(Overlay::Links { urls, selected, confirm }) => links::render(frame, urls, *selected, *confirm),
+        Some(Overlay::Peer(id)) => peers::render(frame, state, id),
+        Some(Overlay::Quit) => render_quit(frame, state),
+        Some(Overlay::Destroy) => render_destroy(frame, state),
+        Some(Overlay::Destroyed { notice, last }) => render_destroyed(frame, notice, *last),
+        Some(Overlay::Info(index)) => chat::render_info(frame, state, *index),
+        Some(Overlay::Emoji { query, selected }) => emoji::render(frame, query, *selected),
+        Some(Overlay::React { selected, .. }) => render_react(frame, *selected),
+        Some(Overlay::Forward { selected, .. }) => render_forward(frame, state, *selected),
+        None => {}
+    }
+}
+
+/// Renders the emoji picker opened with `e` in selection mode.
+fn render_react(frame: &mut Frame, selected: usize) {
+    let mut spans = Vec::new();
+    for (i, emoji) in reactions::EMOJI.iter().enumerate() {
+        let style = if i == selected {
+            Style::default().bg(Color::Indexed(237)).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default()
+        };
+        spans.push(Span::styled(format!(" {} {} ", i + 1, emoji), style));
+    }
+
+    let area = centered(frame.area(), 44, 3);
+    let popup = Paragraph::new(Line::from(spans)).centered().block(Block::default()
+        .borders(Borders::ALL)
+        .border_type(ratatui::widgets::BorderType::Rounded)
+        .border_style(Style::default().fg(Color::Cyan))
+        .title(format!(" {} ", t!("react-title"))));
+
+    frame.render_widget(ratatui::widgets::Clear, area);
+    frame.render_widget(popup, area);
+}
+
+/// The room picker for forwarding a message: every tab but the active one.
+fn render_forward(frame: &mut Frame, state: &AppState, selected: usize) {
+    let mut lines: Vec<Line> = state.rooms.iter().enumerate().filter(|(i, _)| *i != state.active).map(|(_, room)| room.label())
+        .enumerate()
+        .map(|(i, label)| match i == selected {
+            true => Line::styled(label, Style::default().fg(Color::Black).bg(Color::LightBlue)),
+            false => Line::styled(label, Style::default().fg(Color::LightBlue)),
+        })
+        .collect();
+    lines.push(Line::raw(""));
+    lines.push(Line::styled(t!("forward-keys"), Style::default().fg(Color::DarkGray)));
+    let area = centered(frame.area(), 48, lines.len() as u16 + 2);
+    let popup = Paragraph::new(lines).block(Block::default()
+        .borders(Borders::ALL)
+        .border_type(ratatui::widgets::BorderType::Rounded)
+        .border_style(Style::default().fg(Color::LightBlue))
+        .title(format!(" {} ", t!("forward-title")))
+        .padding(Padding::horizontal(1)));
+
+    frame.render_widget(ratatui::widgets::Clear, area);
+    frame.render_widget(popup, area);
+}
+
+/// The y/n prompt before leaving, warning about drafts that would be lost.
+fn render_quit(frame: &mut Frame, state: &AppState) {
+    let mut lines = vec![
+        Line::styled(t!("quit-confirm"), Style::default().fg(Color::White)),
+    ];
+    for room in state.rooms.iter().filter(|r| !r.input.is_empty()) {
+        lines.push(Line::styled(t!("quit-draft", room = room.label()), Style::default().fg(Color::Yellow)));
+    }
+    lines.push(Line::raw(""));
+    lines.push(Line::styled(t!("quit-keys"), Style::default().fg(Color::DarkGray)));
+
+    let area = centered(frame.area(), 48, lines.len() as u16 + 2);
+    let popup = Paragraph::new(lines).block(Block::default()
+        .borders(Borders::ALL)
+        .border_type(ratatui::widgets::BorderType::Rounded)
+        .border_style(Style::default().fg(Color::Red))
+        .title(format!(" {} ", t!("quit-title")))
+        .padding(Padding::horizontal(1)));
+
+    frame.render_widget(ratatui::widgets::Clear, area);
+    frame.render_widget(popup, area);
+}
+
+/// The y/n prompt before `/destroy`.
+fn render_destroy(frame: &mut Frame, state: &AppState) {
+    let lines = vec![
+        Line::styled(t!("destroy-confirm", room = state.room().label()), Style::default().fg(Color::White)),
+        Line::styled(t!("destroy-warning"), Style::default().fg(Color::Yellow)),
+        Line::raw(""),
+        Line::styled(t!("destroy-keys"), Style::default().fg(Color::DarkGray)),
+    ];
+    let area = centered(frame.area(), 56, lines.len() as u16 + 2);
+    let popup = Paragraph::new(lines).block(Block::default()
+        .borders(Borders::ALL)
+        .border_type(ratatui::widgets::BorderType::Rounded)
+        .border_style(Style::default().fg(Color::Red))
+        .title(format!(" {} ", t!("destroy-title")))
+        .padding(Padding::horizontal(1)));
+
+    frame.render_widget(ratatui::widgets::Clear, area);
+    frame.render_widget(popup, area);
+}
+
+/// The notice that a room was destroyed; over a blank screen when it was the `last`.
+fn render_destroyed(frame: &mut Frame, notice: &str, last: bool) {
+    let keys = if last { t!("destroyed-keys-last") } else { t!("destroyed-keys") };
+    let lines = vec![
+        Line::styled(notice.to_string(), Style::default().fg(Color::White)),
+        Line::raw(""),
+        Line::styled(keys, Style::default().fg(Color::DarkGray)),
+    ];
+    if last {
+        frame.render_widget(ratatui::widgets::Clear, frame.area());
+    }
+    let area = centered(frame.area(), 56, lines.len() as u16 + 2);
+    let popup = Paragraph::new(lines).alignment(Alignment::Center).block(Block::default()
+        .borders(Borders::ALL)
+        .border_type(ratatui::widgets::BorderType::Rounded)
+        .border_style(Style::default().fg(Color::Red))
+        .title(format!(" {} ", t!("destroyed-title"))));
+
+    frame.render_widget(ratatui::widgets::Clear, area);
+    frame.render_widget(popup, area);
+}
+
+/// A `width` x `height` rectangle centered in `area`, clamped to fit.
+fn centered(area: Rect, width: u16, height: u16) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect::new(area.x + (area.width - width) / 2, area.y + (area.height - height) / 2, width, height)
 }
\ No newline at end of file
@@ -0,0 +1,242 @@
+use std::ops::Range;
+
+use ratatui::prelude::*;
+
+/// Byte ranges of every case-insensitive occurrence of `term` in `text`.
+pub fn find_all(text: &str, term: &str) -> Vec<Range<usize>> {
+    let needle: Vec<char> = term.chars().flat_map(char::to_lowercase).collect();
+    let mut found = Vec::new();
+    if needle.is_empty() {
+        return found;
+    }
+
+    let mut pos = 0;
+    while let Some(c) = text[pos..].chars().next() {
+        match match_len(&text[pos..], &needle) {
+            Some(len) => {
+                found.push(pos..pos + len);
+                pos += len;
+            }
+            None => pos += c.len_utf8(),
+        }
+    }
+    found
+}
+
+/// Length in bytes of the prefix of `s` that case-insensitively matches `needle`, if any.
+fn match_len(s: &str, needle: &[char]) -> Option<usize> {
+    let mut pending = needle;
+    for (i, c) in s.char_indices() {
+        for lower in c.to_lowercase() {
+            match pending.split_first() {
+                Some((first, rest)) if *first == lower => pending = rest,
+                _ => return None,
+            }
+        }
+        if pending.is_empty() {
+            return Some(i + c.len_utf8());
+        }
+    }
+    None
+}
+
+/// Byte ranges of `@name` mentions of any of `names`, together with the mentioned name.
+/// Longer names win, so `@Ghost Two` is not mistaken for `@Ghost`.
+pub fn mentions<'n>(text: &str, names: &[&'n str]) -> Vec<(Range<usize>, &'n str)> {
+    let mut names: Vec<(&str, Vec<char>)> = names.iter()
+        .filter(|name| !name.is_empty())
+        .map(|name| (*name, name.chars().flat_map(char::to_lowercase).collect()))
+        .collect();
+    names.sort_by_key(|(_, lower)| std::cmp::Reverse(lower.len()));
+
+    let mut found = Vec::new();
+    let mut prev: Option<char> = None;
+    let mut pos = 0;
+    while let Some(c) = text[pos..].chars().next() {
+        let at_word_start = prev.is_none_or(|p| !p.is_alphanumeric());
+        if c == '@' && at_word_start {
+            let rest = &text[pos + 1..];
+            let hit = names.iter().find_map(|(name, lower)| {
+                let len = match_len(rest, lower)?;
+                let ends_word = rest[len..].chars().next().is_none_or(|n| !n.is_alphanumeric());
+                ends_word.then_some((len, *name))
+            });
+            if let Some((len, name)) = hit {
+                let end = pos + 1 + len;
+                found.push((pos..end, name));
+                prev = text[..end].chars().next_back();
+                pos = end;
+                continue;
+            }
+        }
+        prev = Some(c);
+        pos += c.len_utf8();
+    }
+    found
+}
+
+/// Splits `text` into spans styled with `base`, patched by each overlay in order
+/// (later overlays win where they overlap).
+pub fn paint<'a>(text: &'a str, base: Style, overlays: &[(Range<usize>, Style)]) -> Vec<Span<'a>> {
+    let mut cuts: Vec<usize> = overlays.iter()
+        .flat_map(|(range, _)| [range.start, range.end])
+        .chain([0, text.len()])
+        .collect();
+    cuts.sort_unstable();
+    cuts.dedup();
+
+    let mut spans = Vec::new();
+    for pair in cuts.windows(2) {
+        let (start, end) = (pair[0], pair[1]);
+        let style = overlays.iter()
+            .filter(|(range, _)| range.start <= start && end <= range.end)
+            .fold(base, |style, (_, patch)| style.patch(*patch));
+        spans.push(Span::styled(&text[start..end], style));
+    }
+    if spans.is_empty() {
+        spans.push(Span::styled(text, base));
+    }
+    spans
+}
+
+/// Byte ranges of the `http://` / `https://` URLs in `text`.
+/// Trailing punctuation is left out, as is a closing paren without a matching open one.
+pub fn urls(text: &str) -> Vec<Range<usize>> {
+    let mut found = Vec::new();
+    let mut pos = 0;
+    while pos < text.len() {
+        let rest = &text[pos..];
+        let Some(offset) = rest.find("http://").into_iter().chain(rest.find("https://")).min() else { break };
+        let start = pos + offset;
+        let at_word_start = text[..start].chars().next_back().is_none_or(|c| !c.is_alphanumeric());
+
+        let len = text[start..].find(char::is_whitespace).unwrap_or(text.len() - start);
+        let mut url = &text[start..start + len];
+        loop {
+            let trimmed = url.trim_end_matches(['.', ',', ';', ':', '!', '?', '\'', '"', ']', '}', '>']);
+            let trimmed = match trimmed.strip_suffix(')') {
+                Some(inner) if !inner.contains('(') => inner,
+                _ => trimmed,
+            };
+            if trimmed.len() == url.len() {
+                break;
+            }
+            url = trimmed;
+        }
+
+        if at_word_start && !url.ends_with("//") {
+            found.push(start..start + url.len());
+        }
+        pos = start + len.max(1);
+    }
+    found
+}
+
+/// One line of inline-formatted text: the displayed string plus style ranges into it.
+pub struct Styled {
+    pub text: String,
+    pub styles: Vec<(Range<usize>, Style)>,
+}
+
+/// A chunk of a message after Markdown parsing.
+pub enum Block {
+    Text(Styled),
+    /// Lines of a fenced ``` code block, shown verbatim.
+    Code(Vec<String>),
+}
+
+pub fn code_style() -> Style {
+    Style::default().fg(Color::LightYellow).bg(Color::Indexed(236))
+}
+
+/// Parses the small Markdown subset we render: `*bold*`, `_italic_`, `` `code` `` and fenced blocks.
+/// The wire always carries the raw text, this only affects display.
+pub fn markdown(text: &str) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    let mut code: Option<Vec<String>> = None;
+
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if let Some(lines) = code.as_mut() {
+            if trimmed.starts_with("```") {
+                blocks.push(Block::Code(code.take().unwrap_or_default()));
+            } else {
+                lines.push(line.to_string());
+            }
+        } else if trimmed.len() > 6 && trimmed.starts_with("```") && trimmed.ends_with("```") {
+            blocks.push(Block::Code(vec![trimmed[3..trimmed.len() - 3].to_string()]));
+        } else if trimmed.starts_with("```") {
+            code = Some(Vec::new());
+        } else {
+            blocks.push(Block::Text(inline(line)));
+        }
+    }
+    // An unterminated fence still renders as code rather than swallowing the text.
+    if let Some(lines) = code {
+        blocks.push(Block::Code(lines));
+    }
+    if blocks.is_empty() {
+        blocks.push(Block::Text(inline("")));
+    }
+    blocks
+}
+
+/// Inline emphasis for a single line. URLs are copied through untouched so
+/// underscores in paths don't turn into italics.
+fn inline(line: &str) -> Styled {
+    let links = urls(line);
+    let mut out = Styled { text: String::new(), styles: Vec::new() };
+    let mut pos = 0;
+
+    while let Some(c) = line[pos..].chars().next() {
+        if let Some(link) = links.iter().find(|r| r.start == pos) {
+            out.text.push_str(&line[link.clone()]);
+            pos = link.end;
+            continue;
+        }
+
+        if c == '`' {
+            if let Some(len) = line[pos + 1..].find('`').filter(|&len| len > 0) {
+                let start = out.text.len();
+                out.text.push_str(&line[pos + 1..pos + 1 + len]);
+                out.styles.push((start..out.text.len(), code_style()));
+                pos += len + 2;
+                continue;
+            }
+        }
+
+        if c == '*' || c == '_' {
+            if let Some(len) = emphasis_len(line, pos, c) {
+                let inner = inline(&line[pos + 1..pos + 1 + len]);
+                let start = out.text.len();
+                out.text.push_str(&inner.text);
+                let modifier = if c == '*' { Modifier::BOLD } else { Modifier::ITALIC };
+                out.styles.push((start..out.text.len(), Style::default().add_modifier(modifier)));
+                out.styles.extend(inner.styles.into_iter().map(|(r, s)| (r.start + start..r.end + start, s)));
+                pos += len + 2;
+                continue;
+            }
+        }
+
+        out.text.push(c);
+        pos += c.len_utf8();
+    }
+    out
+}
+
+/// Length of the emphasised content if the `marker` at `pos` opens a valid span.
+/// Markers must sit on word boundaries, so `snake_case_names` and `2*3*4` stay literal.
+fn emphasis_len(line: &str, pos: usize, marker: char) -> Option<usize> {
+    let opens = line[..pos].chars().next_back().is_none_or(|p| !p.is_alphanumeric())
+        && line[pos + 1..].chars().next().is_some_and(|n| !n.is_whitespace() && n != marker);
+    if !opens {
+        return None;
+    }
+
+    let body = &line[pos + 1..];
+    body.match_indices(marker).map(|(i, _)| i).find(|&i| {
+        i > 0
+            && !body[..i].ends_with(char::is_whitespace)
+            && body[i + 1..].chars().next().is_none_or(|n| !n.is_alphanumeric())
+    })
+}
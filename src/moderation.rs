@@ -0,0 +1,222 @@
+use std::{collections::HashMap, time::{Duration, Instant}};
+
+use chrono::Utc;
+use iroh::{NodeId, SecretKey};
+use iroh_gossip::proto::TopicId;
+use serde::{Deserialize, Serialize};
+
+use crate::{i18n::t, identity, Message};
+
+/// How long a `/kick` keeps someone out; a `/ban` lasts as long as the room.
+pub const KICK: Duration = Duration::from_secs(10 * 60);
+
+/// What a peer may do in a room. The host is the ticket's first node; everyone else is a
+/// member until the host says otherwise with `/role`, or read-only in a broadcast room.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Role {
+    Host,
+    /// Pins, kicks, bans and sets the topic, like the host; can't touch the host or other moderators.
+    Moderator,
+    Member,
+    /// Reads along; whatever they post, pin or react is dropped.
+    ReadOnly,
+}
+
+impl Role {
+    /// Pinning, `/kick`, `/ban` and `/topic`.
+    pub fn moderates(self) -> bool {
+        matches!(self, Role::Host | Role::Moderator)
+    }
+
+    pub fn posts(self) -> bool {
+        self != Role::ReadOnly
+    }
+
+    /// Whether someone with this role may kick or ban someone with `other`.
+    pub fn outranks(self, other: Role) -> bool {
+        match self {
+            Role::Host => other != Role::Host,
+            Role::Moderator => matches!(other, Role::Member | Role::ReadOnly),
+            Role::Member | Role::ReadOnly => false,
+        }
+    }
+
+    pub fn name(self) -> String {
+        match self {
+            Role::Host => t!("role-host"),
+            Role::Moderator => t!("role-moderator"),
+            Role::Member => t!("role-member"),
+            Role::ReadOnly => t!("role-read-only"),
+        }
+    }
+}
+
+/// What a ban's signer signs: the room and the peer, whether it's only a kick, and when,
+/// so an announcement can't be replayed in another room, turned into the other kind, or
+/// used to kick someone again once their kick is over.
+fn ban_bytes(topic: TopicId, node: NodeId, kick: bool, at: i64) -> Vec<u8> {
+    let mut bytes = b"ghostterm ban".to_vec();
+    bytes.extend(topic.as_bytes());
+    bytes.extend(node.as_bytes());
+    bytes.push(kick as u8);
+    bytes.extend(at.to_le_bytes());
+    bytes
+}
+
+/// What the host signs to give `node` a role. `at` orders assignments, so an old one
+/// can't be replayed over a newer one.
+fn role_bytes(topic: TopicId, node: NodeId, role: Role, at: i64) -> Vec<u8> {
+    let mut bytes = b"ghostterm role".to_vec();
+    bytes.extend(topic.as_bytes());
+    bytes.extend(node.as_bytes());
+    bytes.push(role as u8);
+    bytes.extend(at.to_le_bytes());
+    bytes
+}
+
+/// What the host signs to turn broadcast mode on or off from `at` on.
+fn mode_bytes(topic: TopicId, broadcast: bool, at: i64) -> Vec<u8> {
+    let mut bytes = b"ghostterm mode".to_vec();
+    bytes.extend(topic.as_bytes());
+    bytes.push(broadcast as u8);
+    bytes.extend(at.to_le_bytes());
+    bytes
+}
+
+/// What the host signs to close the room for good. There's nothing to order: once is enough.
+fn close_bytes(topic: TopicId) -> Vec<u8> {
+    let mut bytes = b"ghostterm close".to_vec();
+    bytes.extend(topic.as_bytes());
+    bytes
+}
+
+/// The `Ban` message putting `node` out of `topic`, signed with our key; `by` names us
+/// when we aren't the host.
+pub fn announce(key: &SecretKey, topic: TopicId, node: NodeId, kick: bool, by: Option<NodeId>) -> Message {
+    let at = Utc::now().timestamp_millis();
+    Message::Ban { node, kick, by, at, signature: identity::sign(key, &ban_bytes(topic, node, kick, at)) }
+}
+
+/// The host's `Role` message giving `node` `role` from now on.
+pub fn assign(key: &SecretKey, topic: TopicId, node: NodeId, role: Role) -> Message {
+    let at = Utc::now().timestamp_millis();
+    Message::Role { node, role, at, signature: identity::sign(key, &role_bytes(topic, node, role, at)) }
+}
+
+/// The host's `Mode` message making the room a broadcast channel, or with `broadcast`
+/// false, an ordinary room again.
+pub fn mode(key: &SecretKey, topic: TopicId, broadcast: bool) -> Message {
+    let at = Utc::now().timestamp_millis();
+    Message::Mode { broadcast, at, signature: identity::sign(key, &mode_bytes(topic, broadcast, at)) }
+}
+
+/// The host's `RoomClose` message destroying `topic`.
+pub fn close(key: &SecretKey, topic: TopicId) -> Message {
+    Message::RoomClose { signature: identity::sign(key, &close_bytes(topic)) }
+}
+
+/// Whether `host` signed a `RoomClose` for `topic`.
+pub fn closes(host: NodeId, topic: TopicId, signature: &str) -> bool {
+    identity::verify(host, &close_bytes(topic), signature)
+}
+
+/// Who has been put out of one room.
+#[derive(Default)]
+pub struct Bans {
+    /// Until when, for kicks.
+    out: HashMap<NodeId, Option<Instant>>,
+    /// When the last kick of each peer was signed, so it can't be replayed.
+    kicked: HashMap<NodeId, i64>,
+    /// Who signed each standing ban, when and how, to repeat to peers who join later.
+    signatures: HashMap<NodeId, (Option<NodeId>, i64, String)>,
+}
+
+impl Bans {
+    /// Records a ban if `signer` signed it, returning false if `node` was already out. A
+    /// kick never shortens a ban, or restarts a kick still running, and only holds until
+    /// `KICK` after it was signed, if it's newer than the last one.
+    #[allow(clippy::too_many_arguments)]
+    pub fn apply(&mut self, topic: TopicId, node: NodeId, kick: bool, signer: NodeId, by: Option<NodeId>, at: i64, signature: String) -> bool {
+        if !identity::verify(signer, &ban_bytes(topic, node, kick, at), &signature) {
+            return false;
+        }
+        if kick {
+            let left = at.saturating_add(KICK.as_millis() as i64).saturating_sub(Utc::now().timestamp_millis());
+            if self.contains(&node) || left <= 0 || self.kicked.get(&node).is_some_and(|seen| at <= *seen) {
+                return false;
+            }
+            self.kicked.insert(node, at);
+            self.out.insert(node, Some(Instant::now() + Duration::from_millis(left as u64)));
+        } else {
+            if self.out.get(&node).is_some_and(Option::is_none) {
+                return false;
+            }
+            self.out.insert(node, None);
+            self.signatures.insert(node, (by, at, signature));
+        }
+        true
+    }
+
+    /// Whether messages from `node` are dropped.
+    pub fn contains(&self, node: &NodeId) -> bool {
+        self.out.get(node).is_some_and(|until| until.is_none_or(|until| Instant::now() < until))
+    }
+
+    /// The standing bans, as announcements to send again.
+    pub fn announcements(&self) -> Vec<Message> {
+        self.signatures.iter()
+            .map(|(node, (by, at, signature))| Message::Ban { node: *node, kick: false, by: *by, at: *at, signature: signature.clone() })
+            .collect()
+    }
+}
+
+/// The roles the host has handed out in one room, with its signatures to pass on.
+#[derive(Default)]
+pub struct Roles {
+    assigned: HashMap<NodeId, (Role, i64, String)>,
+    /// Set in broadcast mode, as the host signed it: until then everyone is a member, but
+    /// in a broadcast room only the host and those it gave a role may post.
+    mode: Option<(bool, i64, String)>,
+}
+
+impl Roles {
+    /// Records an assignment if `host` signed it and it's newer than what we have.
+    pub fn apply(&mut self, host: NodeId, topic: TopicId, node: NodeId, role: Role, at: i64, signature: String) -> bool {
+        let newer = self.assigned.get(&node).is_none_or(|(_, seen, _)| at > *seen);
+        if !newer || node == host || role == Role::Host || !identity::verify(host, &role_bytes(topic, node, role, at), &signature) {
+            return false;
+        }
+        self.assigned.insert(node, (role, at, signature));
+        true
+    }
+
+    /// Switches broadcast mode if `host` signed it and it's newer than the last switch.
+    pub fn set_mode(&mut self, host: NodeId, topic: TopicId, broadcast: bool, at: i64, signature: String) -> bool {
+        let newer = self.mode.as_ref().is_none_or(|(_, seen, _)| at > *seen);
+        if !newer || !identity::verify(host, &mode_bytes(topic, broadcast, at), &signature) {
+            return false;
+        }
+        self.mode = Some((broadcast, at, signature));
+        true
+    }
+
+    pub fn broadcast(&self) -> bool {
+        self.mode.as_ref().is_some_and(|(broadcast, _, _)| *broadcast)
+    }
+
+    /// `node`'s role, not counting whether it's the host.
+    pub fn get(&self, node: &NodeId) -> Role {
+        let default = if self.broadcast() { Role::ReadOnly } else { Role::Member };
+        self.assigned.get(node).map_or(default, |(role, _, _)| *role)
+    }
+
+    /// The mode and every assignment, as announcements to send again.
+    pub fn announcements(&self) -> Vec<Message> {
+        let mode = self.mode.iter()
+            .map(|(broadcast, at, signature)| Message::Mode { broadcast: *broadcast, at: *at, signature: signature.clone() });
+        mode.chain(self.assigned.iter()
+            .map(|(node, (role, at, signature))| Message::Role { node: *node, role: *role, at: *at, signature: signature.clone() }))
+            .collect()
+    }
+}
@@ -0,0 +1,129 @@
+use crossterm::event::{MouseButton, MouseEvent, MouseEventKind};
+use ratatui::layout::{Position, Rect};
+use unicode_width::UnicodeWidthStr;
+
+use crate::{markup, AppState, Overlay};
+
+/// Where the panes were drawn last frame, for hit-testing clicks.
+#[derive(Clone, Copy, Default)]
+pub struct Areas {
+    pub sidebar: Rect,
+    /// The focused chat pane.
+    pub chat: Rect,
+    /// The other split pane, if any.
+    pub other: Rect,
+    pub input: Rect,
+}
+
+/// One screen row of the focused chat pane: the message drawn there and its cells' symbols.
+pub type Row = (Option<usize>, Vec<String>);
+
+/// The rows a drag covers, as (row, first column, end column) offsets into the chat pane.
+pub fn span(drag: (Position, Position), area: Rect) -> Vec<(u16, u16, u16)> {
+    let (a, b) = drag;
+    let (start, end) = if (a.y, a.x) <= (b.y, b.x) { (a, b) } else { (b, a) };
+    (start.y..=end.y)
+        .map(|y| {
+            let from = if y == start.y { start.x - area.x } else { 0 };
+            let to = if y == end.y { end.x - area.x + 1 } else { area.width };
+            (y - area.y, from, to)
+        })
+        .collect()
+}
+
+/// The text of `cells[from..to]`, skipping the blanks hidden behind wide characters.
+fn text(cells: &[String], from: usize, to: usize) -> String {
+    let mut out = String::new();
+    let mut i = from;
+    while i < to.min(cells.len()) {
+        out.push_str(&cells[i]);
+        i += cells[i].width().max(1);
+    }
+    out
+}
+
+/// The link under column `col` of a chat row, if any.
+fn url_at(cells: &[String], col: usize) -> Option<String> {
+    let mut line = String::new();
+    let mut offset = None;
+    let mut i = 0;
+    while i < cells.len() {
+        if i <= col {
+            offset = Some(line.len());
+        }
+        line.push_str(&cells[i]);
+        i += cells[i].width().max(1);
+    }
+    let offset = offset?;
+    markup::urls(&line).into_iter().find(|r| r.contains(&offset)).map(|r| line[r].to_string())
+}
+
+fn clamp(at: Position, area: Rect) -> Position {
+    Position::new(
+        at.x.clamp(area.x, area.right().saturating_sub(1)),
+        at.y.clamp(area.y, area.bottom().saturating_sub(1)),
+    )
+}
+
+/// Wheel scrolls the history; clicks focus panes, open peers and links, and select
+/// messages; a drag across the chat copies the text it covers.
+pub fn handle(state: &mut AppState, event: MouseEvent) {
+    if state.overlay.is_some() {
+        return;
+    }
+    let areas = state.areas.get();
+    let at = Position::new(event.column, event.row);
+    match event.kind {
+        MouseEventKind::ScrollUp if !areas.sidebar.contains(at) => state.scroll_by(3),
+        MouseEventKind::ScrollDown if !areas.sidebar.contains(at) => state.scroll_by(-3),
+        MouseEventKind::Down(MouseButton::Left) => {
+            if areas.chat.contains(at) {
+                state.drag = Some((at, at));
+            } else if areas.other.contains(at) {
+                if let Some((_, other)) = state.split {
+                    state.switch_room(other);
+                }
+            } else if areas.input.contains(at) {
+                state.selected = None;
+                state.peer_cursor = None;
+                if let Some(vim) = state.vim.as_mut() {
+                    vim.mode = crate::vim::Mode::Insert;
+                }
+            } else if areas.sidebar.contains(at) {
+                // Title and top padding, then "You", then the peers.
+                let row = at.y.saturating_sub(areas.sidebar.y + 2) as usize;
+                if let Some((id, _)) = row.checked_sub(1).and_then(|i| state.room().peers().get(i).copied()) {
+                    state.overlay = Some(Overlay::Peer(id));
+                }
+            }
+        }
+        MouseEventKind::Drag(MouseButton::Left) => {
+            if let Some((_, end)) = state.drag.as_mut() {
+                *end = clamp(at, areas.chat);
+            }
+        }
+        MouseEventKind::Up(MouseButton::Left) => {
+            let Some(drag) = state.drag.take() else { return };
+            let rows = state.chat_rows.borrow().clone();
+            if drag.0 != drag.1 {
+                let lines: Vec<String> = span(drag, areas.chat)
+                    .into_iter()
+                    .filter_map(|(y, from, to)| rows.get(y as usize).map(|(_, cells)| text(cells, from as usize, to as usize)))
+                    .map(|line| line.trim_end().to_string())
+                    .collect();
+                state.copy(lines.join("\n").trim());
+                return;
+            }
+            let (x, y) = ((drag.0.x - areas.chat.x) as usize, (drag.0.y - areas.chat.y) as usize);
+            let Some((Some(index), cells)) = rows.get(y) else { return };
+            match url_at(cells, x) {
+                Some(url) => state.show_links(vec![url]),
+                None => {
+                    state.selected = Some(*index);
+                    state.reveal(*index);
+                }
+            }
+        }
+        _ => {}
+    }
+}
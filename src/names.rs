@@ -0,0 +1,111 @@
+use chrono::Utc;
+use iroh::{NodeId, SecretKey};
+use iroh_gossip::proto::TopicId;
+use serde::{Deserialize, Serialize};
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{i18n::t, identity};
+
+/// Longest display name, in user-perceived characters.
+pub const MAX_LEN: usize = 32;
+
+/// Characters that render as nothing or reorder text around them, which lets a name hide
+/// what it really says.
+fn invisible(c: char) -> bool {
+    matches!(c, '\u{200B}'..='\u{200F}' | '\u{202A}'..='\u{202E}' | '\u{2060}'..='\u{2064}' | '\u{2066}'..='\u{2069}' | '\u{FEFF}' | '\u{00AD}')
+}
+
+/// Validates a `--name` or `/nick` name.
+pub fn parse(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(t!("name-empty"));
+    }
+    if name.graphemes(true).count() > MAX_LEN {
+        return Err(t!("name-too-long", max = MAX_LEN));
+    }
+    if name.chars().any(|c| c.is_control() || invisible(c)) {
+        return Err(t!("name-invisible"));
+    }
+    Ok(name.to_string())
+}
+
+/// Letters from other scripts (and digits) that pass for Latin ones.
+fn latin(c: char) -> char {
+    match c {
+        'а' | 'α' | 'ɑ' => 'a',
+        'в' | 'β' => 'b',
+        'с' | 'ϲ' => 'c',
+        'ԁ' => 'd',
+        'е' | 'ε' | 'ё' => 'e',
+        'һ' => 'h',
+        'і' | 'ι' | 'ı' | 'ӏ' | '1' | '|' => 'l',
+        'ј' => 'j',
+        'к' | 'κ' => 'k',
+        'м' => 'm',
+        'п' | 'η' => 'n',
+        'о' | 'ο' | 'σ' | '0' => 'o',
+        'р' | 'ρ' => 'p',
+        'ԛ' => 'q',
+        'ѕ' | '5' => 's',
+        'т' | 'τ' => 't',
+        'υ' | 'ц' => 'u',
+        'ν' => 'v',
+        'ѡ' | 'ω' => 'w',
+        'х' | 'χ' => 'x',
+        'у' | 'γ' => 'y',
+        'ᴢ' => 'z',
+        c => c,
+    }
+}
+
+/// What a name looks like, ignoring case, spacing and common look-alike letters.
+fn skeleton(name: &str) -> String {
+    let lower: String = name.chars().filter(|c| !c.is_whitespace() && !invisible(*c)).flat_map(char::to_lowercase).collect();
+    // `i` joins `l` and `1`, which look the same in many terminal fonts.
+    let mapped: String = lower.chars().map(latin).map(|c| if c == 'i' { 'l' } else { c }).collect();
+    mapped.replace("rn", "m").replace("vv", "w")
+}
+
+/// Whether one name could be mistaken for the other. Identical names count.
+pub fn looks_like(a: &str, b: &str) -> bool {
+    skeleton(a) == skeleton(b)
+}
+
+/// The first of `others` that `name` could be mistaken for.
+pub fn lookalike<'a>(name: &str, mut others: impl Iterator<Item = &'a str>) -> Option<&'a str> {
+    others.find(|other| looks_like(name, other))
+}
+
+/// A peer's signature on the name it goes by, so nobody else can put that name on its key,
+/// or an old name back on it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Claim {
+    /// Whose name it is; not whoever passed it on.
+    pub node: NodeId,
+    /// When it was taken, in Unix milliseconds; the latest claim wins.
+    pub at: i64,
+    pub signature: String,
+}
+
+/// What a peer signs to go by `name` in room `topic` from time `at` on.
+fn claim_bytes(topic: TopicId, node: NodeId, name: &str, at: i64) -> Vec<u8> {
+    let mut bytes = b"ghostterm name".to_vec();
+    bytes.extend(topic.as_bytes());
+    bytes.extend(node.as_bytes());
+    bytes.extend(at.to_be_bytes());
+    bytes.extend(name.as_bytes());
+    bytes
+}
+
+/// Our claim to `name` in `topic`, as of now.
+pub fn claim(key: &SecretKey, topic: TopicId, name: &str) -> Claim {
+    let (node, at) = (key.public(), Utc::now().timestamp_millis());
+    Claim { node, at, signature: identity::sign(key, &claim_bytes(topic, node, name, at)) }
+}
+
+impl Claim {
+    pub fn valid(&self, topic: TopicId, name: &str) -> bool {
+        identity::verify(self.node, &claim_bytes(topic, self.node, name, self.at), &self.signature)
+    }
+}
@@ -0,0 +1,39 @@
+use std::io::Write;
+
+use crate::i18n::t;
+
+/// What to do when a message arrives while you're scrolled up, in another tab, or in another window.
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum Alert {
+    Off,
+    Bell,
+    /// Briefly highlight the input border.
+    Flash,
+    Both,
+}
+
+impl Alert {
+    pub fn bell(self) -> bool {
+        matches!(self, Alert::Bell | Alert::Both)
+    }
+
+    pub fn flash(self) -> bool {
+        matches!(self, Alert::Flash | Alert::Both)
+    }
+
+    pub fn name(self) -> String {
+        match self {
+            Alert::Off => t!("alert-off"),
+            Alert::Bell => t!("alert-bell"),
+            Alert::Flash => t!("alert-flash"),
+            Alert::Both => t!("alert-both"),
+        }
+    }
+}
+
+/// Rings the terminal bell. Most terminals turn this into a taskbar flash or sound.
+pub fn bell() {
+    let mut stdout = std::io::stdout();
+    let _ = stdout.write_all(b"\x07");
+    let _ = stdout.flush();
+}
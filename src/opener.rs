@@ -0,0 +1,34 @@
+use std::{ffi::OsStr, process::{Command, Stdio}};
+
+use anyhow::{bail, Result};
+
+use crate::i18n::t;
+
+/// Opens an http(s) URL in the platform's default browser.
+pub fn open(url: &str) -> Result<()> {
+    if !(url.starts_with("http://") || url.starts_with("https://")) {
+        bail!(t!("link-not-http"));
+    }
+    launch(url)
+}
+
+/// Hands a URL or file path to the platform's default handler.
+pub fn launch(target: impl AsRef<OsStr>) -> Result<()> {
+    // `cmd /C start` re-parses '&' and friends, so go through the URL handler directly on Windows.
+    let mut command = if cfg!(target_os = "windows") {
+        let mut c = Command::new("rundll32");
+        c.arg("url.dll,FileProtocolHandler");
+        c
+    } else if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else {
+        Command::new("xdg-open")
+    };
+
+    command.arg(target)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    Ok(())
+}
@@ -0,0 +1,95 @@
+use std::{cmp::Ordering, time::Instant};
+
+use iroh::{endpoint::ConnectionType, NodeId};
+use ratatui::{
+    prelude::*,
+    widgets::{Block, BorderType, Borders, Clear, Padding, Paragraph},
+};
+
+use crate::{centered, i18n::t, identity::fingerprint, protocol, AppState};
+
+fn ago(since: Instant) -> String {
+    match since.elapsed().as_secs() {
+        s if s < 60 => t!("ago-seconds", n = s),
+        s if s < 3600 => t!("ago-minutes", n = s / 60),
+        s => t!("ago-hours", n = s / 3600),
+    }
+}
+
+/// Renders the details popup for `id`, opened from the sidebar (Ctrl+P).
+pub fn render(frame: &mut Frame, state: &AppState, id: &NodeId) {
+    let key = Style::default().fg(Color::White).add_modifier(Modifier::BOLD);
+    let dim = Style::default().fg(Color::Gray);
+    let room = state.room();
+    let name = room.peer_names.get(id).cloned().unwrap_or_else(|| t!("unknown-peer"));
+
+    // Live from the endpoint, so latency and path update while the popup is open.
+    let remote = state.endpoint.remote_info(*id);
+    let connection = match remote.as_ref().map(|r| &r.conn_type) {
+        Some(ConnectionType::Direct(addr)) => t!("peer-direct", addr = addr.to_string()),
+        Some(ConnectionType::Relay(url)) => t!("peer-relayed", url = url.to_string()),
+        Some(ConnectionType::Mixed(addr, _)) => t!("peer-mixed", addr = addr.to_string()),
+        _ => t!("peer-not-connected"),
+    };
+    let latency = remote.as_ref()
+        .and_then(|r| r.latency)
+        .map(|l| format!("{} ms", l.as_millis()))
+        .unwrap_or_else(|| t!("peer-latency-unknown"));
+    let last_seen = room.last_seen.get(id).map(|t| ago(*t)).unwrap_or_else(|| t!("peer-never"));
+
+    let protocol = match room.protocols.get(id) {
+        None => t!("peer-protocol-unknown"),
+        Some(info) => match info.compare() {
+            Ordering::Equal => t!("peer-protocol-same", version = info.version),
+            Ordering::Greater => t!("peer-protocol-newer", version = info.version, ours = protocol::VERSION),
+            Ordering::Less if info.missing().is_empty() => t!("peer-protocol-behind", version = info.version, ours = protocol::VERSION),
+            Ordering::Less => t!("peer-protocol-older", version = info.version, types = info.missing().join(", ")),
+        },
+    };
+
+    let info = room.protocols.get(id);
+    let client = match info.and_then(|info| info.client.as_deref()) {
+        Some(version) => t!("peer-client-version", version = version),
+        None => t!("peer-client-unknown"),
+    };
+    let features = match info.and_then(protocol::Info::lacks) {
+        None => t!("peer-features-unknown"),
+        Some(lacks) if lacks.is_empty() => t!("peer-features-all"),
+        Some(lacks) => t!("peer-features-lacking", features = lacks.join(", ")),
+    };
+
+    let (status, status_color) = if state.blocked.contains(id) {
+        (t!("peer-blocked"), Color::Red)
+    } else if state.verified.contains(id) {
+        (t!("peer-verified"), Color::Green)
+    } else {
+        (t!("peer-unverified"), Color::Yellow)
+    };
+
+    let id_text = id.to_string();
+    let mut lines = vec![
+        Line::from(vec![Span::styled(format!("{:<14}", "NodeId"), key), Span::styled(id_text[..32].to_string(), dim)]),
+        Line::from(vec![Span::styled(format!("{:<14}", ""), key), Span::styled(id_text[32..].to_string(), dim)]),
+        Line::from(vec![Span::styled(format!("{:<14}", t!("peer-fingerprint")), key), Span::styled(fingerprint(id), Style::default().fg(Color::Cyan))]),
+        Line::from(vec![Span::styled(format!("{:<14}", t!("peer-status")), key), Span::styled(status, Style::default().fg(status_color))]),
+    ];
+    let role = room.role(id).name();
+    let presence = room.statuses.get(id).cloned().unwrap_or_default().describe();
+    for (label, value) in [(t!("peer-role"), role), (t!("peer-presence"), presence), (t!("peer-connection"), connection), (t!("peer-latency"), latency), (t!("peer-last-seen"), last_seen), (t!("peer-protocol"), protocol), (t!("peer-client"), client), (t!("peer-features"), features)] {
+        lines.push(Line::from(vec![Span::styled(format!("{:<14}", label), key), Span::styled(value, dim)]));
+    }
+    lines.push(Line::raw(""));
+    lines.push(Line::styled(t!("peer-compare-hint"), Style::default().fg(Color::DarkGray)));
+
+    let area = centered(frame.area(), 64, lines.len() as u16 + 4);
+    let popup = Paragraph::new(lines).block(Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(Color::Cyan))
+        .title(format!(" {} ", name))
+        .title_bottom(Line::from(format!(" {} ", t!("peer-keys"))).right_aligned())
+        .padding(Padding::new(1, 1, 1, 0)));
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(popup, area);
+}
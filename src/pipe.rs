@@ -0,0 +1,409 @@
+use std::collections::HashSet;
+
+use anyhow::Result;
+use iroh::Endpoint;
+use iroh_gossip::{net::{Event, Gossip, GossipEvent}, proto::TopicId};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::{io::{AsyncBufReadExt, BufReader}, sync::mpsc};
+
+use crate::{broadcast, chunks, expiry, hooks::Hooks, location, moderation, names, new_id, notify, polls, presence, protocol, rich, rooms, transport, whereabouts, ChatEvent, Forwarded, Message, Quote};
+
+/// One line of stdin in `pipe` mode.
+#[derive(Deserialize)]
+#[serde(tag = "cmd", rename_all = "snake_case")]
+enum Command {
+    /// Post to `room` (a topic id or its prefix; default: the first room), in the thread
+    /// under message `thread` if given.
+    Send {
+        text: String,
+        #[serde(default)]
+        room: Option<String>,
+        #[serde(default)]
+        thread: Option<String>,
+        /// Rings through rooms peers have muted, if we haven't sent one in a while.
+        #[serde(default)]
+        urgent: bool,
+        /// Seconds until peers drop the message, as with `/tmp`.
+        #[serde(default)]
+        ttl: Option<u32>,
+        /// Marks it a copy of a message from elsewhere: `{"sender","room","time"}`.
+        #[serde(default)]
+        forwarded: Option<Forwarded>,
+    },
+    /// Posts a poll taking votes for `minutes` (default an hour).
+    Poll {
+        question: String,
+        options: Vec<String>,
+        #[serde(default)]
+        minutes: Option<u32>,
+        #[serde(default)]
+        room: Option<String>,
+    },
+    /// Shares a point on the map.
+    Location { lat: f64, lon: f64, #[serde(default)] label: String, #[serde(default)] room: Option<String> },
+    /// Puts up a banner, pinned with `pin`; host and moderators only.
+    Announce { text: String, #[serde(default)] pin: bool, #[serde(default)] room: Option<String> },
+    /// Makes a room we host a broadcast channel, or with `on` false an ordinary room again.
+    Broadcast { on: bool, #[serde(default)] room: Option<String> },
+    /// Sends a message of an extension type, named `namespace/type`, carrying any JSON `body`.
+    Extension {
+        #[serde(rename = "type")]
+        kind: String,
+        #[serde(default)]
+        body: Value,
+        #[serde(default)]
+        room: Option<String>,
+    },
+    /// Deletes message `target` for everyone: ours, or as host or moderator anyone's below us.
+    Redact { target: String, #[serde(default)] room: Option<String> },
+    /// Votes for option `option` (from 0) of poll `target`, or with `remove`, takes it back.
+    Vote { target: String, option: usize, #[serde(default)] remove: bool, #[serde(default)] room: Option<String> },
+    Join { ticket: String },
+    Host,
+    #[serde(alias = "list_peers", alias = "list-peers")]
+    Peers { #[serde(default)] room: Option<String> },
+    Leave { room: String },
+    /// Wipes a room we host for everyone in it, and leaves it.
+    Destroy { room: String },
+    /// Tells every room we're `online`, `away` or `busy`, with a word on why.
+    Status { status: presence::Presence, #[serde(default)] text: String },
+    /// Repeats `ready` for every open room, for clients attaching to a daemon.
+    Rooms,
+    Quit,
+}
+
+/// Where a session's events go: stdout in `pipe` mode, every attached client for the daemon.
+pub type Out<'a> = dyn Fn(Value) + Send + Sync + 'a;
+
+/// Writes one event as a line of JSON on stdout.
+fn stdout(event: Value) {
+    println!("{}", event);
+}
+
+/// `out`, with the `ref` of the command being answered added to every event, so a client can
+/// tell which of its commands an event is the answer to.
+fn answering<'a>(out: &'a Out<'a>, reference: &'a Option<Value>) -> impl Fn(Value) + Send + Sync + 'a {
+    move |mut event| {
+        if let Some(reference) = reference {
+            event["ref"] = reference.clone();
+        }
+        out(event)
+    }
+}
+
+fn error(out: &Out, message: impl std::fmt::Display) {
+    out(json!({ "event": "error", "message": message.to_string() }));
+}
+
+fn find(rooms: &[rooms::Room], room: Option<&str>) -> Option<usize> {
+    match room {
+        None => (!rooms.is_empty()).then_some(0),
+        Some(prefix) => rooms.iter().position(|r| r.topic.to_string().starts_with(prefix)),
+    }
+}
+
+fn ready(out: &Out, room: &rooms::Room, node_id: iroh::NodeId) {
+    out(json!({
+        "event": "ready", "room": room.topic.to_string(), "ticket": room.ticket, "node_id": node_id.to_string(),
+        "name": room.info.name, "topic": room.info.topic,
+    }));
+}
+
+/// Applies one gossip event to `room` and reports it.
+fn handle_gossip(out: &Out, hooks: &Hooks, endpoint: &Endpoint, room: &mut rooms::Room, event: Event) {
+    let topic = room.topic;
+    match event {
+        Event::Gossip(GossipEvent::Received(msg)) => {
+            for event in room.receive(msg, &HashSet::new(), None) {
+                let mut json = event.to_json(topic);
+                match event {
+                    ChatEvent::Message { urgent, mentions_me, .. } => hooks.message(&json, mentions_me || urgent),
+                    ChatEvent::Other { message: Message::Image { .. } | Message::FileOffer { .. } | Message::Poll { .. } | Message::Location { .. }, .. } => {
+                        hooks.message(&json, false)
+                    }
+                    ChatEvent::Peer { old: None, .. } => hooks.join(&json),
+                    ChatEvent::Redeemed { node, .. } => {
+                        json["address"] = json!(whereabouts::address(endpoint, node).map(|a| a.to_string()));
+                    }
+                    // Scripts only see what happens while they're here, so old messages can't retrigger them.
+                    ChatEvent::Other { message: Message::HistoryRequest {} | Message::History { .. }, .. } => continue,
+                    _ => {}
+                }
+                out(json);
+            }
+        }
+        Event::Gossip(GossipEvent::Joined(peers)) => room.neighbors.extend(peers),
+        Event::Gossip(GossipEvent::NeighborUp(id)) => {
+            room.neighbors.insert(id);
+            room.introduce();
+            out(ChatEvent::NeighborUp(id).to_json(topic));
+        }
+        Event::Gossip(GossipEvent::NeighborDown(id)) => {
+            room.neighbors.remove(&id);
+            out(ChatEvent::NeighborDown(id).to_json(topic));
+        }
+        Event::Lagged => out(json!({ "event": "lagged", "room": topic.to_string() })),
+    }
+}
+
+/// Runs without a TUI: newline-delimited JSON events on stdout, JSON commands on stdin.
+/// Stdin closing ends the session like `{"cmd":"quit"}`.
+pub async fn run(endpoint: Endpoint, gossip: Gossip, channel: rooms::Channel, my_name: String, hooks: &Hooks) -> Result<()> {
+    let (lines_tx, lines) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        let mut stdin = BufReader::new(tokio::io::stdin()).lines();
+        while let Ok(Some(line)) = stdin.next_line().await {
+            if lines_tx.send(line).is_err() {
+                break;
+            }
+        }
+    });
+    serve(endpoint, gossip, Some(channel), my_name, lines, hooks, &stdout).await
+}
+
+/// The session behind `pipe` and the daemon: runs JSON command lines from `lines` against
+/// the open rooms and reports everything to `out` (and `hooks`), until `quit` or `lines` closes.
+pub async fn serve(
+    endpoint: Endpoint,
+    gossip: Gossip,
+    channel: Option<rooms::Channel>,
+    my_name: String,
+    mut lines: mpsc::UnboundedReceiver<String>,
+    hooks: &Hooks,
+    out: &Out<'_>,
+) -> Result<()> {
+    let node_id = endpoint.node_id();
+    let (events_tx, mut events) = mpsc::unbounded_channel::<(TopicId, Event)>();
+    // With the `ref` of the `join` or `host` that opened it.
+    let (opened_tx, mut opened) = mpsc::unbounded_channel::<(Option<Value>, Result<rooms::Channel>)>();
+    let mut rooms = Vec::new();
+    let mut status = presence::Status::default();
+    if let Some(channel) = channel {
+        rooms.push(rooms::Room::spawn(channel, my_name.clone(), notify::Alert::Off, false, events_tx.clone()));
+        ready(out, &rooms[0], node_id);
+    }
+
+    loop {
+        tokio::select! {
+            Some((topic, event)) = events.recv() => {
+                if let Some(room) = rooms.iter_mut().find(|r| r.topic == topic) {
+                    handle_gossip(out, hooks, &endpoint, room, event);
+                }
+                rooms.retain(|room| !room.destroyed());
+            }
+
+            Some((reference, result)) = opened.recv() => {
+                let out: &Out = &answering(out, &reference);
+                match result {
+                    Ok(channel) => match rooms.iter().position(|r| r.topic == channel.topic) {
+                        // Joining a room we're already in just confirms it.
+                        Some(i) => ready(out, &rooms[i], node_id),
+                        None => {
+                            let mut room = rooms::Room::spawn(channel, my_name.clone(), notify::Alert::Off, false, events_tx.clone());
+                            room.set_status(status.clone());
+                            rooms.push(room);
+                            if let Some(room) = rooms.last() {
+                                ready(out, room, node_id);
+                            }
+                        }
+                    },
+                    Err(err) => error(out, format!("could not open room: {}", err)),
+                }
+            }
+
+            line = lines.recv() => {
+                let Some(line) = line else { break };
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let mut command = match serde_json::from_str::<Value>(&line) {
+                    Ok(command) => command,
+                    Err(err) => {
+                        error(out, format!("bad command: {}", err));
+                        continue;
+                    }
+                };
+                let reference = command.as_object_mut().and_then(|command| command.remove("ref"));
+                let out: &Out = &answering(out, &reference);
+                let command = match serde_json::from_value::<Command>(command) {
+                    Ok(command) => command,
+                    Err(err) => {
+                        error(out, format!("bad command: {}", err));
+                        continue;
+                    }
+                };
+                match command {
+                    Command::Send { text, .. } if text.len() > chunks::MAX_TEXT => error(out, "message too long"),
+                    Command::Send { ttl: Some(ttl), .. } if !(1..=expiry::LONGEST).contains(&ttl) => error(out, "ttl is 1 second to a week"),
+                    Command::Send { text, room, thread, urgent, ttl, forwarded } => match find(&rooms, room.as_deref()) {
+                        Some(i) => {
+                            let id = new_id();
+                            let rich = rich::parse(&text, &rooms[i].peer_names, node_id, &my_name);
+                            broadcast(&rooms[i].sender, &Message::Chat { text, id: id.clone(), reply: None, thread, urgent, rich, ttl, forwarded }).await;
+                            out(json!({ "event": "sent", "room": rooms[i].topic.to_string(), "id": id, "neighbors": rooms[i].neighbors.len() }));
+                        }
+                        None => error(out, "no such room"),
+                    },
+                    Command::Poll { question, options, minutes, room } => match (find(&rooms, room.as_deref()), polls::sanitize(&question, &options)) {
+                        (Some(i), Some((question, options))) => {
+                            let id = new_id();
+                            let open = minutes.map_or(polls::DEFAULT, |m| chrono::Duration::minutes(m.into()));
+                            let closes = chrono::Utc::now() + open;
+                            broadcast(&rooms[i].sender, &Message::Poll { id: id.clone(), question, options, closes }).await;
+                            out(json!({ "event": "sent", "room": rooms[i].topic.to_string(), "id": id, "neighbors": rooms[i].neighbors.len() }));
+                        }
+                        (None, _) => error(out, "no such room"),
+                        (_, None) => error(out, format!("a poll needs a question and 2 to {} options", polls::MAX_OPTIONS)),
+                    },
+                    Command::Location { lat, lon, label, room } => match (find(&rooms, room.as_deref()), location::Location::new(lat, lon)) {
+                        (Some(i), Some(_)) => {
+                            let id = new_id();
+                            broadcast(&rooms[i].sender, &Message::Location { id: id.clone(), lat, lon, label: location::label(&label) }).await;
+                            out(json!({ "event": "sent", "room": rooms[i].topic.to_string(), "id": id, "neighbors": rooms[i].neighbors.len() }));
+                        }
+                        (None, _) => error(out, "no such room"),
+                        (_, None) => error(out, "lat must be within ±90 and lon within ±180"),
+                    },
+                    Command::Announce { text, .. } if text.len() > chunks::MAX_TEXT => error(out, "message too long"),
+                    Command::Announce { text, pin, room } => match find(&rooms, room.as_deref()) {
+                        Some(i) if !rooms[i].role(&node_id).moderates() => error(out, "only the host and moderators can announce"),
+                        Some(i) => {
+                            let id = new_id();
+                            broadcast(&rooms[i].sender, &Message::Announcement { id: id.clone(), text: text.clone() }).await;
+                            if pin {
+                                let quote = Quote::new(id.clone(), my_name.clone(), &text);
+                                broadcast(&rooms[i].sender, &Message::Pin { quote, remove: false }).await;
+                            }
+                            out(json!({ "event": "sent", "room": rooms[i].topic.to_string(), "id": id, "neighbors": rooms[i].neighbors.len() }));
+                        }
+                        None => error(out, "no such room"),
+                    },
+                    Command::Broadcast { on, room } => match find(&rooms, room.as_deref()) {
+                        Some(i) if rooms[i].host != node_id => error(out, "only the host can make this a broadcast room"),
+                        Some(i) => {
+                            let mode = moderation::mode(endpoint.secret_key(), rooms[i].topic, on);
+                            broadcast(&rooms[i].sender, &mode).await;
+                            if let Message::Mode { at, signature, .. } = mode {
+                                rooms[i].set_mode(on, at, signature);
+                            }
+                            out(json!({ "event": "mode", "room": rooms[i].topic.to_string(), "broadcast": on }));
+                        }
+                        None => error(out, "no such room"),
+                    },
+                    Command::Extension { kind, .. } if !protocol::extension(&kind) => error(out, "extension types are named namespace/type"),
+                    Command::Extension { body, .. } if body.to_string().len() > chunks::MAX_TEXT => error(out, "message too long"),
+                    Command::Extension { kind, body, room } => match find(&rooms, room.as_deref()) {
+                        Some(i) => {
+                            let id = new_id();
+                            broadcast(&rooms[i].sender, &Message::Extension { id: id.clone(), kind, body: body.to_string() }).await;
+                            out(json!({ "event": "sent", "room": rooms[i].topic.to_string(), "id": id, "neighbors": rooms[i].neighbors.len() }));
+                        }
+                        None => error(out, "no such room"),
+                    },
+                    Command::Redact { target, room } => match find(&rooms, room.as_deref()) {
+                        Some(i) => {
+                            broadcast(&rooms[i].sender, &Message::Redact { target }).await;
+                            out(json!({ "event": "sent", "room": rooms[i].topic.to_string(), "neighbors": rooms[i].neighbors.len() }));
+                        }
+                        None => error(out, "no such room"),
+                    },
+                    Command::Vote { target, option, remove, room } => match find(&rooms, room.as_deref()) {
+                        Some(i) => {
+                            broadcast(&rooms[i].sender, &Message::Vote { target, option, remove }).await;
+                            out(json!({ "event": "sent", "room": rooms[i].topic.to_string(), "neighbors": rooms[i].neighbors.len() }));
+                        }
+                        None => error(out, "no such room"),
+                    },
+                    Command::Join { ticket } => {
+                        let (iroh, tx) = (transport::Iroh::new(&endpoint, &gossip), opened_tx.clone());
+                        let known = rooms::topic(&ticket).ok().filter(|t| rooms.iter().any(|r| r.topic == *t));
+                        match known {
+                            Some(topic) => {
+                                if let Some(room) = rooms.iter().find(|r| r.topic == topic) {
+                                    ready(out, room, node_id);
+                                }
+                            }
+                            None => {
+                                let reference = reference.clone();
+                                tokio::spawn(async move {
+                                    let _ = tx.send((reference, rooms::join(&iroh, &ticket).await));
+                                });
+                            }
+                        }
+                    }
+                    Command::Host => {
+                        let _ = opened_tx.send((reference.clone(), rooms::host(&transport::Iroh::new(&endpoint, &gossip), "").await));
+                    }
+                    Command::Peers { room } => match find(&rooms, room.as_deref()) {
+                        Some(i) => {
+                            let room = &rooms[i];
+                            let peers: Vec<Value> = room.peers().into_iter()
+                                .map(|(id, name)| {
+                                    let status = room.statuses.get(&id).cloned().unwrap_or_default();
+                                    let info = room.protocols.get(&id);
+                                    json!({
+                                        "node": id.to_string(), "name": name, "neighbor": room.neighbors.contains(&id), "status": status.presence, "text": status.text,
+                                        "client": info.and_then(|info| info.client.clone()), "features": info.map(|info| info.features.clone()),
+                                    })
+                                })
+                                .collect();
+                            out(json!({ "event": "peers", "room": room.topic.to_string(), "peers": peers }));
+                        }
+                        None => error(out, "no such room"),
+                    },
+                    Command::Leave { room } => match find(&rooms, Some(&room)) {
+                        Some(i) => {
+                            let room = rooms.remove(i);
+                            out(json!({ "event": "left", "room": room.topic.to_string() }));
+                        }
+                        None => error(out, "no such room"),
+                    },
+                    Command::Destroy { room } => match find(&rooms, Some(&room)) {
+                        Some(i) if rooms[i].host != node_id => error(out, "only the host can destroy a room"),
+                        Some(i) => {
+                            let close = moderation::close(endpoint.secret_key(), rooms[i].topic);
+                            broadcast(&rooms[i].sender, &close).await;
+                            let room = rooms.remove(i);
+                            out(json!({ "event": "destroyed", "room": room.topic.to_string() }));
+                        }
+                        None => error(out, "no such room"),
+                    },
+                    Command::Status { status: presence, text } => {
+                        status = presence::Status::received(presence, &text);
+                        for room in &mut rooms {
+                            room.set_status(status.clone());
+                        }
+                    }
+                    Command::Rooms => {
+                        for room in &rooms {
+                            ready(out, room, node_id);
+                        }
+                    }
+                    Command::Quit => break,
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// `ghost send`: posts one message to an already-joined channel, then lingers so the
+/// neighbors can pass it on before the connection drops. Gossip has no acknowledgements,
+/// so "delivered" means handed to at least one connected neighbor.
+pub async fn send(channel: rooms::Channel, my_name: String, text: String, linger: u64, urgent: bool) -> Result<()> {
+    let neighbors = channel.neighbors.len();
+    if neighbors == 0 {
+        anyhow::bail!("no peers reachable in this room");
+    }
+    // Introduce ourselves first so the message isn't shown as from "Unknown".
+    let claim = names::claim(&channel.key, channel.topic, &my_name);
+    broadcast(&channel.sender, &Message::AboutMe { name: my_name, protocol: Some(protocol::Info::ours()), claim: Some(claim) }).await;
+    let id = new_id();
+    // Without having heard anyone's name, mentions couldn't be pinned to anyone, so the
+    // formatting is left to the receivers.
+    broadcast(&channel.sender, &Message::Chat { text, id: id.clone(), reply: None, thread: None, urgent, rich: Vec::new(), ttl: None, forwarded: None }).await;
+    tokio::time::sleep(std::time::Duration::from_secs(linger)).await;
+    println!("Sent {} to {} neighbor(s)", id, neighbors);
+    Ok(())
+}
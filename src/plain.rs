@@ -0,0 +1,93 @@
+use std::{collections::HashMap, io::IsTerminal, sync::Mutex};
+
+use anyhow::Result;
+use iroh::Endpoint;
+use iroh_gossip::net::Gossip;
+use serde_json::{json, Value};
+use tokio::{io::{AsyncBufReadExt, BufReader}, sync::mpsc};
+
+use crate::{clock, hooks::Hooks, i18n::t, pipe, rooms};
+
+/// Whether the full-screen interface can work here: both ends of stdio are a terminal,
+/// and one that understands cursor movement.
+pub fn supported() -> bool {
+    let dumb = std::env::var("TERM").is_ok_and(|term| term == "dumb");
+    std::io::stdin().is_terminal() && std::io::stdout().is_terminal() && !dumb
+}
+
+/// A line the user typed, as a `pipe` command.
+fn command(line: &str) -> Option<Value> {
+    let Some(rest) = line.strip_prefix('/') else {
+        return Some(json!({ "cmd": "send", "text": line }));
+    };
+    match rest.split_whitespace().next().unwrap_or_default() {
+        "quit" | "q" => Some(json!({ "cmd": "quit" })),
+        "peers" => Some(json!({ "cmd": "peers" })),
+        "ticket" => Some(json!({ "cmd": "rooms" })),
+        "help" => {
+            println!("{}", t!("plain-help"));
+            None
+        }
+        name => {
+            println!("{}", t!("plain-unknown", name = name));
+            None
+        }
+    }
+}
+
+/// Prints one session event as a line of text; the rest are left to `pipe` mode.
+fn show(names: &Mutex<HashMap<String, String>>, event: Value) {
+    let text = |key: &str| event[key].as_str().unwrap_or_default().to_string();
+    let time = clock::now().format("%H:%M");
+    let mut names = names.lock().unwrap_or_else(|e| e.into_inner());
+    let line = match event["event"].as_str().unwrap_or_default() {
+        "ready" => t!("plain-ready", ticket = text("ticket")),
+        "peer" => {
+            let line = match names.get(&text("node")) {
+                Some(old) => t!("peer-renamed", old = old.as_str(), name = text("name")),
+                None => t!("plain-joined", name = text("name")),
+            };
+            names.insert(text("node"), text("name"));
+            line
+        }
+        "message" => format!("[{}] {}: {}", time, text("sender"), text("text").replace('\n', "\n    ")),
+        "image" => format!("[{}] {}", time, t!("plain-image", sender = text("sender"), name = text("name"))),
+        "file" => format!("[{}] {}", time, t!("plain-file", sender = text("sender"), name = text("name"))),
+        "edit" => {
+            let sender = names.get(&text("from")).cloned().unwrap_or_else(|| t!("unknown-peer"));
+            format!("[{}] {}", time, t!("plain-edit", sender = sender, text = text("text")))
+        }
+        "peers" => {
+            let peers: Vec<&str> = event["peers"].as_array().into_iter().flatten().filter_map(|p| p["name"].as_str()).collect();
+            if peers.is_empty() { t!("no-peers") } else { t!("plain-peers", names = peers.join(", ")) }
+        }
+        "lagged" => t!("lagged"),
+        "error" => text("message"),
+        _ => return,
+    };
+    println!("{}", line);
+}
+
+/// The chat without the full-screen interface, for pipes, CI logs and dumb terminals:
+/// messages are printed one per line, and every line read from stdin is sent (or run, if
+/// it's one of the few `/` commands that make sense here). Stdin closing ends the session.
+pub async fn run(endpoint: Endpoint, gossip: Gossip, channel: rooms::Channel, my_name: String, hooks: &Hooks) -> Result<()> {
+    println!("{}", t!("plain-help"));
+    let (lines_tx, lines) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        let mut stdin = BufReader::new(tokio::io::stdin()).lines();
+        while let Ok(Some(line)) = stdin.next_line().await {
+            let line = line.trim_end();
+            if line.trim().is_empty() {
+                continue;
+            }
+            if let Some(command) = command(line) {
+                if lines_tx.send(command.to_string()).is_err() {
+                    break;
+                }
+            }
+        }
+    });
+    let names = Mutex::new(HashMap::new());
+    pipe::serve(endpoint, gossip, Some(channel), my_name, lines, hooks, &move |event| show(&names, event)).await
+}
@@ -0,0 +1,189 @@
+//! Features that live outside the core, like auto-translation or logging, hooked into
+//! what happens in a room. Plugins are compiled in and registered at startup, or, with the
+//! `plugins` feature, loaded from shared libraries in the `plugins` config directory.
+
+// What the hooks are handed, so a plugin needn't depend on iroh itself.
+pub use iroh::NodeId;
+pub use iroh_gossip::proto::TopicId;
+
+/// Bumped whenever `Plugin` or `Declaration` change, so an old library isn't loaded.
+pub const API: u32 = 2;
+
+/// This `ghost_messaging`'s version, which a plugin library has to have been built against.
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// A message from someone else, as a plugin sees it.
+pub struct Incoming<'a> {
+    pub room: TopicId,
+    pub id: &'a str,
+    pub from: NodeId,
+    pub sender: &'a str,
+    pub text: &'a str,
+    pub mentions_me: bool,
+}
+
+/// What a plugin wants done in reply.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action {
+    /// A line in the room that only we see, like a translation.
+    Show(String),
+    /// A message posted to the room as us.
+    Send(String),
+}
+
+/// Hooks into a room's life. Every method has a default that does nothing, so a plugin only
+/// writes the ones it needs.
+pub trait Plugin: Send {
+    /// Shown in `/help` and when it's loaded.
+    fn name(&self) -> &str;
+
+    /// What we show of a message from someone else, or of their edit to one: its text,
+    /// changed or not, or `None` to hide it. Runs before `on_message_in`, which sees the result.
+    fn filter_in(&mut self, _room: TopicId, text: String) -> Option<String> {
+        Some(text)
+    }
+
+    /// A message from someone else arrived.
+    fn on_message_in(&mut self, _message: &Incoming) -> Vec<Action> {
+        Vec::new()
+    }
+
+    /// We're about to post `text`: return it, changed or not, or `None` to not send it.
+    fn on_message_out(&mut self, _room: TopicId, text: String) -> Option<String> {
+        Some(text)
+    }
+
+    /// A peer introduced itself in a room for the first time.
+    fn on_peer_join(&mut self, _room: TopicId, _node: NodeId, _name: &str) -> Vec<Action> {
+        Vec::new()
+    }
+
+    /// The slash commands it adds, without the `/`, each with a line for `/help`. Built-in
+    /// commands of the same name win.
+    fn commands(&self) -> Vec<(String, String)> {
+        Vec::new()
+    }
+
+    /// One of its `commands` was typed, with everything after the name in `args`.
+    fn command(&mut self, _room: TopicId, _name: &str, _args: &str) -> Vec<Action> {
+        Vec::new()
+    }
+}
+
+/// What a plugin library exports as `GHOST_PLUGIN`, through `declare_plugin!`.
+#[repr(C)]
+pub struct Declaration {
+    pub api: u32,
+    /// The `VERSION` it was built against.
+    pub version: &'static str,
+    pub make: fn() -> Box<dyn Plugin>,
+}
+
+/// Exports a plugin from a `cdylib` crate for the `plugins` directory. It has to be built
+/// with the same Rust compiler and `ghost_messaging` version as the client loading it.
+///
+/// ```ignore
+/// ghost_messaging::declare_plugin!(|| Box::new(Shouter));
+/// ```
+#[macro_export]
+macro_rules! declare_plugin {
+    ($make:expr) => {
+        #[no_mangle]
+        pub static GHOST_PLUGIN: $crate::plugins::Declaration =
+            $crate::plugins::Declaration { api: $crate::plugins::API, version: $crate::plugins::VERSION, make: $make };
+    };
+}
+
+/// The plugins in use, called in the order they were registered.
+#[derive(Default)]
+pub struct Registry {
+    plugins: Vec<Box<dyn Plugin>>,
+    /// Kept open for as long as the plugins they made; declared after them so they're
+    /// dropped last.
+    #[cfg(feature = "plugins")]
+    libraries: Vec<libloading::Library>,
+}
+
+impl Registry {
+    pub fn register(&mut self, plugin: Box<dyn Plugin>) {
+        tracing::info!(name = plugin.name(), "plugin registered");
+        self.plugins.push(plugin);
+    }
+
+    /// Loads every shared library in `dir`, returning those that couldn't be with why. A
+    /// missing directory is no plugins.
+    #[cfg(feature = "plugins")]
+    pub fn load(&mut self, dir: &std::path::Path) -> Vec<(std::path::PathBuf, String)> {
+        let Ok(entries) = std::fs::read_dir(dir) else { return Vec::new() };
+        let mut failed = Vec::new();
+        let mut paths: Vec<_> = entries.flatten().map(|e| e.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == std::env::consts::DLL_EXTENSION))
+            .collect();
+        paths.sort();
+        for path in paths {
+            if let Err(err) = self.open(&path) {
+                tracing::warn!(%err, path = %path.display(), "could not load plugin");
+                failed.push((path, err));
+            }
+        }
+        failed
+    }
+
+    #[cfg(feature = "plugins")]
+    fn open(&mut self, path: &std::path::Path) -> Result<(), String> {
+        // SAFETY: running a library's initialisers and reading its declaration is only as safe
+        // as the library; the plugins directory is the user's own, like their shell's rc files.
+        let library = unsafe { libloading::Library::new(path) }.map_err(|e| e.to_string())?;
+        let declaration = unsafe { library.get::<*const Declaration>(b"GHOST_PLUGIN\0") }.map_err(|e| e.to_string())?;
+        let declaration = unsafe { &**declaration };
+        if declaration.api != API {
+            return Err(format!("plugin API {}, this client has {}", declaration.api, API));
+        }
+        if declaration.version != VERSION {
+            return Err(format!("built for {}, this client is {}", declaration.version, VERSION));
+        }
+        let plugin = (declaration.make)();
+        self.register(plugin);
+        self.libraries.push(library);
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.plugins.is_empty()
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.plugins.iter().map(|p| p.name()).collect()
+    }
+
+    /// `text` passed through every plugin's `filter_in`; `None` once one of them hides it.
+    pub fn filter_in(&mut self, room: TopicId, text: String) -> Option<String> {
+        self.plugins.iter_mut().try_fold(text, |text, p| p.filter_in(room, text))
+    }
+
+    pub fn message_in(&mut self, message: &Incoming) -> Vec<Action> {
+        self.plugins.iter_mut().flat_map(|p| p.on_message_in(message)).collect()
+    }
+
+    /// `text` passed through every plugin in turn; `None` once one of them holds it back.
+    pub fn message_out(&mut self, room: TopicId, text: String) -> Option<String> {
+        self.plugins.iter_mut().try_fold(text, |text, p| p.on_message_out(room, text))
+    }
+
+    pub fn peer_join(&mut self, room: TopicId, node: NodeId, name: &str) -> Vec<Action> {
+        self.plugins.iter_mut().flat_map(|p| p.on_peer_join(room, node, name)).collect()
+    }
+
+    /// Every plugin command, as `/name` with its description.
+    pub fn commands(&self) -> Vec<(String, String)> {
+        self.plugins.iter().flat_map(|p| p.commands()).map(|(name, desc)| (format!("/{}", name), desc)).collect()
+    }
+
+    /// Runs `input` if it's a plugin's command, by the first plugin with one of that name.
+    pub fn command(&mut self, room: TopicId, input: &str) -> Option<Vec<Action>> {
+        let rest = input.trim().strip_prefix('/')?;
+        let (name, args) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+        let plugin = self.plugins.iter_mut().find(|p| p.commands().iter().any(|(n, _)| n == name))?;
+        Some(plugin.command(room, name, args.trim()))
+    }
+}
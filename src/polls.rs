@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use iroh::NodeId;
+
+use crate::i18n::t;
+
+/// How long a `/poll` takes votes when it doesn't say.
+pub const DEFAULT: chrono::Duration = chrono::Duration::hours(1);
+/// The longest a poll may stay open; a later closing time from a peer is cut to this.
+const LONGEST: chrono::Duration = chrono::Duration::days(7);
+/// Number keys 1..=9 vote, so no more options than that.
+pub const MAX_OPTIONS: usize = 9;
+const MAX_QUESTION: usize = 200;
+const MAX_OPTION: usize = 80;
+/// Cells in a full result bar.
+const BAR: usize = 12;
+
+/// A poll posted in a room, and the votes on it so far. The question is the message text.
+pub struct Poll {
+    pub options: Vec<String>,
+    pub closes: DateTime<Utc>,
+    /// One option per voter; voting again moves the vote.
+    pub votes: HashMap<NodeId, usize>,
+}
+
+/// `30s`, `10m`, `2h` or `1d`, for how long a poll stays open.
+pub fn duration(s: &str) -> Option<chrono::Duration> {
+    let split = s.find(|c: char| !c.is_ascii_digit())?;
+    let n: i64 = s[..split].parse().ok().filter(|&n| n > 0)?;
+    let duration = match &s[split..] {
+        "s" => chrono::Duration::seconds(n),
+        "m" => chrono::Duration::minutes(n),
+        "h" => chrono::Duration::hours(n),
+        "d" => chrono::Duration::days(n),
+        _ => return None,
+    };
+    (duration <= LONGEST).then_some(duration)
+}
+
+/// `text` on one line and cut to `max` characters.
+fn clean(text: &str, max: usize) -> String {
+    text.chars().map(|c| if c.is_control() { ' ' } else { c }).collect::<String>().trim().chars().take(max).collect()
+}
+
+/// The question and options as they should go out or be shown, or `None` if there aren't
+/// two to nine options left once cleaned up.
+pub fn sanitize(question: &str, options: &[String]) -> Option<(String, Vec<String>)> {
+    let question = clean(question, MAX_QUESTION);
+    let options: Vec<String> = options.iter().map(|o| clean(o, MAX_OPTION)).filter(|o| !o.is_empty()).collect();
+    (!question.is_empty() && (2..=MAX_OPTIONS).contains(&options.len())).then_some((question, options))
+}
+
+impl Poll {
+    /// A poll received now, closing at `closes` but no later than `LONGEST` from now.
+    pub fn new(options: Vec<String>, closes: DateTime<Utc>) -> Self {
+        Poll { options, closes: closes.min(Utc::now() + LONGEST), votes: HashMap::new() }
+    }
+
+    pub fn open(&self) -> bool {
+        Utc::now() < self.closes
+    }
+
+    /// Records `who`'s vote for `option`, or with `remove` takes it back. Returns false for
+    /// votes after closing or for options the poll doesn't have.
+    pub fn vote(&mut self, who: NodeId, option: usize, remove: bool) -> bool {
+        if !self.open() || option >= self.options.len() {
+            return false;
+        }
+        match remove {
+            true if self.votes.get(&who) == Some(&option) => { self.votes.remove(&who); }
+            true => return false,
+            false => { self.votes.insert(who, option); }
+        }
+        true
+    }
+
+    pub fn counts(&self) -> Vec<usize> {
+        let mut counts = vec![0; self.options.len()];
+        for &option in self.votes.values() {
+            counts[option] += 1;
+        }
+        counts
+    }
+
+    /// The lines under the question: each option with its bar, and our pick marked.
+    pub fn lines(&self, me: &NodeId) -> Vec<String> {
+        let total = self.votes.len();
+        let width = self.options.iter().map(|o| o.chars().count()).max().unwrap_or(0);
+        self.options.iter().zip(self.counts()).enumerate().map(|(i, (option, count))| {
+            let mark = if self.votes.get(me) == Some(&i) { '●' } else { '○' };
+            let filled = (count * BAR).checked_div(total).unwrap_or(0);
+            format!("{} {} {:<width$} {}{} {}", mark, i + 1, option, "█".repeat(filled), "░".repeat(BAR - filled), count, width = width)
+        }).collect()
+    }
+
+    /// How many have voted, and how long it stays open.
+    pub fn status(&self) -> String {
+        match self.open() {
+            true => t!("poll-open", votes = self.votes.len(), left = left(self.closes - Utc::now())),
+            false => t!("poll-closed", votes = self.votes.len()),
+        }
+    }
+}
+
+/// Roughly how long is left: `45s`, `12m`, `3h`, `2d`.
+fn left(duration: chrono::Duration) -> String {
+    let secs = duration.num_seconds().max(0);
+    match secs {
+        s if s < 60 => format!("{}s", s),
+        s if s < 3600 => format!("{}m", s / 60),
+        s if s < 86400 => format!("{}h", s / 3600),
+        s => format!("{}d", s / 86400),
+    }
+}
@@ -0,0 +1,61 @@
+use ratatui::style::Color;
+use serde::{Deserialize, Serialize};
+
+use crate::i18n::t;
+
+/// Longest status text kept from the wire, in characters.
+const MAX_TEXT: usize = 80;
+
+/// Whether someone is around to answer.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Presence {
+    #[default]
+    Online,
+    Away,
+    Busy,
+}
+
+impl Presence {
+    /// The colour of their dot in the sidebar.
+    pub fn color(self) -> Color {
+        match self {
+            Presence::Online => Color::Green,
+            Presence::Away => Color::Yellow,
+            Presence::Busy => Color::Red,
+        }
+    }
+
+    pub fn name(self) -> String {
+        match self {
+            Presence::Online => t!("presence-online"),
+            Presence::Away => t!("presence-away"),
+            Presence::Busy => t!("presence-busy"),
+        }
+    }
+}
+
+/// What a peer says about being around: set with `/away`, `/busy` and `/back`, and
+/// passed on in `Status`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Status {
+    pub presence: Presence,
+    /// A word on why, like "lunch"; may be empty.
+    pub text: String,
+}
+
+impl Status {
+    /// What a peer sent, its text on one line and cut to `MAX_TEXT`.
+    pub fn received(presence: Presence, text: &str) -> Self {
+        let text = text.trim().chars().map(|c| if c.is_control() { ' ' } else { c }).take(MAX_TEXT).collect();
+        Status { presence, text }
+    }
+
+    /// `away: lunch`, or just `away`.
+    pub fn describe(&self) -> String {
+        match self.text.is_empty() {
+            true => self.presence.name(),
+            false => format!("{}: {}", self.presence.name(), self.text),
+        }
+    }
+}
@@ -0,0 +1,216 @@
+use std::time::Duration;
+
+use anyhow::{bail, Result};
+use iroh_gossip::proto::TopicId;
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+
+use crate::markup;
+
+/// Pages are only read this far; titles and descriptions live in `<head>`.
+const MAX_BYTES: usize = 256 * 1024;
+const TIMEOUT: Duration = Duration::from_secs(5);
+const MAX_TITLE: usize = 120;
+const MAX_DESCRIPTION: usize = 300;
+
+/// What a link in a message is about, fetched by the sender so nobody else's client has to
+/// make a request to the site.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Preview {
+    pub url: String,
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+}
+
+impl Preview {
+    /// The host the link points at, shown under the card.
+    pub fn site(&self) -> &str {
+        let rest = self.url.split_once("://").map_or(self.url.as_str(), |(_, rest)| rest);
+        rest.split(['/', '?', '#']).next().unwrap_or(rest)
+    }
+}
+
+/// A finished lookup: the preview for message `id` in `topic`.
+pub struct Fetched {
+    pub topic: TopicId,
+    pub id: String,
+    pub preview: Preview,
+}
+
+/// Fetches previews for links we send, reporting the ones that found something.
+pub struct Fetcher {
+    client: reqwest::Client,
+    done: mpsc::UnboundedSender<Fetched>,
+}
+
+impl Fetcher {
+    pub fn new(done: mpsc::UnboundedSender<Fetched>) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .user_agent(concat!("ghostterm/", env!("CARGO_PKG_VERSION")))
+            .timeout(TIMEOUT)
+            .build()?;
+        Ok(Fetcher { client, done })
+    }
+
+    /// Looks up the first link in `text`, the message `id` we just sent, in the background.
+    /// Pages that fail to load or say nothing about themselves are dropped quietly.
+    pub fn fetch(&self, topic: TopicId, id: String, text: &str) {
+        let Some(range) = markup::urls(text).into_iter().next() else { return };
+        let (url, client, done) = (text[range].to_string(), self.client.clone(), self.done.clone());
+        tokio::spawn(async move {
+            match get(&client, &url).await {
+                Ok(Some(preview)) => {
+                    let _ = done.send(Fetched { topic, id, preview });
+                }
+                Ok(None) => {}
+                Err(err) => tracing::debug!(%err, url, "no link preview"),
+            }
+        });
+    }
+}
+
+async fn get(client: &reqwest::Client, url: &str) -> Result<Option<Preview>> {
+    let mut response = client.get(url).send().await?.error_for_status()?;
+    let html = response.headers().get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("text/html") || v.starts_with("application/xhtml"));
+    if !html {
+        bail!("not a web page");
+    }
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        body.extend_from_slice(&chunk);
+        if body.len() >= MAX_BYTES || body.windows(7).any(|w| w.eq_ignore_ascii_case(b"</head>")) {
+            break;
+        }
+    }
+    Ok(parse(&String::from_utf8_lossy(&body), url))
+}
+
+/// The page's `og:`/`twitter:` title and description, falling back to `<title>` and the
+/// plain `description` meta tag.
+fn parse(html: &str, url: &str) -> Option<Preview> {
+    let lower = html.to_ascii_lowercase();
+    let (mut title, mut description) = (None, None);
+    let (mut plain_title, mut plain_description) = (None, None);
+    let mut pos = 0;
+    while let Some(start) = lower[pos..].find("<meta").map(|i| pos + i) {
+        let end = lower[start..].find('>').map_or(html.len(), |i| start + i);
+        let tag = &html[start..end];
+        let key = attribute(tag, "property").or_else(|| attribute(tag, "name")).map(|k| k.to_ascii_lowercase());
+        if let (Some(key), Some(content)) = (key, attribute(tag, "content")) {
+            match key.as_str() {
+                "og:title" | "twitter:title" => { title.get_or_insert(content); }
+                "og:description" | "twitter:description" => { description.get_or_insert(content); }
+                "description" => { plain_description.get_or_insert(content); }
+                _ => {}
+            }
+        }
+        pos = end;
+    }
+    if let Some(start) = lower.find("<title") {
+        let open = lower[start..].find('>').map(|i| start + i + 1);
+        let close = open.and_then(|open| lower[open..].find("</title").map(|i| (open, open + i)));
+        plain_title = close.map(|(open, close)| html[open..close].to_string());
+    }
+    received(Preview {
+        url: url.to_string(),
+        title: entities(&title.or(plain_title).unwrap_or_default()),
+        description: entities(&description.or(plain_description).unwrap_or_default()),
+    })
+}
+
+/// The value of `name="..."` (or single-quoted) in one tag, entities and all.
+fn attribute(tag: &str, name: &str) -> Option<String> {
+    let lower = tag.to_ascii_lowercase();
+    let mut pos = 0;
+    while let Some(at) = lower[pos..].find(name).map(|i| pos + i) {
+        pos = at + name.len();
+        let before = lower[..at].chars().next_back();
+        let rest = lower[pos..].trim_start();
+        if !before.is_some_and(char::is_whitespace) || !rest.starts_with('=') {
+            continue;
+        }
+        let value = tag[tag.len() - rest.len() + 1..].trim_start();
+        let quote = value.chars().next().filter(|c| matches!(c, '"' | '\''))?;
+        let value = &value[1..];
+        return Some(value[..value.find(quote)?].to_string());
+    }
+    None
+}
+
+/// Decodes the handful of entities titles actually use.
+fn entities(text: &str) -> String {
+    text.replace("&quot;", "\"").replace("&#39;", "'").replace("&#x27;", "'").replace("&apos;", "'")
+        .replace("&lt;", "<").replace("&gt;", ">").replace("&nbsp;", " ").replace("&amp;", "&")
+}
+
+/// `text` on one line, whitespace collapsed, cut to `max` characters.
+fn clean(text: &str, max: usize) -> String {
+    let words: Vec<&str> = text.split(|c: char| c.is_whitespace() || c.is_control()).filter(|w| !w.is_empty()).collect();
+    let joined = words.join(" ");
+    match joined.chars().count() > max {
+        true => format!("{}…", joined.chars().take(max - 1).collect::<String>()),
+        false => joined,
+    }
+}
+
+/// A preview fit to show: cleaned up, and `None` without a title or an http(s) link.
+pub fn received(preview: Preview) -> Option<Preview> {
+    let Preview { url, title, description } = preview;
+    let title = clean(&title, MAX_TITLE);
+    let http = url.starts_with("http://") || url.starts_with("https://");
+    (http && !title.is_empty()).then(|| Preview { url, title, description: clean(&description, MAX_DESCRIPTION) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const URL: &str = "https://example.com/a";
+
+    fn card(html: &str) -> Option<(String, String)> {
+        parse(html, URL).map(|p| (p.title, p.description))
+    }
+
+    #[test]
+    fn open_graph_comes_first() {
+        let html = r#"<head><title>Plain</title><meta name="description" content="plain words">
+            <meta property="og:title" content="Graph"><meta name="twitter:description" content="card words"></head>"#;
+        assert_eq!(card(html), Some(("Graph".to_string(), "card words".to_string())));
+    }
+
+    #[test]
+    fn title_tag_when_nothing_else() {
+        let html = "<html><head><TITLE lang=en>Just a &amp; title</TITLE></head>";
+        assert_eq!(card(html), Some(("Just a & title".to_string(), String::new())));
+    }
+
+    #[test]
+    fn single_and_double_quotes() {
+        let html = r#"<meta property='og:title' content='It&#39;s "quoted"'><meta name="description" content='single'>"#;
+        assert_eq!(card(html), Some((r#"It's "quoted""#.to_string(), "single".to_string())));
+    }
+
+    #[test]
+    fn attribute_names_must_stand_alone() {
+        let html = r#"<meta data-name="og:title" data-content="no" name="og:title" content="yes">"#;
+        assert_eq!(card(html).map(|(title, _)| title), Some("yes".to_string()));
+        assert_eq!(attribute(r#"<meta data-content="x">"#, "content"), None);
+    }
+
+    #[test]
+    fn unterminated_tags_give_nothing() {
+        assert_eq!(card(r#"<meta property="og:title" content="never closed"#), None);
+        assert_eq!(card("<title>never closed"), None);
+        assert_eq!(card("<meta"), None);
+    }
+
+    #[test]
+    fn multibyte_text_next_to_tags() {
+        let html = r#"日本<meta property="og:title" content="Café — ünïcode">語<title>ignored</title>"#;
+        assert_eq!(card(html).map(|(title, _)| title), Some("Café — ünïcode".to_string()));
+        assert_eq!(card("<title>Ωmega 🎉</title>").map(|(title, _)| title), Some("Ωmega 🎉".to_string()));
+    }
+}
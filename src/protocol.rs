@@ -0,0 +1,395 @@
+use std::{cmp::Ordering, sync::OnceLock};
+
+use bincode::Options;
+use iroh::{NodeId, SecretKey};
+use iroh_gossip::proto::TopicId;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{chunks, identity, new_id, sequence::Seq, stego, Message};
+
+/// Wire-format version; bump it when a change would confuse clients that don't know it.
+pub const VERSION: u32 = 3;
+
+/// Starts every binary message; JSON ones start with `{`.
+const MAGIC: &[u8; 2] = b"GT";
+/// Nothing we put together is bigger, so no length prefix in a message is trusted beyond it.
+const LIMIT: u64 = chunks::MAX_MESSAGE as u64;
+/// Bodies at least this big are zstd-compressed; below it the frame header eats the gain.
+const COMPRESS_OVER: usize = 512;
+/// The longest extension type name.
+const MAX_KIND: usize = 64;
+
+static JSON: OnceLock<bool> = OnceLock::new();
+
+/// With `json`, sends everything as JSON, as version 1 did, to read along with a packet dump.
+pub fn init(json: bool) {
+    let _ = JSON.set(json);
+}
+
+/// Every `Message` variant this build understands, by its name on the wire; extensions go
+/// by their own names instead.
+pub const TYPES: &[&str] = &["AboutMe", "Chat", "Image", "Edit", "Pin", "Reaction", "NameChange", "Receipt", "HistoryRequest", "History", "FileOffer", "RoomInfo", "Ban", "Role", "Poll", "Vote", "LinkPreview", "Location", "Announcement", "Mode", "Resend", "Resent", "Status", "RoomClose", "Redact", "Redeem"];
+
+/// What this build does beyond understanding `TYPES`: parts of messages older clients
+/// drop without a word (`threads`, `formatting`, `voice`, `urgent`, `expiry` for `/tmp`),
+/// file transfer over iroh-blobs, reactions, and names and messages signed with the peer's key.
+pub const FEATURES: &[&str] = &["blobs", "reactions", "threads", "formatting", "voice", "urgent", "expiry", "signed-names", "signed-messages"];
+
+/// What a client says about itself in `AboutMe`, so peers can explain what they can't read.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Info {
+    pub version: u32,
+    pub types: Vec<String>,
+    /// Its release, like `0.9.2`; clients from before they said are `None`.
+    #[serde(default)]
+    pub client: Option<String>,
+    /// Which of `FEATURES` (or a newer build's) it has.
+    #[serde(default)]
+    pub features: Vec<String>,
+}
+
+impl Info {
+    pub fn ours() -> Self {
+        Info {
+            version: VERSION,
+            types: TYPES.iter().map(|t| t.to_string()).collect(),
+            client: Some(env!("CARGO_PKG_VERSION").to_string()),
+            features: FEATURES.iter().map(|f| f.to_string()).collect(),
+        }
+    }
+
+    /// Our features this peer lacks, so what we use of them goes unseen there. Unknown,
+    /// `None`, for clients too old to say.
+    pub fn lacks(&self) -> Option<Vec<&'static str>> {
+        self.client.as_ref()?;
+        Some(FEATURES.iter().copied().filter(|f| !self.features.iter().any(|theirs| theirs == f)).collect())
+    }
+
+    /// Our message types this peer doesn't know, so won't see.
+    pub fn missing(&self) -> Vec<&'static str> {
+        TYPES.iter().copied().filter(|t| !self.types.iter().any(|theirs| theirs == t)).collect()
+    }
+
+    /// `Greater` for a peer on a newer version (it may send what we can't show), `Less` for
+    /// an older one (it can't show everything we send).
+    pub fn compare(&self) -> Ordering {
+        match self.version.cmp(&VERSION) {
+            Ordering::Equal if !self.missing().is_empty() => Ordering::Less,
+            Ordering::Equal if self.types.iter().any(|t| !TYPES.contains(&t.as_str())) => Ordering::Greater,
+            order => order,
+        }
+    }
+}
+
+/// What a received message turned out to be.
+#[allow(clippy::large_enum_variant)]
+pub enum Received {
+    /// `id` is the envelope's, empty from clients that predate envelope ids; `seq` is
+    /// missing from them and on bookkeeping. `author` signed it, in the room it was decoded
+    /// for; clients before version 3 don't sign.
+    Message { msg: Message, id: String, seq: Option<Seq>, author: Option<NodeId> },
+    /// A type we don't know, or a known one in a shape we can't read, from a newer version.
+    Newer { kind: String, version: u32 },
+    Junk(String),
+}
+
+/// The binary wire format, as version 3 sends it after `MAGIC`. `body` is the message
+/// itself, encoded on its own so that a client without `kind` can still read the rest.
+#[derive(Serialize, Deserialize)]
+struct Envelope {
+    version: u32,
+    kind: String,
+    id: String,
+    parent: Option<String>,
+    seq: Option<Seq>,
+    quiet: bool,
+    /// `body` is zstd-compressed.
+    compressed: bool,
+    body: Vec<u8>,
+    author: Option<Author>,
+}
+
+/// As version 2 sent it, without an author; still read, and taken on trust as from
+/// whoever passed it on, like unsigned names.
+#[derive(Deserialize)]
+struct Unsigned {
+    version: u32,
+    kind: String,
+    id: String,
+    #[allow(dead_code)]
+    parent: Option<String>,
+    seq: Option<Seq>,
+    quiet: bool,
+    compressed: bool,
+    body: Vec<u8>,
+}
+
+impl From<Unsigned> for Envelope {
+    fn from(old: Unsigned) -> Self {
+        let Unsigned { version, kind, id, parent, seq, quiet, compressed, body } = old;
+        Envelope { version, kind, id, parent, seq, quiet, compressed, body, author: None }
+    }
+}
+
+/// Who wrote a message: their signature over it and the room it was sent in, so it can't
+/// be credited to whoever passed it on, or replayed in another room.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Author {
+    pub node: NodeId,
+    pub signature: String,
+}
+
+/// What an author signs: the room, the envelope's id and number, and the message.
+fn author_bytes(topic: TopicId, id: &str, seq: Option<Seq>, msg: &Message) -> anyhow::Result<Vec<u8>> {
+    let mut bytes = b"ghostterm message".to_vec();
+    bytes.extend(topic.as_bytes());
+    bytes.extend(binary().serialize(id)?);
+    bytes.extend(binary().serialize(&seq)?);
+    bytes.extend(binary().serialize(msg)?);
+    Ok(bytes)
+}
+
+impl Author {
+    /// Ours on `msg`, sent in `topic` with envelope id `id` as number `seq`.
+    fn sign(key: &SecretKey, topic: TopicId, id: &str, seq: Option<Seq>, msg: &Message) -> anyhow::Result<Self> {
+        Ok(Author { node: key.public(), signature: identity::sign(key, &author_bytes(topic, id, seq, msg)?) })
+    }
+
+    /// The author, if they did sign `msg` as it arrived in `topic`.
+    fn verify(&self, topic: TopicId, id: &str, seq: Option<Seq>, msg: &Message) -> Option<NodeId> {
+        let bytes = author_bytes(topic, id, seq, msg).ok()?;
+        identity::verify(self.node, &bytes, &self.signature).then_some(self.node)
+    }
+}
+
+/// Compact and not self-describing: varint integers, fields in declaration order, and
+/// enum variants by position, so new `Message` variants only ever go at the end.
+fn binary() -> impl Options {
+    bincode::DefaultOptions::new().with_limit(LIMIT)
+}
+
+/// Whether `kind` names an extension type: `namespace/type`, both parts lowercase ASCII
+/// letters, digits, `.`, `-` or `_`, so it can never be one of `TYPES`.
+pub fn extension(kind: &str) -> bool {
+    let part = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '.' | '-' | '_'));
+    kind.len() <= MAX_KIND && kind.split_once('/').is_some_and(|(namespace, name)| part(namespace) && part(name))
+}
+
+/// `msg`'s name on the wire: one of `TYPES`, or an extension's own.
+pub fn kind(msg: &Message) -> &str {
+    match msg {
+        Message::AboutMe { .. } => "AboutMe",
+        Message::Chat { .. } => "Chat",
+        Message::Image { .. } => "Image",
+        Message::Edit { .. } => "Edit",
+        Message::Pin { .. } => "Pin",
+        Message::Reaction { .. } => "Reaction",
+        Message::RoomInfo { .. } => "RoomInfo",
+        Message::Poll { .. } => "Poll",
+        Message::Vote { .. } => "Vote",
+        Message::Location { .. } => "Location",
+        Message::LinkPreview { .. } => "LinkPreview",
+        Message::NameChange { .. } => "NameChange",
+        Message::Receipt { .. } => "Receipt",
+        Message::HistoryRequest {} => "HistoryRequest",
+        Message::History { .. } => "History",
+        Message::Ban { .. } => "Ban",
+        Message::Role { .. } => "Role",
+        Message::FileOffer { .. } => "FileOffer",
+        Message::Announcement { .. } => "Announcement",
+        Message::Mode { .. } => "Mode",
+        Message::Extension { kind, .. } => kind,
+        Message::Resend { .. } => "Resend",
+        Message::Resent { .. } => "Resent",
+        Message::Status { .. } => "Status",
+        Message::RoomClose { .. } => "RoomClose",
+        Message::Redact { .. } => "Redact",
+        Message::Redeem { .. } => "Redeem",
+    }
+}
+
+/// The id a message carries itself, which the envelope reuses.
+fn own_id(msg: &Message) -> Option<&str> {
+    match msg {
+        Message::Chat { id, .. } | Message::Image { id, .. } | Message::FileOffer { id, .. } | Message::Poll { id, .. }
+        | Message::Location { id, .. } | Message::Announcement { id, .. } | Message::Extension { id, .. } => Some(id.as_str()).filter(|id| !id.is_empty()),
+        _ => None,
+    }
+}
+
+/// The message `msg` refers to, if any: what it replies to (or the thread's root), edits,
+/// reacts to or pins.
+fn parent(msg: &Message) -> Option<&str> {
+    let parent = match msg {
+        Message::Chat { reply: Some(quote), .. } => &quote.id,
+        Message::Chat { thread, .. } => thread.as_ref()?,
+        Message::Edit { target, .. } | Message::Reaction { target, .. } | Message::Vote { target, .. }
+        | Message::LinkPreview { target, .. } | Message::Redact { target } => target,
+        Message::Pin { quote, .. } => &quote.id,
+        Message::Receipt { targets, .. } if targets.len() == 1 => &targets[0],
+        Message::AboutMe { .. } | Message::Image { .. } | Message::NameChange { .. } | Message::Receipt { .. }
+        | Message::HistoryRequest {} | Message::History { .. } | Message::FileOffer { .. } | Message::RoomInfo { .. } | Message::Ban { .. }
+        | Message::Role { .. } | Message::Poll { .. } | Message::Location { .. } | Message::Announcement { .. }
+        | Message::Mode { .. } | Message::Extension { .. } | Message::Resend { .. } | Message::Resent { .. }
+        | Message::Status { .. } | Message::RoomClose { .. } | Message::Redeem { .. } => return None,
+    };
+    Some(parent.as_str()).filter(|p| !p.is_empty())
+}
+
+/// Bookkeeping that a client without the type should drop unannounced, not show as a
+/// message from a newer version.
+fn quiet(msg: &Message) -> bool {
+    matches!(msg, Message::Receipt { .. } | Message::HistoryRequest {} | Message::History { .. } | Message::RoomInfo { .. } | Message::Role { .. }
+        | Message::LinkPreview { .. } | Message::Mode { .. } | Message::Resend { .. } | Message::Resent { .. } | Message::Status { .. }
+        | Message::Redeem { .. })
+}
+
+/// Serializes `msg` in an `Envelope`: the wire version, an id (Chat, Image, FileOffer, Poll
+/// and Location keep their own), the id of the message it refers to, its place among the
+/// sender's, whether it's bookkeeping, and our signature on it for `topic`; a body of
+/// `COMPRESS_OVER` bytes or more is compressed if that makes it smaller. `AboutMe` stays
+/// JSON so clients on version 1 still learn who we are and that we're newer; so does
+/// everything after `init(true)`.
+pub fn encode(msg: &Message, seq: Option<Seq>, key: &SecretKey, topic: TopicId) -> anyhow::Result<Vec<u8>> {
+    let id = own_id(msg).map_or_else(new_id, str::to_string);
+    let author = Author::sign(key, topic, &id, seq, msg)?;
+    if matches!(msg, Message::AboutMe { .. }) || JSON.get() == Some(&true) {
+        return Ok(json(msg, id, seq, author)?);
+    }
+    let mut body = binary().serialize(msg)?;
+    let mut compressed = false;
+    if body.len() >= COMPRESS_OVER {
+        let packed = zstd::bulk::compress(&body, zstd::DEFAULT_COMPRESSION_LEVEL)?;
+        if packed.len() < body.len() {
+            (body, compressed) = (packed, true);
+        }
+    }
+    let envelope = Envelope {
+        version: VERSION,
+        kind: kind(msg).to_string(),
+        id,
+        parent: parent(msg).map(str::to_string),
+        seq,
+        quiet: quiet(msg),
+        compressed,
+        body,
+        author: Some(author),
+    };
+    let mut bytes = MAGIC.to_vec();
+    binary().serialize_into(&mut bytes, &envelope)?;
+    Ok(bytes)
+}
+
+/// The JSON format, with the envelope fields inside the variant's object, as in
+/// `{"Chat":{"v":2,"id":"01J...","parent":"01H...","text":...}}`; `"quiet":true` marks
+/// bookkeeping. Clients from before envelopes ignore the extra fields, where a wrapper
+/// would have broken them.
+fn json(msg: &Message, id: String, seq: Option<Seq>, author: Author) -> serde_json::Result<Vec<u8>> {
+    let mut value = serde_json::to_value(msg)?;
+    if let Some(Value::Object(fields)) = value.as_object_mut().and_then(|m| m.values_mut().next()) {
+        fields.insert("v".to_string(), VERSION.into());
+        if fields.get("id").and_then(Value::as_str).is_none_or(str::is_empty) {
+            fields.insert("id".to_string(), id.into());
+        }
+        fields.insert("author".to_string(), serde_json::to_value(author)?);
+        if let Some(parent) = parent(msg) {
+            fields.insert("parent".to_string(), parent.into());
+        }
+        if let Some(seq) = seq {
+            fields.insert("seq".to_string(), serde_json::to_value(seq)?);
+        }
+        if quiet(msg) {
+            fields.insert("quiet".to_string(), true.into());
+        }
+    }
+    serde_json::to_vec(&value)
+}
+
+/// Parses a message in either format that arrived in `topic`, telling one from a newer
+/// version apart from junk. JSON messages without a version come from clients that predate
+/// it, which spoke version 1; quiet ones are junk to anyone who can't read them. So is a
+/// message whose author's signature doesn't hold.
+pub fn decode(raw: &[u8], topic: TopicId) -> Received {
+    let received = match raw.strip_prefix(MAGIC) {
+        Some(raw) => decode_binary(raw),
+        None => decode_json(raw),
+    };
+    match received {
+        (Received::Message { msg, id, seq, .. }, Some(author)) => match author.verify(topic, &id, seq, &msg) {
+            Some(author) => Received::Message { msg, id, seq, author: Some(author) },
+            None => Received::Junk(format!("{} not signed by {}", kind(&msg), author.node.fmt_short())),
+        },
+        (received, _) => received,
+    }
+}
+
+/// The message, and who says they wrote it.
+fn decode_binary(raw: &[u8]) -> (Received, Option<Author>) {
+    let envelope = match binary().deserialize::<Envelope>(raw) {
+        Ok(envelope) => envelope,
+        Err(err) => match binary().deserialize::<Unsigned>(raw) {
+            Ok(old) => old.into(),
+            Err(_) => return (Received::Junk(err.to_string()), None),
+        },
+    };
+    let Envelope { version, kind: name, id, seq, quiet, compressed, body, author, .. } = envelope;
+    (decode_body(version, name, id, seq, quiet, compressed, body), author)
+}
+
+fn decode_body(version: u32, name: String, id: String, seq: Option<Seq>, quiet: bool, compressed: bool, body: Vec<u8>) -> Received {
+    let body = match compressed {
+        true => match zstd::bulk::decompress(&body, LIMIT as usize) {
+            Ok(body) => body,
+            Err(err) => return Received::Junk(err.to_string()),
+        },
+        false => body,
+    };
+    match binary().deserialize::<Message>(&body) {
+        Ok(Message::Extension { kind, .. }) if !extension(&kind) => Received::Junk(format!("extension named {}", kind)),
+        // Under another name, the position means something else to whoever sent it.
+        Ok(msg) if kind(&msg) == name => Received::Message { msg, id, seq, author: None },
+        _ if !quiet && (version > VERSION || !TYPES.contains(&name.as_str())) => Received::Newer { kind: name, version },
+        Ok(msg) => Received::Junk(format!("{} arrived as {}", name, kind(&msg))),
+        Err(err) => Received::Junk(err.to_string()),
+    }
+}
+
+fn decode_json(raw: &[u8]) -> (Received, Option<Author>) {
+    let value: Value = match serde_json::from_slice(raw) {
+        Ok(value) => value,
+        Err(err) => return (Received::Junk(err.to_string()), None),
+    };
+    let envelope = value.as_object().filter(|m| m.len() == 1).and_then(|m| m.iter().next()).map(|(kind, fields)| {
+        let id = fields.get("id").and_then(Value::as_str).unwrap_or_default().to_string();
+        let quiet = fields.get("quiet").and_then(Value::as_bool).unwrap_or(false);
+        let seq = fields.get("seq").and_then(|seq| serde_json::from_value(seq.clone()).ok());
+        let author = fields.get("author").and_then(|author| serde_json::from_value(author.clone()).ok());
+        (kind.clone(), fields.get("v").and_then(Value::as_u64).map_or(1, |v| v as u32), id, quiet, seq, author)
+    });
+    let received = match serde_json::from_value::<Message>(value) {
+        Ok(Message::Extension { kind, .. }) if !extension(&kind) => Received::Junk(format!("extension named {}", kind)),
+        Ok(msg) => {
+            let (id, seq) = envelope.as_ref().map(|(_, _, id, _, seq, _)| (id.clone(), *seq)).unwrap_or_default();
+            Received::Message { msg, id, seq, author: None }
+        }
+        Err(err) => match &envelope {
+            Some((kind, version, _, false, _, _)) if *version > VERSION || !TYPES.contains(&kind.as_str()) => {
+                Received::Newer { kind: kind.clone(), version: *version }
+            }
+            _ => Received::Junk(err.to_string()),
+        },
+    };
+    (received, envelope.and_then(|(.., author)| author))
+}
+
+/// `ghostterm version`, and with `protocol`, what this build speaks on the wire.
+pub fn report(protocol: bool) {
+    println!("ghostterm {}", env!("CARGO_PKG_VERSION"));
+    if protocol {
+        println!("protocol  {}", VERSION);
+        println!("messages  {}", TYPES.join(", "));
+        println!("features  {}", FEATURES.join(", "));
+        println!("tickets   {}", stego::SCHEMES.join(", "));
+    }
+}
@@ -0,0 +1,44 @@
+use std::collections::HashSet;
+
+use iroh::NodeId;
+use ratatui::prelude::*;
+
+/// What the picker offers, in order; number keys 1..=6 pick directly.
+pub const EMOJI: &[&str] = &["👍", "❤️", "😂", "😮", "😢", "🎉"];
+
+/// One emoji under a message and everyone who reacted with it.
+pub struct Reaction {
+    pub emoji: String,
+    pub from: HashSet<NodeId>,
+}
+
+/// Adds or removes `who`'s `emoji`, keeping first-use order and dropping empty entries.
+pub fn apply(reactions: &mut Vec<Reaction>, emoji: &str, who: NodeId, remove: bool) {
+    match reactions.iter_mut().position(|r| r.emoji == emoji) {
+        Some(i) if remove => {
+            reactions[i].from.remove(&who);
+            if reactions[i].from.is_empty() {
+                reactions.remove(i);
+            }
+        }
+        Some(i) => { reactions[i].from.insert(who); }
+        None if remove => {}
+        None => reactions.push(Reaction { emoji: emoji.to_string(), from: HashSet::from([who]) }),
+    }
+}
+
+/// The counts line under a bubble, with our own reactions highlighted.
+pub fn line(reactions: &[Reaction], me: &NodeId) -> Line<'static> {
+    let mut spans = Vec::new();
+    for r in reactions {
+        let style = if r.from.contains(me) {
+            Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(Color::Gray)
+        };
+        spans.push(Span::styled(format!("{} {}", r.emoji, r.from.len()), style));
+        spans.push(Span::raw("  "));
+    }
+    spans.pop();
+    Line::from(spans)
+}
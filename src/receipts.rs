@@ -0,0 +1,71 @@
+use std::collections::HashSet;
+
+use iroh::NodeId;
+use ratatui::prelude::*;
+
+use crate::{i18n::t, Message};
+
+/// Who has acknowledged one of our messages.
+#[derive(Default)]
+pub struct Receipts {
+    pub delivered: HashSet<NodeId>,
+    pub read: HashSet<NodeId>,
+}
+
+impl Receipts {
+    /// Records `from`'s receipt; one for reading implies delivery.
+    pub fn apply(&mut self, from: NodeId, read: bool) {
+        self.delivered.insert(from);
+        if read {
+            self.read.insert(from);
+        }
+    }
+
+    /// ✓ once anyone has the message, a cyan ✓✓ once anyone has seen it.
+    pub fn mark(&self) -> Option<Span<'static>> {
+        if !self.read.is_empty() {
+            Some(Span::styled(" ✓✓", Style::default().fg(Color::Cyan)))
+        } else if !self.delivered.is_empty() {
+            Some(Span::styled(" ✓", Style::default().fg(Color::DarkGray)))
+        } else {
+            None
+        }
+    }
+
+    /// The same, in words, for the screen-reader layout.
+    pub fn text(&self) -> Option<String> {
+        if !self.read.is_empty() {
+            Some(t!("receipt-read"))
+        } else if !self.delivered.is_empty() {
+            Some(t!("receipt-delivered"))
+        } else {
+            None
+        }
+    }
+}
+
+/// Receipts waiting to go out for one room, so a burst of messages costs one each.
+#[derive(Default)]
+pub struct Outbox {
+    delivered: Vec<String>,
+    read: Vec<String>,
+}
+
+impl Outbox {
+    pub fn push(&mut self, id: &str, read: bool) {
+        let queue = if read { &mut self.read } else { &mut self.delivered };
+        if !queue.iter().any(|queued| queued == id) {
+            queue.push(id.to_string());
+        }
+    }
+
+    /// The queued receipts as messages, emptying the queue.
+    pub fn take(&mut self) -> Vec<Message> {
+        let delivered = std::mem::take(&mut self.delivered);
+        let read = std::mem::take(&mut self.read);
+        [(delivered, false), (read, true)].into_iter()
+            .filter(|(targets, _)| !targets.is_empty())
+            .map(|(targets, read)| Message::Receipt { targets, read })
+            .collect()
+    }
+}
@@ -0,0 +1,171 @@
+use std::collections::HashMap;
+
+use iroh::NodeId;
+use ratatui::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::markup::{self, Block, Styled};
+
+/// A run of text formatted one way. A message's spans, end to end, are what it shows;
+/// `\n` in them breaks the line.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Span {
+    pub text: String,
+    #[serde(default)]
+    pub bold: bool,
+    #[serde(default)]
+    pub italic: bool,
+    /// Inline `code`.
+    #[serde(default)]
+    pub code: bool,
+    /// A line of a fenced code block, shown verbatim on a line of its own.
+    #[serde(default)]
+    pub block: bool,
+    /// Where the text links to.
+    #[serde(default)]
+    pub link: Option<String>,
+    /// Who the text mentions, by node rather than by a name they may have changed since.
+    #[serde(default)]
+    pub mention: Option<NodeId>,
+}
+
+impl Span {
+    fn same_format(&self, other: &Span) -> bool {
+        (self.bold, self.italic, self.code, self.block, &self.link, self.mention)
+            == (other.bold, other.italic, other.code, other.block, &other.link, other.mention)
+    }
+}
+
+/// `text` as the sender's Markdown reads it, with `@name` mentions of `names` (and of us,
+/// as `my_name`) pinned to their nodes, so every client shows the same thing.
+pub fn parse(text: &str, names: &HashMap<NodeId, String>, me: NodeId, my_name: &str) -> Vec<Span> {
+    let mut people: Vec<(NodeId, &str)> = names.iter().map(|(id, name)| (*id, name.as_str())).collect();
+    people.push((me, my_name));
+    let just_names: Vec<&str> = people.iter().map(|(_, name)| *name).collect();
+
+    let mut lines: Vec<Vec<Span>> = Vec::new();
+    for block in markup::markdown(text) {
+        match block {
+            Block::Text(styled) => lines.push(line(&styled, &people, &just_names)),
+            Block::Code(code) => lines.extend(code.into_iter().map(|text| vec![Span { text, block: true, ..Span::default() }])),
+        }
+    }
+
+    let count = lines.len();
+    let mut spans: Vec<Span> = Vec::new();
+    for (i, line) in lines.into_iter().enumerate() {
+        let block = line.first().is_some_and(|s| s.block);
+        let start = spans.len();
+        spans.extend(line.into_iter().filter(|s| !s.text.is_empty()));
+        if i + 1 < count {
+            match spans.get_mut(start..).and_then(<[Span]>::last_mut) {
+                Some(last) => last.text.push('\n'),
+                None => spans.push(Span { text: "\n".to_string(), block, ..Span::default() }),
+            }
+        }
+    }
+    spans
+}
+
+/// One parsed line cut into spans wherever its formatting changes. `markdown` only ever
+/// styles text as bold, italic or code, so the styles translate back exactly.
+fn line(styled: &Styled, people: &[(NodeId, &str)], names: &[&str]) -> Vec<Span> {
+    let text = &styled.text;
+    let links = markup::urls(text);
+    let mentions = markup::mentions(text, names);
+    let mut cuts: Vec<usize> = styled.styles.iter().map(|(range, _)| range.clone())
+        .chain(links.iter().cloned())
+        .chain(mentions.iter().map(|(range, _)| range.clone()))
+        .flat_map(|range| [range.start, range.end])
+        .chain([0, text.len()])
+        .collect();
+    cuts.sort_unstable();
+    cuts.dedup();
+
+    let mut spans: Vec<Span> = Vec::new();
+    for pair in cuts.windows(2) {
+        let within = |range: &std::ops::Range<usize>| range.start <= pair[0] && pair[1] <= range.end;
+        let styles = styled.styles.iter().filter(|(range, _)| within(range)).map(|(_, style)| *style);
+        let mut span = Span { text: text[pair[0]..pair[1]].to_string(), ..Span::default() };
+        for style in styles {
+            span.code |= style == markup::code_style();
+            span.bold |= style.add_modifier.contains(Modifier::BOLD);
+            span.italic |= style.add_modifier.contains(Modifier::ITALIC);
+        }
+        span.link = links.iter().find(|range| within(range)).map(|range| text[range.clone()].to_string());
+        span.mention = mentions.iter().find(|(range, _)| within(range))
+            .and_then(|(_, name)| people.iter().find(|(_, n)| n == name).map(|(id, _)| *id));
+        match spans.last_mut() {
+            Some(last) if last.same_format(&span) => last.text.push_str(&span.text),
+            _ => spans.push(span),
+        }
+    }
+    spans
+}
+
+/// Spans fit to show: links that aren't http(s) lose their target.
+pub fn received(mut spans: Vec<Span>) -> Vec<Span> {
+    for span in &mut spans {
+        span.link = span.link.take().filter(|link| link.starts_with("http://") || link.starts_with("https://"));
+    }
+    spans
+}
+
+/// Whether a message mentions us: by its spans if the sender sent any, otherwise by
+/// looking for `@my_name` in the text.
+pub fn mentions(text: &str, spans: &[Span], me: NodeId, my_name: &str) -> bool {
+    match spans.is_empty() {
+        true => !markup::mentions(text, &[my_name]).is_empty(),
+        false => spans.iter().any(|s| s.mention == Some(me)),
+    }
+}
+
+/// The spans as display blocks, styled the way `chat` styles Markdown, links and mentions.
+pub fn blocks(spans: &[Span], me: NodeId) -> Vec<Block> {
+    let mut lines: Vec<(Styled, bool)> = vec![(Styled { text: String::new(), styles: Vec::new() }, false)];
+    for span in spans {
+        for (i, piece) in span.text.split('\n').enumerate() {
+            if i > 0 {
+                lines.push((Styled { text: String::new(), styles: Vec::new() }, false));
+            }
+            let Some((line, block)) = lines.last_mut() else { continue };
+            // What follows a span's last `\n` may be the start of a line of another kind.
+            *block |= span.block && (i == 0 || !piece.is_empty());
+            let start = line.text.len();
+            line.text.push_str(piece);
+            if let Some(style) = style(span, me).filter(|_| !piece.is_empty()) {
+                line.styles.push((start..line.text.len(), style));
+            }
+        }
+    }
+
+    let mut blocks = Vec::new();
+    for (line, block) in lines {
+        match (block, blocks.last_mut()) {
+            (true, Some(Block::Code(code))) => code.push(line.text),
+            (true, _) => blocks.push(Block::Code(vec![line.text])),
+            (false, _) => blocks.push(Block::Text(line)),
+        }
+    }
+    blocks
+}
+
+fn style(span: &Span, me: NodeId) -> Option<Style> {
+    let mut style = Style::default();
+    if span.code {
+        style = style.patch(markup::code_style());
+    }
+    if span.bold {
+        style = style.add_modifier(Modifier::BOLD);
+    }
+    if span.italic {
+        style = style.add_modifier(Modifier::ITALIC);
+    }
+    if span.link.is_some() {
+        style = style.fg(Color::LightBlue).add_modifier(Modifier::UNDERLINED);
+    }
+    if let Some(node) = span.mention {
+        style = style.fg(if node == me { Color::Yellow } else { Color::Cyan }).add_modifier(Modifier::BOLD);
+    }
+    (style != Style::default()).then_some(style)
+}
@@ -0,0 +1,955 @@
+use std::{collections::{HashMap, HashSet}, ops::RangeInclusive, path::Path, str::FromStr, time::{Duration, Instant}};
+
+use anyhow::{bail, Context, Result};
+use futures_lite::StreamExt;
+use iroh::Endpoint;
+use iroh_gossip::{net::{Event, Gossip}, proto::TopicId};
+use serde::{Deserialize, Serialize};
+use tokio::{sync::mpsc, task::JoinHandle};
+
+use crate::{chunks, client::ChatEvent, expiry, flood, i18n::t, input::Input, location, markup, moderation, names, notify::Alert, polls, presence, preview, protocol, receipts, rich, sequence, stego, store, transport::{Events, Subscription, Transport}, whereabouts, ChatMessage, Message, Origin, Quote, Ticket};
+
+/// A subscribed gossip topic that hasn't been attached to the UI yet.
+pub struct Channel {
+    pub topic: TopicId,
+    /// The node that created the room (first in the ticket).
+    pub host: iroh::NodeId,
+    /// The invite for this room, in `[Ghost:...]` form.
+    pub ticket: String,
+    pub sender: sequence::Sender,
+    pub receiver: Events,
+    /// Who we were connected to on subscribing.
+    pub neighbors: Vec<iroh::NodeId>,
+    /// What the host calls the room; ours to set with `host --room-name`.
+    pub info: Info,
+    /// Ours to start in broadcast mode with `host --broadcast`.
+    pub roles: moderation::Roles,
+    /// Ours, to sign the name we go by here.
+    pub key: iroh::SecretKey,
+}
+
+/// How often one sender's `/urgent` messages may ring through; more often, they arrive as
+/// ordinary messages.
+pub const URGENT_EVERY: Duration = Duration::from_secs(5 * 60);
+
+/// Longest topic line kept from the wire, in characters.
+const MAX_TOPIC: usize = 200;
+
+/// A room's name and topic line, set by its host and passed on in `RoomInfo`.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Info {
+    pub name: String,
+    pub topic: String,
+}
+
+impl Info {
+    /// What a host sent, minus names that wouldn't pass `--name` and control characters.
+    pub fn received(name: &str, topic: &str) -> Self {
+        Info {
+            name: names::parse(name).unwrap_or_default(),
+            topic: topic_line(topic),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.name.is_empty() && self.topic.is_empty()
+    }
+}
+
+/// `topic` on one line, without control characters, cut to `MAX_TOPIC`.
+pub fn topic_line(topic: &str) -> String {
+    topic.trim().chars().map(|c| if c.is_control() { ' ' } else { c }).take(MAX_TOPIC).collect()
+}
+
+/// Handles the TUI needs to open more rooms while running.
+pub struct Net {
+    pub endpoint: Endpoint,
+    pub gossip: Gossip,
+    /// Every room's gossip events, tagged with the room's topic.
+    pub events: mpsc::UnboundedSender<(TopicId, Event)>,
+    /// Results of `/join` and `/host`, which run in the background.
+    pub opened: mpsc::UnboundedSender<Result<Channel>>,
+}
+
+/// Creates a fresh topic with ourselves as the only bootstrap node.
+pub async fn host(transport: &dyn Transport, cover: &str) -> Result<Channel> {
+    let topic = TopicId::from_bytes(rand::random());
+    let mut me = transport.addr().await?;
+    let mut unique_ports = HashSet::new();
+    for addr in &me.direct_addresses { unique_ports.insert(addr.port()); }
+    for port in unique_ports {
+        let localhost = std::net::SocketAddr::from_str(&format!("127.0.0.1:{}", port))?;
+        me.direct_addresses.insert(localhost);
+    }
+
+    let host = me.node_id;
+    let ticket = Ticket { topic, nodes: vec![me] };
+    let ticket = stego::hide(cover, &ticket.to_string());
+    let Subscription { sender, events: receiver, neighbors } = transport.subscribe(topic, vec![], false).await?;
+    let sender = sequence::Sender::new(sender, transport.secret_key().clone(), topic);
+    Ok(Channel { topic, host, ticket, sender, receiver, neighbors, info: Info::default(), roles: Default::default(), key: transport.secret_key().clone() })
+}
+
+/// Decodes a ghost (or raw) ticket, returning it and its `[Ghost:...]` form.
+fn decode(ticket: &str) -> Result<(Ticket, String)> {
+    let decoded = match stego::reveal(ticket) {
+        Ok(s) => s,
+        Err(_) => ticket.to_string(),
+    };
+    let parsed = Ticket::from_str(&decoded).context("Invalid Ticket")?;
+    Ok((parsed, stego::hide("", decoded.trim())))
+}
+
+/// The topic a ticket invites to.
+pub fn topic(ticket: &str) -> Result<TopicId> {
+    Ok(decode(ticket)?.0.topic)
+}
+
+/// Reads a ticket from `path`, or one line of stdin for `-`, since long tickets don't
+/// survive shell quoting well. Binary files (images) are searched for the `[Ghost:...]` marker.
+pub fn read(path: &Path) -> Result<String> {
+    if path == Path::new("-") {
+        let mut line = String::new();
+        std::io::stdin().read_line(&mut line).context("reading the ticket from stdin")?;
+        return Ok(line.trim().to_string());
+    }
+    let bytes = std::fs::read(path).with_context(|| format!("reading {}", path.display()))?;
+    let text = String::from_utf8_lossy(&bytes);
+    Ok(match text.find("[Ghost:") {
+        Some(start) => text[start..].split_inclusive(']').next().unwrap_or_default().to_string(),
+        None => text.trim().to_string(),
+    })
+}
+
+/// Prints what a ticket contains, without connecting to anything.
+pub fn inspect(ticket: &str) -> Result<()> {
+    let (parsed, _) = decode(ticket)?;
+    let field = |label: &str| format!("{}:", t!(label));
+    println!("{:<9}{}", field("ticket-topic"), parsed.topic);
+    println!("{:<9}{}", field("ticket-expires"), t!("ticket-expires-never"));
+    for (i, node) in parsed.nodes.iter().enumerate() {
+        println!();
+        println!("{}", if i == 0 { t!("ticket-node-host", n = i + 1) } else { t!("ticket-node-peer", n = i + 1) });
+        println!("  {:<13}{}", field("ticket-node-id"), node.node_id);
+        println!("  {:<13}{}", field("peer-fingerprint"), crate::identity::fingerprint(&node.node_id));
+        match &node.relay_url {
+            Some(url) => println!("  {:<13}{}", field("ticket-relay"), url),
+            None => println!("  {:<13}{}", field("ticket-relay"), t!("ticket-relay-none")),
+        }
+        for addr in &node.direct_addresses {
+            println!("  {:<13}{}", field("ticket-direct"), addr);
+        }
+    }
+    if parsed.nodes.is_empty() {
+        println!("{}", t!("ticket-no-nodes"));
+    }
+    Ok(())
+}
+
+/// Decodes a ghost (or raw) ticket and joins its topic, giving up after 30 seconds.
+pub async fn join(transport: &dyn Transport, ticket: &str) -> Result<Channel> {
+    let (parsed, ticket) = decode(ticket)?;
+    let Some(host) = parsed.nodes.first().map(|addr| addr.node_id) else { bail!("Invalid Ticket (no nodes)") };
+
+    let connect_future = transport.subscribe(parsed.topic, parsed.nodes, true);
+    let Subscription { sender, events: receiver, neighbors } = match tokio::time::timeout(Duration::from_secs(30), connect_future).await {
+        Ok(res) => res?,
+        Err(_) => bail!("Connection Failed (Timeout)"),
+    };
+
+    let sender = sequence::Sender::new(sender, transport.secret_key().clone(), parsed.topic);
+    crate::broadcast(&sender, &Message::Redeem { relay: transport.home_relay() }).await;
+    Ok(Channel {
+        topic: parsed.topic,
+        host,
+        ticket,
+        sender,
+        receiver,
+        neighbors,
+        info: Info::default(),
+        roles: Default::default(),
+        key: transport.secret_key().clone(),
+    })
+}
+
+/// Everything one tab owns: its history, peers, scroll position and input draft.
+pub struct Room {
+    pub topic: TopicId,
+    pub host: iroh::NodeId,
+    pub ticket: String,
+    pub sender: sequence::Sender,
+    /// Name and topic line, from the host.
+    pub info: Info,
+    pub messages: Vec<ChatMessage>,
+    pub peer_names: HashMap<iroh::NodeId, String>,
+    /// Peers we hold a direct gossip connection to.
+    pub neighbors: HashSet<iroh::NodeId>,
+    /// When each peer was last heard from in this room.
+    pub last_seen: HashMap<iroh::NodeId, Instant>,
+    /// What each peer said it speaks, if it's new enough to say.
+    pub protocols: HashMap<iroh::NodeId, protocol::Info>,
+    /// Message types from a newer version already pointed out, per peer.
+    unsupported: HashSet<(iroh::NodeId, String)>,
+    /// When each sender last got an urgent message through, us included.
+    urgent: HashMap<iroh::NodeId, Instant>,
+    /// When each peer that signs its name signed the one it goes by; their names are never
+    /// taken on trust, so nobody can pass one off as theirs.
+    claims: HashMap<iroh::NodeId, i64>,
+    /// Number of messages hidden below the bottom of the chat pane.
+    pub scroll: usize,
+    /// Arrivals since the user scrolled up, for the "new messages" pill.
+    arrived_below: usize,
+    pub input: Input,
+    /// Set by `r` in selection mode; attached to the next message sent from this tab.
+    pub reply: Option<Quote>,
+    /// Id of our message being edited (Up on an empty input); Enter sends the input as its new text.
+    pub editing: Option<String>,
+    /// The root of the thread open in the chat pane (`t` in selection mode); messages sent
+    /// from this tab go into it.
+    pub thread: Option<String>,
+    /// Messages that arrived while another tab was active.
+    pub unread: usize,
+    pub alert: Alert,
+    /// Whether senders hear when their messages were on our screen here (`/receipts`).
+    pub read_receipts: bool,
+    /// Receipts for the next `send_receipts`.
+    outbox: receipts::Outbox,
+    /// Someone has sent us the history from before we joined; until then, new neighbors are asked.
+    pub backfilled: bool,
+    /// The room's history on disk, with `--history`.
+    pub store: Option<store::Store>,
+    /// Pinned messages, oldest first.
+    pub pins: Vec<Quote>,
+    /// Peers the host or a moderator has kicked or banned.
+    pub bans: moderation::Bans,
+    /// Moderators and read-only peers, as the host has signed them.
+    pub roles: moderation::Roles,
+    /// Parts of messages too big for one gossip message, until the rest arrive.
+    pub chunks: chunks::Chunks,
+    /// How far we've got with each sender's numbered messages, to notice ones that never came.
+    pub numbers: sequence::Numbers,
+    /// Peers that said they're away or busy, or back.
+    pub statuses: HashMap<iroh::NodeId, presence::Status>,
+    /// Ours, as the heartbeat announces it.
+    name: String,
+    key: iroh::SecretKey,
+    /// Our signature on `name`.
+    claim: names::Claim,
+    /// Ours, as the heartbeat repeats it.
+    status: presence::Status,
+    /// Set once the host destroyed the room; it's left, and nothing is sent from here.
+    destroyed: bool,
+    /// The heartbeat, then the event forwarder.
+    tasks: Vec<JoinHandle<()>>,
+}
+
+// --- HEARTBEAT SYSTEM (Fixes "Unknown" Name Bug) ---
+// Sends "AboutMe" every 3 seconds so new peers learn our name immediately.
+// Our status rides along: every time while we're away or busy, and once on starting,
+// which is how peers hear we're back.
+fn heartbeat(sender: sequence::Sender, my_name: String, claim: names::Claim, status: presence::Status) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut first = true;
+        loop {
+            let about = Message::AboutMe { name: my_name.clone(), protocol: Some(protocol::Info::ours()), claim: Some(claim.clone()) };
+            crate::broadcast(&sender, &about).await;
+            if first || status != presence::Status::default() {
+                crate::broadcast(&sender, &Message::Status { presence: status.presence, text: status.text.clone() }).await;
+            }
+            first = false;
+            tokio::time::sleep(Duration::from_secs(3)).await;
+        }
+    })
+}
+
+impl Room {
+    /// Attaches a channel to the UI: starts its heartbeat and forwards its events into `events`.
+    pub fn spawn(channel: Channel, my_name: String, alert: Alert, read_receipts: bool, events: mpsc::UnboundedSender<(TopicId, Event)>) -> Self {
+        let Channel { topic, host, ticket, sender, mut receiver, neighbors, info, roles, key } = channel;
+        let neighbors = neighbors.into_iter().collect();
+
+        let claim = names::claim(&key, topic, &my_name);
+        let heartbeat = heartbeat(sender.clone(), my_name.clone(), claim.clone(), presence::Status::default());
+
+        let forward = tokio::spawn(async move {
+            while let Some(event) = receiver.next().await {
+                match event {
+                    Ok(event) => {
+                        if events.send((topic, event)).is_err() {
+                            break;
+                        }
+                    }
+                    Err(err) => {
+                        tracing::warn!(%err, %topic, "room receiver failed");
+                        break;
+                    }
+                }
+            }
+            tracing::info!(%topic, "left room");
+        });
+        tracing::info!(%topic, %host, "opened room");
+
+        Room {
+            topic,
+            host,
+            ticket,
+            sender,
+            info,
+            messages: Vec::new(),
+            peer_names: HashMap::new(),
+            neighbors,
+            last_seen: HashMap::new(),
+            protocols: HashMap::new(),
+            unsupported: HashSet::new(),
+            urgent: HashMap::new(),
+            claims: HashMap::new(),
+            scroll: 0,
+            arrived_below: 0,
+            input: Input::default(),
+            reply: None,
+            editing: None,
+            thread: None,
+            unread: 0,
+            alert,
+            read_receipts,
+            outbox: receipts::Outbox::default(),
+            backfilled: false,
+            store: None,
+            pins: Vec::new(),
+            bans: Default::default(),
+            roles,
+            chunks: Default::default(),
+            numbers: Default::default(),
+            statuses: HashMap::new(),
+            name: my_name,
+            key,
+            claim,
+            status: presence::Status::default(),
+            destroyed: false,
+            tasks: vec![heartbeat, forward],
+        }
+    }
+
+    /// Announces our new name here and introduces ourselves by it from now on.
+    pub async fn rename(&mut self, old: &str, name: &str) {
+        let claim = names::claim(&self.key, self.topic, name);
+        crate::broadcast(&self.sender, &Message::NameChange { old: old.to_string(), name: name.to_string(), claim: Some(claim.clone()) }).await;
+        self.name = name.to_string();
+        self.claim = claim;
+        self.introduce();
+    }
+
+    /// Short name for tabs and the status bar: the host's name for the room, else the
+    /// start of its topic id.
+    pub fn label(&self) -> String {
+        match self.info.name.is_empty() {
+            true => format!("#{}", &self.topic.to_string()[..8]),
+            false => self.info.name.clone(),
+        }
+    }
+
+    /// Sends the room's roles, standing bans, name and topic to everyone in the background,
+    /// when we're its host; for peers who just turned up.
+    pub fn announce(&self, me: iroh::NodeId) {
+        if me != self.host {
+            return;
+        }
+        let mut messages = self.roles.announcements();
+        messages.extend(self.bans.announcements());
+        if !self.info.is_empty() {
+            messages.push(Message::RoomInfo { name: self.info.name.clone(), topic: self.info.topic.clone() });
+        }
+        let sender = self.sender.clone();
+        tokio::spawn(async move {
+            for msg in &messages {
+                crate::broadcast(&sender, msg).await;
+            }
+        });
+    }
+
+    /// What `node` may do here.
+    pub fn role(&self, node: &iroh::NodeId) -> moderation::Role {
+        match *node == self.host {
+            true => moderation::Role::Host,
+            false => self.roles.get(node),
+        }
+    }
+
+    /// Gives `node` `role` if the host signed it. False if it doesn't hold or is old news.
+    pub fn assign(&mut self, node: iroh::NodeId, role: moderation::Role, at: i64, signature: String) -> bool {
+        self.roles.apply(self.host, self.topic, node, role, at, signature)
+    }
+
+    /// Switches broadcast mode if the host signed it. False if it doesn't hold or is old news.
+    pub fn set_mode(&mut self, broadcast: bool, at: i64, signature: String) -> bool {
+        self.roles.set_mode(self.host, self.topic, broadcast, at, signature)
+    }
+
+    /// Puts `node` out of the room if the host, or a moderator `by` outranking it, signed
+    /// it: drops it from the peer list and its messages from now on. False if the ban doesn't
+    /// hold, or `node` was out already.
+    pub fn ban(&mut self, node: iroh::NodeId, kick: bool, by: Option<iroh::NodeId>, at: i64, signature: String) -> bool {
+        let signer = by.unwrap_or(self.host);
+        if !self.role(&signer).outranks(self.role(&node)) || !self.bans.apply(self.topic, node, kick, signer, by, at, signature) {
+            return false;
+        }
+        self.peer_names.remove(&node);
+        self.protocols.remove(&node);
+        self.last_seen.remove(&node);
+        true
+    }
+
+    /// Wipes the room and leaves it if the host signed its `RoomClose`: its messages, pins
+    /// and peers go, and its history is closed for the caller to delete. False if the
+    /// signature doesn't hold, or it's gone already.
+    pub fn destroy(&mut self, signature: &str) -> bool {
+        if self.destroyed || !moderation::closes(self.host, self.topic, signature) {
+            return false;
+        }
+        for task in &self.tasks {
+            task.abort();
+        }
+        self.sender.close();
+        self.destroyed = true;
+        self.store = None;
+        self.messages.clear();
+        self.pins.clear();
+        self.peer_names.clear();
+        self.neighbors.clear();
+        self.last_seen.clear();
+        self.statuses.clear();
+        self.input = Input::default();
+        self.reply = None;
+        self.editing = None;
+        self.thread = None;
+        self.jump_to_bottom();
+        self.unread = 0;
+        true
+    }
+
+    pub fn destroyed(&self) -> bool {
+        self.destroyed
+    }
+
+    /// Whether an urgent message from `from` keeps to `URGENT_EVERY`, counting it if so.
+    pub fn urgent(&mut self, from: iroh::NodeId) -> bool {
+        let allowed = self.urgent.get(&from).is_none_or(|last| last.elapsed() >= URGENT_EVERY);
+        if allowed {
+            self.urgent.insert(from, Instant::now());
+        }
+        allowed
+    }
+
+    /// Who takes `name`, passed on by `from` with `claim`: whoever signed it, or without a
+    /// signature `from` itself, as older clients are taken on trust. `None` for forgeries,
+    /// names older than the one their signer goes by, and unsigned names for a peer that
+    /// signs them.
+    pub fn claimant(&mut self, from: iroh::NodeId, name: &str, claim: Option<names::Claim>) -> Option<iroh::NodeId> {
+        let Some(claim) = claim else { return (!self.claims.contains_key(&from)).then_some(from) };
+        let fresh = self.claims.get(&claim.node).is_none_or(|at| claim.at >= *at);
+        if !fresh || !claim.valid(self.topic, name) {
+            tracing::debug!(%from, node = %claim.node, name, "rejected a name claim");
+            return None;
+        }
+        self.claims.insert(claim.node, claim.at);
+        Some(claim.node)
+    }
+
+    /// `name`, which `id` goes by, with the start of its key if we or another peer go by
+    /// a name that looks the same, so the two can be told apart.
+    pub fn named(&self, id: &iroh::NodeId, name: &str) -> String {
+        let others = self.peer_names.iter().filter(|(other, _)| *other != id).map(|(_, n)| n.as_str());
+        match names::lookalike(name, std::iter::once(self.name.as_str()).chain(others)) {
+            Some(_) => format!("{} ({})", name, id.fmt_short()),
+            None => name.to_string(),
+        }
+    }
+
+    /// Who sent `msg`, as `named` puts it.
+    pub fn sender(&self, msg: &ChatMessage) -> String {
+        match (&msg.origin, msg.from) {
+            (Origin::Peer, Some(id)) => self.named(&id, &msg.sender),
+            _ => msg.sender.clone(),
+        }
+    }
+
+    /// Whether this is the first message of `kind` from `from` that we couldn't read.
+    pub fn unsupported(&mut self, from: iroh::NodeId, kind: &str) -> bool {
+        self.unsupported.insert((from, kind.to_string()))
+    }
+
+    /// Sends our `AboutMe` (name and protocol) now rather than at the next heartbeat, so a
+    /// peer that just connected learns what we speak straight away.
+    pub fn introduce(&mut self) {
+        if self.destroyed {
+            return;
+        }
+        self.tasks[0].abort();
+        self.tasks[0] = heartbeat(self.sender.clone(), self.name.clone(), self.claim.clone(), self.status.clone());
+    }
+
+    /// Tells the room we're away, busy or back, and keeps telling newcomers.
+    pub fn set_status(&mut self, status: presence::Status) {
+        if status != self.status {
+            self.status = status;
+            self.introduce();
+        }
+    }
+
+    /// Queues a receipt for the peer's message `id`; read ones only if this room sends them.
+    pub fn acknowledge(&mut self, id: &str, read: bool) {
+        if !id.is_empty() && (self.read_receipts || !read) {
+            self.outbox.push(id, read);
+        }
+    }
+
+    /// Sends the queued receipts in the background.
+    pub fn send_receipts(&mut self) {
+        let receipts = self.outbox.take();
+        if receipts.is_empty() {
+            return;
+        }
+        let sender = self.sender.clone();
+        tokio::spawn(async move {
+            for receipt in &receipts {
+                crate::broadcast(&sender, receipt).await;
+            }
+        });
+    }
+
+    /// Asks our neighbors for the messages from before we joined, if we have any yet.
+    pub fn ask_history(&self) {
+        if self.neighbors.is_empty() {
+            return;
+        }
+        let sender = self.sender.clone();
+        tokio::spawn(async move { crate::broadcast_neighbors(&sender, &Message::HistoryRequest {}).await });
+    }
+
+    /// Asks the author of `seq` to send again the messages of theirs in `missed`, in the
+    /// background.
+    pub fn ask_resend(&self, seq: sequence::Seq, missed: RangeInclusive<u64>) {
+        let ask = Message::Resend { node: seq.node, session: seq.session, from: *missed.start(), to: *missed.end() };
+        let sender = self.sender.clone();
+        tokio::spawn(async move { crate::broadcast(&sender, &ask).await });
+    }
+
+    /// Sends again, in the background, what `requester` asked for: what we numbered `numbers`
+    /// in `session`, if we still have it.
+    pub fn resend(&self, requester: iroh::NodeId, session: u32, numbers: RangeInclusive<u64>) {
+        let sender = self.sender.clone();
+        tokio::spawn(async move { sender.resend(requester, session, numbers).await });
+    }
+
+    /// Decodes one gossip message and applies what it changes here: names, roles, bans,
+    /// statuses, the room's name, receipts owed and messages missed. Returns what it means,
+    /// in order, for a frontend to show. The room's own bans and roles apply to everyone;
+    /// `blocked` peers are only heard for what the host or a moderator signed, and `flood`,
+    /// if given, mutes peers posting too fast.
+    ///
+    /// A message counts as its author's, who signed it, whoever passed it on. Clients from
+    /// before version 3 don't sign, so theirs are taken as from the peer that delivered
+    /// them, but only when that peer sent them itself; relayed, nobody can say whose they are.
+    pub fn receive(&mut self, msg: iroh_gossip::net::Message, blocked: &HashSet<iroh::NodeId>, mut flood: Option<&mut flood::Flood>) -> Vec<ChatEvent> {
+        let me = self.key.public();
+        let relay = msg.delivered_from;
+        let mut events = Vec::new();
+        if self.bans.contains(&relay) {
+            return events;
+        }
+        let Some(content) = self.chunks.receive(&msg.content) else { return events };
+        let (decoded, envelope, seq, from) = match protocol::decode(&content, self.topic) {
+            protocol::Received::Message { msg: decoded, id, seq, author } => match author {
+                Some(author) => (decoded, id, seq, author),
+                None if msg.scope.is_direct() => (decoded, id, seq, relay),
+                None => {
+                    tracing::debug!(%relay, kind = protocol::kind(&decoded), "dropped an unsigned message passed on");
+                    return events;
+                }
+            },
+            protocol::Received::Newer { kind, version } => {
+                let from = relay;
+                tracing::debug!(%from, kind, version, "message from a newer version");
+                if !blocked.contains(&from) {
+                    events.push(ChatEvent::Unsupported { from, kind, version });
+                }
+                return events;
+            }
+            protocol::Received::Junk(err) => {
+                tracing::debug!(%err, %relay, "undecodable message");
+                events.push(ChatEvent::Error(format!("undecodable message from {}: {}", relay.fmt_short(), err)));
+                return events;
+            }
+        };
+        if self.bans.contains(&from) {
+            return events;
+        }
+        self.last_seen.insert(from, Instant::now());
+        // Only its author numbers a message.
+        if let Some(seq) = seq.filter(|seq| seq.node == from && seq.node != me && !blocked.contains(&seq.node)) {
+            if let sequence::Arrival::After(missed) = self.numbers.arrive(seq) {
+                events.push(ChatEvent::Gap { node: seq.node, first: *missed.start(), last: *missed.end() });
+                self.ask_resend(seq, missed);
+            }
+        }
+        if let Some(flood) = flood.as_deref_mut().filter(|_| flood::counted(&decoded) && !blocked.contains(&from)) {
+            match flood.post(from) {
+                flood::Verdict::Pass => {}
+                flood::Verdict::Muted => {
+                    events.push(ChatEvent::Muted(from));
+                    return events;
+                }
+                flood::Verdict::Dropped => return events,
+            }
+        }
+        let sender = self.peer_names.get(&from).cloned().unwrap_or_else(|| t!("unknown-peer"));
+        let posts = self.role(&from).posts();
+        let moderator = self.role(&from).moderates();
+        let event = match decoded {
+            Message::Resend { node, session, from: first, to } => {
+                if node == me {
+                    self.resend(from, session, first..=to);
+                }
+                return events;
+            }
+            Message::Resent { messages } => {
+                // Only what we were missing; the rest went round the first time.
+                let missed: Vec<Vec<u8>> = messages.into_iter().filter(|bytes| match protocol::decode(bytes, self.topic) {
+                    protocol::Received::Message { seq: Some(seq), author, .. } if seq.node == author.unwrap_or(from) => {
+                        !matches!(self.numbers.arrive(seq), sequence::Arrival::Again)
+                    }
+                    _ => false,
+                }).collect();
+                for content in missed {
+                    let again = iroh_gossip::net::Message { content: content.into(), scope: msg.scope, delivered_from: from };
+                    events.extend(self.receive(again, blocked, flood.as_deref_mut()));
+                }
+                return events;
+            }
+            Message::Status { presence, text } => {
+                let status = presence::Status::received(presence, &text);
+                if self.statuses.get(&from) == Some(&status) {
+                    return events;
+                }
+                self.statuses.insert(from, status.clone());
+                ChatEvent::Presence { node: from, status }
+            }
+            // Names are kept even for blocked peers, who just aren't heard saying them.
+            Message::AboutMe { name, protocol, claim } => {
+                let Some(node) = self.claimant(from, &name, claim) else { return events };
+                let old = self.named_as(node, &name);
+                if old.is_none() {
+                    self.announce(me);
+                }
+                if let Some(info) = protocol {
+                    if !info.missing().is_empty() && self.protocols.get(&node) != Some(&info) && !blocked.contains(&node) {
+                        events.push(ChatEvent::Outdated { node, info: info.clone() });
+                    }
+                    self.protocols.insert(node, info);
+                }
+                if old.as_ref() == Some(&name) {
+                    return events;
+                }
+                ChatEvent::Peer { node, name, old }
+            }
+            Message::NameChange { old, name, claim } => {
+                let Some(node) = self.claimant(from, &name, claim) else { return events };
+                // Our own record of the old name beats the claimed one.
+                let previous = self.named_as(node, &name);
+                if previous.is_none() {
+                    self.announce(me);
+                }
+                let old = previous.unwrap_or(old);
+                if old == name {
+                    return events;
+                }
+                ChatEvent::Peer { node, name, old: Some(old) }
+            }
+            // Signed by the host, so it counts whoever passed it on, even someone blocked.
+            Message::RoomClose { signature } => {
+                if !self.destroy(&signature) {
+                    return events;
+                }
+                ChatEvent::Destroyed
+            }
+            // Reported even from blocked peers: the host should know who holds the ticket.
+            Message::Redeem { relay } => {
+                if self.host != me || from == me {
+                    return events;
+                }
+                ChatEvent::Redeemed { node: from, relay: whereabouts::relay_name(relay) }
+            }
+            _ if blocked.contains(&from) => return events,
+            // Signed by the host or a moderator, so it counts whoever passed it on.
+            Message::Ban { node, kick, by, at, signature } => {
+                let name = self.peer_names.get(&node).cloned();
+                if !self.ban(node, kick, by, at, signature) {
+                    return events;
+                }
+                ChatEvent::Banned { node, name, kick }
+            }
+            Message::Role { node, role, at, signature } => {
+                if !self.assign(node, role, at, signature.clone()) {
+                    return events;
+                }
+                ChatEvent::Other { id: envelope, from, sender, message: Message::Role { node, role, at, signature } }
+            }
+            Message::Mode { broadcast, at, signature } => {
+                if !self.set_mode(broadcast, at, signature.clone()) {
+                    return events;
+                }
+                ChatEvent::Other { id: envelope, from, sender, message: Message::Mode { broadcast, at, signature } }
+            }
+            Message::Chat { .. } | Message::Image { .. } | Message::FileOffer { .. } | Message::Edit { .. }
+            | Message::Reaction { .. } | Message::Pin { .. } | Message::Poll { .. } | Message::Vote { .. }
+            | Message::LinkPreview { .. } | Message::Location { .. } | Message::Extension { .. } if !posts => return events,
+            Message::Announcement { .. } | Message::RoomInfo { .. } if !moderator => return events,
+            Message::RoomInfo { name, topic } => {
+                // Moderators set the topic line; only the host names the room.
+                let name = if from == self.host { name } else { self.info.name.clone() };
+                let info = Info::received(&name, &topic);
+                let old = std::mem::replace(&mut self.info, info.clone());
+                ChatEvent::Info { info, old }
+            }
+            Message::Chat { text, id, reply, thread, urgent, rich, ttl, forwarded } => {
+                self.acknowledge(&id, false);
+                self.send_receipts();
+                let urgent = urgent && self.urgent(from);
+                let rich = rich::received(rich);
+                let mentions_me = rich::mentions(&text, &rich, me, &self.name);
+                ChatEvent::Message {
+                    id, from, sender, text, rich, reply, thread, urgent, announcement: false, ttl: expiry::received(ttl), forwarded, mentions_me,
+                }
+            }
+            Message::Announcement { id, text } => {
+                self.acknowledge(&id, false);
+                self.send_receipts();
+                let mentions_me = !markup::mentions(&text, &[&self.name]).is_empty();
+                ChatEvent::Message {
+                    id, from, sender, text, rich: Vec::new(), reply: None, thread: None, urgent: false, announcement: true, ttl: None,
+                    forwarded: None, mentions_me,
+                }
+            }
+            Message::Poll { id, question, options, closes } => {
+                let Some((question, options)) = polls::sanitize(&question, &options) else { return events };
+                self.acknowledge(&id, false);
+                self.send_receipts();
+                ChatEvent::Other { id: envelope, from, sender, message: Message::Poll { id, question, options, closes } }
+            }
+            Message::Location { id, lat, lon, label } => {
+                if location::Location::new(lat, lon).is_none() {
+                    return events;
+                }
+                self.acknowledge(&id, false);
+                self.send_receipts();
+                ChatEvent::Other { id: envelope, from, sender, message: Message::Location { id, lat, lon, label: location::label(&label) } }
+            }
+            Message::LinkPreview { target, preview } => {
+                let Some(preview) = preview::received(preview) else { return events };
+                ChatEvent::Other { id: envelope, from, sender, message: Message::LinkPreview { target, preview } }
+            }
+            message @ (Message::Image { .. } | Message::FileOffer { .. } | Message::Extension { .. }) => {
+                if let Message::Image { id, .. } | Message::FileOffer { id, .. } | Message::Extension { id, .. } = &message {
+                    self.acknowledge(id, false);
+                }
+                self.send_receipts();
+                ChatEvent::Other { id: envelope, from, sender, message }
+            }
+            Message::Edit { target, text, rich } => ChatEvent::Edit { id: envelope, target, from, text, rich: rich::received(rich) },
+            Message::Reaction { target, emoji, remove } => ChatEvent::Reaction { id: envelope, target, from, emoji, remove },
+            Message::Redact { target } => ChatEvent::Redact { id: envelope, target, from, moderator },
+            Message::Receipt { targets, read } => ChatEvent::Receipt { targets, from, read },
+            message => ChatEvent::Other { id: envelope, from, sender, message },
+        };
+        events.push(event);
+        events
+    }
+
+    /// Records that `node` goes by `name`, relabelling what it sent if that's new. Returns
+    /// what it went by before, if anything.
+    fn named_as(&mut self, node: iroh::NodeId, name: &str) -> Option<String> {
+        let old = self.peer_names.insert(node, name.to_string());
+        if old.as_deref() != Some(name) {
+            self.keep_peer(node, name);
+            self.relabel(Some(node), name);
+        }
+        old
+    }
+
+    /// Known peers in sidebar order (by name, then id, so the list doesn't reshuffle).
+    pub fn peers(&self) -> Vec<(iroh::NodeId, &str)> {
+        let mut peers: Vec<_> = self.peer_names.iter().map(|(id, name)| (*id, name.as_str())).collect();
+        peers.sort_by(|a, b| a.1.cmp(b.1).then(a.0.cmp(&b.0)));
+        peers
+    }
+
+    pub fn push(&mut self, msg: ChatMessage) {
+        if let Some(store) = &self.store {
+            store.save(&msg);
+        }
+        let shown = self.shows(&msg, |id| self.messages.iter().any(|m| m.id == id));
+        self.messages.push(msg);
+        // Keep the view anchored while the user is reading history.
+        if self.scroll > 0 && shown {
+            self.scroll += 1;
+            self.arrived_below += 1;
+        }
+    }
+
+    /// Indices of the messages the chat pane shows: the open thread's root and replies, or
+    /// everything outside threads. Replies whose root we never saw stay in the main view.
+    pub fn view(&self) -> Vec<usize> {
+        let ids: HashSet<&str> = self.messages.iter().map(|m| m.id.as_str()).filter(|id| !id.is_empty()).collect();
+        (0..self.messages.len()).filter(|&i| self.shows(&self.messages[i], |id| ids.contains(id))).collect()
+    }
+
+    /// Whether `msg` belongs in the chat pane; `known` says if a root id is among our messages.
+    fn shows(&self, msg: &ChatMessage, known: impl Fn(&str) -> bool) -> bool {
+        match (&self.thread, &msg.thread) {
+            (Some(root), thread) => msg.id == *root || thread.as_ref() == Some(root),
+            (None, Some(root)) => !known(root),
+            (None, None) => true,
+        }
+    }
+
+    /// How many replies the thread under message `id` has.
+    pub fn replies(&self, id: &str) -> usize {
+        if id.is_empty() {
+            return 0;
+        }
+        self.messages.iter().filter(|m| m.thread.as_deref() == Some(id)).count()
+    }
+
+    /// Saves the current state of message `id`, after an edit, reaction or receipt.
+    pub fn keep(&self, id: &str) {
+        let Some(store) = &self.store else { return };
+        if let Some(msg) = self.messages.iter().rev().find(|m| !id.is_empty() && m.id == id) {
+            store.save(msg);
+        }
+    }
+
+    /// Deletes message `id` for everyone, if `by` may: its sender, or the host or a moderator
+    /// outranking them. It stays as a "message deleted" placeholder, and is dropped from
+    /// disk, the pins and the quotes in replies. False if it isn't here or `by` may not.
+    pub fn redact(&mut self, id: &str, by: iroh::NodeId) -> bool {
+        let me = self.key.public();
+        let Some(msg) = self.messages.iter().rev().find(|m| !id.is_empty() && m.id == id && m.origin != Origin::System && !m.redacted) else { return false };
+        let Some(author) = (if msg.origin == Origin::Me { Some(me) } else { msg.from }) else { return false };
+        if by != author && !self.role(&by).outranks(self.role(&author)) {
+            return false;
+        }
+        let Some(msg) = self.messages.iter_mut().rev().find(|m| m.id == id) else { return false };
+        let placeholder = t!("message-deleted");
+        msg.rich = vec![rich::Span { text: placeholder.clone(), italic: true, ..Default::default() }];
+        msg.text = placeholder;
+        msg.redacted = true;
+        msg.image = None;
+        msg.file = None;
+        msg.poll = None;
+        msg.preview = None;
+        msg.location = None;
+        msg.reactions.clear();
+        msg.edited = false;
+        msg.repeats = 0;
+        if self.editing.as_deref() == Some(id) {
+            self.editing = None;
+        }
+        if let Some(store) = &self.store {
+            store.delete(id);
+        }
+        self.pins.retain(|pin| pin.id != id);
+        let mut quoting = Vec::new();
+        for msg in &mut self.messages {
+            if let Some(quote) = msg.quote.as_mut().filter(|quote| quote.id == id) {
+                quote.snippet = t!("message-deleted");
+                quoting.push(msg.id.clone());
+            }
+        }
+        for id in &quoting {
+            self.keep(id);
+        }
+        true
+    }
+
+    /// Shows the messages `node` sent (ours, for `None`) under `name`, here and wherever
+    /// they are quoted or pinned.
+    pub fn relabel(&mut self, node: Option<iroh::NodeId>, name: &str) {
+        let mut ids = HashSet::new();
+        for msg in &mut self.messages {
+            let theirs = match node {
+                Some(node) => msg.from == Some(node),
+                None => msg.origin == Origin::Me,
+            };
+            if theirs && msg.sender != name {
+                msg.sender = name.to_string();
+                ids.insert(msg.id.clone());
+            }
+        }
+        ids.remove("");
+        let quotes = self.messages.iter_mut().filter_map(|m| m.quote.as_mut()).chain(&mut self.pins);
+        for quote in quotes.filter(|q| ids.contains(&q.id)) {
+            quote.sender = name.to_string();
+        }
+    }
+
+    pub fn keep_peer(&self, id: iroh::NodeId, name: &str) {
+        if let Some(store) = &self.store {
+            store.peer(id, name);
+        }
+    }
+
+    /// Drops the `/tmp` messages whose time is up, with pins of them and the text replies
+    /// quote from them. Returns the indices they had, in order.
+    pub fn expire(&mut self) -> Vec<usize> {
+        let now = chrono::Utc::now();
+        let gone: Vec<usize> = self.messages.iter().enumerate()
+            .filter(|(_, m)| m.expires.is_some_and(|at| at <= now))
+            .map(|(i, _)| i)
+            .collect();
+        if gone.is_empty() {
+            return gone;
+        }
+        let shown = self.messages.len() - self.scroll.min(self.messages.len());
+        self.scroll -= gone.iter().filter(|i| **i >= shown).count().min(self.scroll);
+        let ids: HashSet<String> = gone.iter().map(|i| self.messages[*i].id.clone()).filter(|id| !id.is_empty()).collect();
+        let mut index = 0;
+        self.messages.retain(|_| {
+            index += 1;
+            gone.binary_search(&(index - 1)).is_err()
+        });
+        self.pins.retain(|pin| !ids.contains(&pin.id));
+        if self.thread.as_ref().is_some_and(|root| ids.contains(root)) {
+            self.thread = None;
+        }
+        let mut quoting = Vec::new();
+        for msg in &mut self.messages {
+            if let Some(quote) = msg.quote.as_mut().filter(|quote| ids.contains(&quote.id)) {
+                quote.snippet = t!("message-expired");
+                quoting.push(msg.id.clone());
+            }
+        }
+        for id in &quoting {
+            self.keep(id);
+        }
+        gone
+    }
+
+    pub fn jump_to_bottom(&mut self) {
+        self.scroll = 0;
+        self.arrived_below = 0;
+    }
+
+    /// Messages that came in while scrolled up and are still below the view.
+    pub fn new_below(&self) -> usize {
+        self.arrived_below.min(self.scroll)
+    }
+}
+
+impl Drop for Room {
+    fn drop(&mut self) {
+        // Dropping the receiver (held by the forwarder) is what leaves the topic.
+        for task in &self.tasks {
+            task.abort();
+        }
+    }
+}
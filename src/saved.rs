@@ -0,0 +1,88 @@
+use std::{collections::BTreeMap, path::{Path, PathBuf}};
+
+use anyhow::{anyhow, Context, Result};
+use iroh::SecretKey;
+use iroh_gossip::proto::TopicId;
+use serde::{Deserialize, Serialize};
+
+use crate::{i18n::t, identity, rooms};
+
+const FILE: &str = "rooms.enc";
+
+/// Room tickets kept under short aliases, encrypted with a key derived from the identity.
+pub struct Saved {
+    path: PathBuf,
+    key: [u8; 32],
+    pub rooms: BTreeMap<String, String>,
+    /// The last name and topic line seen in each saved room, by topic id, for `rooms list`.
+    info: BTreeMap<String, rooms::Info>,
+}
+
+/// What's in the file. Before room names it held only the aliases' tickets.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum File {
+    Current { rooms: BTreeMap<String, String>, info: BTreeMap<String, rooms::Info> },
+    Tickets(BTreeMap<String, String>),
+}
+
+impl Saved {
+    /// The profile's saved rooms; none yet if the file doesn't exist.
+    pub fn load(dir: &Path, identity: &SecretKey) -> Result<Self> {
+        let path = dir.join(FILE);
+        let key = identity::subkey(identity, "ghostterm saved rooms");
+        let file = match std::fs::read_to_string(&path) {
+            Ok(text) => {
+                let data = hex::decode(text.trim()).context("corrupt saved rooms file")?;
+                let plain = identity::open(&key, &data)
+                    .map_err(|_| anyhow!("{} belongs to a different identity", path.display()))?;
+                serde_json::from_slice(&plain).context("corrupt saved rooms file")?
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => File::Tickets(BTreeMap::new()),
+            Err(err) => return Err(err).with_context(|| format!("reading {}", path.display())),
+        };
+        let (rooms, info) = match file {
+            File::Current { rooms, info } => (rooms, info),
+            File::Tickets(rooms) => (rooms, BTreeMap::new()),
+        };
+        Ok(Self { path, key, rooms, info })
+    }
+
+    /// Writes the file, keeping names and topics only for rooms still saved.
+    pub fn save(&self) -> Result<()> {
+        let topics: Vec<String> = self.rooms.values().filter_map(|ticket| rooms::topic(ticket).ok()).map(|t| t.to_string()).collect();
+        let mut info = self.info.clone();
+        info.retain(|topic, _| topics.contains(topic));
+        let plain = serde_json::to_vec(&File::Current { rooms: self.rooms.clone(), info })?;
+        identity::save(&self.path, &hex::encode(identity::seal(&self.key, &plain)))
+    }
+
+    /// The name and topic line last seen in `topic`, if any.
+    pub fn info(&self, topic: TopicId) -> Option<&rooms::Info> {
+        self.info.get(&topic.to_string())
+    }
+
+    /// Keeps `info` for `topic`, returning whether that's news for a saved room.
+    pub fn remember(&mut self, topic: TopicId, info: &rooms::Info) -> bool {
+        let saved = self.rooms.values().any(|ticket| rooms::topic(ticket).ok() == Some(topic));
+        if !saved || self.info(topic) == Some(info) {
+            return false;
+        }
+        self.info.insert(topic.to_string(), info.clone());
+        true
+    }
+
+    /// Drops every alias for a ticket to `topic`, returning how many there were.
+    pub fn forget(&mut self, topic: TopicId) -> usize {
+        let before = self.rooms.len();
+        self.rooms.retain(|_, ticket| rooms::topic(ticket).ok() != Some(topic));
+        before - self.rooms.len()
+    }
+
+    pub fn get(&self, alias: &str) -> Result<&str> {
+        self.rooms
+            .get(alias)
+            .map(String::as_str)
+            .ok_or_else(|| anyhow!(t!("rooms-unknown", alias = alias)))
+    }
+}
@@ -0,0 +1,141 @@
+//! Rhai scripts from the `scripts` config directory, each running as a plugin: automation
+//! like an auto-responder or a dice bot, without building anything.
+//!
+//! A script defines whichever hooks it needs. `on_message(msg)` gets a map with the
+//! message's `room`, `id`, `from`, `sender`, `text` and `mentions_me`; `on_message_out(text)`
+//! returns the text to post, or nothing to hold it back; `on_peer_join(name, node)` runs
+//! when someone new introduces itself; and `on_command(name, args)` runs for the commands
+//! it registers at the top level with `register_command(name, description)`. Any of them
+//! may call `send(text)` to post as us and `show(text)` for a line only we see, and `this`
+//! is a map kept between calls.
+
+use std::{path::{Path, PathBuf}, sync::{Arc, Mutex}};
+
+use rhai::{CallFnOptions, Dynamic, Engine, FuncArgs, Map, Scope, AST};
+
+use crate::{chunks, i18n::t, plugins::{Action, Incoming, NodeId, Plugin, TopicId}};
+
+/// Steps one hook may run before it's stopped, so a loop that never ends can't hang the chat.
+const MAX_OPERATIONS: u64 = 1_000_000;
+
+/// One loaded script.
+pub struct Script {
+    name: String,
+    engine: Engine,
+    ast: AST,
+    scope: Scope<'static>,
+    /// `this` in every hook.
+    state: Dynamic,
+    /// What `send` and `show` asked for during the hook that's running.
+    actions: Arc<Mutex<Vec<Action>>>,
+    commands: Vec<(String, String)>,
+}
+
+impl Script {
+    /// Compiles the script at `path` and runs its top level, which registers its commands.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let name = path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+        let actions = Arc::new(Mutex::new(Vec::new()));
+        let commands = Arc::new(Mutex::new(Vec::new()));
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        engine.set_max_string_size(chunks::MAX_TEXT);
+        // Its output would garble the screen or `pipe`'s events, so it goes to the log.
+        let script = name.clone();
+        engine.on_print(move |text| tracing::info!(script, text, "script"));
+        let script = name.clone();
+        engine.on_debug(move |text, _, pos| tracing::debug!(script, text, %pos, "script"));
+        let sent = actions.clone();
+        engine.register_fn("send", move |text: &str| sent.lock().unwrap_or_else(|e| e.into_inner()).push(Action::Send(text.to_string())));
+        let shown = actions.clone();
+        engine.register_fn("show", move |text: &str| shown.lock().unwrap_or_else(|e| e.into_inner()).push(Action::Show(text.to_string())));
+        let registered = commands.clone();
+        engine.register_fn("register_command", move |name: &str, description: &str| {
+            registered.lock().unwrap_or_else(|e| e.into_inner()).push((name.trim_start_matches('/').to_string(), description.to_string()));
+        });
+        // For dice and coin flips: a whole number from `low` to `high`, both included.
+        engine.register_fn("random", |low: i64, high: i64| if low < high { rand::Rng::gen_range(&mut rand::thread_rng(), low..=high) } else { low });
+
+        let ast = engine.compile_file(path.to_path_buf()).map_err(|e| e.to_string())?;
+        let mut scope = Scope::new();
+        engine.run_ast_with_scope(&mut scope, &ast).map_err(|e| e.to_string())?;
+        let commands = std::mem::take(&mut *commands.lock().unwrap_or_else(|e| e.into_inner()));
+        Ok(Script { name, engine, ast, scope, state: Map::new().into(), actions, commands })
+    }
+
+    /// Runs hook `hook` if the script has one taking `arity` arguments, with what it returned
+    /// and what it asked for. A failure is reported as a line only we see.
+    fn call(&mut self, hook: &str, arity: usize, args: impl FuncArgs) -> (Option<Dynamic>, Vec<Action>) {
+        if !self.ast.iter_functions().any(|f| f.name == hook && f.params.len() == arity) {
+            return (None, Vec::new());
+        }
+        let options = CallFnOptions::new().eval_ast(false).bind_this_ptr(&mut self.state);
+        let result = self.engine.call_fn_with_options::<Dynamic>(options, &mut self.scope, &self.ast, hook, args);
+        let mut actions = std::mem::take(&mut *self.actions.lock().unwrap_or_else(|e| e.into_inner()));
+        match result {
+            Ok(value) => (Some(value), actions),
+            Err(err) => {
+                tracing::warn!(script = %self.name, hook, %err, "script failed");
+                actions.push(Action::Show(t!("script-error", name = self.name.as_str(), err = err.to_string())));
+                (None, actions)
+            }
+        }
+    }
+}
+
+impl Plugin for Script {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn on_message_in(&mut self, message: &Incoming) -> Vec<Action> {
+        let mut msg = Map::new();
+        msg.insert("room".into(), message.room.to_string().into());
+        msg.insert("id".into(), message.id.into());
+        msg.insert("from".into(), message.from.to_string().into());
+        msg.insert("sender".into(), message.sender.into());
+        msg.insert("text".into(), message.text.into());
+        msg.insert("mentions_me".into(), message.mentions_me.into());
+        self.call("on_message", 1, (msg,)).1
+    }
+
+    fn on_message_out(&mut self, _room: TopicId, text: String) -> Option<String> {
+        match self.call("on_message_out", 1, (text.clone(),)) {
+            // A script that doesn't have the hook, or whose hook failed, sends it unchanged.
+            (None, _) => Some(text),
+            (Some(value), _) if value.is_unit() => None,
+            (Some(value), _) => Some(value.into_string().unwrap_or(text)),
+        }
+    }
+
+    fn on_peer_join(&mut self, _room: TopicId, node: NodeId, name: &str) -> Vec<Action> {
+        self.call("on_peer_join", 2, (name.to_string(), node.to_string())).1
+    }
+
+    fn commands(&self) -> Vec<(String, String)> {
+        self.commands.clone()
+    }
+
+    fn command(&mut self, _room: TopicId, name: &str, args: &str) -> Vec<Action> {
+        self.call("on_command", 2, (name.to_string(), args.to_string())).1
+    }
+}
+
+/// Every `.rhai` file in `dir`, in name order, and those that wouldn't load with why. A
+/// missing directory is no scripts.
+pub fn load(dir: &Path) -> (Vec<Script>, Vec<(PathBuf, String)>) {
+    let Ok(entries) = std::fs::read_dir(dir) else { return (Vec::new(), Vec::new()) };
+    let mut paths: Vec<_> = entries.flatten().map(|e| e.path()).filter(|path| path.extension().is_some_and(|ext| ext == "rhai")).collect();
+    paths.sort();
+    let (mut scripts, mut failed) = (Vec::new(), Vec::new());
+    for path in paths {
+        match Script::load(&path) {
+            Ok(script) => scripts.push(script),
+            Err(err) => {
+                tracing::warn!(%err, path = %path.display(), "could not load script");
+                failed.push((path, err));
+            }
+        }
+    }
+    (scripts, failed)
+}
@@ -0,0 +1,70 @@
+use crate::{markup::find_all, ChatMessage};
+
+/// State of the Ctrl+F search bar.
+/// The query is free text, optionally prefixed with `from:<name>` to filter by sender.
+#[derive(Default)]
+pub struct Search {
+    pub query: String,
+    /// Index (into the message history) of the match currently in focus.
+    pub current: Option<usize>,
+}
+
+impl Search {
+    /// Splits the query into the `from:` sender filter and the text term.
+    fn parts(&self) -> (Option<&str>, String) {
+        let mut sender = None;
+        let mut words = Vec::new();
+        for word in self.query.split_whitespace() {
+            match word.strip_prefix("from:") {
+                Some(name) if !name.is_empty() => sender = Some(name),
+                _ => words.push(word),
+            }
+        }
+        (sender, words.join(" "))
+    }
+
+    pub fn term(&self) -> String {
+        self.parts().1
+    }
+
+    /// True if the message passes the `from:` filter (or there is none).
+    pub fn shows(&self, msg: &ChatMessage) -> bool {
+        match self.parts().0 {
+            Some(name) => msg.sender.eq_ignore_ascii_case(name),
+            None => true,
+        }
+    }
+
+    /// Indices of every message matching both the sender filter and the term.
+    pub fn matches(&self, messages: &[ChatMessage]) -> Vec<usize> {
+        let term = self.term();
+        messages.iter().enumerate()
+            .filter(|(_, msg)| self.shows(msg))
+            .filter(|(_, msg)| term.is_empty() || !find_all(&msg.text, &term).is_empty())
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Focuses the newest match. Called whenever the query changes.
+    pub fn reset(&mut self, messages: &[ChatMessage]) {
+        self.current = self.matches(messages).last().copied();
+    }
+
+    /// Moves the focus to the next older match.
+    pub fn older(&mut self, messages: &[ChatMessage]) {
+        let matches = self.matches(messages);
+        self.current = match self.current {
+            Some(cur) => matches.iter().rev().find(|&&i| i < cur).or(matches.first()).copied(),
+            None => matches.last().copied(),
+        };
+    }
+
+    /// Moves the focus to the next newer match.
+    pub fn newer(&mut self, messages: &[ChatMessage]) {
+        let matches = self.matches(messages);
+        self.current = match self.current {
+            Some(cur) => matches.iter().find(|&&i| i > cur).or(matches.last()).copied(),
+            None => matches.last().copied(),
+        };
+    }
+}
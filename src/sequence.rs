@@ -0,0 +1,185 @@
+use std::{
+    collections::{BTreeSet, HashMap, VecDeque},
+    ops::RangeInclusive,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use iroh::{NodeId, SecretKey};
+use iroh_gossip::proto::TopicId;
+use serde::{Deserialize, Serialize};
+
+use crate::{chunks, protocol, transport::Broadcast, Message};
+
+/// How many of our own numbered messages a room keeps to send again, and how far back a
+/// receiver keeps track of what it's missing.
+const KEEP: usize = 256;
+
+/// A `Resend` is small and what answers it up to `chunks::MAX_TEXT`, so each peer gets one
+/// answer this often, and a message sent again doesn't go out again for `RESENT_FOR`.
+const RESEND_EVERY: Duration = Duration::from_secs(10);
+const RESENT_FOR: Duration = Duration::from_secs(60);
+
+/// Where a message stands among its sender's: `number` counts up from 0 within one
+/// `session`, picked at random each time the sender opens the room, so a restart doesn't
+/// look like a repeat.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Seq {
+    pub node: NodeId,
+    pub session: u32,
+    pub number: u64,
+}
+
+/// What gets numbered: messages shown in the chat, not bookkeeping.
+fn numbered(msg: &Message) -> bool {
+    matches!(msg, Message::Chat { .. } | Message::Image { .. } | Message::FileOffer { .. } | Message::Poll { .. }
+        | Message::Location { .. } | Message::Announcement { .. } | Message::Extension { .. })
+}
+
+#[derive(Default)]
+struct Sent {
+    next: u64,
+    /// Encoded, for `resend`; oldest first.
+    kept: VecDeque<(u64, Vec<u8>)>,
+    /// When each number last went out again, and when each peer last had an answer.
+    resent: HashMap<u64, Instant>,
+    asked: HashMap<NodeId, Instant>,
+}
+
+/// A room's gossip sender. Numbers what we post, and keeps the last `KEEP` of it to send
+/// again on request.
+#[derive(Clone)]
+pub struct Sender {
+    /// Shared by every clone; `None` once `close` lets go of the topic.
+    gossip: Arc<Mutex<Option<Arc<dyn Broadcast>>>>,
+    /// Ours, to sign what we send in `topic` with.
+    key: SecretKey,
+    topic: TopicId,
+    session: u32,
+    sent: Arc<Mutex<Sent>>,
+}
+
+impl Sender {
+    pub fn new(gossip: Arc<dyn Broadcast>, key: SecretKey, topic: TopicId) -> Self {
+        Sender { gossip: Arc::new(Mutex::new(Some(gossip))), key, topic, session: rand::random(), sent: Default::default() }
+    }
+
+    /// Sends `msg` to the room, or with `neighbors_only`, to our direct neighbors, who don't
+    /// pass it on.
+    pub async fn send(&self, msg: &Message, neighbors_only: bool) {
+        let seq = numbered(msg).then(|| {
+            let mut sent = self.sent.lock().unwrap_or_else(|e| e.into_inner());
+            sent.next += 1;
+            Seq { node: self.key.public(), session: self.session, number: sent.next - 1 }
+        });
+        let bytes = match protocol::encode(msg, seq, &self.key, self.topic) {
+            Ok(bytes) => bytes,
+            Err(err) => return tracing::error!(%err, "could not encode message"),
+        };
+        if let Some(seq) = seq {
+            let mut sent = self.sent.lock().unwrap_or_else(|e| e.into_inner());
+            if sent.kept.len() >= KEEP {
+                sent.kept.pop_front();
+            }
+            sent.kept.push_back((seq.number, bytes.clone()));
+        }
+        let Some(gossip) = self.gossip.lock().unwrap_or_else(|e| e.into_inner()).clone() else { return };
+        let size = bytes.len();
+        let Some(frames) = chunks::split(bytes) else { return tracing::error!(size, "message too big to send") };
+        for frame in frames {
+            if let Err(err) = gossip.broadcast(frame, neighbors_only).await {
+                return tracing::warn!(%err, "broadcast failed");
+            }
+        }
+    }
+
+    /// Lets go of the topic, here and in every clone: nothing more goes out, and once the
+    /// receiver is dropped too, gossip leaves it.
+    pub fn close(&self) {
+        self.gossip.lock().unwrap_or_else(|e| e.into_inner()).take();
+    }
+
+    /// Sends again, in one `Resent`, what we numbered `numbers` in `session` and still
+    /// have, for `requester`. Gossip drops a message it has passed on before, so the
+    /// originals wouldn't get through a second time. Asking again within `RESEND_EVERY`, or
+    /// for what went out again within `RESENT_FOR`, gets nothing.
+    pub async fn resend(&self, requester: NodeId, session: u32, numbers: RangeInclusive<u64>) {
+        if session != self.session {
+            return;
+        }
+        let now = Instant::now();
+        let mut budget = chunks::MAX_TEXT;
+        let messages: Vec<Vec<u8>> = {
+            let mut sent = self.sent.lock().unwrap_or_else(|e| e.into_inner());
+            let Sent { kept, resent, asked, .. } = &mut *sent;
+            asked.retain(|_, at| now.duration_since(*at) < RESEND_EVERY);
+            resent.retain(|_, at| now.duration_since(*at) < RESENT_FOR);
+            if asked.insert(requester, now).is_some() {
+                return;
+            }
+            let again: Vec<(u64, Vec<u8>)> = kept.iter()
+                .filter(|(number, _)| numbers.contains(number) && !resent.contains_key(number))
+                .take_while(|(_, bytes)| {
+                    let fits = bytes.len() <= budget;
+                    budget = budget.saturating_sub(bytes.len());
+                    fits
+                })
+                .cloned()
+                .collect();
+            again.into_iter().map(|(number, bytes)| {
+                resent.insert(number, now);
+                bytes
+            }).collect()
+        };
+        if !messages.is_empty() {
+            self.send(&Message::Resent { messages }, false).await;
+        }
+    }
+}
+
+struct Stream {
+    session: u32,
+    highest: u64,
+    missing: BTreeSet<u64>,
+}
+
+/// What became of a numbered message.
+pub enum Arrival {
+    New,
+    /// We had it already, as when it's sent again for someone else.
+    Again,
+    /// New, and the numbers before it that never came.
+    After(RangeInclusive<u64>),
+}
+
+/// The numbers we've had from each sender in one room.
+#[derive(Default)]
+pub struct Numbers {
+    streams: HashMap<NodeId, Stream>,
+}
+
+impl Numbers {
+    /// Counts `seq` in. The first we hear of a sender, or of a new session of theirs, is
+    /// where we start; what came before it isn't missing, just from before we were here.
+    pub fn arrive(&mut self, seq: Seq) -> Arrival {
+        let Some(stream) = self.streams.get_mut(&seq.node).filter(|s| s.session == seq.session) else {
+            self.streams.insert(seq.node, Stream { session: seq.session, highest: seq.number, missing: BTreeSet::new() });
+            return Arrival::New;
+        };
+        if seq.number <= stream.highest {
+            return match stream.missing.remove(&seq.number) {
+                true => Arrival::New,
+                false => Arrival::Again,
+            };
+        }
+        let first = (stream.highest + 1).max(seq.number.saturating_sub(KEEP as u64));
+        stream.highest = seq.number;
+        stream.missing.extend(first..seq.number);
+        let oldest = seq.number.saturating_sub(KEEP as u64);
+        stream.missing.retain(|n| *n >= oldest);
+        match first < seq.number {
+            true => Arrival::After(first..=seq.number - 1),
+            false => Arrival::New,
+        }
+    }
+}
@@ -0,0 +1,291 @@
+use std::{ffi::OsString, path::{Path, PathBuf}, process::Command};
+
+use anyhow::{anyhow, bail, Context, Result};
+
+use crate::{config, i18n::t};
+
+/// `ghost service ...`
+#[derive(clap::Subcommand)]
+pub enum Action {
+    /// Start the daemon at login and restart it if it fails (systemd user unit, launchd agent or Windows service)
+    Install {
+        #[arg(short, long, default_value = "Ghost", value_parser = crate::names::parse)]
+        name: String,
+    },
+    /// Stop the daemon and unregister it
+    Uninstall,
+    /// Run the daemon the way the service manager does, logging to `daemon.log` in the config directory
+    Run {
+        #[arg(short, long, default_value = "Ghost", value_parser = crate::names::parse)]
+        name: String,
+    },
+}
+
+/// Where `service run` logs unless `--log-file` says otherwise.
+pub fn default_log(profile: Option<&str>) -> Option<PathBuf> {
+    config::dir(profile).map(|dir| dir.join("daemon.log"))
+}
+
+/// The unit, agent or service name; each profile gets its own.
+fn unit(profile: Option<&str>) -> String {
+    match profile {
+        Some(profile) => format!("ghostterm-{}", profile),
+        None => "ghostterm".to_string(),
+    }
+}
+
+/// The command line the service manager runs.
+fn command_line(profile: Option<&str>, name: &str) -> Result<Vec<OsString>> {
+    let mut args = vec![std::env::current_exe().context("finding this executable")?.into_os_string()];
+    if let Some(profile) = profile {
+        args.extend(["--profile".into(), profile.into()]);
+    }
+    args.extend(["service".into(), "run".into(), "--name".into(), name.into()]);
+    Ok(args)
+}
+
+fn run(program: &str, args: &[&str]) -> Result<()> {
+    let status = Command::new(program).args(args).status().with_context(|| format!("running {}", program))?;
+    if !status.success() {
+        bail!("`{} {}` failed ({})", program, args.join(" "), status);
+    }
+    Ok(())
+}
+
+fn home() -> Result<PathBuf> {
+    std::env::var_os("HOME").map(PathBuf::from).ok_or_else(|| anyhow!("HOME is not set"))
+}
+
+fn write(path: &Path, contents: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, contents).with_context(|| format!("writing {}", path.display()))?;
+    println!("{}", t!("service-wrote", path = path.display().to_string()));
+    Ok(())
+}
+
+// --- SYSTEMD (Linux) ---
+
+fn systemd_path(profile: Option<&str>) -> Result<PathBuf> {
+    let base = std::env::var_os("XDG_CONFIG_HOME").filter(|v| !v.is_empty()).map(PathBuf::from);
+    let base = match base {
+        Some(base) => base,
+        None => home()?.join(".config"),
+    };
+    Ok(base.join("systemd/user").join(format!("{}.service", unit(profile))))
+}
+
+/// One ExecStart word, quoted the way systemd splits them.
+fn systemd_quote(arg: &str) -> String {
+    if arg.is_empty() || arg.contains(|c: char| c.is_whitespace() || matches!(c, '"' | '\\' | '\'' | '%' | '$')) {
+        format!("\"{}\"", arg.replace('\\', "\\\\").replace('"', "\\\"").replace('%', "%%").replace('$', "$$"))
+    } else {
+        arg.to_string()
+    }
+}
+
+fn install_systemd(profile: Option<&str>, name: &str) -> Result<()> {
+    let exec: Vec<String> = command_line(profile, name)?.iter().map(|a| systemd_quote(&a.to_string_lossy())).collect();
+    let unit_file = format!(
+        "[Unit]\nDescription=GhostTerm daemon ({})\nWants=network-online.target\nAfter=network-online.target\n\n\
+         [Service]\nExecStart={}\nRestart=on-failure\nRestartSec=5\n\n[Install]\nWantedBy=default.target\n",
+        profile.unwrap_or("default profile"),
+        exec.join(" "),
+    );
+    write(&systemd_path(profile)?, &unit_file)?;
+    run("systemctl", &["--user", "daemon-reload"])?;
+    run("systemctl", &["--user", "enable", "--now", &format!("{}.service", unit(profile))])?;
+    println!("{}", t!("service-started", unit = unit(profile)));
+    Ok(())
+}
+
+fn uninstall_systemd(profile: Option<&str>) -> Result<()> {
+    run("systemctl", &["--user", "disable", "--now", &format!("{}.service", unit(profile))])?;
+    let path = systemd_path(profile)?;
+    std::fs::remove_file(&path).with_context(|| format!("removing {}", path.display()))?;
+    run("systemctl", &["--user", "daemon-reload"])
+}
+
+// --- LAUNCHD (macOS) ---
+
+fn launchd_label(profile: Option<&str>) -> String {
+    format!("com.{}.daemon", unit(profile))
+}
+
+fn launchd_path(profile: Option<&str>) -> Result<PathBuf> {
+    Ok(home()?.join("Library/LaunchAgents").join(format!("{}.plist", launchd_label(profile))))
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+fn install_launchd(profile: Option<&str>, name: &str) -> Result<()> {
+    let args: String = command_line(profile, name)?
+        .iter()
+        .map(|a| format!("        <string>{}</string>\n", xml_escape(&a.to_string_lossy())))
+        .collect();
+    // KeepAlive restarts it only after a crash, not after `ghostterm attach` sends quit.
+    let plist = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n\
+         <plist version=\"1.0\">\n<dict>\n    <key>Label</key>\n    <string>{}</string>\n    <key>ProgramArguments</key>\n    <array>\n{}    </array>\n\
+         \x20   <key>RunAtLoad</key>\n    <true/>\n    <key>KeepAlive</key>\n    <dict>\n        <key>SuccessfulExit</key>\n        <false/>\n    </dict>\n\
+         \x20   <key>ThrottleInterval</key>\n    <integer>5</integer>\n</dict>\n</plist>\n",
+        launchd_label(profile),
+        args,
+    );
+    let path = launchd_path(profile)?;
+    write(&path, &plist)?;
+    run("launchctl", &["load", "-w", &path.to_string_lossy()])
+}
+
+fn uninstall_launchd(profile: Option<&str>) -> Result<()> {
+    let path = launchd_path(profile)?;
+    run("launchctl", &["unload", "-w", &path.to_string_lossy()])?;
+    std::fs::remove_file(&path).with_context(|| format!("removing {}", path.display()))
+}
+
+// --- WINDOWS SERVICE ---
+
+fn windows_quote(arg: &str) -> String {
+    if arg.contains(' ') {
+        format!("\"{}\"", arg)
+    } else {
+        arg.to_string()
+    }
+}
+
+fn install_windows(profile: Option<&str>, name: &str) -> Result<()> {
+    let service = unit(profile);
+    let bin: Vec<String> = command_line(profile, name)?.iter().map(|a| windows_quote(&a.to_string_lossy())).collect();
+    run("sc.exe", &["create", &service, "start=", "auto", "DisplayName=", "GhostTerm daemon", "binPath=", &bin.join(" ")])?;
+    run("sc.exe", &["failure", &service, "reset=", "86400", "actions=", "restart/5000/restart/5000/restart/60000"])?;
+    // Services run as LocalSystem; point it at this user's config so it keeps this identity.
+    if let Some(appdata) = std::env::var_os("APPDATA") {
+        let key = format!(r"HKLM\SYSTEM\CurrentControlSet\Services\{}", service);
+        run("reg.exe", &["add", &key, "/v", "Environment", "/t", "REG_MULTI_SZ", "/d", &format!("APPDATA={}", appdata.to_string_lossy()), "/f"])?;
+    }
+    run("sc.exe", &["start", &service])
+}
+
+fn uninstall_windows(profile: Option<&str>) -> Result<()> {
+    let service = unit(profile);
+    // Already stopped is fine.
+    let _ = run("sc.exe", &["stop", &service]);
+    run("sc.exe", &["delete", &service])
+}
+
+pub fn install(profile: Option<&str>, name: &str) -> Result<()> {
+    if cfg!(windows) {
+        install_windows(profile, name)
+    } else if cfg!(target_os = "macos") {
+        install_launchd(profile, name)
+    } else {
+        install_systemd(profile, name)
+    }
+}
+
+pub fn uninstall(profile: Option<&str>) -> Result<()> {
+    if cfg!(windows) {
+        uninstall_windows(profile)
+    } else if cfg!(target_os = "macos") {
+        uninstall_launchd(profile)
+    } else {
+        uninstall_systemd(profile)
+    }
+}
+
+#[cfg(windows)]
+pub use windows::dispatch;
+
+/// The Windows service manager can't run an async daemon directly, so `service run` under it
+/// supervises a `ghostterm daemon` child with the same arguments.
+#[cfg(windows)]
+mod windows {
+    use std::{ffi::OsString, path::PathBuf, sync::{mpsc, OnceLock}, time::Duration};
+
+    use anyhow::Result;
+    use windows_service::{
+        define_windows_service,
+        service::{ServiceControl, ServiceControlAccept, ServiceExitCode, ServiceState, ServiceStatus, ServiceType},
+        service_control_handler::{self, ServiceControlHandlerResult},
+        service_dispatcher,
+    };
+
+    static SERVICE: OnceLock<(String, Option<PathBuf>)> = OnceLock::new();
+
+    define_windows_service!(ffi_service_main, service_main);
+
+    /// Hands the process to the service manager if it started us. False when run from a
+    /// console, which then runs the daemon in the foreground instead.
+    pub fn dispatch(profile: Option<&str>, log_file: Option<PathBuf>) -> Result<bool> {
+        let name = super::unit(profile);
+        let _ = SERVICE.set((name.clone(), log_file));
+        match service_dispatcher::start(&name, ffi_service_main) {
+            Ok(()) => Ok(true),
+            // ERROR_FAILED_SERVICE_CONTROLLER_CONNECT
+            Err(windows_service::Error::Winapi(err)) if err.raw_os_error() == Some(1063) => Ok(false),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn service_main(_arguments: Vec<OsString>) {
+        if let Err(err) = supervise() {
+            tracing::error!(%err, "service failed");
+        }
+    }
+
+    fn status(state: ServiceState, exit: u32) -> ServiceStatus {
+        ServiceStatus {
+            service_type: ServiceType::OWN_PROCESS,
+            current_state: state,
+            controls_accepted: if state == ServiceState::Running { ServiceControlAccept::STOP | ServiceControlAccept::SHUTDOWN } else { ServiceControlAccept::empty() },
+            exit_code: ServiceExitCode::Win32(exit),
+            checkpoint: 0,
+            wait_hint: Duration::default(),
+            process_id: None,
+        }
+    }
+
+    fn supervise() -> Result<()> {
+        let (name, log_file) = SERVICE.get().cloned().unwrap_or_default();
+        let (stop_tx, stop) = mpsc::channel();
+        let handle = service_control_handler::register(&name, move |control| match control {
+            ServiceControl::Stop | ServiceControl::Shutdown => {
+                let _ = stop_tx.send(());
+                ServiceControlHandlerResult::NoError
+            }
+            ServiceControl::Interrogate => ServiceControlHandlerResult::NoError,
+            _ => ServiceControlHandlerResult::NotImplemented,
+        })?;
+
+        // Our own arguments, with `service run` swapped for `daemon`.
+        let mut args: Vec<OsString> = std::env::args_os().skip(1).collect();
+        if let Some(i) = args.windows(2).position(|w| w[0] == "service" && w[1] == "run") {
+            args.splice(i..i + 2, [OsString::from("daemon")]);
+        }
+        let mut command = std::process::Command::new(std::env::current_exe()?);
+        command.args(args);
+        if let Some(log_file) = log_file {
+            command.env("GHOST_LOG_FILE", log_file);
+        }
+        let mut child = command.spawn()?;
+        handle.set_service_status(status(ServiceState::Running, 0))?;
+
+        let exit = loop {
+            if stop.recv_timeout(Duration::from_secs(1)).is_ok() {
+                let _ = child.kill();
+                let _ = child.wait();
+                break 0;
+            }
+            if let Some(code) = child.try_wait()? {
+                // A non-zero exit makes the service manager apply its restart actions.
+                break if code.success() { 0 } else { 1 };
+            }
+        };
+        handle.set_service_status(status(ServiceState::Stopped, exit))?;
+        Ok(())
+    }
+}
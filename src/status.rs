@@ -0,0 +1,79 @@
+use std::io::Write;
+
+use ratatui::{prelude::*, widgets::Paragraph};
+
+use crate::{boss, clock, i18n::t, voice, AppState};
+
+/// How much of the room's topic line fits in the status bar, in characters.
+const TOPIC_WIDTH: usize = 40;
+
+/// Renders the one-line status bar along the bottom of the screen.
+pub fn render(frame: &mut Frame, area: Rect, state: &AppState) {
+    let bar = Style::default().bg(Color::Indexed(235)).fg(Color::Gray);
+    let sep = Span::styled(" │ ", bar.fg(Color::DarkGray));
+
+    let room = state.room();
+    let (link, link_color) = if room.neighbors.is_empty() {
+        (t!("status-waiting"), Color::Yellow)
+    } else {
+        (t!("status-connected"), Color::Green)
+    };
+
+    let peers = t!("status-peers", count = room.peer_names.len());
+
+    let mut left = vec![
+        Span::styled(" ● ", bar.fg(link_color)),
+        Span::styled(link, bar.fg(link_color).add_modifier(Modifier::BOLD)),
+        sep.clone(),
+        Span::styled(room.label(), bar.fg(Color::Cyan)),
+        sep.clone(),
+    ];
+    if !room.info.topic.is_empty() {
+        let mut topic: String = room.info.topic.chars().take(TOPIC_WIDTH).collect();
+        if topic.len() < room.info.topic.len() {
+            topic.push('…');
+        }
+        left.extend([Span::styled(topic, bar.fg(Color::White)), sep.clone()]);
+    }
+    if room.roles.broadcast() {
+        left.extend([Span::styled(format!("📢 {}", t!("status-broadcast")), bar.fg(Color::Yellow)), sep.clone()]);
+    }
+    left.extend([
+        Span::styled(peers, bar),
+        sep.clone(),
+        Span::styled("QUIC/TLS 1.3", bar.fg(Color::Green)),
+    ]);
+    if let Some((_, recording)) = &state.recording {
+        let time = voice::clock(recording.elapsed().as_millis() as u32);
+        left.extend([sep, Span::styled(format!("● {}", t!("status-recording", time = time)), bar.fg(Color::Red).add_modifier(Modifier::BOLD))]);
+    }
+    let left = Line::from(left);
+    let right = Line::from(Span::styled(format!("{} ", clock::now().format("%H:%M:%S")), bar)).right_aligned();
+
+    frame.render_widget(Paragraph::new("").style(bar), area);
+    frame.render_widget(left, area);
+    frame.render_widget(right, area);
+}
+
+/// The terminal window title: `ghost — #room (3 unread)`, or the fake prompt's while hidden.
+pub fn window_title(state: &AppState) -> String {
+    if state.disguise.is_some() {
+        return boss::title();
+    }
+    let unread: usize = state.rooms.iter().map(|r| r.unread).sum();
+    t!("window-title", room = state.room().label(), unread = unread)
+}
+
+/// Pushes the current window title onto the terminal's title stack (xterm `CSI 22 t`),
+/// so `restore_title` can put it back on exit. Terminals without a stack ignore this.
+pub fn save_title(out: &mut impl Write) -> std::io::Result<()> {
+    out.write_all(b"\x1b[22;0t")?;
+    out.flush()
+}
+
+pub fn restore_title(out: &mut impl Write) -> std::io::Result<()> {
+    // Blank first, for terminals that can't pop the stack.
+    crossterm::execute!(out, crossterm::terminal::SetTitle(""))?;
+    out.write_all(b"\x1b[23;0t")?;
+    out.flush()
+}
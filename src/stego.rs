@@ -1,5 +1,8 @@
 use anyhow::Result;
 
+/// Ticket encodings `reveal` understands: the `[Ghost:...]` wrapper, and the bare ticket.
+pub const SCHEMES: &[&str] = &["ghost-tag", "raw"];
+
 /// Wraps the ticket in our format: [Ghost:<DATA>]
 /// We no longer use Hex encoding to keep the size small.
 pub fn hide(_cover: &str, secret: &str) -> String {
@@ -0,0 +1,247 @@
+use std::{collections::HashSet, path::{Path, PathBuf}};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use iroh::{NodeId, SecretKey};
+use iroh_gossip::proto::TopicId;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+
+use crate::{i18n::t, identity, location::Location, preview::Preview, reactions::Reaction, rich, ChatMessage, Forwarded, Origin, Quote};
+
+/// How many messages from earlier sessions a room opens with.
+const LOAD: usize = 500;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS messages (id TEXT PRIMARY KEY, time INTEGER NOT NULL, data BLOB NOT NULL);
+    CREATE INDEX IF NOT EXISTS messages_by_time ON messages (time);
+    CREATE TABLE IF NOT EXISTS peers (node TEXT PRIMARY KEY, seen INTEGER NOT NULL, data BLOB NOT NULL);
+";
+
+/// How long `--history` keeps a room's messages on disk.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Retention {
+    Nothing,
+    Days(u32),
+    Forever,
+}
+
+/// Parses `--history`: `none`, `forever`, or a number of days (`30` or `30d`).
+pub fn retention(s: &str) -> Result<Retention, String> {
+    match s.trim().to_lowercase().as_str() {
+        "none" | "0" | "0d" => Ok(Retention::Nothing),
+        "forever" => Ok(Retention::Forever),
+        days => days.strip_suffix('d').unwrap_or(days).parse().map(Retention::Days)
+            .map_err(|_| format!("expected none, forever or a number of days like 30d, not `{}`", s)),
+    }
+}
+
+/// Where each room's database lives, and how it's kept.
+pub struct Archive {
+    dir: PathBuf,
+    /// Set with `--encrypt-history`.
+    key: Option<[u8; 32]>,
+    retention: Retention,
+}
+
+impl Archive {
+    pub fn new(dir: &Path, identity: &SecretKey, retention: Retention, encrypt: bool) -> Self {
+        let key = encrypt.then(|| identity::subkey(identity, "ghostterm history"));
+        Archive { dir: dir.join("history"), key, retention }
+    }
+
+    /// Opens `topic`'s database, dropping what's past the retention. Keeping nothing deletes
+    /// any database an earlier session left.
+    pub fn open(&self, topic: TopicId) -> Result<Option<Store>> {
+        let path = self.dir.join(format!("{}.db", topic));
+        let cutoff = match self.retention {
+            Retention::Nothing => return self.remove(topic).map(|()| None),
+            Retention::Days(days) => Utc::now() - chrono::Duration::days(days.into()),
+            Retention::Forever => DateTime::<Utc>::MIN_UTC,
+        };
+        std::fs::create_dir_all(&self.dir).with_context(|| format!("creating {}", self.dir.display()))?;
+        let db = Connection::open(&path).with_context(|| format!("opening {}", path.display()))?;
+        // Zero what's deleted, so redacted and expired text doesn't linger in free pages.
+        db.pragma_update(None, "secure_delete", true)?;
+        db.execute_batch(SCHEMA)?;
+        let cutoff = cutoff.timestamp_millis();
+        db.execute("DELETE FROM messages WHERE time < ?1", params![cutoff])?;
+        db.execute("DELETE FROM peers WHERE seen < ?1", params![cutoff])?;
+        Ok(Some(Store { db, key: self.key }))
+    }
+
+    /// Deletes `topic`'s database, for a room its host destroyed. Its `Store` must be
+    /// closed first.
+    pub fn remove(&self, topic: TopicId) -> Result<()> {
+        let path = self.dir.join(format!("{}.db", topic));
+        match std::fs::remove_file(&path) {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err).with_context(|| format!("deleting {}", path.display())),
+            _ => Ok(()),
+        }
+    }
+
+    /// For the help overlay's security summary.
+    pub fn summary(&self) -> String {
+        let encrypted = if self.key.is_some() { t!("yes") } else { t!("no") };
+        match self.retention {
+            Retention::Nothing => t!("security-history-value"),
+            Retention::Days(days) => t!("security-history-kept", days = days, encrypted = encrypted),
+            Retention::Forever => t!("security-history-kept", days = 0, encrypted = encrypted),
+        }
+    }
+}
+
+/// A message as kept on disk; the id and time are columns of their own.
+#[derive(Serialize, Deserialize)]
+struct Saved {
+    from: Option<NodeId>,
+    sender: String,
+    text: String,
+    mine: bool,
+    quote: Option<Quote>,
+    #[serde(default)]
+    thread: Option<String>,
+    #[serde(default)]
+    preview: Option<Preview>,
+    #[serde(default)]
+    location: Option<Location>,
+    #[serde(default)]
+    announcement: bool,
+    #[serde(default)]
+    urgent: bool,
+    #[serde(default)]
+    rich: Vec<rich::Span>,
+    edited: bool,
+    #[serde(default)]
+    repeats: u32,
+    #[serde(default)]
+    forwarded: Option<Forwarded>,
+    reactions: Vec<(String, Vec<NodeId>)>,
+    delivered: Vec<NodeId>,
+    read: Vec<NodeId>,
+    relayed: Option<NodeId>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Peer {
+    name: String,
+}
+
+/// One room's history on disk.
+pub struct Store {
+    db: Connection,
+    key: Option<[u8; 32]>,
+}
+
+impl Store {
+    fn seal(&self, value: &impl Serialize) -> Result<Vec<u8>> {
+        let plain = serde_json::to_vec(value)?;
+        Ok(match &self.key {
+            Some(key) => identity::seal(key, &plain),
+            None => plain,
+        })
+    }
+
+    /// Reads a row written by `seal`, or in the clear from before `--encrypt-history`.
+    fn open<T: for<'de> Deserialize<'de>>(&self, data: &[u8]) -> Option<T> {
+        if let Ok(value) = serde_json::from_slice(data) {
+            return Some(value);
+        }
+        identity::open(self.key.as_ref()?, data).ok().and_then(|plain| serde_json::from_slice(&plain).ok())
+    }
+
+    /// Writes `msg`, or its latest state if it was saved before. Local notices, images, file
+    /// offers, polls, `/tmp` messages and deleted ones aren't kept.
+    pub fn save(&self, msg: &ChatMessage) {
+        if msg.origin == Origin::System || msg.image.is_some() || msg.file.is_some() || msg.poll.is_some() || msg.expires.is_some() || msg.redacted || msg.id.is_empty() {
+            return;
+        }
+        let saved = Saved {
+            from: msg.from,
+            sender: msg.sender.clone(),
+            text: msg.text.clone(),
+            mine: msg.origin == Origin::Me,
+            quote: msg.quote.clone(),
+            thread: msg.thread.clone(),
+            preview: msg.preview.clone(),
+            location: msg.location,
+            announcement: msg.announcement,
+            urgent: msg.urgent,
+            rich: msg.rich.clone(),
+            edited: msg.edited,
+            repeats: msg.repeats,
+            forwarded: msg.forwarded.clone(),
+            reactions: msg.reactions.iter().map(|r| (r.emoji.clone(), r.from.iter().copied().collect())).collect(),
+            delivered: msg.receipts.delivered.iter().copied().collect(),
+            read: msg.receipts.read.iter().copied().collect(),
+            relayed: msg.relayed,
+        };
+        let written = self.seal(&saved).and_then(|data| {
+            self.db.execute(
+                "INSERT OR REPLACE INTO messages (id, time, data) VALUES (?1, ?2, ?3)",
+                params![msg.id, msg.time.timestamp_millis(), data],
+            )?;
+            Ok(())
+        });
+        if let Err(err) = written {
+            tracing::warn!(%err, id = msg.id, "could not save message");
+        }
+    }
+
+    /// Forgets message `id`, deleted for everyone.
+    pub fn delete(&self, id: &str) {
+        if let Err(err) = self.db.execute("DELETE FROM messages WHERE id = ?1", params![id]) {
+            tracing::warn!(%err, id, "could not delete message");
+        }
+    }
+
+    /// Remembers the name `id` goes by here.
+    pub fn peer(&self, id: NodeId, name: &str) {
+        let written = self.seal(&Peer { name: name.to_string() }).and_then(|data| {
+            self.db.execute(
+                "INSERT OR REPLACE INTO peers (node, seen, data) VALUES (?1, ?2, ?3)",
+                params![id.to_string(), Utc::now().timestamp_millis(), data],
+            )?;
+            Ok(())
+        });
+        if let Err(err) = written {
+            tracing::warn!(%err, peer = %id, "could not save peer");
+        }
+    }
+
+    /// The latest messages from earlier sessions, oldest first. Rows this identity can't
+    /// read are skipped.
+    pub fn load(&self) -> Result<Vec<ChatMessage>> {
+        let mut query = self.db.prepare("SELECT id, time, data FROM messages ORDER BY time DESC LIMIT ?1")?;
+        let rows = query.query_map(params![LOAD as i64], |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?, row.get::<_, Vec<u8>>(2)?)))?;
+        let mut messages = Vec::new();
+        for row in rows {
+            let (id, time, data) = row?;
+            let (Some(saved), Some(time)) = (self.open::<Saved>(&data), DateTime::from_timestamp_millis(time)) else { continue };
+            let mut msg = ChatMessage::system(saved.text);
+            msg.id = id;
+            msg.from = saved.from;
+            msg.sender = saved.sender;
+            msg.time = time;
+            msg.origin = if saved.mine { Origin::Me } else { Origin::Peer };
+            msg.quote = saved.quote;
+            msg.thread = saved.thread;
+            msg.preview = saved.preview;
+            msg.location = saved.location;
+            msg.announcement = saved.announcement;
+            msg.urgent = saved.urgent;
+            msg.rich = saved.rich;
+            msg.edited = saved.edited;
+            msg.repeats = saved.repeats;
+            msg.forwarded = saved.forwarded;
+            msg.reactions = saved.reactions.into_iter().map(|(emoji, from)| Reaction { emoji, from: from.into_iter().collect::<HashSet<_>>() }).collect();
+            msg.receipts.delivered = saved.delivered.into_iter().collect();
+            msg.receipts.read = saved.read.into_iter().collect();
+            msg.relayed = saved.relayed;
+            msg.seen = true;
+            messages.push(msg);
+        }
+        messages.reverse();
+        Ok(messages)
+    }
+}
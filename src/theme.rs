@@ -0,0 +1,68 @@
+use ratatui::{buffer::Buffer, style::{Color, Modifier}};
+
+/// Colour scheme, applied to the finished frame so the widgets can keep
+/// describing intent with the default palette.
+#[derive(Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum Theme {
+    Default,
+    /// Bright foregrounds on black, no dim greys.
+    HighContrast,
+    /// Terminal default colours only; highlights become reverse video.
+    NoColor,
+}
+
+impl Theme {
+    pub fn apply(self, buf: &mut Buffer) {
+        if self == Theme::Default {
+            return;
+        }
+        for cell in buf.content.iter_mut() {
+            let (fg, bg) = (cell.fg, cell.bg);
+            match self {
+                Theme::Default => {}
+                Theme::NoColor => {
+                    if bg != Color::Reset && !matches!(bg, Color::Indexed(235) | Color::Indexed(236)) {
+                        cell.modifier.insert(Modifier::REVERSED);
+                    }
+                    cell.fg = Color::Reset;
+                    cell.bg = Color::Reset;
+                }
+                Theme::HighContrast => {
+                    cell.fg = bright(fg);
+                    cell.bg = match bg {
+                        Color::Indexed(235) | Color::Indexed(236) => Color::Black,
+                        Color::Indexed(237) | Color::DarkGray => Color::Blue,
+                        Color::Indexed(53) => Color::Magenta,
+                        other => other,
+                    };
+                    if cell.bg == Color::Blue || cell.bg == Color::Magenta {
+                        cell.fg = Color::White;
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn bright(color: Color) -> Color {
+    match color {
+        Color::DarkGray | Color::Gray | Color::Indexed(_) => Color::White,
+        Color::Cyan => Color::LightCyan,
+        Color::Blue => Color::LightBlue,
+        Color::Green => Color::LightGreen,
+        Color::Yellow => Color::LightYellow,
+        Color::Red => Color::LightRed,
+        Color::Magenta => Color::LightMagenta,
+        other => other,
+    }
+}
+
+/// Blanks box-drawing characters, for screen readers that would read them out.
+pub fn strip_boxes(buf: &mut Buffer) {
+    for cell in buf.content.iter_mut() {
+        let boxy = cell.symbol().chars().next().is_some_and(|c| ('\u{2500}'..='\u{257F}').contains(&c));
+        if boxy {
+            cell.set_symbol(" ");
+        }
+    }
+}
@@ -0,0 +1,206 @@
+//! What rooms travel over: subscribing to a topic, broadcasting to it, and who's connected.
+//! [`Iroh`] is gossip on an iroh endpoint, which every real room uses; [`Loopback`] passes
+//! messages between nodes in one process, to try things out without a network.
+
+use std::{collections::HashMap, sync::{Arc, Mutex}};
+
+use anyhow::{bail, Result};
+use futures_lite::{future::Boxed, stream, StreamExt};
+use iroh::{Endpoint, NodeAddr, NodeId, SecretKey};
+use iroh_gossip::{net::{Event, Gossip, GossipEvent, GossipSender}, proto::{DeliveryScope, TopicId}};
+use tokio::sync::mpsc;
+
+use crate::whereabouts;
+
+/// A topic's events as they come, in gossip's terms whatever carries them.
+pub type Events = stream::Boxed<Result<Event>>;
+
+/// A way to reach the rest of a room.
+pub trait Transport: Send + Sync {
+    /// Ours, to sign with; our node id is its public half.
+    fn secret_key(&self) -> &SecretKey;
+
+    /// How others reach us, as put in a ticket.
+    fn addr(&self) -> Boxed<Result<NodeAddr>>;
+
+    /// The relay we can be reached through, by host name.
+    fn home_relay(&self) -> Option<String>;
+
+    /// Subscribes to `topic`, starting from `peers`. With `wait`, only returns once
+    /// connected to one of them.
+    fn subscribe(&self, topic: TopicId, peers: Vec<NodeAddr>, wait: bool) -> Boxed<Result<Subscription>>;
+}
+
+/// The sending half of a subscription.
+pub trait Broadcast: Send + Sync {
+    /// Sends `bytes` to the topic, or with `neighbors_only`, to our direct neighbors, who
+    /// don't pass it on.
+    fn broadcast(&self, bytes: Vec<u8>, neighbors_only: bool) -> Boxed<Result<()>>;
+}
+
+/// A subscribed topic. Dropping both halves leaves it.
+pub struct Subscription {
+    pub sender: Arc<dyn Broadcast>,
+    pub events: Events,
+    /// Who we're connected to already; `events` tells who comes and goes from here on.
+    pub neighbors: Vec<NodeId>,
+}
+
+/// iroh-gossip on an endpoint.
+#[derive(Clone)]
+pub struct Iroh {
+    pub endpoint: Endpoint,
+    pub gossip: Gossip,
+}
+
+impl Iroh {
+    pub fn new(endpoint: &Endpoint, gossip: &Gossip) -> Self {
+        Iroh { endpoint: endpoint.clone(), gossip: gossip.clone() }
+    }
+}
+
+impl Transport for Iroh {
+    fn secret_key(&self) -> &SecretKey {
+        self.endpoint.secret_key()
+    }
+
+    fn addr(&self) -> Boxed<Result<NodeAddr>> {
+        let endpoint = self.endpoint.clone();
+        Box::pin(async move { endpoint.node_addr().await })
+    }
+
+    fn home_relay(&self) -> Option<String> {
+        whereabouts::home_relay(&self.endpoint)
+    }
+
+    fn subscribe(&self, topic: TopicId, peers: Vec<NodeAddr>, wait: bool) -> Boxed<Result<Subscription>> {
+        let Iroh { endpoint, gossip } = self.clone();
+        Box::pin(async move {
+            let ids = peers.iter().map(|addr| addr.node_id).collect();
+            for addr in peers { endpoint.add_node_addr(addr)?; }
+            let topic = match wait {
+                true => gossip.subscribe_and_join(topic, ids).await?,
+                false => gossip.subscribe(topic, ids)?,
+            };
+            let (sender, receiver) = topic.split();
+            // `subscribe_and_join` already consumed the Joined event, so ask the receiver.
+            let neighbors = receiver.neighbors().collect();
+            let events = receiver.map(|event| event.map_err(anyhow::Error::from)).boxed();
+            Ok(Subscription { sender: Arc::new(sender), events, neighbors })
+        })
+    }
+}
+
+impl Broadcast for GossipSender {
+    fn broadcast(&self, bytes: Vec<u8>, neighbors_only: bool) -> Boxed<Result<()>> {
+        let sender = self.clone();
+        Box::pin(async move {
+            match neighbors_only {
+                true => sender.broadcast_neighbors(bytes.into()).await?,
+                false => sender.broadcast(bytes.into()).await?,
+            }
+            Ok(())
+        })
+    }
+}
+
+type Members = HashMap<NodeId, mpsc::UnboundedSender<Event>>;
+
+/// Gossip without a network: every node made from the same `Loopback` is a direct neighbor
+/// of every other on the topics they share, and gets what they send straight away.
+#[derive(Clone, Default)]
+pub struct Loopback {
+    topics: Arc<Mutex<HashMap<TopicId, Members>>>,
+}
+
+impl Loopback {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Someone new on it, with a key of their own.
+    pub fn node(&self) -> LoopbackNode {
+        LoopbackNode { hub: self.clone(), key: SecretKey::generate(rand::rngs::OsRng) }
+    }
+
+    /// `topic`'s members, less those who left, whom the rest are told about.
+    fn members(&self, topic: TopicId, f: impl FnOnce(&mut Members)) {
+        let mut topics = self.topics.lock().unwrap_or_else(|e| e.into_inner());
+        let members = topics.entry(topic).or_default();
+        let gone: Vec<NodeId> = members.iter().filter(|(_, tx)| tx.is_closed()).map(|(id, _)| *id).collect();
+        for id in gone {
+            members.remove(&id);
+            for tx in members.values() {
+                let _ = tx.send(Event::Gossip(GossipEvent::NeighborDown(id)));
+            }
+        }
+        f(members);
+    }
+}
+
+/// One node on a [`Loopback`].
+pub struct LoopbackNode {
+    hub: Loopback,
+    key: SecretKey,
+}
+
+impl Transport for LoopbackNode {
+    fn secret_key(&self) -> &SecretKey {
+        &self.key
+    }
+
+    fn addr(&self) -> Boxed<Result<NodeAddr>> {
+        let addr = NodeAddr::new(self.key.public());
+        Box::pin(async move { Ok(addr) })
+    }
+
+    fn home_relay(&self) -> Option<String> {
+        None
+    }
+
+    fn subscribe(&self, topic: TopicId, _peers: Vec<NodeAddr>, wait: bool) -> Boxed<Result<Subscription>> {
+        let me = self.key.public();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut neighbors = Vec::new();
+        self.hub.members(topic, |members| {
+            neighbors = members.keys().copied().collect();
+            for tx in members.values() {
+                let _ = tx.send(Event::Gossip(GossipEvent::NeighborUp(me)));
+            }
+            members.insert(me, tx);
+        });
+        let sender = Arc::new(LoopbackSender { hub: self.hub.clone(), topic, me });
+        Box::pin(async move {
+            if wait && neighbors.is_empty() {
+                bail!("Connection Failed (nobody in the room)");
+            }
+            let events = stream::poll_fn(move |cx| rx.poll_recv(cx).map(|event| event.map(Ok))).boxed();
+            Ok(Subscription { sender, events, neighbors })
+        })
+    }
+}
+
+struct LoopbackSender {
+    hub: Loopback,
+    topic: TopicId,
+    me: NodeId,
+}
+
+impl Broadcast for LoopbackSender {
+    fn broadcast(&self, bytes: Vec<u8>, neighbors_only: bool) -> Boxed<Result<()>> {
+        // Everyone is a neighbor here, so only the scope tells the two apart.
+        let scope = match neighbors_only {
+            true => DeliveryScope::Neighbors,
+            false => DeliveryScope::Swarm(0u16.into()),
+        };
+        self.hub.members(self.topic, |members| {
+            for (id, tx) in members.iter() {
+                if *id != self.me {
+                    let msg = iroh_gossip::net::Message { content: bytes.clone().into(), scope, delivered_from: self.me };
+                    let _ = tx.send(Event::Gossip(GossipEvent::Received(msg)));
+                }
+            }
+        });
+        Box::pin(async { Ok(()) })
+    }
+}
@@ -0,0 +1,126 @@
+use std::{sync::mpsc, thread, time::Duration};
+
+use anyhow::{anyhow, Context, Result};
+use serde_json::Value;
+use tauri_winrt_notification::Toast;
+use tray_icon::{
+    menu::{Menu, MenuEvent, MenuItem, PredefinedMenuItem},
+    Icon, TrayIconBuilder,
+};
+use windows_sys::Win32::UI::WindowsAndMessaging::{DispatchMessageW, PeekMessageW, TranslateMessage, MSG, PM_REMOVE};
+
+/// A message the tray should count and announce.
+struct Arrival {
+    sender: String,
+    text: String,
+}
+
+/// The daemon's tray icon (Windows only). It lives on its own thread, which also runs the
+/// window message loop the icon and its menu need.
+pub struct Tray {
+    arrivals: mpsc::Sender<Arrival>,
+}
+
+impl Tray {
+    /// Shows the icon. "Open" starts `ghostterm attach` in a new console; "Quit" sends
+    /// `quit` into the daemon's session through `lines`.
+    pub fn spawn(lines: tokio::sync::mpsc::UnboundedSender<String>, profile: Option<String>) -> Result<Self> {
+        let (arrivals, incoming) = mpsc::channel();
+        let (ready_tx, ready) = mpsc::channel();
+        thread::spawn(move || {
+            // The icon and menu belong to the thread that made them.
+            let unread = MenuItem::new("No unread messages", false, None);
+            let open = MenuItem::new("Open", true, None);
+            let quit = MenuItem::new("Quit", true, None);
+            let menu = Menu::new();
+            let built = menu
+                .append_items(&[&unread, &PredefinedMenuItem::separator(), &open, &quit])
+                .map_err(|err| anyhow!(err))
+                .and_then(|_| Icon::from_resource(1, None).map_err(|err| anyhow!(err)))
+                .and_then(|icon| {
+                    TrayIconBuilder::new()
+                        .with_menu(Box::new(menu))
+                        .with_icon(icon)
+                        .with_tooltip("GhostTerm")
+                        .build()
+                        .map_err(|err| anyhow!(err))
+                });
+            let icon = match built {
+                Ok(icon) => {
+                    let _ = ready_tx.send(Ok(()));
+                    icon
+                }
+                Err(err) => {
+                    let _ = ready_tx.send(Err(err));
+                    return;
+                }
+            };
+
+            let mut count = 0;
+            loop {
+                // SAFETY: plain Win32 message pumping on this thread's own queue.
+                unsafe {
+                    let mut msg: MSG = std::mem::zeroed();
+                    while PeekMessageW(&mut msg, std::ptr::null_mut(), 0, 0, PM_REMOVE) != 0 {
+                        TranslateMessage(&msg);
+                        DispatchMessageW(&msg);
+                    }
+                }
+                let before = count;
+                while let Ok(event) = MenuEvent::receiver().try_recv() {
+                    if &event.id == open.id() {
+                        count = 0;
+                        let mut command = std::process::Command::new("cmd");
+                        command.args(["/c", "start", "GhostTerm"]);
+                        command.arg(std::env::current_exe().unwrap_or_else(|_| "ghostterm".into()));
+                        if let Some(profile) = &profile {
+                            command.args(["--profile", profile]);
+                        }
+                        if let Err(err) = command.arg("attach").spawn() {
+                            tracing::warn!(%err, "could not open a console");
+                        }
+                    } else if &event.id == quit.id() {
+                        let _ = lines.send(r#"{"cmd":"quit"}"#.to_string());
+                        return;
+                    }
+                }
+                loop {
+                    match incoming.try_recv() {
+                        Ok(Arrival { sender, text }) => {
+                            count += 1;
+                            if let Err(err) = Toast::new(Toast::POWERSHELL_APP_ID).title(&sender).text1(&text).show() {
+                                tracing::debug!(%err, "toast failed");
+                            }
+                        }
+                        Err(mpsc::TryRecvError::Empty) => break,
+                        // The daemon stopped.
+                        Err(mpsc::TryRecvError::Disconnected) => return,
+                    }
+                }
+                if count != before {
+                    let label = match count {
+                        0 => "No unread messages".to_string(),
+                        1 => "1 unread message".to_string(),
+                        n => format!("{} unread messages", n),
+                    };
+                    unread.set_text(&label);
+                    let tooltip = if count == 0 { "GhostTerm".to_string() } else { format!("GhostTerm: {}", label) };
+                    if let Err(err) = icon.set_tooltip(Some(tooltip)) {
+                        tracing::debug!(%err, "tray tooltip failed");
+                    }
+                }
+                thread::sleep(Duration::from_millis(50));
+            }
+        });
+        ready.recv().context("tray thread exited")??;
+        Ok(Self { arrivals })
+    }
+
+    /// Counts and announces new messages among the daemon's events.
+    pub fn event(&self, event: &Value) {
+        if event["event"] == "message" {
+            let text = |key: &str| event[key].as_str().unwrap_or_default().to_string();
+            let _ = self.arrivals.send(Arrival { sender: text("sender"), text: text("text") });
+        }
+    }
+}
@@ -0,0 +1,158 @@
+use std::{
+    path::Path,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, Context, Result};
+use ed25519_dalek::{Signature, VerifyingKey};
+use semver::Version;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use crate::i18n::t;
+
+const REPO: &str = "tirthikdurgam/GhostMessaging";
+/// Hex ed25519 key that signs release binaries, baked in by release builds. Builds without
+/// one can still check for updates but won't install any.
+const RELEASE_KEY: Option<&str> = option_env!("GHOST_RELEASE_KEY");
+/// Where the background check caches what it found: `<unix time> <latest version>`.
+const CACHE: &str = "update-check";
+const CHECK_EVERY: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[derive(Deserialize)]
+struct Release {
+    tag_name: String,
+    assets: Vec<Asset>,
+}
+
+#[derive(Deserialize)]
+struct Asset {
+    name: String,
+    browser_download_url: String,
+}
+
+fn current() -> Version {
+    Version::parse(env!("CARGO_PKG_VERSION")).expect("the package version is semver")
+}
+
+fn version(tag: &str) -> Result<Version> {
+    Version::parse(tag.trim_start_matches('v')).with_context(|| format!("release tag `{}` is not a version", tag))
+}
+
+/// The release asset built for this platform, like `ghostterm-x86_64-linux`.
+fn asset_name() -> String {
+    format!("ghostterm-{}-{}{}", std::env::consts::ARCH, std::env::consts::OS, std::env::consts::EXE_SUFFIX)
+}
+
+fn client() -> Result<reqwest::Client> {
+    Ok(reqwest::Client::builder()
+        .user_agent(concat!("ghostterm/", env!("CARGO_PKG_VERSION")))
+        .timeout(Duration::from_secs(60))
+        .build()?)
+}
+
+async fn latest(client: &reqwest::Client) -> Result<Release> {
+    let url = format!("https://api.github.com/repos/{}/releases/latest", REPO);
+    Ok(client.get(url).send().await?.error_for_status()?.json().await?)
+}
+
+async fn download(client: &reqwest::Client, release: &Release, name: &str) -> Result<Vec<u8>> {
+    let asset = release
+        .assets
+        .iter()
+        .find(|a| a.name == name)
+        .ok_or_else(|| anyhow!("release {} has no `{}`", release.tag_name, name))?;
+    Ok(client.get(&asset.browser_download_url).send().await?.error_for_status()?.bytes().await?.to_vec())
+}
+
+/// What a release signs for each binary: its version, its asset name and its hash, so a
+/// signed binary can't be passed off as another version or platform.
+fn manifest(version: &Version, name: &str, binary: &[u8]) -> String {
+    format!("ghostterm {}\n{}\nsha256 {}\n", version, name, hex::encode(Sha256::digest(binary)))
+}
+
+/// Checks `signature` over the manifest of `binary` as release `version`'s asset `name`.
+fn verify(version: &Version, name: &str, binary: &[u8], signature: &[u8]) -> Result<()> {
+    let key = RELEASE_KEY.ok_or_else(|| anyhow!("this build has no release key to check downloads against; update it by hand"))?;
+    let key: [u8; 32] = hex::decode(key).ok().and_then(|k| k.try_into().ok()).ok_or_else(|| anyhow!("bad built-in release key"))?;
+    let signature: [u8; 64] = hex::decode(String::from_utf8_lossy(signature).trim())
+        .ok()
+        .and_then(|s| s.try_into().ok())
+        .ok_or_else(|| anyhow!("malformed signature file"))?;
+    VerifyingKey::from_bytes(&key)?
+        .verify_strict(manifest(version, name, binary).as_bytes(), &Signature::from_bytes(&signature))
+        .map_err(|_| anyhow!("signature check failed; not installing"))
+}
+
+/// Puts `binary` where the running executable is. The old one is moved aside first, which
+/// works even on Windows, where a running program can be renamed but not overwritten.
+fn replace(binary: &[u8]) -> Result<()> {
+    let exe = std::env::current_exe()?;
+    let old = exe.with_extension("old");
+    let _ = std::fs::remove_file(&old);
+    std::fs::rename(&exe, &old).with_context(|| format!("moving {} aside", exe.display()))?;
+    if let Err(err) = std::fs::write(&exe, binary) {
+        let _ = std::fs::rename(&old, &exe);
+        return Err(err).with_context(|| format!("writing {}", exe.display()));
+    }
+    #[cfg(unix)]
+    std::fs::set_permissions(&exe, std::os::unix::fs::PermissionsExt::from_mode(0o755))?;
+    // Still running on Windows; the next update clears it.
+    let _ = std::fs::remove_file(&old);
+    Ok(())
+}
+
+/// `ghost update`: installs the latest release if it's newer, or just reports with `check`.
+pub async fn run(check: bool) -> Result<()> {
+    let client = client()?;
+    let release = latest(&client).await.context("looking up the latest release")?;
+    let newest = version(&release.tag_name)?;
+    if newest <= current() {
+        println!("{}", t!("update-current", version = current().to_string()));
+        return Ok(());
+    }
+    if check {
+        println!("{}", t!("update-available", version = newest.to_string(), current = current().to_string()));
+        return Ok(());
+    }
+    let name = asset_name();
+    println!("{}", t!("update-downloading", version = newest.to_string(), asset = name.as_str()));
+    let binary = download(&client, &release, &name).await?;
+    let signature = download(&client, &release, &format!("{}.sig", name)).await?;
+    verify(&newest, &name, &binary, &signature)?;
+    replace(&binary)?;
+    println!("{}", t!("update-done", current = current().to_string(), version = newest.to_string()));
+    Ok(())
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs())
+}
+
+/// A one-line notice if the last background check found a newer release.
+pub fn notice(dir: &Path) -> Option<String> {
+    let cache = std::fs::read_to_string(dir.join(CACHE)).ok()?;
+    let newest = Version::parse(cache.split_whitespace().nth(1)?).ok()?;
+    (newest > current()).then(|| format!("GhostTerm {} is available; run `ghostterm update` to install it.", newest))
+}
+
+/// Refreshes the cached latest version in the background, at most once a day. Failures
+/// (offline, rate-limited) are only logged.
+pub fn spawn_check(dir: &Path) {
+    let path = dir.join(CACHE);
+    let checked = std::fs::read_to_string(&path).ok().and_then(|c| c.split_whitespace().next()?.parse::<u64>().ok());
+    if checked.is_some_and(|t| now().saturating_sub(t) < CHECK_EVERY.as_secs()) {
+        return;
+    }
+    tokio::spawn(async move {
+        let result = async {
+            let release = latest(&client()?).await?;
+            let newest = version(&release.tag_name)?;
+            std::fs::write(&path, format!("{} {}\n", now(), newest))?;
+            anyhow::Ok(())
+        };
+        if let Err(err) = result.await {
+            tracing::debug!(%err, "update check failed");
+        }
+    });
+}
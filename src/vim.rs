@@ -0,0 +1,94 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::AppState;
+
+/// Which keymap drives the main view.
+#[derive(Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum Keymap {
+    Default,
+    /// Modal: normal mode scrolls and navigates, `i` types.
+    Vim,
+}
+
+#[derive(Clone, Copy, PartialEq, Default)]
+pub enum Mode {
+    #[default]
+    Normal,
+    Insert,
+}
+
+#[derive(Default)]
+pub struct Vim {
+    pub mode: Mode,
+    /// First half of a two-key command (`gg`, `dd`).
+    pending: Option<char>,
+}
+
+fn insert(state: &mut AppState) {
+    if let Some(vim) = state.vim.as_mut() {
+        vim.mode = Mode::Insert;
+    }
+}
+
+/// Handles `key` if the vim keymap wants it. Returns false to let the default keymap
+/// see it, which is how Enter, the arrows and the F-keys keep working in both modes.
+pub fn key(state: &mut AppState, key: KeyEvent) -> bool {
+    let Some(vim) = state.vim.as_mut() else { return false };
+    let pending = vim.pending.take();
+    if vim.mode == Mode::Insert {
+        if key.code == KeyCode::Esc {
+            vim.mode = Mode::Normal;
+            return true;
+        }
+        return false;
+    }
+    if key.modifiers.contains(KeyModifiers::ALT) {
+        return false;
+    }
+
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+    let half = (state.chat_height.get() / 2).max(1) as isize;
+    match key.code {
+        KeyCode::Char('d') if ctrl => state.scroll_by(-half),
+        KeyCode::Char('u') if ctrl => state.scroll_by(half),
+        _ if ctrl => return false,
+        KeyCode::Char('i') => insert(state),
+        KeyCode::Char('a') => {
+            state.room_mut().input.right();
+            insert(state);
+        }
+        KeyCode::Char('A') => {
+            state.room_mut().input.end();
+            insert(state);
+        }
+        KeyCode::Char('I') => {
+            state.room_mut().input.home();
+            insert(state);
+        }
+        KeyCode::Char(':') => {
+            state.room_mut().input.set("/".to_string());
+            insert(state);
+        }
+        KeyCode::Char('j') => state.scroll_by(-1),
+        KeyCode::Char('k') => state.scroll_by(1),
+        KeyCode::Char('h') => state.room_mut().input.left(),
+        KeyCode::Char('l') => state.room_mut().input.right(),
+        KeyCode::Char('0') => state.room_mut().input.home(),
+        KeyCode::Char('$') => state.room_mut().input.end(),
+        KeyCode::Char('x') => state.room_mut().input.delete(),
+        KeyCode::Char('d') if pending == Some('d') => state.room_mut().input.clear(),
+        KeyCode::Char('g') if pending == Some('g') => state.scroll_by(isize::MAX),
+        KeyCode::Char(c @ ('d' | 'g')) => {
+            if let Some(vim) = state.vim.as_mut() {
+                vim.pending = Some(c);
+            }
+        }
+        KeyCode::Char('G') => state.room_mut().jump_to_bottom(),
+        KeyCode::Char('/') => state.search = Some(crate::search::Search::default()),
+        KeyCode::Char('v') => state.enter_selection(),
+        // Nothing else types in normal mode.
+        KeyCode::Char(_) => {}
+        _ => return false,
+    }
+    true
+}
@@ -0,0 +1,273 @@
+use std::time::Duration;
+
+pub use imp::{play, record, Playback, Recording};
+
+/// Clips stop recording by themselves after this long.
+pub const MAX: Duration = Duration::from_secs(60);
+
+/// `m:ss`, for clip lengths and playback.
+pub fn clock(ms: u32) -> String {
+    let secs = ms / 1000;
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
+
+/// Recording from the default microphone and playing clips on the default output, as Ogg
+/// Opus files any player can open.
+#[cfg(feature = "voice")]
+mod imp {
+    use std::{
+        fs::File,
+        io::{BufReader, BufWriter, Write},
+        path::{Path, PathBuf},
+        sync::{atomic::{AtomicUsize, Ordering}, Arc, Mutex},
+        time::{Duration, Instant},
+    };
+
+    use anyhow::{anyhow, bail, Result};
+    use cpal::{
+        traits::{DeviceTrait, HostTrait, StreamTrait},
+        FromSample, Sample, SampleFormat, SizedSample, StreamConfig,
+    };
+    use ogg::{reading::PacketReader, writing::{PacketWriteEndInfo, PacketWriter}};
+
+    use crate::i18n::t;
+
+    /// Opus runs at 48 kHz; clips are mono.
+    const RATE: u32 = 48_000;
+    /// 20 ms.
+    const FRAME: usize = 960;
+
+    pub struct Recording {
+        started: Instant,
+        /// Mono, at the microphone's rate.
+        samples: Arc<Mutex<Vec<f32>>>,
+        rate: u32,
+        _stream: cpal::Stream,
+    }
+
+    /// Starts recording from the default microphone.
+    pub fn record() -> Result<Recording> {
+        let device = cpal::default_host().default_input_device().ok_or_else(|| anyhow!(t!("voice-no-microphone")))?;
+        let supported = device.default_input_config()?;
+        let (config, format) = (supported.config(), supported.sample_format());
+        let samples = Arc::new(Mutex::new(Vec::new()));
+        let stream = match format {
+            SampleFormat::F32 => input::<f32>(&device, &config, samples.clone()),
+            SampleFormat::I16 => input::<i16>(&device, &config, samples.clone()),
+            SampleFormat::U16 => input::<u16>(&device, &config, samples.clone()),
+            other => bail!("unsupported microphone sample format {}", other),
+        }?;
+        stream.play()?;
+        Ok(Recording { started: Instant::now(), samples, rate: config.sample_rate.0, _stream: stream })
+    }
+
+    fn input<T: SizedSample>(device: &cpal::Device, config: &StreamConfig, samples: Arc<Mutex<Vec<f32>>>) -> Result<cpal::Stream>
+    where
+        f32: FromSample<T>,
+    {
+        let channels = config.channels as usize;
+        let stream = device.build_input_stream(
+            config,
+            move |data: &[T], _: &cpal::InputCallbackInfo| {
+                let mut samples = samples.lock().unwrap_or_else(|e| e.into_inner());
+                samples.extend(data.chunks(channels).map(|frame| frame.iter().map(|&s| f32::from_sample(s)).sum::<f32>() / channels as f32));
+            },
+            |err| tracing::warn!(%err, "microphone error"),
+            None,
+        )?;
+        Ok(stream)
+    }
+
+    impl Recording {
+        pub fn elapsed(&self) -> Duration {
+            self.started.elapsed()
+        }
+
+        /// Stops recording and writes the clip to a temporary file, returning it with the
+        /// clip's length in milliseconds.
+        pub fn finish(self) -> Result<(PathBuf, u32)> {
+            let Recording { samples, rate, _stream, .. } = self;
+            drop(_stream);
+            let samples = resample(&samples.lock().unwrap_or_else(|e| e.into_inner()), rate, RATE);
+            if samples.len() < FRAME {
+                bail!(t!("voice-too-short"));
+            }
+            let path = std::env::temp_dir().join(format!("voice-{}.opus", crate::new_id()));
+            encode(&samples, &path)?;
+            Ok((path, (samples.len() as u64 * 1000 / RATE as u64) as u32))
+        }
+    }
+
+    /// Linear interpolation; good enough for speech.
+    fn resample(samples: &[f32], from: u32, to: u32) -> Vec<f32> {
+        if from == to {
+            return samples.to_vec();
+        }
+        let len = (samples.len() as u64 * to as u64 / from as u64) as usize;
+        (0..len)
+            .map(|i| {
+                let pos = i as f64 * from as f64 / to as f64;
+                let (j, frac) = (pos as usize, pos.fract() as f32);
+                let a = samples[j];
+                a + (samples.get(j + 1).copied().unwrap_or(a) - a) * frac
+            })
+            .collect()
+    }
+
+    /// Writes 48 kHz mono samples as an Ogg Opus file (RFC 7845).
+    fn encode(samples: &[f32], path: &Path) -> Result<()> {
+        let mut encoder = opus::Encoder::new(RATE, opus::Channels::Mono, opus::Application::Voip)?;
+        let skip = encoder.get_lookahead()? as u16;
+        let mut out = PacketWriter::new(BufWriter::new(File::create(path)?));
+        let serial = rand::random();
+
+        let mut head = b"OpusHead".to_vec();
+        head.extend([1, 1]);
+        head.extend(skip.to_le_bytes());
+        head.extend(RATE.to_le_bytes());
+        head.extend([0, 0, 0]);
+        out.write_packet(head, serial, PacketWriteEndInfo::EndPage, 0)?;
+        let vendor = b"ghostterm";
+        let mut tags = b"OpusTags".to_vec();
+        tags.extend((vendor.len() as u32).to_le_bytes());
+        tags.extend(vendor);
+        tags.extend(0u32.to_le_bytes());
+        out.write_packet(tags, serial, PacketWriteEndInfo::EndPage, 0)?;
+
+        let frames = samples.len().div_ceil(FRAME);
+        let mut packet = [0u8; 4000];
+        for (i, frame) in samples.chunks(FRAME).enumerate() {
+            let mut frame = frame.to_vec();
+            frame.resize(FRAME, 0.0);
+            let len = encoder.encode_float(&frame, &mut packet)?;
+            let last = i + 1 == frames;
+            let (end, granule) = match last {
+                true => (PacketWriteEndInfo::EndStream, samples.len()),
+                false => (PacketWriteEndInfo::NormalPacket, (i + 1) * FRAME),
+            };
+            out.write_packet(packet[..len].to_vec(), serial, end, skip as u64 + granule as u64)?;
+        }
+        out.into_inner().flush()?;
+        Ok(())
+    }
+
+    /// Reads an Ogg Opus file back into 48 kHz mono samples.
+    fn decode(path: &Path) -> Result<Vec<f32>> {
+        let mut reader = PacketReader::new(BufReader::new(File::open(path)?));
+        let head = reader.read_packet()?.filter(|p| p.data.starts_with(b"OpusHead") && p.data.len() >= 19)
+            .ok_or_else(|| anyhow!(t!("voice-not-opus")))?;
+        let skip = u16::from_le_bytes([head.data[10], head.data[11]]) as usize;
+        // The comment header.
+        reader.read_packet()?;
+        let mut decoder = opus::Decoder::new(RATE, opus::Channels::Mono)?;
+        let mut samples = Vec::new();
+        // The longest Opus packet, 120 ms.
+        let mut buf = vec![0f32; 5760];
+        while let Some(packet) = reader.read_packet()? {
+            let n = decoder.decode_float(&packet.data, &mut buf, false)?;
+            samples.extend_from_slice(&buf[..n]);
+        }
+        Ok(samples.split_off(skip.min(samples.len())))
+    }
+
+    /// A clip playing on the default output; dropping it stops it.
+    pub struct Playback {
+        position: Arc<AtomicUsize>,
+        len: usize,
+        rate: u32,
+        _stream: cpal::Stream,
+    }
+
+    /// Starts playing the clip at `path`.
+    pub fn play(path: &Path) -> Result<Playback> {
+        let samples = decode(path)?;
+        let device = cpal::default_host().default_output_device().ok_or_else(|| anyhow!(t!("voice-no-speaker")))?;
+        let supported = device.default_output_config()?;
+        let (config, format) = (supported.config(), supported.sample_format());
+        let samples = Arc::new(resample(&samples, RATE, config.sample_rate.0));
+        let position = Arc::new(AtomicUsize::new(0));
+        let stream = match format {
+            SampleFormat::F32 => output::<f32>(&device, &config, samples.clone(), position.clone()),
+            SampleFormat::I16 => output::<i16>(&device, &config, samples.clone(), position.clone()),
+            SampleFormat::U16 => output::<u16>(&device, &config, samples.clone(), position.clone()),
+            other => bail!("unsupported speaker sample format {}", other),
+        }?;
+        stream.play()?;
+        Ok(Playback { position, len: samples.len(), rate: config.sample_rate.0, _stream: stream })
+    }
+
+    fn output<T: SizedSample + FromSample<f32>>(
+        device: &cpal::Device,
+        config: &StreamConfig,
+        samples: Arc<Vec<f32>>,
+        position: Arc<AtomicUsize>,
+    ) -> Result<cpal::Stream> {
+        let channels = config.channels as usize;
+        let stream = device.build_output_stream(
+            config,
+            move |data: &mut [T], _: &cpal::OutputCallbackInfo| {
+                for frame in data.chunks_mut(channels) {
+                    let i = position.fetch_add(1, Ordering::Relaxed);
+                    frame.fill(T::from_sample(samples.get(i).copied().unwrap_or(0.0)));
+                }
+            },
+            |err| tracing::warn!(%err, "speaker error"),
+            None,
+        )?;
+        Ok(stream)
+    }
+
+    impl Playback {
+        /// Milliseconds played so far, and in all.
+        pub fn progress(&self) -> (u32, u32) {
+            let ms = |samples: usize| (samples as u64 * 1000 / self.rate as u64) as u32;
+            (ms(self.position.load(Ordering::Relaxed).min(self.len)), ms(self.len))
+        }
+
+        pub fn done(&self) -> bool {
+            self.position.load(Ordering::Relaxed) >= self.len
+        }
+    }
+}
+
+/// Builds without the `voice` feature: every attempt says so.
+#[cfg(not(feature = "voice"))]
+mod imp {
+    use std::{path::{Path, PathBuf}, time::Duration};
+
+    use anyhow::{bail, Result};
+
+    use crate::i18n::t;
+
+    pub enum Recording {}
+
+    pub enum Playback {}
+
+    pub fn record() -> Result<Recording> {
+        bail!(t!("voice-unsupported"))
+    }
+
+    pub fn play(_path: &Path) -> Result<Playback> {
+        bail!(t!("voice-unsupported"))
+    }
+
+    impl Recording {
+        pub fn elapsed(&self) -> Duration {
+            match *self {}
+        }
+
+        pub fn finish(self) -> Result<(PathBuf, u32)> {
+            match self {}
+        }
+    }
+
+    impl Playback {
+        pub fn progress(&self) -> (u32, u32) {
+            match *self {}
+        }
+
+        pub fn done(&self) -> bool {
+            match *self {}
+        }
+    }
+}
@@ -0,0 +1,158 @@
+//! Message filters compiled to WebAssembly, from untrusted hands: a module in the `plugins`
+//! config directory gets no imports at all, so no files, network or clock, just the text it
+//! is given, and runs on a fuel and memory budget. Enough for a profanity filter or a codec
+//! that hides text in other text.
+//!
+//! A module exports its `memory`, `ghost_alloc(len) -> ptr` for the client to write text
+//! into, and `ghost_filter_in(ptr, len) -> i64` for what we're sent and
+//! `ghost_filter_out(ptr, len) -> i64` for what we post, either or both. A filter returns
+//! its result's UTF-8 as `ptr << 32 | len` in its memory, or -1 to hold the message back.
+
+use std::path::{Path, PathBuf};
+
+pub use imp::{load, Filter};
+
+/// The `.wasm` files in `dir`, in name order.
+fn modules(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else { return Vec::new() };
+    let mut paths: Vec<_> = entries.flatten().map(|e| e.path()).filter(|path| path.extension().is_some_and(|ext| ext == "wasm")).collect();
+    paths.sort();
+    paths
+}
+
+#[cfg(feature = "wasm")]
+mod imp {
+    use std::path::{Path, PathBuf};
+
+    use anyhow::{bail, Context, Result};
+    use wasmtime::{Config, Engine, Instance, Memory, Module, Store, StoreLimits, StoreLimitsBuilder, TypedFunc};
+
+    use crate::{chunks, plugins::{Plugin, TopicId}};
+
+    /// Roughly the instructions one call may run before it's stopped.
+    const FUEL: u64 = 10_000_000;
+    /// The most memory a module may grow to.
+    const MEMORY: usize = 16 << 20;
+
+    /// One loaded module, with its own store: nothing it does reaches another.
+    pub struct Filter {
+        name: String,
+        store: Store<StoreLimits>,
+        memory: Memory,
+        alloc: TypedFunc<i32, i32>,
+        filter_in: Option<TypedFunc<(i32, i32), i64>>,
+        filter_out: Option<TypedFunc<(i32, i32), i64>>,
+    }
+
+    impl Filter {
+        fn load(engine: &Engine, path: &Path) -> Result<Self> {
+            let name = path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+            let module = Module::from_file(engine, path)?;
+            // Refused outright rather than stubbed, so it fails here and not on the first message.
+            if let Some(import) = module.imports().next() {
+                bail!("it imports {}::{}, and filters get nothing from outside", import.module(), import.name());
+            }
+            let limits = StoreLimitsBuilder::new().memory_size(MEMORY).instances(1).tables(1).memories(1).build();
+            let mut store = Store::new(engine, limits);
+            store.limiter(|limits| limits);
+            store.set_fuel(FUEL)?;
+            let instance = Instance::new(&mut store, &module, &[])?;
+            let memory = instance.get_memory(&mut store, "memory").context("it exports no memory")?;
+            let alloc = instance.get_typed_func(&mut store, "ghost_alloc")?;
+            let filter_in = instance.get_typed_func(&mut store, "ghost_filter_in").ok();
+            let filter_out = instance.get_typed_func(&mut store, "ghost_filter_out").ok();
+            if filter_in.is_none() && filter_out.is_none() {
+                bail!("it exports neither ghost_filter_in nor ghost_filter_out");
+            }
+            Ok(Filter { name, store, memory, alloc, filter_in, filter_out })
+        }
+
+        /// `text` through `filter` on a fresh fuel budget: the new text, or `None` to hold it.
+        fn run(&mut self, filter: TypedFunc<(i32, i32), i64>, text: &str) -> Result<Option<String>> {
+            self.store.set_fuel(FUEL)?;
+            let len = i32::try_from(text.len())?;
+            let ptr = self.alloc.call(&mut self.store, len)?;
+            self.memory.write(&mut self.store, ptr as u32 as usize, text.as_bytes())?;
+            let out = filter.call(&mut self.store, (ptr, len))?;
+            if out < 0 {
+                return Ok(None);
+            }
+            let (ptr, len) = ((out >> 32) as u32 as usize, out as u32 as usize);
+            if len > chunks::MAX_TEXT {
+                bail!("it returned {} bytes", len);
+            }
+            let mut bytes = vec![0; len];
+            self.memory.read(&self.store, ptr, &mut bytes)?;
+            Ok(Some(String::from_utf8(bytes)?))
+        }
+
+        /// A filter that fails, runs out of fuel or returns garbage leaves the text as it was.
+        fn apply(&mut self, filter: Option<TypedFunc<(i32, i32), i64>>, text: String) -> Option<String> {
+            let Some(filter) = filter else { return Some(text) };
+            match self.run(filter, &text) {
+                Ok(filtered) => filtered,
+                Err(err) => {
+                    tracing::warn!(filter = %self.name, %err, "wasm filter failed");
+                    Some(text)
+                }
+            }
+        }
+    }
+
+    impl Plugin for Filter {
+        fn name(&self) -> &str {
+            &self.name
+        }
+
+        fn filter_in(&mut self, _room: TopicId, text: String) -> Option<String> {
+            self.apply(self.filter_in.clone(), text)
+        }
+
+        fn on_message_out(&mut self, _room: TopicId, text: String) -> Option<String> {
+            self.apply(self.filter_out.clone(), text)
+        }
+    }
+
+    /// Every module in `dir`, and those that wouldn't load with why.
+    pub fn load(dir: &Path) -> (Vec<Filter>, Vec<(PathBuf, String)>) {
+        let paths = super::modules(dir);
+        if paths.is_empty() {
+            return (Vec::new(), Vec::new());
+        }
+        let engine = match Engine::new(Config::new().consume_fuel(true)) {
+            Ok(engine) => engine,
+            Err(err) => return (Vec::new(), paths.into_iter().map(|path| (path, err.to_string())).collect()),
+        };
+        let (mut filters, mut failed) = (Vec::new(), Vec::new());
+        for path in paths {
+            match Filter::load(&engine, &path) {
+                Ok(filter) => filters.push(filter),
+                Err(err) => {
+                    tracing::warn!(%err, path = %path.display(), "could not load wasm filter");
+                    failed.push((path, format!("{:#}", err)));
+                }
+            }
+        }
+        (filters, failed)
+    }
+}
+
+/// Builds without the `wasm` feature: every module found says so.
+#[cfg(not(feature = "wasm"))]
+mod imp {
+    use std::path::{Path, PathBuf};
+
+    use crate::{i18n::t, plugins::Plugin};
+
+    pub enum Filter {}
+
+    impl Plugin for Filter {
+        fn name(&self) -> &str {
+            match *self {}
+        }
+    }
+
+    pub fn load(dir: &Path) -> (Vec<Filter>, Vec<(PathBuf, String)>) {
+        (Vec::new(), super::modules(dir).into_iter().map(|path| (path, t!("wasm-unsupported"))).collect())
+    }
+}
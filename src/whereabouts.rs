@@ -0,0 +1,36 @@
+//! Roughly where a peer is on the network, for the host hearing its ticket was redeemed.
+
+use std::net::IpAddr;
+
+use iroh::{endpoint::ConnectionType, Endpoint, NodeId};
+
+use crate::i18n::t;
+
+/// Our home relay by host name, as told to a host whose ticket we redeem.
+pub fn home_relay(endpoint: &Endpoint) -> Option<String> {
+    endpoint.home_relay().get().ok().flatten().and_then(|url| url.host_str().map(|host| host.trim_end_matches('.').to_string()))
+}
+
+/// A relay name from the wire, if it looks like a host name.
+pub fn relay_name(relay: Option<String>) -> Option<String> {
+    relay.filter(|r| (1..=253).contains(&r.len()) && r.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '.')))
+}
+
+/// The address `id` reaches us from directly, if it does: roughly where it is.
+pub fn address(endpoint: &Endpoint, id: NodeId) -> Option<IpAddr> {
+    match endpoint.remote_info(id)?.conn_type {
+        ConnectionType::Direct(addr) | ConnectionType::Mixed(addr, _) => Some(addr.ip()),
+        _ => None,
+    }
+}
+
+/// Where a ticket was redeemed from, for the host's notice: the joiner's address if we
+/// see it, and the home relay they named.
+pub fn describe(endpoint: &Endpoint, id: NodeId, relay: Option<&str>) -> String {
+    match (address(endpoint, id), relay) {
+        (Some(addr), Some(relay)) => t!("redeemed-address-relay", address = addr.to_string(), relay = relay),
+        (Some(addr), None) => addr.to_string(),
+        (None, Some(relay)) => t!("redeemed-relay", relay = relay),
+        (None, None) => t!("redeemed-nowhere"),
+    }
+}
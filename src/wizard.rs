@@ -0,0 +1,93 @@
+use std::{io::Write, path::Path};
+
+use anyhow::{bail, Result};
+use clap::ValueEnum;
+use iroh::SecretKey;
+
+use crate::{config, i18n::t, id, identity, names, plain, theme::Theme};
+
+/// A first run: nothing saved yet, and someone at a terminal to ask.
+pub fn due(file: &Path, dir: &Path) -> bool {
+    !file.exists() && !identity::exists(dir) && plain::supported()
+}
+
+/// Prints `prompt` and reads the answer, trimmed.
+fn read(prompt: &str) -> Result<String> {
+    print!("{}", prompt);
+    std::io::stdout().flush()?;
+    let mut line = String::new();
+    if std::io::stdin().read_line(&mut line)? == 0 {
+        bail!("setup cancelled");
+    }
+    Ok(line.trim().to_string())
+}
+
+/// Asks `question`, offering `default` for an empty answer.
+fn ask(question: &str, default: &str) -> Result<String> {
+    let answer = read(&format!("{} [{}]: ", question, default))?;
+    Ok(if answer.is_empty() { default.to_string() } else { answer })
+}
+
+fn yes(question: &str, default: bool) -> Result<bool> {
+    let (yes, no) = (t!("setup-yes"), t!("setup-no"));
+    let hint = if default { format!("{}/{}", yes.to_uppercase(), no) } else { format!("{}/{}", yes, no.to_uppercase()) };
+    loop {
+        match read(&format!("{} [{}]: ", question, hint))?.to_lowercase() {
+            answer if answer.is_empty() => return Ok(default),
+            answer if answer == "y" || answer == yes => return Ok(true),
+            answer if answer == "n" || answer == no => return Ok(false),
+            _ => println!("{}", t!("setup-yes-no", yes = yes.as_str(), no = no.as_str())),
+        }
+    }
+}
+
+/// The setting `key` in the config file, to offer back when setup runs again.
+fn current(file: &Path, key: &str) -> Option<toml::Value> {
+    let table: toml::value::Table = toml::from_str(&std::fs::read_to_string(file).ok()?).ok()?;
+    table.get(key).cloned()
+}
+
+/// Asks for a name, colour scheme, whether to keep an identity between runs and whether to
+/// protect it, saves the answers to `file` and creates the identity in `dir`. Returns the
+/// new identity, so it needn't be unlocked again straight away.
+pub fn run(file: &Path, dir: &Path, name: Option<&str>) -> Result<Option<SecretKey>> {
+    println!("{}\n", t!("setup-welcome"));
+    let saved = |key| current(file, key).and_then(|v| v.as_str().map(str::to_string));
+    let default_name = name.map(str::to_string).or_else(|| saved("name")).unwrap_or_else(|| "Ghost".to_string());
+    let name = loop {
+        match names::parse(&ask(&t!("setup-name"), &default_name)?) {
+            Ok(name) => break name,
+            Err(err) => println!("{}", err),
+        }
+    };
+
+    let themes: Vec<String> = Theme::value_variants().iter().filter_map(|t| t.to_possible_value()).map(|v| v.get_name().to_string()).collect();
+    let default_theme = saved("theme").unwrap_or_else(|| themes[0].clone());
+    let theme = loop {
+        let answer = ask(&t!("setup-theme", themes = themes.join(", ")), &default_theme)?.to_lowercase();
+        if themes.contains(&answer) {
+            break answer;
+        }
+        println!("{}", t!("setup-theme-unknown", themes = themes.join(", ")));
+    };
+
+    let was_ephemeral = current(file, "ephemeral").and_then(|v| v.as_bool()).unwrap_or(false);
+    let keep = yes(&t!("setup-keep"), !was_ephemeral)?;
+    config::remember(file, "name", name.as_str(), true)?;
+    config::remember(file, "theme", theme.as_str(), true)?;
+    config::remember(file, "ephemeral", !keep, true)?;
+    let path = file.display().to_string();
+    if !keep {
+        println!("{}", t!("setup-done-ephemeral", path = path));
+        return Ok(None);
+    }
+
+    let key = if identity::exists(dir) {
+        None
+    } else {
+        let protect = yes(&t!("setup-passphrase"), false)?;
+        Some(id::load_or_create(dir, protect)?)
+    };
+    println!("{}", t!("setup-done", path = path));
+    Ok(key)
+}
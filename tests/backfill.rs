@@ -0,0 +1,45 @@
+use chrono::Utc;
+use ghost_messaging::{backfill::{self, Past}, Origin};
+use iroh::{NodeId, SecretKey};
+
+fn node() -> NodeId {
+    SecretKey::generate(rand::rngs::OsRng).public()
+}
+
+fn past(id: &str, from: NodeId) -> Past {
+    Past {
+        id: id.to_string(),
+        from,
+        sender: "Someone".to_string(),
+        time: Utc::now(),
+        text: "hi".to_string(),
+        reply: None,
+        thread: None,
+        preview: None,
+        location: None,
+        rich: Vec::new(),
+        forwarded: None,
+    }
+}
+
+#[test]
+fn history_claiming_to_be_ours_is_dropped() {
+    let (me, peer, relay) = (node(), node(), node());
+    let mut messages = Vec::new();
+    let added = backfill::merge(&mut messages, vec![past("a", me), past("b", peer)], me, relay, |_| true, &mut []);
+    assert_eq!(added, ["b"]);
+    assert_eq!(messages.len(), 1);
+    assert!(messages[0].origin == Origin::Peer);
+    assert_eq!(messages[0].from, Some(peer));
+    assert_eq!(messages[0].relayed, Some(relay));
+}
+
+#[test]
+fn known_ids_and_turned_down_senders_are_skipped() {
+    let (me, peer, banned, relay) = (node(), node(), node(), node());
+    let mut messages = Vec::new();
+    backfill::merge(&mut messages, vec![past("a", peer)], me, relay, |_| true, &mut []);
+    let added = backfill::merge(&mut messages, vec![past("a", peer), past("b", banned)], me, relay, |id| *id != banned, &mut []);
+    assert!(added.is_empty());
+    assert_eq!(messages.len(), 1);
+}
@@ -0,0 +1,51 @@
+use std::{sync::Arc, time::Duration};
+
+use futures_lite::StreamExt;
+use ghost_messaging::{transport::Loopback, ChatEvent, GhostClient};
+
+/// The next event `matches` accepts, skipping the rest, or a panic after five seconds.
+async fn next_matching(client: &mut GhostClient, matches: impl Fn(&ChatEvent) -> bool) -> ChatEvent {
+    let wait = async {
+        loop {
+            let event = client.next().await.expect("the room ended");
+            if matches(&event) {
+                return event;
+            }
+        }
+    };
+    tokio::time::timeout(Duration::from_secs(5), wait).await.expect("no such event in time")
+}
+
+#[tokio::test]
+async fn a_message_arrives() {
+    let hub = Loopback::new();
+    let mut alice = GhostClient::host_on(Arc::new(hub.node()), "Alice").await.unwrap();
+    let mut bob = GhostClient::join_on(Arc::new(hub.node()), alice.ticket(), "Bob").await.unwrap();
+    let id = bob.send("hello").await.unwrap();
+
+    match next_matching(&mut alice, |e| matches!(e, ChatEvent::Message { .. })).await {
+        ChatEvent::Message { id: got, from, text, .. } => {
+            assert_eq!(got, id);
+            assert_eq!(from, bob.node_id());
+            assert_eq!(text, "hello");
+        }
+        _ => unreachable!(),
+    }
+}
+
+#[tokio::test]
+async fn leaving_is_a_neighbor_down() {
+    let hub = Loopback::new();
+    let mut alice = GhostClient::host_on(Arc::new(hub.node()), "Alice").await.unwrap();
+    let bob = GhostClient::join_on(Arc::new(hub.node()), alice.ticket(), "Bob").await.unwrap();
+    let bob_id = bob.node_id();
+    next_matching(&mut alice, |e| matches!(e, ChatEvent::NeighborUp(id) if *id == bob_id)).await;
+
+    drop(bob);
+    // Let the aborted tasks go, taking Bob's subscription with them.
+    tokio::task::yield_now().await;
+    // The loopback notices who left the next time the topic is used, by us or our heartbeat.
+    alice.send("anyone?").await.unwrap();
+    next_matching(&mut alice, |e| matches!(e, ChatEvent::NeighborDown(id) if *id == bob_id)).await;
+}
+
@@ -0,0 +1,224 @@
+use std::{collections::HashSet, time::Duration};
+
+use ghost_messaging::{
+    flood, moderation, names, notify, protocol, rooms, sequence::Seq,
+    transport::{Loopback, LoopbackNode, Transport},
+    ChatEvent, Message,
+};
+use iroh::{NodeId, SecretKey};
+use iroh_gossip::proto::{DeliveryScope, TopicId};
+use tokio::sync::mpsc;
+
+/// A room hosted by a node of its own, and that node, to sign as the host with.
+async fn hosted() -> (rooms::Room, LoopbackNode) {
+    let host = Loopback::new().node();
+    let channel = rooms::host(&host, "").await.unwrap();
+    let (events, _) = mpsc::unbounded_channel();
+    (rooms::Room::spawn(channel, "Host".to_string(), notify::Alert::Off, false, events), host)
+}
+
+fn key() -> SecretKey {
+    SecretKey::generate(rand::rngs::OsRng)
+}
+
+/// `msg` as `author` signs it for `room`, passed on by `relay`.
+fn gossip(room: &rooms::Room, msg: &Message, seq: Option<Seq>, author: &SecretKey, relay: NodeId) -> iroh_gossip::net::Message {
+    let content = protocol::encode(msg, seq, author, room.topic).unwrap();
+    iroh_gossip::net::Message { content: content.into(), scope: DeliveryScope::Swarm(1u16.into()), delivered_from: relay }
+}
+
+fn chat(text: &str) -> Message {
+    Message::Chat {
+        text: text.to_string(), id: ghost_messaging::new_id(), reply: None, thread: None, urgent: false, rich: Vec::new(), ttl: None,
+        forwarded: None,
+    }
+}
+
+fn receive(room: &mut rooms::Room, msg: &Message, author: &SecretKey, relay: NodeId) -> Vec<ChatEvent> {
+    let msg = gossip(room, msg, None, author, relay);
+    room.receive(msg, &HashSet::new(), None)
+}
+
+#[tokio::test]
+async fn chat_arrives_and_mentions_are_noticed() {
+    let (mut room, _host) = hosted().await;
+    let peer = key();
+    match receive(&mut room, &chat("hi @Host"), &peer, peer.public()).as_slice() {
+        [ChatEvent::Message { from, text, mentions_me, announcement: false, .. }] => {
+            assert_eq!(*from, peer.public());
+            assert_eq!(text, "hi @Host");
+            assert!(mentions_me);
+        }
+        other => panic!("unexpected events: {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn messages_go_to_whoever_signed_them() {
+    let (mut room, _host) = hosted().await;
+    let (alice, relay) = (key(), key().public());
+    match receive(&mut room, &chat("from Alice"), &alice, relay).as_slice() {
+        [ChatEvent::Message { from, .. }] => assert_eq!(*from, alice.public()),
+        other => panic!("unexpected events: {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn signatures_for_another_room_are_refused() {
+    let (mut room, _host) = hosted().await;
+    let peer = key();
+    let elsewhere = TopicId::from_bytes(rand::random());
+    let content = protocol::encode(&chat("hi"), None, &peer, elsewhere).unwrap();
+    let msg = iroh_gossip::net::Message { content: content.into(), scope: DeliveryScope::Neighbors, delivered_from: peer.public() };
+    assert!(matches!(room.receive(msg, &HashSet::new(), None).as_slice(), [ChatEvent::Error(_)]));
+}
+
+#[tokio::test]
+async fn unsigned_messages_are_only_heard_from_neighbors() {
+    let (mut room, _host) = hosted().await;
+    let peer = key().public();
+    let unsigned = |scope| iroh_gossip::net::Message {
+        content: br#"{"Chat":{"text":"hi","id":"x","v":2}}"#.to_vec().into(), scope, delivered_from: peer,
+    };
+    assert!(room.receive(unsigned(DeliveryScope::Swarm(1u16.into())), &HashSet::new(), None).is_empty());
+    match room.receive(unsigned(DeliveryScope::Neighbors), &HashSet::new(), None).as_slice() {
+        [ChatEvent::Message { from, .. }] => assert_eq!(*from, peer),
+        other => panic!("unexpected events: {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn read_only_peers_are_not_heard() {
+    let (mut room, host) = hosted().await;
+    let (peer, relay) = (key(), key().public());
+    let role = moderation::assign(host.secret_key(), room.topic, peer.public(), moderation::Role::ReadOnly);
+    let passed_on = receive(&mut room, &role, host.secret_key(), relay);
+    assert!(matches!(passed_on.as_slice(), [ChatEvent::Other { message: Message::Role { .. }, .. }]));
+    assert!(receive(&mut room, &chat("hello?"), &peer, peer.public()).is_empty());
+}
+
+#[tokio::test]
+async fn roles_need_the_hosts_signature() {
+    let (mut room, _host) = hosted().await;
+    let peer = key();
+    let forged = moderation::assign(&key(), room.topic, peer.public(), moderation::Role::ReadOnly);
+    assert!(receive(&mut room, &forged, &peer, peer.public()).is_empty());
+    assert_eq!(receive(&mut room, &chat("still here"), &peer, peer.public()).len(), 1);
+}
+
+#[tokio::test]
+async fn broadcast_rooms_hear_only_what_moderators_signed() {
+    let (mut room, host) = hosted().await;
+    let peer = key();
+    let mode = moderation::mode(host.secret_key(), room.topic, true);
+    assert_eq!(receive(&mut room, &mode, host.secret_key(), peer.public()).len(), 1);
+    // The host passing a member's post on doesn't make it the host's.
+    assert!(receive(&mut room, &chat("me too"), &peer, host.secret_key().public()).is_empty());
+    match receive(&mut room, &chat("from the host"), host.secret_key(), peer.public()).as_slice() {
+        [ChatEvent::Message { from, .. }] => assert_eq!(*from, host.secret_key().public()),
+        other => panic!("unexpected events: {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn only_the_host_names_the_room() {
+    let (mut room, host) = hosted().await;
+    let moderator = key();
+    let role = moderation::assign(host.secret_key(), room.topic, moderator.public(), moderation::Role::Moderator);
+    receive(&mut room, &role, host.secret_key(), moderator.public());
+    let info = |name: &str, topic: &str| Message::RoomInfo { name: name.to_string(), topic: topic.to_string() };
+    receive(&mut room, &info("Lobby", "welcome"), host.secret_key(), moderator.public());
+    match receive(&mut room, &info("Mine", "house rules"), &moderator, moderator.public()).as_slice() {
+        [ChatEvent::Info { info, .. }] => assert_eq!((info.name.as_str(), info.topic.as_str()), ("Lobby", "house rules")),
+        other => panic!("unexpected events: {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn bans_hold_whoever_passes_them_on() {
+    let (mut room, host) = hosted().await;
+    let (peer, relay) = (key(), key().public());
+    let about = Message::AboutMe { name: "Mallory".to_string(), protocol: None, claim: None };
+    receive(&mut room, &about, &peer, peer.public());
+    let ban = moderation::announce(host.secret_key(), room.topic, peer.public(), false, None);
+    match receive(&mut room, &ban, host.secret_key(), relay).as_slice() {
+        [ChatEvent::Banned { node, name, kick: false }] => {
+            assert_eq!(*node, peer.public());
+            assert_eq!(name.as_deref(), Some("Mallory"));
+        }
+        other => panic!("unexpected events: {other:?}"),
+    }
+    assert!(receive(&mut room, &chat("let me back in"), &peer, relay).is_empty());
+    assert!(receive(&mut room, &chat("passing it on"), &key(), peer.public()).is_empty());
+    assert!(!room.peer_names.contains_key(&peer.public()));
+}
+
+#[tokio::test]
+async fn kicks_count_once() {
+    let (mut room, host) = hosted().await;
+    let peer = key().public();
+    let kick = moderation::announce(host.secret_key(), room.topic, peer, true, None);
+    assert!(matches!(receive(&mut room, &kick, host.secret_key(), peer).as_slice(), [ChatEvent::Banned { kick: true, .. }]));
+    assert!(receive(&mut room, &kick, &key(), peer).is_empty());
+}
+
+#[tokio::test]
+async fn names_go_to_whoever_signed_them() {
+    let (mut room, _host) = hosted().await;
+    let (alice, relay) = (key(), key());
+    let forged = names::claim(&alice, room.topic, "Alice");
+    let stolen = Message::AboutMe { name: "Bob".to_string(), protocol: None, claim: Some(forged.clone()) };
+    assert!(receive(&mut room, &stolen, &relay, relay.public()).is_empty());
+
+    let passed_on = Message::AboutMe { name: "Alice".to_string(), protocol: None, claim: Some(forged) };
+    match receive(&mut room, &passed_on, &relay, relay.public()).as_slice() {
+        [ChatEvent::Peer { node, name, old: None }] => {
+            assert_eq!(*node, alice.public());
+            assert_eq!(name, "Alice");
+        }
+        other => panic!("unexpected events: {other:?}"),
+    }
+    assert_eq!(room.peer_names.get(&alice.public()).map(String::as_str), Some("Alice"));
+    assert!(!room.peer_names.contains_key(&relay.public()));
+}
+
+#[tokio::test]
+async fn blocked_peers_are_only_heard_for_what_the_host_signed() {
+    let (mut room, host) = hosted().await;
+    let peer = key();
+    let blocked = HashSet::from([peer.public()]);
+    assert!(room.receive(gossip(&room, &chat("hi"), None, &peer, peer.public()), &blocked, None).is_empty());
+    let close = moderation::close(host.secret_key(), room.topic);
+    let passed_on = gossip(&room, &close, None, &peer, peer.public());
+    assert!(matches!(room.receive(passed_on, &blocked, None).as_slice(), [ChatEvent::Destroyed]));
+    assert!(room.destroyed());
+}
+
+#[tokio::test]
+async fn fast_posters_are_muted() {
+    let (mut room, _host) = hosted().await;
+    let peer = key();
+    let mut flood = flood::Flood::new(flood::Limits { posts: 2, window: Duration::from_secs(60), mute: Duration::from_secs(60) });
+    let mut post = |room: &mut rooms::Room| {
+        let msg = gossip(room, &chat("spam"), None, &peer, peer.public());
+        room.receive(msg, &HashSet::new(), Some(&mut flood))
+    };
+    assert_eq!(post(&mut room).len(), 1);
+    assert_eq!(post(&mut room).len(), 1);
+    assert!(matches!(post(&mut room).as_slice(), [ChatEvent::Muted(node)] if *node == peer.public()));
+    assert!(post(&mut room).is_empty());
+}
+
+#[tokio::test]
+async fn missing_messages_are_reported() {
+    let (mut room, _host) = hosted().await;
+    let peer = key();
+    let seq = |number| Some(Seq { node: peer.public(), session: 1, number });
+    let one = gossip(&room, &chat("one"), seq(1), &peer, peer.public());
+    assert_eq!(room.receive(one, &HashSet::new(), None).len(), 1);
+    let four = gossip(&room, &chat("four"), seq(4), &peer, peer.public());
+    match room.receive(four, &HashSet::new(), None).as_slice() {
+        [ChatEvent::Gap { node, first: 2, last: 3 }, ChatEvent::Message { .. }] => assert_eq!(*node, peer.public()),
+        other => panic!("unexpected events: {other:?}"),
+    }
+}